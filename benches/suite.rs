@@ -0,0 +1,56 @@
+//! Criterion benchmarks of lexing, validation, parsing into values and into
+//! a tape, serialization and pointer lookup, see `yaj::bench`, on generated
+//! documents of various shapes and on `big_json.txt` when it exists.
+//!
+//! Run with `cargo bench --features bench --bench suite`.
+
+use criterion::Criterion;
+
+/// An event with `n` faces, of about 200 bytes each.
+fn faces_document(n: usize) -> String {
+    let face = r#"{"uid": 12, "age": {"value": 31, "confidence": 0.87}, "faceRect": {"x": 0.1, "y": 0.25, "width": 0.2, "height": 0.3}, "tags": ["a", "b\n", null, true]}"#;
+    let faces = vec![face; n].join(",");
+    format!(r#"{{"eventType": "faceCapture", "faceCapture": [{{"faces": [{}]}}]}}"#, faces)
+}
+
+/// An array of `n` strings of `len` bytes, each ending with an escape.
+fn strings_document(n: usize, len: usize) -> String {
+    let text = "lorem ipsum dolor sit amet ".repeat(len / 27 + 1);
+    let strings = vec![format!("\"{}\\n\"", &text[..len]); n].join(", ");
+    format!("[{}]", strings)
+}
+
+/// Arrays and objects nested `depth` times each.
+fn nested_document(depth: usize) -> String {
+    let mut doc = String::new();
+    for _ in 0..depth {
+        doc.push_str("[1,{\"a\":");
+    }
+    doc.push_str("null");
+    for _ in 0..depth {
+        doc.push_str("}]");
+    }
+    doc
+}
+
+fn main() {
+    let mut c = Criterion::default().configure_from_args();
+    let large = faces_document(10_000);
+    let pretty = yaj::to_string_pretty(&yaj::parse(&large), yaj::PrettyConfig::default());
+    let mut documents = vec![
+        ("small (1 face)", faces_document(1)),
+        ("medium (100 faces)", faces_document(100)),
+        ("large (10k faces)", large),
+        ("pretty (10k faces)", pretty),
+        // Two containers per level, within the default depth limit.
+        ("nested (depth 60)", nested_document(60)),
+        ("strings (1k x 1 KiB)", strings_document(1_000, 1_024)),
+    ];
+    if let Ok(big) = std::fs::read_to_string("big_json.txt") {
+        documents.push(("big_json.txt", big));
+    }
+    for (name, source) in &documents {
+        yaj::bench::bench_document(&mut c, name, source).unwrap();
+    }
+    c.final_summary();
+}
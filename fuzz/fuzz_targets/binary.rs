@@ -0,0 +1,15 @@
+//! Binary decoders, and encoding what they decoded back.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use yaj::{cbor, msgpack};
+
+fuzz_target!(|bytes: &[u8]| {
+    if let Ok(value) = cbor::from_slice(bytes) {
+        assert!(cbor::from_slice(&cbor::to_vec(&value)).is_ok());
+    }
+    if let Ok(value) = msgpack::decode(bytes) {
+        assert!(msgpack::decode(&msgpack::encode(&value)).is_ok());
+    }
+});
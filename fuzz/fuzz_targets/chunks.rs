@@ -0,0 +1,18 @@
+//! Incremental inputs: the first byte tells where the rest is split.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use yaj::FeedLexer;
+
+fuzz_target!(|data: &[u8]| {
+    let (split, bytes) = match data.split_first() {
+        Some((&split, bytes)) => (usize::from(split).min(bytes.len()), bytes),
+        None => return,
+    };
+    let mut lexer = FeedLexer::new();
+    let _ = lexer.feed(&bytes[..split]);
+    let _ = lexer.feed(&bytes[split..]);
+    let _ = lexer.finish();
+    let _ = yaj::from_reader(bytes);
+});
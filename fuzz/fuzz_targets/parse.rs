@@ -0,0 +1,37 @@
+//! Every entry point reading text, with each parser option.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use yaj::{jsonpath, tape, JsonReader, Parser};
+
+fuzz_target!(|source: &str| {
+    let parsed = yaj::try_parse(source);
+    assert_eq!(yaj::validate(source).is_ok(), parsed.is_ok());
+    if let Ok(value) = parsed {
+        let _ = yaj::to_string(&value);
+        let _ = yaj::to_canonical_string(&value);
+        let _ = jsonpath::query(&value, "$..*[?@.a > 1 || @ == 'x'][-1:0:-2]");
+    }
+    for &json5 in &[false, true] {
+        for &relaxed in &[false, true] {
+            let parser = Parser::new().json5(json5).allow_comments(relaxed).allow_control_chars(relaxed);
+            let _ = parser.parse(source);
+            let _ = parser.parse_with_spans(source);
+        }
+    }
+    let _ = yaj::parse_lenient(source);
+    let _ = yaj::minify_with_comments(source);
+    let _ = tape::parse(source).map(|tape| tape.root().to_value());
+    if let Ok(members) = yaj::parse_shallow(source) {
+        for (_, raw) in members {
+            let _ = raw.parse();
+        }
+    }
+    for _ in yaj::StreamParser::new(source) {}
+    for _ in yaj::ndjson::parse_lines(source) {}
+    let mut reader = JsonReader::new(source);
+    while let Ok(Some(_)) = reader.next_event() {}
+    let _ = yaj::from_str::<serde::de::IgnoredAny>(source);
+    let _ = jsonpath::JsonPath::parse(source);
+});
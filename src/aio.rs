@@ -0,0 +1,150 @@
+//! Reading documents from `tokio` readers without blocking the runtime.
+//!
+//! ```
+//! # async fn run() -> Result<(), yaj::JsonError> {
+//! let body: &[u8] = br#"{"portNo": 8080}"#;
+//! let value = yaj::aio::from_async_reader(body).await?;
+//! assert_eq!(value["portNo"], yaj::json!(8080));
+//! # Ok(())
+//! # }
+//! ```
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt};
+
+use crate::encoding::{decode_error, Encoding};
+use crate::ndjson::parse_record;
+use crate::{from_slice, JsonError, JsonValue, JsonValueOwned};
+
+/// Reads `reader` to the end, then parses the document as
+/// [`from_slice`] does.
+pub async fn from_async_reader<R: AsyncRead + Unpin>(mut reader: R) -> Result<JsonValueOwned, JsonError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    from_slice(&bytes).map(JsonValue::into_owned)
+}
+
+/// Stream of the records of an NDJSON source, see [`ndjson_stream`].
+pub struct NdjsonStream<R> {
+    reader: R,
+    /// The line read so far.
+    buffer: Vec<u8>,
+    /// Byte offset of the line in `buffer`.
+    offset: usize,
+    /// Number of the line in `buffer`, starting at 1.
+    line: usize,
+    done: bool,
+}
+
+/// Parses each non-blank line of `reader` as a document, as it is read, like
+/// [`parse_lines`](crate::ndjson::parse_lines). Parse errors do not stop the
+/// stream, read errors do.
+pub fn ndjson_stream<R: AsyncBufRead + Unpin>(reader: R) -> NdjsonStream<R> {
+    NdjsonStream {
+        reader,
+        buffer: Vec::new(),
+        offset: 0,
+        line: 1,
+        done: false,
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> NdjsonStream<R> {
+    /// Parses the line in `buffer` and moves to the next one.
+    fn parse_line(&mut self) -> Option<Result<JsonValueOwned, JsonError>> {
+        let (offset, line) = (self.offset, self.line);
+        self.offset += self.buffer.len();
+        self.line += 1;
+        let bytes = core::mem::take(&mut self.buffer);
+        match core::str::from_utf8(&bytes) {
+            Ok(text) => parse_record(text, offset, line).map(|result| result.map(JsonValue::into_owned)),
+            Err(e) => {
+                let error = decode_error(&bytes, Encoding::Utf8, e.valid_up_to());
+                Some(Err(JsonError::Lex(error).on_line(offset, line)))
+            }
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for NdjsonStream<R> {
+    type Item = Result<JsonValueOwned, JsonError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.done {
+            let (end_of_line, used) = match Pin::new(&mut this.reader).poll_fill_buf(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(e)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                Poll::Ready(Ok([])) => {
+                    this.done = true;
+                    (true, 0)
+                }
+                Poll::Ready(Ok(available)) => match available.iter().position(|&b| b == b'\n') {
+                    Some(idx) => {
+                        this.buffer.extend_from_slice(&available[..=idx]);
+                        (true, idx + 1)
+                    }
+                    None => {
+                        this.buffer.extend_from_slice(available);
+                        (false, available.len())
+                    }
+                },
+            };
+            Pin::new(&mut this.reader).consume(used);
+            if end_of_line {
+                if let Some(result) = this.parse_line() {
+                    return Poll::Ready(Some(result));
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::future::poll_fn;
+    use tokio::io::BufReader;
+
+    async fn collect<R: AsyncBufRead + Unpin>(reader: R) -> Vec<Result<JsonValueOwned, JsonError>> {
+        let mut stream = ndjson_stream(reader);
+        let mut records = Vec::new();
+        while let Some(record) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            records.push(record);
+        }
+        records
+    }
+
+    #[tokio::test]
+    async fn whole_document() {
+        let value = from_async_reader(&b"\xef\xbb\xbf[1, {\"a\": null}]"[..]).await.unwrap();
+        assert_eq!(value, json!([1, {"a": null}]));
+        assert!(from_async_reader(&b"[1,"[..]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn ndjson_records() {
+        let source = "{\"a\": 1}\r\n\n[2,\n  \n\"caf\u{e9}\"";
+        // A tiny buffer splits lines and characters across reads.
+        let records = collect(BufReader::with_capacity(3, source.as_bytes())).await;
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().unwrap(), &json!({"a": 1}));
+        match &records[1] {
+            Err(JsonError::Parse { offset, line, .. }) => assert_eq!((*offset, *line), (14, 3)),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert_eq!(records[2].as_ref().unwrap(), &json!("café"));
+        assert!(collect(&b"\n\n"[..]).await.is_empty());
+        match collect(&b"1\n\"\xff\"\n"[..]).await.as_slice() {
+            [Ok(_), Err(JsonError::Lex(e))] => assert_eq!((e.offset, e.line), (3, 2)),
+            other => panic!("expected a lexing error, got {:?}", other),
+        }
+    }
+}
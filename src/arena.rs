@@ -0,0 +1,232 @@
+//! Documents allocated in a caller-provided [`Bump`] arena: the arrays,
+//! objects and escaped strings of an [`ArenaValue`] are all allocated
+//! there, and released at once when the arena is reset or dropped.
+//!
+//! ```
+//! use yaj::arena::Bump;
+//! let mut bump = Bump::new();
+//! for line in [r#"{"uid": 1}"#, r#"{"uid": 2}"#].iter() {
+//!     let value = yaj::arena::parse_in(line, &bump).unwrap();
+//!     assert!(value.get("uid").is_some());
+//!     drop(value);
+//!     bump.reset();
+//! }
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+
+pub use bumpalo::Bump;
+use bumpalo::collections::Vec as BumpVec;
+
+use crate::parser::{DuplicateKeys, ParseOptions};
+use crate::prelude::*;
+use crate::{JsonError, JsonEvent, JsonNumber, JsonReader, JsonValue, Map};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaValue<'a> {
+    /// A string with its escape sequences resolved. Borrows from the source
+    /// unless it contained escapes.
+    String(&'a str),
+    Number(JsonNumber),
+    Boolean(bool),
+    Null,
+    Array(&'a [ArenaValue<'a>]),
+    /// Members in source order, repeated keys included, with their escapes
+    /// resolved like [`JsonValue`] keys.
+    Object(&'a [(&'a str, ArenaValue<'a>)]),
+}
+
+impl<'a> ArenaValue<'a> {
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&'a [ArenaValue<'a>]> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&'a [(&'a str, ArenaValue<'a>)]> {
+        match self {
+            Self::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Value of the member `key` of an object, the last one if the key is
+    /// repeated, like the value [`try_parse`](crate::try_parse) keeps.
+    pub fn get(&self, key: &str) -> Option<&'a ArenaValue<'a>> {
+        self.as_object()?.iter().rev().find(|(k, _)| *k == key).map(|(_, value)| value)
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&'a ArenaValue<'a>> {
+        self.as_array()?.get(index)
+    }
+
+    /// Builds the [`JsonValue`] this value stands for, borrowing its
+    /// strings from the arena.
+    pub fn to_value(&self) -> JsonValue<'a> {
+        match *self {
+            Self::String(s) => JsonValue::String(Cow::Borrowed(s)),
+            Self::Number(n) => JsonValue::Number(n),
+            Self::Boolean(b) => JsonValue::Boolean(b),
+            Self::Null => JsonValue::Null,
+            Self::Array(array) => JsonValue::Array(array.iter().map(|e| e.to_value()).collect()),
+            Self::Object(members) => {
+                let mut obj = Map::new();
+                for (key, value) in members {
+                    obj.insert(Cow::Borrowed(*key), value.to_value());
+                }
+                JsonValue::Object(obj)
+            }
+        }
+    }
+}
+
+/// An array or object being built.
+enum Frame<'a> {
+    Array(BumpVec<'a, ArenaValue<'a>>),
+    /// Members read so far, the key of the next one, and with
+    /// [`DuplicateKeys::FirstWins`] the keys already taken.
+    Object(BumpVec<'a, (&'a str, ArenaValue<'a>)>, &'a str, BTreeSet<&'a str>),
+}
+
+impl<'a> Frame<'a> {
+    fn finish(self) -> ArenaValue<'a> {
+        match self {
+            Frame::Array(array) => ArenaValue::Array(array.into_bump_slice()),
+            Frame::Object(members, _, _) => ArenaValue::Object(members.into_bump_slice()),
+        }
+    }
+}
+
+/// Parses `source` into values allocated in `bump`, with the same errors
+/// as [`try_parse`](crate::try_parse).
+pub fn parse_in<'a>(source: &'a str, bump: &'a Bump) -> Result<ArenaValue<'a>, JsonError> {
+    parse_in_with(source, bump, ParseOptions::default())
+}
+
+/// Same as [`parse_in`] with `options`, accepting what
+/// [`try_parse_with`](crate::try_parse_with) accepts. Repeated keys are
+/// all kept unless [`DuplicateKeys::FirstWins`] drops the later ones.
+pub fn parse_in_with<'a>(source: &'a str, bump: &'a Bump, options: ParseOptions) -> Result<ArenaValue<'a>, JsonError> {
+    let first_wins = options.duplicate_keys == DuplicateKeys::FirstWins;
+    let mut reader = JsonReader::with_options(source, options);
+    let alloc = |s: Cow<'a, str>| -> &'a str {
+        match s {
+            Cow::Borrowed(s) => s,
+            Cow::Owned(s) => bump.alloc_str(&s),
+        }
+    };
+    let mut frames: Vec<Frame<'a>> = Vec::new();
+    let mut root = None;
+    while let Some(event) = reader.next_event()? {
+        let value = match event {
+            JsonEvent::StartArray => {
+                frames.push(Frame::Array(BumpVec::new_in(bump)));
+                continue;
+            }
+            JsonEvent::StartObject => {
+                frames.push(Frame::Object(BumpVec::new_in(bump), "", BTreeSet::new()));
+                continue;
+            }
+            JsonEvent::Key(key) => {
+                if let Some(Frame::Object(_, next, _)) = frames.last_mut() {
+                    *next = alloc(key);
+                }
+                continue;
+            }
+            JsonEvent::EndArray | JsonEvent::EndObject => match frames.pop() {
+                Some(frame) => frame.finish(),
+                None => continue,
+            },
+            JsonEvent::Value(JsonValue::String(s)) => ArenaValue::String(alloc(s)),
+            JsonEvent::Value(JsonValue::Number(n)) => ArenaValue::Number(n),
+            JsonEvent::Value(JsonValue::Boolean(b)) => ArenaValue::Boolean(b),
+            JsonEvent::Value(_) => ArenaValue::Null,
+        };
+        match frames.last_mut() {
+            None => root = Some(value),
+            Some(Frame::Array(array)) => array.push(value),
+            Some(Frame::Object(members, key, taken)) => {
+                if !first_wins || taken.insert(*key) {
+                    members.push((*key, value));
+                }
+            }
+        }
+    }
+    root.ok_or(JsonError::Empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, try_parse, try_parse_with};
+
+    #[test]
+    fn same_values_as_parsing() {
+        let bump = Bump::new();
+        let source = r#"{"faces": [{"uid": 1, "tags": ["a\nb", "c"]}, null], "ok": true, "ok": false}"#;
+        let value = parse_in(source, &bump).unwrap();
+        assert_eq!(value.to_value(), parse(source));
+        assert_eq!(value.get("ok"), Some(&ArenaValue::Boolean(false)));
+        let tags = value.get("faces").and_then(|f| f.get_index(0)).and_then(|f| f.get("tags")).unwrap();
+        assert_eq!(tags.get_index(0).and_then(ArenaValue::as_str), Some("a\nb"));
+        assert_eq!(value.as_object().map(<[_]>::len), Some(3));
+    }
+
+    #[test]
+    fn same_errors_as_parsing() {
+        let bump = Bump::new();
+        for source in ["", "[1,]", r#"{"a" 1}"#, "[] []", "[\"\\q\"]", &"[".repeat(200)].iter() {
+            assert_eq!(parse_in(source, &bump).map(|_| ()), try_parse(source).map(|_| ()), "{}", source);
+        }
+    }
+
+    #[test]
+    fn options() {
+        let bump = Bump::new();
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let source = "{a: [1, 2,], 'b': Infinity, a: null, // comment\n}";
+        let value = parse_in_with(source, &bump, json5.clone()).unwrap();
+        assert_eq!(value.to_value(), try_parse_with(source, json5).unwrap());
+
+        let first_wins = ParseOptions {
+            duplicate_keys: DuplicateKeys::FirstWins,
+            ..ParseOptions::default()
+        };
+        let value = parse_in_with(r#"{"a": 1, "a": 2}"#, &bump, first_wins).unwrap();
+        assert_eq!(value.as_object().map(<[_]>::len), Some(1));
+        assert_eq!(value.get("a"), Some(&ArenaValue::Number(JsonNumber::Integer(1))));
+
+        let duplicate_error = ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..ParseOptions::default()
+        };
+        let limits = ParseOptions {
+            max_depth: 2,
+            max_len: Some(2),
+            ..ParseOptions::default()
+        };
+        let cases = [
+            (r#"{"a": 1, "a": [}"#, &duplicate_error),
+            (r#"{"a": 1, "a": 2}"#, &duplicate_error),
+            ("[[[]]]", &limits),
+            ("[1, 2, 3]", &limits),
+        ];
+        for (source, options) in cases.iter() {
+            let parsed = parse_in_with(source, &bump, (*options).clone()).map(|_| ());
+            assert_eq!(parsed, try_parse_with(source, (*options).clone()).map(|_| ()), "{}", source);
+            assert!(parsed.is_err(), "{}", source);
+        }
+    }
+}
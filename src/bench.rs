@@ -0,0 +1,75 @@
+//! Criterion benchmarks of the main operations of the crate on any
+//! document, to compare them on one's own data or across changes of the
+//! parser.
+//!
+//! ```no_run
+//! use criterion::Criterion;
+//! let source = std::fs::read_to_string("events.json").unwrap();
+//! let mut c = Criterion::default().configure_from_args();
+//! yaj::bench::bench_document(&mut c, "events", &source).unwrap();
+//! c.final_summary();
+//! ```
+
+use criterion::{Criterion, Throughput};
+
+use crate::pointer::push_token;
+use crate::{JsonError, JsonValue};
+
+/// Benchmarks, in a group named `name` and with the size of `source` as
+/// throughput: lexing, [validating](crate::validate), parsing into values
+/// and into a [`tape`](crate::tape), serializing the parsed value, and
+/// looking up its last leaf by JSON Pointer (see [`last_leaf_pointer`]).
+///
+/// Fails without running anything when `source` is not a valid document.
+pub fn bench_document(c: &mut Criterion, name: &str, source: &str) -> Result<(), JsonError> {
+    let value = crate::try_parse(source)?;
+    let pointer = last_leaf_pointer(&value);
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("lex", |b| b.iter(|| crate::try_lex(source)));
+    group.bench_function("validate", |b| b.iter(|| crate::validate(source)));
+    group.bench_function("parse", |b| b.iter(|| crate::try_parse(source)));
+    group.bench_function("tape", |b| b.iter(|| crate::tape::parse(source)));
+    group.bench_function("to_string", |b| b.iter(|| crate::to_string(&value)));
+    group.bench_function("pointer", |b| b.iter(|| value.pointer(&pointer).is_some()));
+    group.finish();
+    Ok(())
+}
+
+/// JSON Pointer of the value reached by following the last element or
+/// member of every array and object from the root of `value`, which a
+/// lookup reaches after skipping over all the others.
+pub fn last_leaf_pointer(value: &JsonValue<'_>) -> String {
+    let mut pointer = String::new();
+    let mut value = value;
+    loop {
+        value = match value {
+            JsonValue::Array(array) if !array.is_empty() => {
+                push_token(&mut pointer, &(array.len() - 1).to_string());
+                &array[array.len() - 1]
+            }
+            JsonValue::Object(obj) => match obj.iter().last() {
+                Some((key, member)) => {
+                    push_token(&mut pointer, key);
+                    member
+                }
+                None => return pointer,
+            },
+            _ => return pointer,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_leaf() {
+        let value = json!([1, {"a/b": [true, {"~": null}]}]);
+        assert_eq!(last_leaf_pointer(&value), "/1/a~1b/1/~0");
+        assert_eq!(value.pointer(&last_leaf_pointer(&value)), Some(&JsonValue::Null));
+        assert_eq!(last_leaf_pointer(&json!([1, []])), "/1");
+        assert_eq!(last_leaf_pointer(&json!(1)), "");
+    }
+}
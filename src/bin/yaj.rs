@@ -0,0 +1,303 @@
+//! The `yaj` command: checks, reformats and queries JSON documents read from
+//! files, or from the standard input when no file or `-` is given.
+//!
+//! ```text
+//! yaj fmt [--indent N] [--sort-keys] [FILE]...
+//! yaj validate [FILE]...
+//! yaj minify [FILE]...
+//! yaj get [--indent N] POINTER [FILE]
+//! yaj diff [--indent N] [--sort-keys] OLD NEW
+//! yaj patch [--indent N] [--sort-keys] PATCH [FILE]
+//! ```
+//!
+//! `diff` writes the RFC 6902 JSON Patch that turns `OLD` into `NEW`, and
+//! `patch` applies such a patch to `FILE` and writes the result.
+//!
+//! The exit code is 0 on success, 1 when a document is invalid, a pointer
+//! designates no value or a patch does not apply, and 2 on a usage or I/O
+//! error.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process;
+
+use yaj::patch::{self, JsonPatch};
+use yaj::{JsonValue, PrettyConfig};
+
+const USAGE: &str = "\
+Usage: yaj fmt [--indent N] [--sort-keys] [FILE]...
+       yaj validate [FILE]...
+       yaj minify [FILE]...
+       yaj get [--indent N] POINTER [FILE]
+       yaj diff [--indent N] [--sort-keys] OLD NEW
+       yaj patch [--indent N] [--sort-keys] PATCH [FILE]
+
+Reads the standard input when no FILE or `-` is given.";
+
+/// Exit codes.
+const INVALID: i32 = 1;
+const USAGE_ERROR: i32 = 2;
+
+/// Why a command failed: the message to print and the exit code.
+struct Failure {
+    msg: String,
+    code: i32,
+}
+
+impl Failure {
+    fn usage(msg: impl Into<String>) -> Self {
+        Failure {
+            msg: format!("{}\n\n{}", msg.into(), USAGE),
+            code: USAGE_ERROR,
+        }
+    }
+}
+
+/// An input of the command, with its name for error messages.
+struct Input {
+    name: String,
+    text: String,
+}
+
+/// The options and operands of a command line.
+#[derive(Default)]
+struct Args {
+    config: PrettyConfig,
+    operands: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, Failure> {
+    let mut parsed = Args::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--indent" => {
+                let width = args.next().ok_or_else(|| Failure::usage("Missing value of --indent"))?;
+                parsed.config.indent_width = width
+                    .parse()
+                    .map_err(|_| Failure::usage(format!("Invalid indent width '{}'", width)))?;
+            }
+            "--sort-keys" => parsed.config.sort_keys = true,
+            "--" => {
+                parsed.operands.extend(args.cloned());
+                break;
+            }
+            option if option.starts_with("--") => return Err(Failure::usage(format!("Unknown option '{}'", option))),
+            _ => parsed.operands.push(arg.clone()),
+        }
+    }
+    Ok(parsed)
+}
+
+fn read_inputs(paths: &[String], stdin: &mut dyn Read) -> Result<Vec<Input>, Failure> {
+    if paths.is_empty() {
+        return read_inputs(&[String::from("-")], stdin);
+    }
+    paths
+        .iter()
+        .map(|path| {
+            let (name, bytes) = if path == "-" {
+                let mut bytes = Vec::new();
+                let read = stdin.read_to_end(&mut bytes);
+                (String::from("<stdin>"), read.map(|_| bytes))
+            } else {
+                (path.clone(), fs::read(path))
+            };
+            let bytes = bytes.map_err(|e| Failure {
+                msg: format!("{}: {}", name, e),
+                code: USAGE_ERROR,
+            })?;
+            let text = String::from_utf8(bytes).map_err(|e| Failure {
+                msg: format!("{}: Invalid UTF-8 at byte {}", name, e.utf8_error().valid_up_to()),
+                code: INVALID,
+            })?;
+            Ok(Input { name, text })
+        })
+        .collect()
+}
+
+/// Parses `input`, the error naming the input and its location.
+fn parse(input: &Input) -> Result<JsonValue<'_>, Failure> {
+    yaj::try_parse(&input.text).map_err(|e| Failure {
+        msg: format!("{}: {}", input.name, e),
+        code: INVALID,
+    })
+}
+
+fn write_error(e: io::Error) -> Failure {
+    Failure {
+        msg: format!("Cannot write the output: {}", e),
+        code: USAGE_ERROR,
+    }
+}
+
+/// Runs the command line `args`, without the program name.
+fn run(args: &[String], stdin: &mut dyn Read, stdout: &mut dyn Write) -> Result<(), Failure> {
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), parse_args(args)?),
+        None => return Err(Failure::usage("Missing command")),
+    };
+    match command {
+        "fmt" => {
+            for input in read_inputs(&args.operands, stdin)? {
+                let value = parse(&input)?;
+                writeln!(stdout, "{}", yaj::to_string_pretty(&value, args.config.clone())).map_err(write_error)?;
+            }
+        }
+        "validate" => {
+            // Every input is checked, all errors being reported.
+            let errors: Vec<String> = read_inputs(&args.operands, stdin)?
+                .iter()
+                .filter_map(|input| Some(format!("{}: {}", input.name, yaj::validate(&input.text).err()?)))
+                .collect();
+            if !errors.is_empty() {
+                return Err(Failure {
+                    msg: errors.join("\n"),
+                    code: INVALID,
+                });
+            }
+        }
+        "minify" => {
+            for input in read_inputs(&args.operands, stdin)? {
+                let minified = yaj::minify(&input.text).map_err(|e| Failure {
+                    msg: format!("{}: {}", input.name, e),
+                    code: INVALID,
+                })?;
+                writeln!(stdout, "{}", minified).map_err(write_error)?;
+            }
+        }
+        "get" => {
+            let (pointer, paths) = match args.operands.split_first() {
+                Some((pointer, paths)) if paths.len() <= 1 => (pointer, paths),
+                Some(_) => return Err(Failure::usage("get reads a single FILE")),
+                None => return Err(Failure::usage("Missing POINTER")),
+            };
+            let inputs = read_inputs(paths, stdin)?;
+            let value = parse(&inputs[0])?;
+            let found = value.pointer(pointer).ok_or_else(|| Failure {
+                msg: format!("{}: No value at '{}'", inputs[0].name, pointer),
+                code: INVALID,
+            })?;
+            writeln!(stdout, "{}", yaj::to_string_pretty(found, args.config)).map_err(write_error)?;
+        }
+        "diff" => {
+            if args.operands.len() != 2 {
+                return Err(Failure::usage("diff compares two files, OLD and NEW"));
+            }
+            let inputs = read_inputs(&args.operands, stdin)?;
+            let patch = patch::diff(&parse(&inputs[0])?, &parse(&inputs[1])?);
+            writeln!(stdout, "{}", yaj::to_string_pretty(&patch.to_value(), args.config)).map_err(write_error)?;
+        }
+        "patch" => {
+            let (patch_path, paths) = match args.operands.split_first() {
+                Some((patch_path, paths)) if paths.len() <= 1 => (patch_path, paths),
+                Some(_) => return Err(Failure::usage("patch reads a single FILE")),
+                None => return Err(Failure::usage("Missing PATCH")),
+            };
+            let patch_input = read_inputs(std::slice::from_ref(patch_path), stdin)?.remove(0);
+            let invalid_patch = |e: patch::PatchError| Failure {
+                msg: format!("{}: {}", patch_input.name, e),
+                code: INVALID,
+            };
+            let ops = JsonPatch::from_value(&parse(&patch_input)?).map_err(invalid_patch)?;
+            let inputs = read_inputs(paths, stdin)?;
+            let mut value = parse(&inputs[0])?;
+            patch::apply(&mut value, &ops).map_err(invalid_patch)?;
+            writeln!(stdout, "{}", yaj::to_string_pretty(&value, args.config)).map_err(write_error)?;
+        }
+        "help" | "--help" | "-h" => writeln!(stdout, "{}", USAGE).map_err(write_error)?,
+        _ => return Err(Failure::usage(format!("Unknown command '{}'", command))),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let stdout = io::stdout();
+    let result = run(&args, &mut io::stdin(), &mut stdout.lock());
+    if let Err(failure) = result {
+        eprintln!("yaj: {}", failure.msg);
+        process::exit(failure.code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yaj::json;
+
+    /// The exit code of `args` run on `stdin`, with the output or the error
+    /// message.
+    fn yaj(args: &[&str], stdin: &str) -> (i32, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut stdout = Vec::new();
+        match run(&args, &mut stdin.as_bytes(), &mut stdout) {
+            Ok(()) => (0, String::from_utf8(stdout).unwrap()),
+            Err(failure) => (failure.code, failure.msg),
+        }
+    }
+
+    #[test]
+    fn commands() {
+        let source = r#" {"a": [1, {"b": null}]} "#;
+        assert_eq!(
+            yaj(&["fmt", "--indent", "1"], source),
+            (0, String::from("{\n \"a\": [\n  1,\n  {\n   \"b\": null\n  }\n ]\n}\n"))
+        );
+        assert_eq!(
+            yaj(&["minify", "-"], source),
+            (0, String::from("{\"a\":[1,{\"b\":null}]}\n"))
+        );
+        assert_eq!(yaj(&["validate"], source), (0, String::new()));
+        assert_eq!(yaj(&["get", "/a/1", "--indent", "0"], source).1, "{\n\"b\": null\n}\n");
+        assert_eq!(yaj(&["get", "/a/0"], source), (0, String::from("1\n")));
+    }
+
+    #[test]
+    fn failures() {
+        assert_eq!(
+            yaj(&["validate"], "[1,\n 2"),
+            (
+                1,
+                String::from("<stdin>: Unexpected end of file near '' at line 2 column 3")
+            )
+        );
+        assert_eq!(yaj(&["fmt"], "{").0, 1);
+        assert_eq!(
+            yaj(&["get", "/b"], "{}"),
+            (1, String::from("<stdin>: No value at '/b'"))
+        );
+        assert_eq!(yaj(&["get", "/b", "a", "b"], "{}").0, 2);
+        assert_eq!(yaj(&["fmt", "--indent"], "{}").0, 2);
+        assert_eq!(yaj(&["fmt", "--tabs"], "{}").0, 2);
+        assert_eq!(yaj(&["lint"], "{}").0, 2);
+        assert_eq!(yaj(&[], "{}").0, 2);
+        assert_eq!(yaj(&["fmt", "/nonexistent/file.json"], "").0, 2);
+    }
+
+    #[test]
+    fn diff_and_patch() {
+        let path = std::env::temp_dir().join(format!("yaj-cli-{}.json", process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, r#"{"tags": ["a"]}"#).unwrap();
+        let (code, diff) = yaj(&["diff", path, "-"], r#"{"tags": ["a", "b"]}"#);
+        assert_eq!(code, 0);
+        assert_eq!(
+            yaj::parse(&diff),
+            json!([{"op": "add", "path": "/tags/-", "value": "b"}])
+        );
+        let (code, patched) = yaj(&["patch", "-", path], &diff);
+        assert_eq!(code, 0);
+        assert_eq!(yaj::parse(&patched), json!({"tags": ["a", "b"]}));
+
+        let failing = r#"[{"op": "test", "path": "/tags/0", "value": "b"}]"#;
+        assert_eq!(
+            yaj(&["patch", "-", path], failing),
+            (1, String::from("<stdin>: Test failed in patch operation 0"))
+        );
+        assert_eq!(yaj(&["patch", "-", path], r#"[{"op": "bump"}]"#).0, 1);
+        fs::remove_file(path).unwrap();
+        assert_eq!(yaj(&["diff", "-"], "{}").0, 2);
+        assert_eq!(yaj(&["patch"], "[]").0, 2);
+    }
+}
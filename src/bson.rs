@@ -0,0 +1,578 @@
+//! Conversion between values and BSON documents, the binary encoding of
+//! MongoDB, e.g. for the events of change streams.
+//!
+//! ```
+//! use yaj::{bson, json};
+//! let value = json!({"uid": 12, "tags": ["a", null]});
+//! let bytes = bson::to_document_bytes(&value).unwrap();
+//! assert_eq!(bson::from_document_bytes(&bytes).unwrap(), value);
+//! ```
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::extjson::date_time;
+use crate::parser::ParseOptions;
+use crate::pointer::JsonPointer;
+use crate::prelude::*;
+use crate::{JsonNumber, JsonValue, Map};
+
+const DOUBLE: u8 = 0x01;
+const STRING: u8 = 0x02;
+const DOCUMENT: u8 = 0x03;
+const ARRAY: u8 = 0x04;
+const BINARY: u8 = 0x05;
+const UNDEFINED: u8 = 0x06;
+const OBJECT_ID: u8 = 0x07;
+const BOOLEAN: u8 = 0x08;
+const DATE_TIME: u8 = 0x09;
+const NULL: u8 = 0x0a;
+const REGEX: u8 = 0x0b;
+const CODE: u8 = 0x0d;
+const SYMBOL: u8 = 0x0e;
+const CODE_WITH_SCOPE: u8 = 0x0f;
+const INT32: u8 = 0x10;
+const TIMESTAMP: u8 = 0x11;
+const INT64: u8 = 0x12;
+const DECIMAL128: u8 = 0x13;
+
+/// A value that cannot be encoded as a BSON document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BsonEncodeError {
+    pub msg: &'static str,
+    /// Where the offending value is.
+    pub pointer: JsonPointer,
+}
+impl fmt::Display for BsonEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at '{}'", self.msg, self.pointer)
+    }
+}
+impl core::error::Error for BsonEncodeError {}
+
+/// Encodes the object `value` as a BSON document. Integers are written as
+/// 32-bit integers when they fit and as 64-bit ones otherwise; integers
+/// above `i64::MAX` and keys containing a NUL byte are errors.
+pub fn to_document_bytes(value: &JsonValue) -> Result<Vec<u8>, BsonEncodeError> {
+    let root = JsonPointer::root();
+    let obj = match value {
+        JsonValue::Object(obj) => obj,
+        _ => {
+            return Err(BsonEncodeError {
+                msg: "BSON document that is not an object",
+                pointer: root,
+            })
+        }
+    };
+    let mut out = Vec::new();
+    document(
+        &mut out,
+        obj.iter().map(|(key, member)| (Cow::Borrowed(&**key), member)),
+        &root,
+    )?;
+    Ok(out)
+}
+
+/// Writes the elements with their length and terminator.
+fn document<'k, 'v: 'k, I>(out: &mut Vec<u8>, elements: I, pointer: &JsonPointer) -> Result<(), BsonEncodeError>
+where
+    I: Iterator<Item = (Cow<'k, str>, &'k JsonValue<'v>)>,
+{
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    for (key, value) in elements {
+        let mut child = pointer.clone();
+        child.push(&key);
+        element(out, &key, value, &child)?;
+    }
+    out.push(0);
+    match i32::try_from(out.len() - start) {
+        Ok(len) => out[start..start + 4].copy_from_slice(&len.to_le_bytes()),
+        Err(_) => {
+            return Err(BsonEncodeError {
+                msg: "BSON document larger than 2 GiB",
+                pointer: pointer.clone(),
+            })
+        }
+    }
+    Ok(())
+}
+
+fn element(out: &mut Vec<u8>, key: &str, value: &JsonValue, pointer: &JsonPointer) -> Result<(), BsonEncodeError> {
+    let error = |msg| BsonEncodeError {
+        msg,
+        pointer: pointer.clone(),
+    };
+    if key.contains('\0') {
+        return Err(error("Key containing a NUL byte"));
+    }
+    let kind = match value {
+        JsonValue::Null => NULL,
+        JsonValue::Boolean(_) => BOOLEAN,
+        JsonValue::Number(JsonNumber::Integer(n)) if i32::try_from(*n).is_ok() => INT32,
+        JsonValue::Number(JsonNumber::Integer(_)) => INT64,
+        JsonValue::Number(JsonNumber::Unsigned(n)) if i32::try_from(*n).is_ok() => INT32,
+        JsonValue::Number(JsonNumber::Unsigned(n)) if i64::try_from(*n).is_ok() => INT64,
+        JsonValue::Number(JsonNumber::Unsigned(_)) => return Err(error("Integer out of the BSON range")),
+        JsonValue::Number(JsonNumber::Float(_)) => DOUBLE,
+        JsonValue::String(_) => STRING,
+        JsonValue::Array(_) => ARRAY,
+        JsonValue::Object(_) => DOCUMENT,
+    };
+    out.push(kind);
+    out.extend_from_slice(key.as_bytes());
+    out.push(0);
+    match value {
+        JsonValue::Null => {}
+        JsonValue::Boolean(b) => out.push(u8::from(*b)),
+        JsonValue::Number(JsonNumber::Integer(n)) if kind == INT32 => out.extend_from_slice(&(*n as i32).to_le_bytes()),
+        JsonValue::Number(JsonNumber::Integer(n)) => out.extend_from_slice(&n.to_le_bytes()),
+        JsonValue::Number(JsonNumber::Unsigned(n)) if kind == INT32 => {
+            out.extend_from_slice(&(*n as i32).to_le_bytes())
+        }
+        JsonValue::Number(JsonNumber::Unsigned(n)) => out.extend_from_slice(&(*n as i64).to_le_bytes()),
+        JsonValue::Number(JsonNumber::Float(f)) => out.extend_from_slice(&f.to_le_bytes()),
+        JsonValue::String(s) => {
+            let len = i32::try_from(s.len() + 1).map_err(|_| error("BSON string larger than 2 GiB"))?;
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(s.as_bytes());
+            out.push(0);
+        }
+        JsonValue::Array(array) => {
+            let elements = array
+                .iter()
+                .enumerate()
+                .map(|(index, element)| (Cow::Owned(index.to_string()), element));
+            document(out, elements, pointer)?;
+        }
+        JsonValue::Object(obj) => {
+            document(
+                out,
+                obj.iter().map(|(key, member)| (Cow::Borrowed(&**key), member)),
+                pointer,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// The bytes are not a BSON document that can be represented as a value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BsonError {
+    pub msg: &'static str,
+    /// Offset of the offending byte.
+    pub offset: usize,
+}
+impl fmt::Display for BsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.msg, self.offset)
+    }
+}
+impl core::error::Error for BsonError {}
+
+/// Decodes a single BSON document, borrowing strings and keys from
+/// `bytes`.
+///
+/// Types without a JSON equivalent become strings in the style of MongoDB
+/// Extended JSON: an ObjectId its 24 hexadecimal digits, a date and time
+/// `2024-05-27T07:32:00.000Z` in UTC, a Decimal128 its digits such as
+/// `1.50` or `1E+3`, binary data its base64 encoding, and a regular
+/// expression `/pattern/options`. JavaScript code and symbols become their
+/// text, timestamps integers and `undefined` `null`. DBPointers and the
+/// min and max keys are rejected.
+pub fn from_document_bytes(bytes: &[u8]) -> Result<JsonValue<'_>, BsonError> {
+    let mut decoder = Decoder {
+        bytes,
+        offset: 0,
+        depth: 0,
+    };
+    let value = decoder.document(DOCUMENT)?;
+    if decoder.offset < bytes.len() {
+        return decoder.error("Trailing bytes after the document");
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error<T>(&self, msg: &'static str) -> Result<T, BsonError> {
+        Err(BsonError {
+            msg,
+            offset: self.offset,
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BsonError> {
+        match self.bytes.get(self.offset..self.offset.saturating_add(len)) {
+            Some(slice) if self.offset.checked_add(len).is_some() => {
+                self.offset += len;
+                Ok(slice)
+            }
+            _ => self.error("Unexpected end of input"),
+        }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], BsonError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// A length prefix, checked against the bytes left so that it cannot
+    /// trigger a huge allocation.
+    fn len(&mut self, min: usize) -> Result<usize, BsonError> {
+        let start = self.offset;
+        let len = i32::from_le_bytes(self.array()?);
+        match usize::try_from(len) {
+            Ok(len) if len >= min && len <= self.bytes.len() - start => Ok(len),
+            _ => {
+                self.offset = start;
+                self.error("Invalid length")
+            }
+        }
+    }
+
+    fn utf8(&mut self, bytes: &'a [u8], start: usize) -> Result<&'a str, BsonError> {
+        core::str::from_utf8(bytes).or_else(|e| {
+            self.offset = start + e.valid_up_to();
+            self.error("Invalid UTF-8 in string")
+        })
+    }
+
+    fn cstring(&mut self) -> Result<&'a str, BsonError> {
+        let start = self.offset;
+        match self.bytes[start..].iter().position(|&b| b == 0) {
+            Some(len) => {
+                self.offset += len + 1;
+                self.utf8(&self.bytes[start..start + len], start)
+            }
+            None => self.error("Unterminated string"),
+        }
+    }
+
+    fn string(&mut self) -> Result<&'a str, BsonError> {
+        let len = self.len(1)?;
+        let start = self.offset;
+        let bytes = self.take(len)?;
+        if bytes[len - 1] != 0 {
+            self.offset -= 1;
+            return self.error("Unterminated string");
+        }
+        self.utf8(&bytes[..len - 1], start)
+    }
+
+    /// A document, as an object or, for [`ARRAY`], as the array of its
+    /// values.
+    fn document(&mut self, kind: u8) -> Result<JsonValue<'a>, BsonError> {
+        if self.depth == ParseOptions::default().max_depth {
+            return self.error("Nesting depth exceeds the limit");
+        }
+        self.depth += 1;
+        let start = self.offset;
+        let end = start + self.len(5)?;
+        let mut obj = Map::new();
+        let mut array = Vec::new();
+        while self.offset + 1 < end {
+            let kind_offset = self.offset;
+            let element_kind = self.take(1)?[0];
+            let key = self.cstring()?;
+            let value = self.element(element_kind, kind_offset)?;
+            if kind == ARRAY {
+                array.push(value);
+            } else {
+                obj.insert(Cow::Borrowed(key), value);
+            }
+        }
+        if self.offset + 1 != end || self.bytes[self.offset] != 0 {
+            return self.error("Document does not end at its length");
+        }
+        self.offset = end;
+        self.depth -= 1;
+        Ok(if kind == ARRAY {
+            JsonValue::Array(array)
+        } else {
+            JsonValue::Object(obj)
+        })
+    }
+
+    fn element(&mut self, kind: u8, kind_offset: usize) -> Result<JsonValue<'a>, BsonError> {
+        let value = match kind {
+            DOUBLE => JsonValue::Number(JsonNumber::Float(f64::from_le_bytes(self.array()?))),
+            STRING | CODE | SYMBOL => JsonValue::String(Cow::Borrowed(self.string()?)),
+            DOCUMENT | ARRAY => self.document(kind)?,
+            BINARY => {
+                let len = self.len(0)?;
+                self.take(1)?;
+                JsonValue::String(Cow::Owned(base64(self.take(len)?)))
+            }
+            UNDEFINED | NULL => JsonValue::Null,
+            OBJECT_ID => JsonValue::String(Cow::Owned(
+                self.take(12)?.iter().map(|b| format!("{:02x}", b)).collect(),
+            )),
+            BOOLEAN => match self.take(1)?[0] {
+                0 => JsonValue::Boolean(false),
+                1 => JsonValue::Boolean(true),
+                _ => {
+                    self.offset -= 1;
+                    return self.error("Invalid boolean");
+                }
+            },
+            DATE_TIME => JsonValue::String(Cow::Owned(date_time(i64::from_le_bytes(self.array()?)))),
+            REGEX => {
+                let pattern = self.cstring()?;
+                let options = self.cstring()?;
+                JsonValue::String(Cow::Owned(format!("/{}/{}", pattern, options)))
+            }
+            CODE_WITH_SCOPE => {
+                let start = self.offset;
+                let end = start + self.len(14)?;
+                let code = self.string()?;
+                self.document(DOCUMENT)?;
+                if self.offset != end {
+                    self.offset = start;
+                    return self.error("Invalid length");
+                }
+                JsonValue::String(Cow::Borrowed(code))
+            }
+            INT32 => JsonValue::Number(JsonNumber::Integer(i64::from(i32::from_le_bytes(self.array()?)))),
+            TIMESTAMP => {
+                let n = u64::from_le_bytes(self.array()?);
+                JsonValue::Number(match i64::try_from(n) {
+                    Ok(n) => JsonNumber::Integer(n),
+                    Err(_) => JsonNumber::Unsigned(n),
+                })
+            }
+            INT64 => JsonValue::Number(JsonNumber::Integer(i64::from_le_bytes(self.array()?))),
+            DECIMAL128 => JsonValue::String(Cow::Owned(decimal128(u128::from_le_bytes(self.array()?)))),
+            _ => {
+                self.offset = kind_offset;
+                return self.error("Unsupported element type");
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// An IEEE 754 decimal128 in binary integer decimal encoding as text, in
+/// the format of the General Decimal Arithmetic specification.
+fn decimal128(bits: u128) -> String {
+    let sign = if bits >> 127 == 1 { "-" } else { "" };
+    let combination = (bits >> 122) & 0x1f;
+    let (exponent, coefficient) = match combination {
+        0x1e => return format!("{}Infinity", sign),
+        0x1f => return String::from("NaN"),
+        // A coefficient above 10^34 - 1, which is non-canonical.
+        _ if combination >> 3 == 0b11 => ((bits >> 111) & 0x3fff, 0),
+        _ => ((bits >> 113) & 0x3fff, bits & ((1 << 113) - 1)),
+    };
+    let exponent = exponent as i64 - 6176;
+    let coefficient = if coefficient >= 10u128.pow(34) { 0 } else { coefficient };
+    let digits = coefficient.to_string();
+    let adjusted = exponent + digits.len() as i64 - 1;
+    if exponent <= 0 && adjusted >= -6 {
+        let point = digits.len() as i64 + exponent;
+        if exponent == 0 {
+            format!("{}{}", sign, digits)
+        } else if point > 0 {
+            let (int, frac) = digits.split_at(point as usize);
+            format!("{}{}.{}", sign, int, frac)
+        } else {
+            format!("{}0.{}{}", sign, "0".repeat(-point as usize), digits)
+        }
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        format!("{}{}{}{}E{:+}", sign, first, point, rest, adjusted)
+    }
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        let hex: String = hex.split_whitespace().collect();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn spec_examples() {
+        let value = json!({"hello": "world"});
+        let bytes = to_document_bytes(&value).unwrap();
+        assert_eq!(hex(&bytes), "160000000268656c6c6f0006000000776f726c640000");
+        assert_eq!(from_document_bytes(&bytes), Ok(value));
+        let bytes = unhex("31000000 04 42534f4e00 26000000 02 3000 08000000 617765736f6d6500 01 3100 333333333333 1440 10 3200 c2070000 00 00");
+        assert_eq!(
+            from_document_bytes(&bytes),
+            Ok(json!({"BSON": ["awesome", 5.05, 1986]}))
+        );
+        assert_eq!(
+            to_document_bytes(&json!({"BSON": ["awesome", 5.05, 1986]})).unwrap(),
+            bytes
+        );
+        assert!(matches!(
+            from_document_bytes(&bytes).unwrap()["BSON"][0],
+            JsonValue::String(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = json!({
+            "a": [null, true, false, -2147483648, 2147483648u64, -9223372036854775808i64, 0.5, "é"],
+            "b": {"c": {}, "d": []},
+        });
+        assert_eq!(from_document_bytes(&to_document_bytes(&value).unwrap()), Ok(value));
+
+        let error = |value| to_document_bytes(&value).unwrap_err().to_string();
+        assert_eq!(error(json!([1])), "BSON document that is not an object at ''");
+        assert_eq!(
+            error(json!({"a": [18446744073709551615u64]})),
+            "Integer out of the BSON range at '/a/0'"
+        );
+        assert_eq!(
+            error(json!({"a": {"b\u{0}": 1}})),
+            "Key containing a NUL byte at '/a/b\u{0}'"
+        );
+    }
+
+    #[test]
+    fn extended_types() {
+        let element = |kind: u8, payload: &str| {
+            let mut bytes = vec![0, 0, 0, 0, kind, b'x', 0];
+            bytes.extend(unhex(payload));
+            bytes.push(0);
+            bytes[0] = bytes.len() as u8;
+            from_document_bytes(&bytes).map(|value| value["x"].clone().into_owned())
+        };
+        assert_eq!(
+            element(OBJECT_ID, "507f1f77bcf86cd799439011"),
+            Ok(json!("507f1f77bcf86cd799439011"))
+        );
+        assert_eq!(
+            element(DATE_TIME, "0000000000000000"),
+            Ok(json!("1970-01-01T00:00:00.000Z"))
+        );
+        // 2024-02-29T23:59:59.999Z
+        assert_eq!(
+            element(DATE_TIME, &hex(&1_709_251_199_999i64.to_le_bytes())),
+            Ok(json!("2024-02-29T23:59:59.999Z"))
+        );
+        assert_eq!(
+            element(DATE_TIME, &hex(&(-1i64).to_le_bytes())),
+            Ok(json!("1969-12-31T23:59:59.999Z"))
+        );
+        assert_eq!(element(BINARY, "05000000 00 68656c6c6f"), Ok(json!("aGVsbG8=")));
+        assert_eq!(element(BINARY, "00000000 04"), Ok(json!("")));
+        assert_eq!(element(REGEX, "5e612400 6900"), Ok(json!("/^a$/i")));
+        assert_eq!(element(UNDEFINED, ""), Ok(json!(null)));
+        assert_eq!(element(TIMESTAMP, "0100000002000000"), Ok(json!(8589934593u64)));
+        assert_eq!(element(SYMBOL, "02000000 6100"), Ok(json!("a")));
+        assert_eq!(
+            element(CODE_WITH_SCOPE, "16000000 02000000 6100 0c000000 10 7900 01000000 00"),
+            Ok(json!("a"))
+        );
+        let decimal = |bits: u128| element(DECIMAL128, &hex(&bits.to_le_bytes())).unwrap();
+        let bits = |sign: u128, exponent: i64, coefficient: u128| {
+            sign << 127 | ((exponent + 6176) as u128) << 113 | coefficient
+        };
+        assert_eq!(decimal(bits(0, 0, 0)), json!("0"));
+        assert_eq!(decimal(bits(0, -2, 150)), json!("1.50"));
+        assert_eq!(decimal(bits(1, -3, 5)), json!("-0.005"));
+        assert_eq!(decimal(bits(0, 3, 1)), json!("1E+3"));
+        assert_eq!(decimal(bits(0, -10, 12345)), json!("0.0000012345"));
+        assert_eq!(decimal(bits(0, -11, 12345)), json!("1.2345E-7"));
+        assert_eq!(decimal(0x7800 << 112), json!("Infinity"));
+        assert_eq!(decimal(0x7c00 << 112), json!("NaN"));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let error = |bytes: &str| from_document_bytes(&unhex(bytes)).unwrap_err();
+        assert_eq!(
+            error(""),
+            BsonError {
+                msg: "Unexpected end of input",
+                offset: 0
+            }
+        );
+        assert_eq!(error("04000000 00").msg, "Invalid length");
+        assert_eq!(error("05000000 01").msg, "Document does not end at its length");
+        assert_eq!(
+            error("05000000 0000"),
+            BsonError {
+                msg: "Trailing bytes after the document",
+                offset: 5
+            }
+        );
+        assert_eq!(
+            error("08000000 ff 7800 00"),
+            BsonError {
+                msg: "Unsupported element type",
+                offset: 4
+            }
+        );
+        assert_eq!(
+            error("09000000 08 7800 02 00"),
+            BsonError {
+                msg: "Invalid boolean",
+                offset: 7
+            }
+        );
+        assert_eq!(
+            error("0e000000 02 7800 02000000 ff00 00").msg,
+            "Invalid UTF-8 in string"
+        );
+        assert_eq!(error("0e000000 02 7800 02000000 6161 00").msg, "Unterminated string");
+        assert_eq!(
+            error("0a000000 10 7800 01000000").msg,
+            "Document does not end at its length"
+        );
+        let mut deep = vec![5, 0, 0, 0, 0];
+        for _ in 0..200 {
+            let mut outer = vec![0, 0, 0, 0, DOCUMENT, b'x', 0];
+            outer.append(&mut deep);
+            outer.push(0);
+            let len = outer.len() as i32;
+            outer[..4].copy_from_slice(&len.to_le_bytes());
+            deep = outer;
+        }
+        assert_eq!(
+            from_document_bytes(&deep).unwrap_err().msg,
+            "Nesting depth exceeds the limit"
+        );
+    }
+}
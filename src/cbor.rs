@@ -0,0 +1,434 @@
+//! Conversion between values and CBOR (RFC 8949), the binary encoding
+//! of the JSON data model used by constrained devices.
+//!
+//! ```
+//! use yaj::{cbor, json};
+//! let value = json!({"uid": 12, "tags": ["a", null]});
+//! let bytes = cbor::to_vec(&value);
+//! assert_eq!(cbor::from_slice(&bytes).unwrap(), value);
+//! ```
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::parser::ParseOptions;
+use crate::{JsonNumber, JsonValue, Map};
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+/// Additional information of an indefinite length, or of the "break" stop
+/// code with [`SIMPLE`].
+const INDEFINITE: u8 = 31;
+
+/// Encodes `value` with the shortest forms: integers and lengths use the
+/// fewest bytes, and floats are written in single precision when it is
+/// lossless. Object keys are text strings.
+pub fn to_vec(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode(&mut out, value);
+    out
+}
+
+fn head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u64::from(u8::MAX) {
+        out.extend_from_slice(&[major | 24, n as u8]);
+    } else if n <= u64::from(u16::MAX) {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u64::from(u32::MAX) {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn encode(out: &mut Vec<u8>, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push(0xf6),
+        JsonValue::Boolean(false) => out.push(0xf4),
+        JsonValue::Boolean(true) => out.push(0xf5),
+        JsonValue::Number(JsonNumber::Integer(n)) if *n < 0 => head(out, NEGATIVE, !*n as u64),
+        JsonValue::Number(JsonNumber::Integer(n)) => head(out, UNSIGNED, *n as u64),
+        JsonValue::Number(JsonNumber::Unsigned(n)) => head(out, UNSIGNED, *n),
+        JsonValue::Number(JsonNumber::Float(f)) => {
+            let single = *f as f32;
+            if f64::from(single) == *f || f.is_nan() {
+                out.push(0xfa);
+                out.extend_from_slice(&single.to_be_bytes());
+            } else {
+                out.push(0xfb);
+                out.extend_from_slice(&f.to_be_bytes());
+            }
+        }
+        JsonValue::String(s) => text(out, s),
+        JsonValue::Array(array) => {
+            head(out, ARRAY, array.len() as u64);
+            for item in array {
+                encode(out, item);
+            }
+        }
+        JsonValue::Object(obj) => {
+            head(out, MAP, obj.len() as u64);
+            for (key, item) in obj {
+                text(out, key);
+                encode(out, item);
+            }
+        }
+    }
+}
+
+fn text(out: &mut Vec<u8>, s: &str) {
+    head(out, TEXT, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// The bytes are not a CBOR item that can be represented as a value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CborError {
+    pub msg: &'static str,
+    /// Offset of the offending byte.
+    pub offset: usize,
+}
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.msg, self.offset)
+    }
+}
+impl core::error::Error for CborError {}
+
+/// Decodes a single CBOR item, borrowing definite-length text strings from
+/// `bytes`.
+///
+/// Tags are ignored and `undefined` becomes `null`. Byte strings, simple
+/// values other than booleans, `null` and `undefined`, and maps with keys
+/// that are not text strings are rejected. Negative integers below
+/// `i64::MIN` become floats.
+pub fn from_slice(bytes: &[u8]) -> Result<JsonValue<'_>, CborError> {
+    let mut decoder = Decoder {
+        bytes,
+        offset: 0,
+        depth: 0,
+    };
+    let value = decoder.item()?;
+    if decoder.offset < bytes.len() {
+        return decoder.error("Trailing bytes after the item");
+    }
+    Ok(value)
+}
+
+/// Initial byte of an item: its major type, and its argument unless the
+/// length is indefinite.
+struct Head {
+    major: u8,
+    info: u8,
+    argument: u64,
+    offset: usize,
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error<T>(&self, msg: &'static str) -> Result<T, CborError> {
+        Err(CborError {
+            msg,
+            offset: self.offset,
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CborError> {
+        match self.bytes.get(self.offset..self.offset.saturating_add(len)) {
+            Some(slice) if self.offset.checked_add(len).is_some() => {
+                self.offset += len;
+                Ok(slice)
+            }
+            _ => self.error("Unexpected end of input"),
+        }
+    }
+
+    fn head(&mut self) -> Result<Head, CborError> {
+        let offset = self.offset;
+        let initial = self.take(1)?[0];
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        let argument = match info {
+            0..=23 => u64::from(info),
+            24 => u64::from(self.take(1)?[0]),
+            25 => {
+                let mut be = [0; 2];
+                be.copy_from_slice(self.take(2)?);
+                u64::from(u16::from_be_bytes(be))
+            }
+            26 => {
+                let mut be = [0; 4];
+                be.copy_from_slice(self.take(4)?);
+                u64::from(u32::from_be_bytes(be))
+            }
+            27 => {
+                let mut be = [0; 8];
+                be.copy_from_slice(self.take(8)?);
+                u64::from_be_bytes(be)
+            }
+            INDEFINITE if matches!(major, BYTES | TEXT | ARRAY | MAP | SIMPLE) => 0,
+            _ => {
+                self.offset = offset;
+                return self.error("Invalid additional information");
+            }
+        };
+        Ok(Head {
+            major,
+            info,
+            argument,
+            offset,
+        })
+    }
+
+    /// Length of a definite string, array or map, checked against the
+    /// bytes left so that it cannot trigger a huge allocation.
+    fn len(&self, head: &Head) -> Result<usize, CborError> {
+        match usize::try_from(head.argument) {
+            Ok(len) if len <= self.bytes.len() - self.offset => Ok(len),
+            _ => self.error("Length exceeds the input"),
+        }
+    }
+
+    /// Whether the next byte is the "break" stop code, which is consumed.
+    fn at_break(&mut self) -> Result<bool, CborError> {
+        match self.bytes.get(self.offset) {
+            Some(0xff) => {
+                self.offset += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => self.error("Unexpected end of input"),
+        }
+    }
+
+    fn item(&mut self) -> Result<JsonValue<'a>, CborError> {
+        let mut head = self.head()?;
+        // Tags are ignored, their content is the value. Chained ones are
+        // skipped here rather than by recursing.
+        while head.major == TAG {
+            head = self.head()?;
+        }
+        let value = match head.major {
+            UNSIGNED => JsonValue::Number(match i64::try_from(head.argument) {
+                Ok(n) => JsonNumber::Integer(n),
+                Err(_) => JsonNumber::Unsigned(head.argument),
+            }),
+            NEGATIVE => JsonValue::Number(match i64::try_from(head.argument) {
+                Ok(n) => JsonNumber::Integer(-1 - n),
+                Err(_) => JsonNumber::Float(-1.0 - head.argument as f64),
+            }),
+            BYTES => {
+                self.offset = head.offset;
+                return self.error("Byte strings are not supported");
+            }
+            TEXT => JsonValue::String(self.text(&head)?),
+            ARRAY | MAP => {
+                if self.depth == ParseOptions::default().max_depth {
+                    self.offset = head.offset;
+                    return self.error("Nesting depth exceeds the limit");
+                }
+                self.depth += 1;
+                let value = if head.major == ARRAY {
+                    self.array(&head)?
+                } else {
+                    self.map(&head)?
+                };
+                self.depth -= 1;
+                value
+            }
+            _ => match head.info {
+                20 => JsonValue::Boolean(false),
+                21 => JsonValue::Boolean(true),
+                22 | 23 => JsonValue::Null,
+                25 => JsonValue::Number(JsonNumber::Float(half_to_f64(head.argument as u16))),
+                26 => JsonValue::Number(JsonNumber::Float(f64::from(f32::from_bits(head.argument as u32)))),
+                27 => JsonValue::Number(JsonNumber::Float(f64::from_bits(head.argument))),
+                _ => {
+                    self.offset = head.offset;
+                    return self.error("Unsupported simple value");
+                }
+            },
+        };
+        Ok(value)
+    }
+
+    fn text(&mut self, head: &Head) -> Result<Cow<'a, str>, CborError> {
+        if head.info != INDEFINITE {
+            let len = self.len(head)?;
+            let start = self.offset;
+            return match core::str::from_utf8(self.take(len)?) {
+                Ok(s) => Ok(Cow::Borrowed(s)),
+                Err(e) => {
+                    self.offset = start + e.valid_up_to();
+                    self.error("Invalid UTF-8 in text string")
+                }
+            };
+        }
+        let mut out = String::new();
+        while !self.at_break()? {
+            let chunk = self.head()?;
+            if chunk.major != TEXT || chunk.info == INDEFINITE {
+                self.offset = chunk.offset;
+                return self.error("Expected a definite text string chunk");
+            }
+            out.push_str(&self.text(&chunk)?);
+        }
+        Ok(Cow::Owned(out))
+    }
+
+    fn array(&mut self, head: &Head) -> Result<JsonValue<'a>, CborError> {
+        let mut array = Vec::new();
+        if head.info == INDEFINITE {
+            while !self.at_break()? {
+                array.push(self.item()?);
+            }
+        } else {
+            let len = self.len(head)?;
+            array.reserve(len);
+            for _ in 0..len {
+                array.push(self.item()?);
+            }
+        }
+        Ok(JsonValue::Array(array))
+    }
+
+    fn map(&mut self, head: &Head) -> Result<JsonValue<'a>, CborError> {
+        let mut obj = Map::new();
+        let mut remaining = if head.info == INDEFINITE { None } else { Some(self.len(head)?) };
+        loop {
+            match &mut remaining {
+                Some(0) => break,
+                Some(n) => *n -= 1,
+                None if self.at_break()? => break,
+                None => {}
+            }
+            let key = self.head()?;
+            if key.major != TEXT {
+                self.offset = key.offset;
+                return self.error("Map keys must be text strings");
+            }
+            let key = self.text(&key)?;
+            let value = self.item()?;
+            obj.insert(key, value);
+        }
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+/// Converts an IEEE 754 half-precision float.
+fn half_to_f64(half: u16) -> f64 {
+    let exponent = i32::from((half >> 10) & 0x1f);
+    let mantissa = f64::from(half & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa / f64::from(1 << 24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mantissa + 1024.0) * f64::from(1 << exponent) / f64::from(1 << 25),
+    };
+    if half & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rfc_8949_examples() {
+        let cases = [
+            ("0", "00"),
+            ("23", "17"),
+            ("24", "1818"),
+            ("1000", "1903e8"),
+            ("1000000000000", "1b000000e8d4a51000"),
+            ("18446744073709551615", "1bffffffffffffffff"),
+            ("-1", "20"),
+            ("-1000", "3903e7"),
+            ("-9223372036854775808", "3b7fffffffffffffff"),
+            ("1.5", "fa3fc00000"),
+            ("100000.0", "fa47c35000"),
+            ("1.1", "fb3ff199999999999a"),
+            ("-4.1", "fbc010666666666666"),
+            ("false", "f4"),
+            ("null", "f6"),
+            (r#""""#, "60"),
+            (r#""ü""#, "62c3bc"),
+            ("[1, [2, 3], [4, 5]]", "8301820203820405"),
+            (r#"{"a": 1}"#, "a1616101"),
+        ];
+        for (json, cbor) in cases.iter() {
+            let value = parse(json);
+            assert_eq!(hex(&to_vec(&value)), *cbor, "{}", json);
+            assert_eq!(from_slice(&unhex(cbor)), Ok(value), "{}", cbor);
+        }
+    }
+
+    #[test]
+    fn decodes_other_forms() {
+        let decode = |cbor| from_slice(&unhex(cbor)).map(|value| value.into_owned());
+        assert_eq!(decode("f93c00"), Ok(json!(1.0)));
+        assert_eq!(decode("f9c400"), Ok(json!(-4.0)));
+        assert_eq!(decode("f90001"), Ok(json!(5.960464477539063e-8)));
+        assert!(matches!(decode("f97c00"), Ok(JsonValue::Number(JsonNumber::Float(f))) if f == f64::INFINITY));
+        assert_eq!(decode("f7"), Ok(json!(null)));
+        assert_eq!(decode("3bffffffffffffffff"), Ok(json!(-18446744073709551616.0)));
+        assert_eq!(decode("c11a514b67b0"), Ok(json!(1363896240)));
+        assert_eq!(decode("7f657374726561646d696e67ff"), Ok(json!("streaming")));
+        assert_eq!(decode("9f018202039f0405ffff"), Ok(json!([1, [2, 3], [4, 5]])));
+        assert_eq!(decode("bf61610161629f0203ffff"), Ok(json!({"a": 1, "b": [2, 3]})));
+        assert!(matches!(from_slice(&unhex("6161")), Ok(JsonValue::String(Cow::Borrowed("a")))));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let error = |cbor| from_slice(&unhex(cbor)).unwrap_err();
+        assert_eq!(error(""), CborError { msg: "Unexpected end of input", offset: 0 });
+        assert_eq!(error("4161").msg, "Byte strings are not supported");
+        assert_eq!(error("a10101").msg, "Map keys must be text strings");
+        assert_eq!(error("62c328"), CborError { msg: "Invalid UTF-8 in text string", offset: 1 });
+        assert_eq!(error("9bffffffffffffffff").msg, "Length exceeds the input");
+        assert_eq!(error("1c").msg, "Invalid additional information");
+        assert_eq!(error("0000").msg, "Trailing bytes after the item");
+        assert_eq!(error("f0").msg, "Unsupported simple value");
+        assert_eq!(error("19ff"), CborError { msg: "Unexpected end of input", offset: 1 });
+        assert_eq!(from_slice(&vec![0x81; 1000]).unwrap_err().msg, "Nesting depth exceeds the limit");
+        let mut tags = vec![0xc6; 100_000];
+        tags.push(0x01);
+        assert_eq!(from_slice(&tags), Ok(json!(1)));
+        assert_eq!(from_slice(&[0xc6; 3]).unwrap_err().msg, "Unexpected end of input");
+    }
+}
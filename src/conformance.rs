@@ -0,0 +1,144 @@
+//! Classification of inputs against RFC 8259, the way the JSONTestSuite
+//! sorts its test files: documents parsers must accept (`y_`), inputs
+//! they must reject (`n_`), and inputs the RFC leaves to them (`i_`).
+//!
+//! ```
+//! use yaj::conformance::{check, Verdict};
+//! assert_eq!(check(br#"{"a": [1, 2.5e3]}"#), Verdict::Accept);
+//! assert_eq!(check(b"[1,]"), Verdict::Reject);
+//! assert_eq!(check(b"[1e999]"), Verdict::ImplementationDefined { accepted: true });
+//! ```
+
+use alloc::borrow::Cow;
+
+use crate::encoding::decode;
+use crate::parser::{LoneSurrogates, ParseOptions};
+use crate::{from_slice, try_lex, try_parse_with, Encoding, JsonNumber, JsonReader, JsonTokenType};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    /// A valid document, accepted by [`from_slice`].
+    Accept,
+    /// An input RFC 8259 does not allow, rejected by [`from_slice`].
+    Reject,
+    /// An input whose handling RFC 8259 leaves to the parser: a number
+    /// beyond the range of doubles or of 64-bit integers, an escaped lone
+    /// surrogate, a byte order mark, text not in UTF-8, be it in another
+    /// encoding or invalid bytes in a string, or arrays and objects nested
+    /// deeper than [`ParseOptions::max_depth`].
+    ImplementationDefined {
+        /// Whether [`from_slice`] accepts the input.
+        accepted: bool,
+    },
+}
+
+/// Classifies `source` by parsing it with the default options: accepted
+/// inputs that do not rely on what the RFC leaves open are
+/// [`Verdict::Accept`], rejected inputs that are invalid whatever the
+/// parser decides about it are [`Verdict::Reject`].
+pub fn check(source: &[u8]) -> Verdict {
+    match from_slice(source) {
+        Ok(_) if strictly_valid(source) => Verdict::Accept,
+        Ok(_) => Verdict::ImplementationDefined { accepted: true },
+        Err(_) if leniently_valid(source) => Verdict::ImplementationDefined { accepted: false },
+        Err(_) => Verdict::Reject,
+    }
+}
+
+/// Whether `source` is an UTF-8 document without byte order mark, lone
+/// surrogates or numbers out of range, given that it parses.
+fn strictly_valid(source: &[u8]) -> bool {
+    if Encoding::detect(source) != Encoding::Utf8 || source.starts_with(b"\xEF\xBB\xBF") {
+        return false;
+    }
+    let text = match core::str::from_utf8(source) {
+        Ok(text) => text,
+        Err(_) => return false,
+    };
+    let options = ParseOptions {
+        lone_surrogates: LoneSurrogates::Error,
+        ..ParseOptions::default()
+    };
+    try_parse_with(text, options).is_ok()
+        && try_lex(text).is_ok_and(|tokens| {
+            tokens
+                .iter()
+                .filter(|token| token.token_type == JsonTokenType::Number)
+                .all(|token| in_range(token.slice))
+        })
+}
+
+/// Whether the number written `slice` is held without overflow, underflow
+/// or, for integers, conversion to a double.
+fn in_range(slice: &str) -> bool {
+    match JsonNumber::parse(slice) {
+        JsonNumber::Float(f) if !f.is_finite() => false,
+        JsonNumber::Float(_) if !slice.contains(['.', 'e', 'E']) => false,
+        JsonNumber::Float(0.0) => {
+            let mantissa = slice.split(['e', 'E']).next().unwrap_or_default();
+            mantissa.bytes().all(|b| matches!(b, b'-' | b'0' | b'.'))
+        }
+        _ => true,
+    }
+}
+
+/// Whether `source` is a well-formed document once invalid bytes are
+/// replaced, whatever its encoding and depth.
+fn leniently_valid(source: &[u8]) -> bool {
+    let text = decode(source, Encoding::detect(source), true).unwrap_or(Cow::Borrowed(""));
+    // The reader keeps a stack of containers instead of recursing, so it
+    // needs no depth limit.
+    let options = ParseOptions {
+        max_depth: usize::MAX,
+        ..ParseOptions::default()
+    };
+    JsonReader::with_options(&text, options).all(|event| event.is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdicts() {
+        let accepted = ["[]", "-0", r#"{"a": "𝄞", "a": 1e-5}"#, "[18446744073709551615]"];
+        for source in accepted.iter() {
+            assert_eq!(check(source.as_bytes()), Verdict::Accept, "{}", source);
+        }
+        let rejected: &[&[u8]] = &[
+            b"",
+            b"[1,]",
+            b"\xEF\xBB\xBF",
+            b"[123\xE5]",
+            b"\xEF\xBB{}",
+            br#"["\uD834\uDd"]"#,
+            &[b'['; 1000],
+            b"[\x0c]",
+            b"[1\x0c]",
+            "[\u{a0}1]".as_bytes(),
+            "[1\u{2028}]".as_bytes(),
+        ];
+        for source in rejected {
+            assert_eq!(check(source), Verdict::Reject, "{:?}", source);
+        }
+        let deep = [&[b'['; 500][..], &[b']'; 500][..]].concat();
+        let implementation_defined: &[(&[u8], bool)] = &[
+            (b"[1.5e+9999]", true),
+            (b"[123e-10000000]", true),
+            (b"[-123123123123123123123123123123]", true),
+            (br#"["\uDADA"]"#, true),
+            (b"\xEF\xBB\xBF{}", true),
+            (b"\xFF\xFE[\x00]\x00", true),
+            (b"[\"\xE9\"]", false),
+            (&deep, false),
+        ];
+        for &(source, accepted) in implementation_defined {
+            assert_eq!(
+                check(source),
+                Verdict::ImplementationDefined { accepted },
+                "{:?}",
+                source
+            );
+        }
+    }
+}
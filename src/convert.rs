@@ -0,0 +1,482 @@
+//! Conversion of Rust types to and from values without serde, see
+//! [`ToJson`] and [`FromJson`].
+//!
+//! The standard types reading with [`FromJson`] also implement
+//! `TryFrom<&JsonValue>`, so that extraction composes with `?`:
+//!
+//! ```
+//! use core::convert::TryInto;
+//!
+//! let v = yaj::json!({"portNo": 8080, "tags": ["door"]});
+//! let port: u16 = (&v["portNo"]).try_into()?;
+//! let tags: Vec<String> = (&v["tags"]).try_into()?;
+//! assert_eq!((port, tags.len()), (8080, 1));
+//! # Ok::<(), yaj::FromJsonError>(())
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use core::convert::TryFrom;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::hash::BuildHasher;
+
+use crate::pointer::push_token;
+use crate::prelude::*;
+use crate::{JsonNumber, JsonValue, JsonValueOwned, Map};
+
+/// A value does not have the shape a [`FromJson`] type expects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FromJsonError {
+    pub msg: String,
+    /// JSON Pointer of the offending value.
+    pub path: String,
+}
+impl FromJsonError {
+    pub fn new<M: Into<String>>(msg: M) -> Self {
+        Self {
+            msg: msg.into(),
+            path: String::new(),
+        }
+    }
+
+    /// `expected` was expected in place of `found`, e.g. `"a string"`.
+    pub fn unexpected(expected: &str, found: &JsonValue<'_>) -> Self {
+        let found = match found {
+            JsonValue::Null => "null",
+            JsonValue::Boolean(_) => "a boolean",
+            JsonValue::Number(_) => "a number",
+            JsonValue::String(_) => "a string",
+            JsonValue::Array(_) => "an array",
+            JsonValue::Object(_) => "an object",
+        };
+        Self::new(format!("Expected {}, found {}", expected, found))
+    }
+
+    /// Locates an error found in the member `key` of an object.
+    pub fn in_member(mut self, key: &str) -> Self {
+        let mut path = String::new();
+        push_token(&mut path, key);
+        self.path.insert_str(0, &path);
+        self
+    }
+
+    /// Locates an error found in the element `index` of an array.
+    pub fn at_index(self, index: usize) -> Self {
+        self.in_member(&index.to_string())
+    }
+}
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "(root)" } else { &self.path };
+        write!(f, "{} at {}", self.msg, path)
+    }
+}
+impl core::error::Error for FromJsonError {}
+
+/// Types that can be written as a value, see also the `ToJson` derive macro
+/// of the `derive` feature.
+pub trait ToJson {
+    fn to_json(&self) -> JsonValueOwned;
+}
+
+/// Types that can be read from a value, see also the `FromJson` derive
+/// macro of the `derive` feature.
+pub trait FromJson: Sized {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError>;
+
+    /// The value of a missing object member, an error by default. `Option`
+    /// gives `None`.
+    fn from_missing() -> Result<Self, FromJsonError> {
+        Err(FromJsonError::new("Missing member"))
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> JsonValueOwned {
+        (**self).to_json()
+    }
+}
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> JsonValueOwned {
+        (**self).to_json()
+    }
+}
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        T::from_json(value).map(Box::new)
+    }
+}
+
+impl ToJson for JsonValue<'_> {
+    fn to_json(&self) -> JsonValueOwned {
+        self.to_owned()
+    }
+}
+impl FromJson for JsonValueOwned {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        Ok(value.to_owned())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> JsonValueOwned {
+        JsonValue::Boolean(*self)
+    }
+}
+impl FromJson for bool {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        value.as_bool().ok_or_else(|| FromJsonError::unexpected("a boolean", value))
+    }
+}
+
+macro_rules! signed {
+    ($($ty:ty),*) => {$(
+        impl ToJson for $ty {
+            fn to_json(&self) -> JsonValueOwned {
+                JsonValue::Number(JsonNumber::Integer(*self as i64))
+            }
+        }
+        impl FromJson for $ty {
+            fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+                match value.as_i64() {
+                    Some(n) => <$ty>::try_from(n).map_err(|_| {
+                        FromJsonError::new(format!("{} is out of the range of {}", n, stringify!($ty)))
+                    }),
+                    None if value.is_u64() => Err(FromJsonError::new(format!(
+                        "{} is out of the range of {}",
+                        value.as_u64().unwrap_or_default(),
+                        stringify!($ty)
+                    ))),
+                    None => Err(FromJsonError::unexpected("an integer", value)),
+                }
+            }
+        }
+    )*};
+}
+signed!(i8, i16, i32, i64, isize);
+
+macro_rules! unsigned {
+    ($($ty:ty),*) => {$(
+        impl ToJson for $ty {
+            fn to_json(&self) -> JsonValueOwned {
+                let n = *self as u64;
+                JsonValue::Number(match i64::try_from(n) {
+                    Ok(n) => JsonNumber::Integer(n),
+                    Err(_) => JsonNumber::Unsigned(n),
+                })
+            }
+        }
+        impl FromJson for $ty {
+            fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+                match (value.as_u64(), value.as_i64()) {
+                    (Some(n), _) => <$ty>::try_from(n).map_err(|_| {
+                        FromJsonError::new(format!("{} is out of the range of {}", n, stringify!($ty)))
+                    }),
+                    (None, Some(n)) => {
+                        Err(FromJsonError::new(format!("{} is out of the range of {}", n, stringify!($ty))))
+                    }
+                    (None, None) => Err(FromJsonError::unexpected("an integer", value)),
+                }
+            }
+        }
+    )*};
+}
+unsigned!(u8, u16, u32, u64, usize);
+
+impl ToJson for f64 {
+    fn to_json(&self) -> JsonValueOwned {
+        JsonValue::Number(JsonNumber::Float(*self))
+    }
+}
+impl FromJson for f64 {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        value.as_f64().ok_or_else(|| FromJsonError::unexpected("a number", value))
+    }
+}
+impl ToJson for f32 {
+    fn to_json(&self) -> JsonValueOwned {
+        JsonValue::Number(JsonNumber::Float(f64::from(*self)))
+    }
+}
+impl FromJson for f32 {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        f64::from_json(value).map(|f| f as f32)
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> JsonValueOwned {
+        JsonValue::String(Cow::Owned(self.to_string()))
+    }
+}
+impl ToJson for String {
+    fn to_json(&self) -> JsonValueOwned {
+        self.as_str().to_json()
+    }
+}
+impl FromJson for String {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        value
+            .as_str()
+            .map(ToString::to_string)
+            .ok_or_else(|| FromJsonError::unexpected("a string", value))
+    }
+}
+
+/// `None` is written as `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> JsonValueOwned {
+        self.as_ref().map_or(JsonValue::Null, T::to_json)
+    }
+}
+/// `null` and missing members are read as `None`.
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+
+    fn from_missing() -> Result<Self, FromJsonError> {
+        Ok(None)
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> JsonValueOwned {
+        JsonValue::Array(self.iter().map(T::to_json).collect())
+    }
+}
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> JsonValueOwned {
+        self.as_slice().to_json()
+    }
+}
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        let array = value.as_array().ok_or_else(|| FromJsonError::unexpected("an array", value))?;
+        array
+            .iter()
+            .enumerate()
+            .map(|(index, element)| T::from_json(element).map_err(|e| e.at_index(index)))
+            .collect()
+    }
+}
+
+/// Reads the members of an object.
+fn members<'v, 'a, T: FromJson>(
+    value: &'v JsonValue<'a>,
+) -> Result<impl Iterator<Item = Result<(String, T), FromJsonError>> + 'v, FromJsonError> {
+    let object = value.as_object().ok_or_else(|| FromJsonError::unexpected("an object", value))?;
+    Ok(object.iter().map(|(key, member)| {
+        let member = T::from_json(member).map_err(|e| e.in_member(key))?;
+        Ok((key.to_string(), member))
+    }))
+}
+
+fn object_to_json<'m, T: ToJson + 'm>(members: impl Iterator<Item = (&'m String, &'m T)>) -> JsonValueOwned {
+    let mut object = Map::new();
+    for (key, member) in members {
+        object.insert(Cow::Owned(key.clone()), member.to_json());
+    }
+    JsonValue::Object(object)
+}
+
+impl<T: ToJson> ToJson for BTreeMap<String, T> {
+    fn to_json(&self) -> JsonValueOwned {
+        object_to_json(self.iter())
+    }
+}
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        members(value)?.collect()
+    }
+}
+#[cfg(feature = "std")]
+impl<T: ToJson, S> ToJson for HashMap<String, T, S> {
+    fn to_json(&self) -> JsonValueOwned {
+        object_to_json(self.iter())
+    }
+}
+#[cfg(feature = "std")]
+impl<T: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, T, S> {
+    fn from_json(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        members(value)?.collect()
+    }
+}
+
+macro_rules! try_from {
+    ($($ty:ty),*) => {$(
+        impl TryFrom<&JsonValue<'_>> for $ty {
+            type Error = FromJsonError;
+
+            fn try_from(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+                FromJson::from_json(value)
+            }
+        }
+    )*};
+}
+try_from!(bool, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64, String);
+
+impl<T: FromJson> TryFrom<&JsonValue<'_>> for Option<T> {
+    type Error = FromJsonError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        FromJson::from_json(value)
+    }
+}
+impl<T: FromJson> TryFrom<&JsonValue<'_>> for Vec<T> {
+    type Error = FromJsonError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        FromJson::from_json(value)
+    }
+}
+impl<T: FromJson> TryFrom<&JsonValue<'_>> for BTreeMap<String, T> {
+    type Error = FromJsonError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        FromJson::from_json(value)
+    }
+}
+#[cfg(feature = "std")]
+impl<T: FromJson, S: BuildHasher + Default> TryFrom<&JsonValue<'_>> for HashMap<String, T, S> {
+    type Error = FromJsonError;
+
+    fn try_from(value: &JsonValue<'_>) -> Result<Self, FromJsonError> {
+        FromJson::from_json(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_and_collections() {
+        let value = json!({"port": 8080, "ratio": 0.5, "tags": ["a", "b"], "on": true, "none": null});
+        assert_eq!(u16::from_json(&value["port"]), Ok(8080));
+        assert_eq!(f32::from_json(&value["ratio"]), Ok(0.5));
+        assert_eq!(Vec::<String>::from_json(&value["tags"]).unwrap(), ["a", "b"]);
+        assert_eq!(Option::<bool>::from_json(&value["none"]), Ok(None));
+        assert_eq!(Option::<bool>::from_json(&value["on"]), Ok(Some(true)));
+        let map = HashMap::<String, JsonValueOwned>::from_json(&value).unwrap();
+        assert_eq!(map.to_json(), value);
+        assert_eq!(u64::MAX.to_json(), JsonValue::Number(JsonNumber::Unsigned(u64::MAX)));
+        assert_eq!(vec![Some(1u8), None].to_json(), json!([1, null]));
+
+        let error = u8::from_json(&value["port"]).unwrap_err();
+        assert_eq!(error.to_string(), "8080 is out of the range of u8 at (root)");
+        let error = Vec::<i32>::from_json(&json!([1, "2"])).unwrap_err();
+        assert_eq!(error.to_string(), "Expected an integer, found a string at /1");
+        let error = BTreeMap::<String, Vec<bool>>::from_json(&json!({"a/b": [null]})).unwrap_err();
+        assert_eq!(error.path, "/a~1b/0");
+        assert!(i64::from_json(&JsonValue::Number(JsonNumber::Unsigned(u64::MAX))).is_err());
+        assert!(u32::from_json(&json!(-1)).is_err());
+    }
+
+    #[test]
+    fn try_from_values() {
+        use core::convert::TryInto;
+
+        let value = json!({"portNo": 8080, "name": "cam", "on": false, "limits": {"a": 1.5}, "ids": [1, 2]});
+        let port: u16 = (&value["portNo"]).try_into().unwrap();
+        let name: String = (&value["name"]).try_into().unwrap();
+        let on: Option<bool> = (&value["on"]).try_into().unwrap();
+        let missing: Option<i64> = (&value["missing"]).try_into().unwrap();
+        let limits: HashMap<String, f64> = (&value["limits"]).try_into().unwrap();
+        let ids = Vec::<u64>::try_from(&value["ids"]).unwrap();
+        assert_eq!((port, name.as_str(), on, missing, limits["a"], ids), (8080, "cam", Some(false), None, 1.5, vec![1, 2]));
+
+        let error = i8::try_from(&value["portNo"]).unwrap_err();
+        assert_eq!(error.msg, "8080 is out of the range of i8");
+        let error = BTreeMap::<String, String>::try_from(&value["limits"]).unwrap_err();
+        assert_eq!(error.to_string(), "Expected a string, found a number at /a");
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use crate::{FromJson, JsonValue, ToJson};
+
+        #[derive(Debug, PartialEq, ToJson, FromJson)]
+        #[json(rename_all = "camelCase")]
+        struct Camera {
+            port_no: u16,
+            #[json(rename = "IP")]
+            ip_address: String,
+            tags: Vec<String>,
+            label: Option<String>,
+            #[json(default)]
+            retries: u8,
+            #[json(default = "default_mode")]
+            mode: Mode,
+        }
+
+        fn default_mode() -> Mode {
+            Mode::Auto
+        }
+
+        #[derive(Debug, PartialEq, ToJson, FromJson)]
+        #[json(rename_all = "snake_case")]
+        enum Mode {
+            Auto,
+            NightVision,
+            Fixed(u32),
+            Zoom { level: f64 },
+            Pair(bool, Wrapper<i8>),
+        }
+
+        #[derive(Debug, PartialEq, ToJson, FromJson)]
+        struct Wrapper<T>(T);
+
+        #[derive(Debug, PartialEq, ToJson, FromJson)]
+        struct Unit;
+
+        #[test]
+        fn round_trips() {
+            let camera = Camera {
+                port_no: 8080,
+                ip_address: "10.0.0.1".into(),
+                tags: vec!["door".into()],
+                label: None,
+                retries: 3,
+                mode: Mode::Zoom { level: 2.5 },
+            };
+            let value = camera.to_json();
+            assert_eq!(
+                value,
+                json!({"portNo": 8080, "IP": "10.0.0.1", "tags": ["door"], "label": null, "retries": 3,
+                       "mode": {"zoom": {"level": 2.5}}})
+            );
+            assert_eq!(Camera::from_json(&value), Ok(camera));
+
+            for mode in [Mode::Auto, Mode::NightVision, Mode::Fixed(4), Mode::Pair(true, Wrapper(-1))] {
+                assert_eq!(Mode::from_json(&mode.to_json()), Ok(mode));
+            }
+            assert_eq!(Mode::NightVision.to_json(), json!("night_vision"));
+            assert_eq!(Mode::Pair(false, Wrapper(2)).to_json(), json!({"pair": [false, 2]}));
+            assert_eq!(Unit.to_json(), JsonValue::Null);
+            assert_eq!(Unit::from_json(&JsonValue::Null), Ok(Unit));
+        }
+
+        #[test]
+        fn missing_members_and_errors() {
+            let camera = Camera::from_json(&json!({"portNo": 1, "IP": "x", "tags": []})).unwrap();
+            assert_eq!((camera.label, camera.retries, camera.mode), (None, 0, Mode::Auto));
+
+            let error = Camera::from_json(&json!({"portNo": 1, "tags": []})).unwrap_err();
+            assert_eq!(error.to_string(), "Missing member at /IP");
+            let error = Camera::from_json(&json!({"portNo": 1, "IP": "x", "tags": [], "mode": {"zoom": {}}}));
+            assert_eq!(error.unwrap_err().path, "/mode/zoom/level");
+            let error = Mode::from_json(&json!({"pair": [true]})).unwrap_err();
+            assert_eq!(error.to_string(), "Expected an array of 2 elements, found an array at /pair");
+            let error = Mode::from_json(&json!("manual")).unwrap_err();
+            assert_eq!(error.msg, "Unknown variant `manual`");
+            assert!(Mode::from_json(&json!({"auto": null, "fixed": 1})).is_err());
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! Concrete syntax trees, which keep all of the text of a document: the
+//! whitespace and comments around each token, the trivia, and the
+//! original spelling of strings and numbers. Writing a tree with `Display`
+//! gives back its source byte for byte, so that formatters can rewrite the
+//! trivia they care about and leave the rest of the layout untouched.
+//!
+//! ```
+//! let source = "{\n  // Port of the camera\n  \"portNo\": 0x1F90,\n}\n";
+//! let mut cst = yaj::Parser::new().json5(true).parse_cst(source).unwrap();
+//! assert_eq!(cst.to_string(), source);
+//!
+//! if let yaj::cst::CstNode::Object(object) = &mut cst.value.node {
+//!     object.members[0].value.leading = "  ";
+//! }
+//! assert!(cst.to_string().contains("\"portNo\":  0x1F90"));
+//! assert_eq!(cst.value.to_value(), yaj::json!({"portNo": 8080}));
+//! ```
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::parser::{expected, scalar, string_content, DuplicateKeys, Parser, TokenParser};
+use crate::prelude::*;
+use crate::{unescape, JsonError, JsonToken, JsonTokenType, JsonValue, LexError, Map};
+
+/// A whole document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cst<'a> {
+    pub value: CstValue<'a>,
+    /// Whitespace and comments after the value.
+    pub trailing: &'a str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstValue<'a> {
+    /// Whitespace and comments before the value.
+    pub leading: &'a str,
+    pub node: CstNode<'a>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CstNode<'a> {
+    /// A string, number or literal, spelled as in the source.
+    Scalar(JsonToken<'a>),
+    Array(CstArray<'a>),
+    Object(CstObject<'a>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstArray<'a> {
+    pub elements: Vec<CstElement<'a>>,
+    /// Whitespace and comments before the `]`.
+    pub closing: &'a str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstElement<'a> {
+    pub value: CstValue<'a>,
+    /// Whitespace and comments before the comma following the element, if
+    /// there is one. The last element has one when commas are trailing.
+    pub comma: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstObject<'a> {
+    /// Members in source order, repeated keys included.
+    pub members: Vec<CstMember<'a>>,
+    /// Whitespace and comments before the `}`.
+    pub closing: &'a str,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstMember<'a> {
+    /// Whitespace and comments before the key.
+    pub leading: &'a str,
+    /// The key as spelled in the source, quotes included.
+    pub key: JsonToken<'a>,
+    /// Whitespace and comments before the colon.
+    pub colon: &'a str,
+    pub value: CstValue<'a>,
+    /// Same as [`CstElement::comma`].
+    pub comma: Option<&'a str>,
+}
+
+impl<'a> CstMember<'a> {
+    /// The key without its quotes and with its escapes resolved.
+    pub fn name(&self) -> Cow<'a, str> {
+        match self.key.token_type {
+            JsonTokenType::String => unescape(string_content(&self.key)),
+            _ => Cow::Borrowed(self.key.slice),
+        }
+    }
+}
+
+impl<'a> CstValue<'a> {
+    /// The value without its trivia. The last of repeated keys wins.
+    pub fn to_value(&self) -> JsonValue<'a> {
+        match &self.node {
+            CstNode::Scalar(token) => scalar(token).unwrap_or(JsonValue::Null),
+            CstNode::Array(array) => JsonValue::Array(array.elements.iter().map(|e| e.value.to_value()).collect()),
+            CstNode::Object(object) => {
+                let mut obj = Map::new();
+                for member in &object.members {
+                    obj.insert(member.name(), member.value.to_value());
+                }
+                JsonValue::Object(obj)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Cst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.trailing)
+    }
+}
+
+fn write_comma(f: &mut fmt::Formatter, comma: Option<&str>) -> fmt::Result {
+    match comma {
+        Some(trivia) => write!(f, "{},", trivia),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for CstValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.leading)?;
+        match &self.node {
+            CstNode::Scalar(token) => f.write_str(token.slice),
+            CstNode::Array(array) => {
+                f.write_str("[")?;
+                for element in &array.elements {
+                    write!(f, "{}", element.value)?;
+                    write_comma(f, element.comma)?;
+                }
+                write!(f, "{}]", array.closing)
+            }
+            CstNode::Object(object) => {
+                f.write_str("{")?;
+                for member in &object.members {
+                    write!(f, "{}{}{}:{}", member.leading, member.key.slice, member.colon, member.value)?;
+                    write_comma(f, member.comma)?;
+                }
+                write!(f, "{}}}", object.closing)
+            }
+        }
+    }
+}
+
+/// Parses `source` into a tree with the default [`Parser`], see
+/// [`Parser::parse_cst`] for comments and JSON5.
+pub fn parse(source: &str) -> Result<Cst<'_>, JsonError> {
+    Parser::new().parse_cst(source)
+}
+
+impl<'a, I> TokenParser<'a, I>
+where
+    I: Iterator<Item = Result<JsonToken<'a>, LexError>>,
+{
+    /// Text between the last token read and `token`.
+    fn trivia(&self, start: usize, token: &JsonToken<'a>) -> &'a str {
+        &self.source()[start..token.offset]
+    }
+
+    pub(crate) fn parse_cst_document(&mut self) -> Result<Cst<'a>, JsonError> {
+        self.check_size()?;
+        let first = match self.next_token()? {
+            Some(token) => token,
+            None => return Err(JsonError::Empty),
+        };
+        let value = self.parse_cst_value(0, first)?;
+        match self.next_token()? {
+            None => Ok(Cst {
+                value,
+                trailing: &self.source()[self.end()..],
+            }),
+            Some(token) => self.error("Invalid token at the end of document", Some(&token), &[]),
+        }
+    }
+
+    /// Parses the value starting with `token`, read from `start`.
+    fn parse_cst_value(&mut self, start: usize, token: JsonToken<'a>) -> Result<CstValue<'a>, JsonError> {
+        let leading = self.trivia(start, &token);
+        let node = match token.token_type {
+            JsonTokenType::LeftBracket => {
+                self.enter(&token)?;
+                let array = self.parse_cst_array()?;
+                self.leave();
+                array
+            }
+            JsonTokenType::LeftBrace => {
+                self.enter(&token)?;
+                let object = self.parse_cst_object()?;
+                self.leave();
+                object
+            }
+            JsonTokenType::String
+            | JsonTokenType::Number
+            | JsonTokenType::True
+            | JsonTokenType::False
+            | JsonTokenType::Null => CstNode::Scalar(token),
+            _ => return self.error("Unexpected token in place of a value", Some(&token), expected::VALUE),
+        };
+        Ok(CstValue { leading, node })
+    }
+
+    fn parse_cst_array(&mut self) -> Result<CstNode<'a>, JsonError> {
+        let mut elements: Vec<CstElement<'a>> = Vec::new();
+        let mut start = self.end();
+        let mut token = self.expect_item(0, expected::FIRST_ELEMENT)?;
+        while token.token_type != JsonTokenType::RightBracket {
+            let value = self.parse_cst_value(start, token)?;
+            let comma_start = self.end();
+            let separator = self.expect_token(expected::AFTER_ELEMENT)?;
+            let comma = match separator.token_type {
+                JsonTokenType::Comma => Some(self.trivia(comma_start, &separator)),
+                JsonTokenType::RightBracket => None,
+                _ => {
+                    return self.error(
+                        "Expected ',' or ']' after array element",
+                        Some(&separator),
+                        expected::AFTER_ELEMENT,
+                    )
+                }
+            };
+            elements.push(CstElement { value, comma });
+            if comma.is_none() {
+                return Ok(CstNode::Array(CstArray {
+                    elements,
+                    closing: self.trivia(comma_start, &separator),
+                }));
+            }
+            start = self.end();
+            token = self.expect_item(elements.len(), expected::VALUE)?;
+            if token.token_type == JsonTokenType::RightBracket && !self.options().json5 {
+                return self.error("Unexpected token in place of a value", Some(&token), expected::VALUE);
+            }
+        }
+        Ok(CstNode::Array(CstArray {
+            elements,
+            closing: self.trivia(start, &token),
+        }))
+    }
+
+    fn parse_cst_object(&mut self) -> Result<CstNode<'a>, JsonError> {
+        let mut members: Vec<CstMember<'a>> = Vec::new();
+        let mut start = self.end();
+        let mut token = self.expect_item(0, expected::FIRST_KEY)?;
+        while token.token_type != JsonTokenType::RightBrace {
+            let key = match self.key(&token) {
+                Some(key) => key,
+                None => {
+                    return self.error(
+                        "Unexpected token in place of string key in object",
+                        Some(&token),
+                        expected::KEY,
+                    )
+                }
+            };
+            if self.options().duplicate_keys == DuplicateKeys::Error && members.iter().any(|m| m.name() == key) {
+                return self.error("Duplicate key in object", Some(&token), &[]);
+            }
+            let leading = self.trivia(start, &token);
+            let colon_start = self.end();
+            let column = self.expect_token(expected::COLUMN)?;
+            if column.token_type != JsonTokenType::Column {
+                return self.error("Expected ':' after object key", Some(&column), expected::COLUMN);
+            }
+            let value_start = self.end();
+            let first = self.expect_token(expected::VALUE)?;
+            let value = self.parse_cst_value(value_start, first)?;
+            let comma_start = self.end();
+            let separator = self.expect_token(expected::AFTER_MEMBER)?;
+            let comma = match separator.token_type {
+                JsonTokenType::Comma => Some(self.trivia(comma_start, &separator)),
+                JsonTokenType::RightBrace => None,
+                _ => {
+                    return self.error(
+                        "Expected ',' or '}' after object member",
+                        Some(&separator),
+                        expected::AFTER_MEMBER,
+                    )
+                }
+            };
+            members.push(CstMember {
+                leading,
+                colon: self.trivia(colon_start, &column),
+                key: token,
+                value,
+                comma,
+            });
+            if comma.is_none() {
+                return Ok(CstNode::Object(CstObject {
+                    members,
+                    closing: self.trivia(comma_start, &separator),
+                }));
+            }
+            start = self.end();
+            token = self.expect_item(members.len(), expected::KEY)?;
+            if token.token_type == JsonTokenType::RightBrace && !self.options().json5 {
+                return self.error(
+                    "Unexpected token in place of string key in object",
+                    Some(&token),
+                    expected::KEY,
+                );
+            }
+        }
+        Ok(CstNode::Object(CstObject {
+            members,
+            closing: self.trivia(start, &token),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{try_parse, ParseOptions};
+
+    #[test]
+    fn round_trips() {
+        let sources = [
+            "0",
+            "\u{feff}null ",
+            "[]",
+            "[ ]",
+            "{ }\n",
+            "\t[1 , [ \"a\\n\" ],{\"k\" :\r\n -1.50E+3 } ]\n",
+            r#"{"a": {"b": [true, false, null]}, "a": "dup"}"#,
+        ];
+        for source in sources.iter() {
+            let cst = parse(source).unwrap();
+            assert_eq!(&cst.to_string(), source);
+            assert_eq!(cst.value.to_value(), try_parse(source).unwrap(), "{}", source);
+        }
+
+        let json5 = "// config\n{\n  name: 'cam', /* inline */ tags: ['a', 'b',],\n  port: +0x50, ratio: .5,\n}";
+        let parser = Parser::new().json5(true);
+        let cst = parser.parse_cst(json5).unwrap();
+        assert_eq!(cst.to_string(), json5);
+        assert_eq!(cst.value.to_value(), parser.parse(json5).unwrap());
+    }
+
+    #[test]
+    fn trivia_is_attached_to_nodes() {
+        let source = "/* head */ [1, // one\n 2 ]  ";
+        let cst = Parser::new().allow_comments(true).parse_cst(source).unwrap();
+        assert_eq!(cst.value.leading, "/* head */ ");
+        assert_eq!(cst.trailing, "  ");
+        let array = match &cst.value.node {
+            CstNode::Array(array) => array,
+            other => panic!("expected an array, got {:?}", other),
+        };
+        assert_eq!(array.elements[0].comma, Some(""));
+        assert_eq!(array.elements[1].value.leading, " // one\n ");
+        assert_eq!(array.elements[1].comma, None);
+        assert_eq!(array.closing, " ");
+
+        let cst = parse(r#"{ "k" : 1 , "l":2}"#).unwrap();
+        let member = match &cst.value.node {
+            CstNode::Object(object) => &object.members[0],
+            other => panic!("expected an object, got {:?}", other),
+        };
+        assert_eq!((member.leading, &*member.name(), member.colon), (" ", "k", " "));
+        assert_eq!((member.value.leading, member.comma), (" ", Some(" ")));
+    }
+
+    #[test]
+    fn reformatting() {
+        let source = "{\"a\": [1,\n2], \"b\": {}}";
+        let mut cst = parse(source).unwrap();
+        fn minify(value: &mut CstValue) {
+            value.leading = "";
+            match &mut value.node {
+                CstNode::Scalar(_) => {}
+                CstNode::Array(array) => {
+                    array.closing = "";
+                    for element in &mut array.elements {
+                        element.comma = element.comma.map(|_| "");
+                        minify(&mut element.value);
+                    }
+                }
+                CstNode::Object(object) => {
+                    object.closing = "";
+                    for member in &mut object.members {
+                        member.leading = "";
+                        member.comma = member.comma.map(|_| "");
+                        minify(&mut member.value);
+                    }
+                }
+            }
+        }
+        minify(&mut cst.value);
+        assert_eq!(cst.to_string(), r#"{"a":[1,2],"b":{}}"#);
+    }
+
+    #[test]
+    fn same_errors_as_parsing() {
+        for source in ["", "[1,]", r#"{"a":1,}"#, r#"{"a" 1}"#, "[1 2]", "[] []", "{1: 2}", "[// c\n]"].iter() {
+            assert_eq!(parse(source).map(|_| ()), try_parse(source).map(|_| ()), "{}", source);
+        }
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        let parser = Parser::from(options);
+        for source in [r#"{"a": 1, "a": 2}"#, "[[[]]]", "[[]]"].iter() {
+            assert_eq!(parser.parse_cst(source).map(|_| ()), parser.parse(source).map(|_| ()), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,477 @@
+//! serde support: deserialize any `T: Deserialize` straight from the token
+//! stream, without building a [`JsonValue`](crate::JsonValue) first.
+
+use alloc::borrow::Cow;
+use core::fmt::Display;
+use core::iter::Peekable;
+
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+
+use crate::parser::{expected, string_content, ParseOptions};
+use crate::{line_column, unescape, JsonError, JsonNumber, JsonToken, JsonTokenType, Lexer};
+
+impl de::Error for JsonError {
+    fn custom<T: Display>(msg: T) -> Self {
+        JsonError::Custom(msg.to_string())
+    }
+}
+
+pub struct Deserializer<'de> {
+    source: &'de str,
+    tokens: Peekable<Lexer<'de>>,
+    /// Number of arrays and objects being read, each inside the previous
+    /// one.
+    depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(source: &'de str) -> Self {
+        Self {
+            source,
+            tokens: Lexer::new(source).peekable(),
+            depth: 0,
+        }
+    }
+
+    /// Counts the array or object opened by `token`, up to
+    /// [`ParseOptions::max_depth`].
+    fn enter(&mut self, token: &JsonToken<'de>) -> Result<(), JsonError> {
+        let limit = ParseOptions::default().max_depth;
+        if self.depth == limit {
+            let (line, column) = line_column(self.source, token.offset);
+            return Err(JsonError::DepthLimitExceeded {
+                limit,
+                offset: token.offset,
+                line,
+                column,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn error<T>(
+        &self,
+        msg: &str,
+        token: Option<&JsonToken<'de>>,
+        expected: &'static [JsonTokenType],
+    ) -> Result<T, JsonError> {
+        Err(JsonError::at_token(self.source, msg, token, expected))
+    }
+
+    fn next_token(&mut self) -> Result<JsonToken<'de>, JsonError> {
+        match self.tokens.next() {
+            Some(token) => Ok(token?),
+            // What may follow depends on the type being deserialized.
+            None => self.error("Unexpected end of file", None, &[]),
+        }
+    }
+
+    fn peek_type(&mut self) -> Result<Option<JsonTokenType>, JsonError> {
+        match self.tokens.peek() {
+            Some(Ok(token)) => Ok(Some(token.token_type.clone())),
+            Some(Err(e)) => Err(e.clone().into()),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads a `token_type` token, reporting `msg` and the `expected` token
+    /// types otherwise.
+    fn expect(
+        &mut self,
+        token_type: JsonTokenType,
+        msg: &str,
+        expected: &'static [JsonTokenType],
+    ) -> Result<(), JsonError> {
+        let token = self.next_token()?;
+        if token.token_type != token_type {
+            return self.error(msg, Some(&token), expected);
+        }
+        Ok(())
+    }
+
+    /// Makes sure the whole source has been consumed.
+    pub fn end(&mut self) -> Result<(), JsonError> {
+        match self.tokens.next() {
+            None => Ok(()),
+            Some(token) => self.error("Invalid token at the end of document", Some(&token?), &[]),
+        }
+    }
+}
+
+/// Deserializes a `T` from the JSON text `source`.
+pub fn from_str<'a, T: de::Deserialize<'a>>(source: &'a str) -> Result<T, JsonError> {
+    let mut deserializer = Deserializer::new(source);
+    if deserializer.peek_type()?.is_none() {
+        return Err(JsonError::Empty);
+    }
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+fn visit_string<'de, V: Visitor<'de>>(raw: &'de str, visitor: V) -> Result<V::Value, JsonError> {
+    match unescape(raw) {
+        Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+        Cow::Owned(s) => visitor.visit_string(s),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = JsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        let token = self.next_token()?;
+        match token.token_type {
+            JsonTokenType::LeftBracket => {
+                self.enter(&token)?;
+                let value = visitor.visit_seq(Seq {
+                    de: &mut *self,
+                    first: true,
+                })?;
+                self.expect(
+                    JsonTokenType::RightBracket,
+                    "Expected ']' at the end of array",
+                    &[JsonTokenType::RightBracket],
+                )?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            JsonTokenType::LeftBrace => {
+                self.enter(&token)?;
+                let value = visitor.visit_map(Map {
+                    de: &mut *self,
+                    first: true,
+                })?;
+                self.expect(
+                    JsonTokenType::RightBrace,
+                    "Expected '}' at the end of object",
+                    &[JsonTokenType::RightBrace],
+                )?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            JsonTokenType::String => visit_string(string_content(&token), visitor),
+            JsonTokenType::Number => match JsonNumber::parse(token.slice) {
+                JsonNumber::Integer(n) => visitor.visit_i64(n),
+                JsonNumber::Unsigned(n) => visitor.visit_u64(n),
+                JsonNumber::Float(f) => visitor.visit_f64(f),
+            },
+            JsonTokenType::True => visitor.visit_bool(true),
+            JsonTokenType::False => visitor.visit_bool(false),
+            JsonTokenType::Null => visitor.visit_unit(),
+            _ => self.error("Unexpected token in place of a value", Some(&token), expected::VALUE),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        if self.peek_type()? == Some(JsonTokenType::Null) {
+            self.next_token()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        match self.peek_type()? {
+            // Unit variant
+            Some(JsonTokenType::String) => {
+                let token = self.next_token()?;
+                visitor.visit_enum(unescape(string_content(&token)).into_owned().into_deserializer())
+            }
+            // `{"Variant": content}`
+            Some(JsonTokenType::LeftBrace) => {
+                let token = self.next_token()?;
+                self.enter(&token)?;
+                let value = visitor.visit_enum(Enum { de: &mut *self })?;
+                self.expect(
+                    JsonTokenType::RightBrace,
+                    "Expected '}' after enum variant",
+                    &[JsonTokenType::RightBrace],
+                )?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            _ => {
+                let token = self.next_token()?;
+                self.error("Expected a string or an object for an enum", Some(&token), &[JsonTokenType::String, JsonTokenType::LeftBrace])
+            }
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct Seq<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+}
+
+impl<'a, 'de> SeqAccess<'de> for Seq<'a, 'de> {
+    type Error = JsonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, JsonError> {
+        if self.de.peek_type()? == Some(JsonTokenType::RightBracket) {
+            return Ok(None);
+        }
+        if !self.first {
+            self.de
+                .expect(
+                    JsonTokenType::Comma,
+                    "Expected ',' or ']' after array element",
+                    expected::AFTER_ELEMENT,
+                )?;
+        }
+        self.first = false;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Map<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    first: bool,
+}
+
+impl<'a, 'de> MapAccess<'de> for Map<'a, 'de> {
+    type Error = JsonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, JsonError> {
+        if self.de.peek_type()? == Some(JsonTokenType::RightBrace) {
+            return Ok(None);
+        }
+        if !self.first {
+            self.de
+                .expect(
+                    JsonTokenType::Comma,
+                    "Expected ',' or '}' after object member",
+                    expected::AFTER_MEMBER,
+                )?;
+        }
+        self.first = false;
+        let token = self.de.next_token()?;
+        if token.token_type != JsonTokenType::String {
+            return self
+                .de
+                .error("Unexpected token in place of string key in object", Some(&token), expected::KEY);
+        }
+        seed.deserialize(Key(string_content(&token))).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, JsonError> {
+        self.de
+            .expect(JsonTokenType::Column, "Expected ':' after object key", expected::COLUMN)?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// Deserializer of the raw content of an object key.
+struct Key<'de>(&'de str);
+
+/// Deserializes a key by parsing its text, numbers and booleans being
+/// written as quoted keys, e.g. `{"7": true}` for a `HashMap<u32, bool>`.
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+                let text = unescape(self.0);
+                match text.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&text), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Key<'de> {
+    type Error = JsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        visit_string(self.0, visitor)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf option unit unit_struct newtype_struct
+        seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'a, 'de> EnumAccess<'de> for Enum<'a, 'de> {
+    type Error = JsonError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), JsonError> {
+        let token = self.de.next_token()?;
+        if token.token_type != JsonTokenType::String {
+            return self.de.error("Expected the name of an enum variant", Some(&token), expected::KEY);
+        }
+        let variant = seed.deserialize(Key(string_content(&token)))?;
+        self.de
+            .expect(JsonTokenType::Column, "Expected ':' after enum variant", expected::COLUMN)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> VariantAccess<'de> for Enum<'a, 'de> {
+    type Error = JsonError;
+
+    fn unit_variant(self) -> Result<(), JsonError> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, JsonError> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, JsonError> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, JsonError> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Rect {
+        x: f64,
+        height: f64,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Kind {
+        Face,
+        Body { tracked: bool },
+        Other(String),
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Face<'a> {
+        uid: u32,
+        face_rect: Rect,
+        label: &'a str,
+        note: Option<String>,
+        tags: Vec<String>,
+        kinds: Vec<Kind>,
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let source = r#"{
+            "uid": 7,
+            "label": "front",
+            "faceRect": {"x": 0.5, "height": 1},
+            "note": null,
+            "tags": ["a\nb", "c"],
+            "kinds": ["Face", {"Body": {"tracked": true}}, {"Other": "x"}],
+            "ignored": [1, {"deep": []}]
+        }"#;
+        let face: Face = from_str(source).unwrap();
+        assert_eq!(
+            face,
+            Face {
+                uid: 7,
+                face_rect: Rect { x: 0.5, height: 1.0 },
+                label: "front",
+                note: None,
+                tags: vec!["a\nb".to_string(), "c".to_string()],
+                kinds: vec![
+                    Kind::Face,
+                    Kind::Body { tracked: true },
+                    Kind::Other("x".to_string())
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_collections() {
+        let map: HashMap<String, (i32, Option<bool>)> =
+            from_str(r#"{"a": [1, true], "bA": [-2, null]}"#).unwrap();
+        assert_eq!(map["a"], (1, Some(true)));
+        assert_eq!(map["bA"], (-2, None));
+        let ids: Vec<u64> = from_str("[0, 18446744073709551615]").unwrap();
+        assert_eq!(ids, vec![0, u64::MAX]);
+
+        let flags: HashMap<u32, bool> = from_str(r#"{"1": true, "\u0037": false}"#).unwrap();
+        assert_eq!((flags[&1], flags[&7]), (true, false));
+        let written = crate::ser::serde::to_string(&flags).unwrap();
+        assert_eq!(from_str::<HashMap<u32, bool>>(&written).unwrap(), flags);
+        let offsets: HashMap<i128, f64> = from_str(r#"{"-170141183460469231731687303715884105728": 0.5}"#).unwrap();
+        assert_eq!(offsets[&i128::MIN], 0.5);
+        let switches: HashMap<bool, f32> = from_str(r#"{"false": 1}"#).unwrap();
+        assert_eq!(switches[&false], 1.0);
+        let error = from_str::<HashMap<u8, bool>>(r#"{"256": true}"#).unwrap_err();
+        assert_eq!(error.to_string(), r#"invalid value: string "256", expected u8"#);
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        assert_eq!(from_str::<u8>(""), Err(JsonError::Empty));
+        assert!(matches!(from_str::<Vec<u8>>("[1,]"), Err(JsonError::Parse { .. })));
+        assert!(matches!(from_str::<Vec<u8>>("[1] 2"), Err(JsonError::Parse { .. })));
+        assert!(matches!(from_str::<(u8,)>("[1, 2]"), Err(JsonError::Parse { .. })));
+        assert!(matches!(from_str::<Rect>(r#"{"x": 1}"#), Err(JsonError::Custom(_))));
+        assert!(matches!(from_str::<u8>("300"), Err(JsonError::Custom(_))));
+        let deep = "[".repeat(100_000);
+        assert!(matches!(from_str::<de::IgnoredAny>(&deep), Err(JsonError::DepthLimitExceeded { limit: 128, .. })));
+    }
+}
@@ -0,0 +1,172 @@
+//! Structural comparison of two documents, e.g. to explain a failed test
+//! assertion or a configuration drift.
+//!
+//! ```
+//! use yaj::{diff, json};
+//! let before = json!({"camera": {"portNo": 8080, "tags": ["a"]}});
+//! let after = json!({"camera": {"portNo": 9090, "tags": ["a", "b"]}});
+//! let differences = diff::compare(&before, &after);
+//! assert_eq!(
+//!     diff::report(&differences, false),
+//!     "- /camera/portNo: 8080\n+ /camera/portNo: 9090\n+ /camera/tags/1: \"b\"\n"
+//! );
+//! ```
+
+use alloc::collections::BTreeSet;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::pointer::push_token;
+use crate::{to_string, JsonValue};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DifferenceKind {
+    /// Only found in the second document.
+    Added,
+    /// Only found in the first document.
+    Removed,
+    /// Found in both documents with different values.
+    Changed,
+}
+
+/// A location where two documents differ.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference<'a> {
+    /// JSON Pointer of the location.
+    pub path: String,
+    pub kind: DifferenceKind,
+    /// Value in the first document, `None` when it was added.
+    pub old: Option<&'a JsonValue<'a>>,
+    /// Value in the second document, `None` when it was removed.
+    pub new: Option<&'a JsonValue<'a>>,
+}
+
+/// Lists the locations where `a` and `b` differ, the deepest that contain
+/// the whole change. Members are compared by key in key order, elements by
+/// index, and values of different types are changed as a whole.
+pub fn compare<'a>(a: &'a JsonValue<'a>, b: &'a JsonValue<'a>) -> Vec<Difference<'a>> {
+    let mut differences = Vec::new();
+    compare_into(a, b, &mut String::new(), &mut differences);
+    differences
+}
+
+fn compare_into<'a>(a: &'a JsonValue<'a>, b: &'a JsonValue<'a>, path: &mut String, differences: &mut Vec<Difference<'a>>) {
+    if a == b {
+        return;
+    }
+    let len = path.len();
+    let push = |differences: &mut Vec<_>, path: &String, old, new| {
+        let kind = match (old, new) {
+            (None, _) => DifferenceKind::Added,
+            (_, None) => DifferenceKind::Removed,
+            _ => DifferenceKind::Changed,
+        };
+        differences.push(Difference {
+            path: path.clone(),
+            kind,
+            old,
+            new,
+        });
+    };
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            let keys: BTreeSet<_> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                push_token(path, key);
+                match (a.get(key), b.get(key)) {
+                    (Some(old), Some(new)) => compare_into(old, new, path, differences),
+                    (old, new) => push(differences, path, old, new),
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                push_token(path, &index.to_string());
+                match (a.get(index), b.get(index)) {
+                    (Some(old), Some(new)) => compare_into(old, new, path, differences),
+                    (old, new) => push(differences, path, old, new),
+                }
+                path.truncate(len);
+            }
+        }
+        _ => push(differences, path, Some(a), Some(b)),
+    }
+}
+
+/// One `- path: old` line for the old value and one `+ path: new` line for
+/// the new value, in compact JSON. The root is shown as `(root)`.
+impl fmt::Display for Difference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_difference(f, self, false)
+    }
+}
+
+fn write_difference<W: fmt::Write>(out: &mut W, difference: &Difference<'_>, color: bool) -> fmt::Result {
+    let path = if difference.path.is_empty() { "(root)" } else { &difference.path };
+    let lines = [("-", "\x1b[31m", difference.old), ("+", "\x1b[32m", difference.new)];
+    for (sign, escape, value) in lines.iter() {
+        if let Some(value) = value {
+            if color {
+                out.write_str(escape)?;
+            }
+            write!(out, "{} {}: {}", sign, path, to_string(value))?;
+            if color {
+                out.write_str("\x1b[0m")?;
+            }
+            out.write_char('\n')?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats `differences` like a unified diff, see the [`Display`](fmt::Display)
+/// implementation of [`Difference`]. With `color`, removed lines are red
+/// and added lines green, using ANSI escape codes.
+pub fn report(differences: &[Difference<'_>], color: bool) -> String {
+    let mut out = String::new();
+    for difference in differences {
+        write_difference(&mut out, difference, color).expect("writing to a String cannot fail");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn differences() {
+        let a = parse(r#"{"faces": [{"uid": 1, "age": 30}, {"uid": 2}], "a/b": 1, "gone": true, "same": [1]}"#);
+        let b = parse(r#"{"faces": [{"uid": 1, "age": 31}], "a/b": [1], "new": null, "same": [1]}"#);
+        let differences = compare(&a, &b);
+        let summary: Vec<_> = differences.iter().map(|d| (d.path.as_str(), d.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/a~1b", DifferenceKind::Changed),
+                ("/faces/0/age", DifferenceKind::Changed),
+                ("/faces/1", DifferenceKind::Removed),
+                ("/gone", DifferenceKind::Removed),
+                ("/new", DifferenceKind::Added),
+            ]
+        );
+        assert_eq!((differences[2].old, differences[2].new), (Some(&json!({"uid": 2})), None));
+        assert!(compare(&a, &a).is_empty());
+        assert_eq!(compare(&json!(1), &json!(1.0))[0].path, "");
+    }
+
+    #[test]
+    fn reports() {
+        let a = json!({"portNo": 8080, "name": "cam"});
+        let b = json!({"portNo": 9090});
+        let differences = compare(&a, &b);
+        assert_eq!(report(&differences, false), "- /name: \"cam\"\n- /portNo: 8080\n+ /portNo: 9090\n");
+        assert_eq!(
+            report(&differences[1..], true),
+            "\x1b[31m- /portNo: 8080\x1b[0m\n\x1b[32m+ /portNo: 9090\x1b[0m\n"
+        );
+        assert_eq!(compare(&json!([1]), &json!(null))[0].to_string(), "- (root): [1]\n+ (root): null\n");
+    }
+}
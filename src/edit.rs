@@ -0,0 +1,163 @@
+//! Edits of JSON text that only rewrite the targeted values, keeping the
+//! whitespace, comments and spelling of everything else, e.g. for tools
+//! updating configuration files written by hand.
+//!
+//! ```
+//! let config = "{\n  // Camera settings\n  \"portNo\": 8080,\n  \"tags\": [\"door\"]\n}\n";
+//! let config = yaj::edit::set(config, "/portNo", &yaj::json!(9090)).unwrap();
+//! let config = yaj::edit::set(&config, "/debug", &yaj::json!(true)).unwrap();
+//! assert_eq!(
+//!     config,
+//!     "{\n  // Camera settings\n  \"portNo\": 9090,\n  \"tags\": [\"door\"],\n  \"debug\": true\n}\n"
+//! );
+//! ```
+
+use core::fmt;
+use core::ops::Range;
+
+use crate::pointer::{array_index, reference_tokens};
+use crate::prelude::*;
+use crate::{to_string, JsonError, JsonValue, Parser, SpannedNode};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditError {
+    /// The source is not a valid document, comments being allowed.
+    Parse(JsonError),
+    /// The pointer is invalid, or designates neither a value, nor a new
+    /// member of an object, nor the end of an array.
+    PathNotFound,
+}
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(e) => e.fmt(f),
+            Self::PathNotFound => f.write_str("Path not found"),
+        }
+    }
+}
+impl core::error::Error for EditError {}
+
+impl From<JsonError> for EditError {
+    fn from(e: JsonError) -> Self {
+        Self::Parse(e)
+    }
+}
+
+/// What separates the item starting at `start` from the previous one: a
+/// line break and the same indentation when it starts its own line.
+fn separator(source: &str, start: usize) -> String {
+    let before = &source[..start];
+    match before.rfind('\n') {
+        Some(line_start) if before[line_start + 1..].chars().all(|c| c == ' ' || c == '\t') => {
+            let newline = if before[..line_start].ends_with('\r') { "\r\n" } else { "\n" };
+            format!(",{}{}", newline, &before[line_start + 1..])
+        }
+        _ => String::from(", "),
+    }
+}
+
+/// Inserts `item` into the container spanning `span`, after its `last`
+/// item or between the brackets when it is empty, dropping the whitespace
+/// there.
+fn append(source: &str, span: Range<usize>, last: Option<Range<usize>>, item: &str) -> String {
+    let mut out = String::with_capacity(source.len() + item.len() + 8);
+    match last {
+        Some(last) => {
+            out.push_str(&source[..last.end]);
+            out.push_str(&separator(source, last.start));
+            out.push_str(item);
+            out.push_str(&source[last.end..]);
+        }
+        None => {
+            out.push_str(&source[..span.start + 1]);
+            out.push_str(item);
+            out.push_str(&source[span.end - 1..]);
+        }
+    }
+    out
+}
+
+/// Sets the value at `pointer` in the document `source` to `new_value`,
+/// written as compact JSON text, and returns the edited text. Only the
+/// previous value is replaced. A missing member of an object is added
+/// after the last one, as is a new element of an array designated by `-`
+/// or by its length, with the indentation of the previous item. Comments
+/// are allowed and kept.
+pub fn set(source: &str, pointer: &str, new_value: &JsonValue) -> Result<String, EditError> {
+    let doc = Parser::new().allow_comments(true).parse_with_spans(source)?;
+    let text = to_string(new_value);
+    if let Some(target) = doc.pointer(pointer) {
+        let span = target.span.clone();
+        return Ok([&source[..span.start], &text, &source[span.end..]].concat());
+    }
+
+    let split = pointer.rfind('/').ok_or(EditError::PathNotFound)?;
+    let parent = doc.pointer(&pointer[..split]).ok_or(EditError::PathNotFound)?;
+    let token = reference_tokens(&pointer[split..])
+        .and_then(|mut tokens| tokens.next())
+        .ok_or(EditError::PathNotFound)?;
+    match &parent.node {
+        SpannedNode::Object(members) => {
+            let last = members
+                .iter()
+                .max_by_key(|member| member.value.span.end)
+                .map(|member| member.key_span.start..member.value.span.end);
+            let member = format!("{}: {}", to_string(&JsonValue::from(token)), text);
+            Ok(append(source, parent.span.clone(), last, &member))
+        }
+        SpannedNode::Array(array) if token == "-" || array_index(&token) == Some(array.len()) => {
+            let last = array.last().map(|element| element.span.clone());
+            Ok(append(source, parent.span.clone(), last, &text))
+        }
+        _ => Err(EditError::PathNotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn values_are_replaced_in_place() {
+        let source = "{ \"a\" : [1,  2 ] ,\"b\":{\"c~/\": \"x\"} } // end";
+        assert_eq!(
+            set(source, "/a/1", &json!({"k": null})).unwrap(),
+            "{ \"a\" : [1,  {\"k\":null} ] ,\"b\":{\"c~/\": \"x\"} } // end"
+        );
+        assert_eq!(
+            set(source, "/b/c~0~1", &json!("y")).unwrap(),
+            "{ \"a\" : [1,  2 ] ,\"b\":{\"c~/\": \"y\"} } // end"
+        );
+        assert_eq!(set(source, "", &json!(1)).unwrap(), "1 // end");
+    }
+
+    #[test]
+    fn items_are_added() {
+        let source = "{\r\n\t\"tags\": [\n    \"a\"\n  ],\r\n\t\"empty\": { },\r\n\t\"inline\": [1, 2]\r\n}";
+        let edited = set(source, "/name", &json!("cam")).unwrap();
+        assert_eq!(
+            edited,
+            "{\r\n\t\"tags\": [\n    \"a\"\n  ],\r\n\t\"empty\": { },\r\n\t\"inline\": [1, 2],\r\n\t\"name\": \"cam\"\r\n}"
+        );
+        let edited = set(&edited, "/tags/-", &json!("b")).unwrap();
+        assert!(edited.contains("[\n    \"a\",\n    \"b\"\n  ]"));
+        let edited = set(&edited, "/inline/2", &json!(3)).unwrap();
+        assert!(edited.contains("[1, 2, 3]"));
+        let edited = set(&edited, "/empty/a\"b", &json!([])).unwrap();
+        assert!(edited.contains(r#"{"a\"b": []}"#));
+        assert_eq!(set("[]", "/-", &json!(1)).unwrap(), "[1]");
+        assert_eq!(
+            try_parse(&edited).unwrap(),
+            json!({"tags": ["a", "b"], "empty": {"a\"b": []}, "inline": [1, 2, 3], "name": "cam"})
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(set("[1,", "/0", &json!(1)), Err(EditError::Parse(_))));
+        for pointer in ["/a/b", "a", "/list/3", "/list/x", "/n/0"].iter() {
+            assert_eq!(set(r#"{"list": [1, 2], "n": 0}"#, pointer, &json!(1)), Err(EditError::PathNotFound), "{}", pointer);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Detection of the Unicode encoding of a document, as described in
+//! RFC 4627 §3, and transcoding to UTF-8.
+
+use alloc::borrow::Cow;
+
+use crate::prelude::*;
+use crate::{line_column, LexError, LexErrorKind};
+
+/// A Unicode encoding a document may be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Be,
+    Utf16Le,
+    Utf32Be,
+    Utf32Le,
+}
+
+impl Encoding {
+    /// Guesses the encoding from a byte order mark or, failing that, from
+    /// the pattern of null bytes: the first two chars of a document are
+    /// ASCII, so their encoding gives the zero bytes away.
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => Self::Utf8,
+            [0, 0, 0xFE, 0xFF, ..] => Self::Utf32Be,
+            [0xFF, 0xFE, 0, 0, ..] => Self::Utf32Le,
+            [0xFE, 0xFF, ..] => Self::Utf16Be,
+            [0xFF, 0xFE, ..] => Self::Utf16Le,
+            [0, 0, 0, _, ..] => Self::Utf32Be,
+            [_, 0, 0, 0, ..] => Self::Utf32Le,
+            // Also a single char in UTF-16.
+            [0, _, ..] => Self::Utf16Be,
+            [_, 0, ..] => Self::Utf16Le,
+            _ => Self::Utf8,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf32Be => "UTF-32BE",
+            Self::Utf32Le => "UTF-32LE",
+        }
+    }
+}
+
+/// Decodes `bytes` written in `encoding`, borrowing them when they are
+/// valid UTF-8 already. A byte order mark is kept, for the lexer to skip.
+/// Invalid sequences are replaced with U+FFFD when `lossy`, otherwise the
+/// error is the offset of the first byte that cannot be decoded.
+pub(crate) fn decode(bytes: &[u8], encoding: Encoding, lossy: bool) -> Result<Cow<'_, str>, usize> {
+    match encoding {
+        Encoding::Utf8 if lossy => Ok(String::from_utf8_lossy(bytes)),
+        Encoding::Utf8 => core::str::from_utf8(bytes).map(Cow::Borrowed).map_err(|e| e.valid_up_to()),
+        Encoding::Utf16Be | Encoding::Utf16Le => {
+            let units = bytes.chunks_exact(2);
+            let odd = !units.remainder().is_empty();
+            let be = encoding == Encoding::Utf16Be;
+            let units = units.map(|unit| {
+                let unit = [unit[0], unit[1]];
+                if be {
+                    u16::from_be_bytes(unit)
+                } else {
+                    u16::from_le_bytes(unit)
+                }
+            });
+            let mut text = String::with_capacity(bytes.len() / 2);
+            let mut offset = 0;
+            for chr in core::char::decode_utf16(units) {
+                let (chr, len) = match chr {
+                    Ok(chr) => (chr, 2 * chr.len_utf16()),
+                    Err(_) if lossy => (char::REPLACEMENT_CHARACTER, 2),
+                    Err(_) => return Err(offset),
+                };
+                offset += len;
+                text.push(chr);
+            }
+            if odd {
+                if !lossy {
+                    return Err(offset);
+                }
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+            Ok(Cow::Owned(text))
+        }
+        Encoding::Utf32Be | Encoding::Utf32Le => {
+            let be = encoding == Encoding::Utf32Be;
+            let mut text = String::with_capacity(bytes.len() / 4);
+            for (idx, unit) in bytes.chunks(4).enumerate() {
+                let chr = match *unit {
+                    [a, b, c, d] if be => core::char::from_u32(u32::from_be_bytes([a, b, c, d])),
+                    [a, b, c, d] => core::char::from_u32(u32::from_le_bytes([a, b, c, d])),
+                    _ => None,
+                };
+                match chr {
+                    Some(chr) => text.push(chr),
+                    None if lossy => text.push(char::REPLACEMENT_CHARACTER),
+                    None => return Err(4 * idx),
+                }
+            }
+            Ok(Cow::Owned(text))
+        }
+    }
+}
+
+/// The error for the byte at `offset` that cannot be decoded, located in
+/// the text decoded before it.
+pub(crate) fn decode_error(bytes: &[u8], encoding: Encoding, offset: usize) -> LexError {
+    let kind = match encoding {
+        Encoding::Utf8 => LexErrorKind::InvalidUtf8,
+        Encoding::Utf16Be | Encoding::Utf16Le => LexErrorKind::InvalidUtf16,
+        Encoding::Utf32Be | Encoding::Utf32Le => LexErrorKind::InvalidUtf32,
+    };
+    let valid = decode(&bytes[..offset], encoding, true).unwrap_or_default();
+    let (line, column) = line_column(&valid, valid.len());
+    LexError {
+        kind,
+        offset,
+        line,
+        column,
+        chr: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_slice, JsonError, JsonValue, Parser};
+
+    #[test]
+    fn detection() {
+        assert_eq!(Encoding::detect(b"\xef\xbb\xbf[]"), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"\xff\xfe\0\0"), Encoding::Utf32Le);
+        assert_eq!(Encoding::detect(b"\xff\xfe[\0"), Encoding::Utf16Le);
+        assert_eq!(Encoding::detect(b"\0\0\0["), Encoding::Utf32Be);
+        assert_eq!(Encoding::detect(b"[\0\0\0"), Encoding::Utf32Le);
+        assert_eq!(Encoding::detect(b"\0[\0]"), Encoding::Utf16Be);
+        assert_eq!(Encoding::detect(b"1\0"), Encoding::Utf16Le);
+        assert_eq!(Encoding::detect(b"1"), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b""), Encoding::Utf8);
+    }
+
+    #[test]
+    fn byte_input() {
+        let bytes = br#"{"name": "caf\u00e9", "id": "x"}"#;
+        let value = from_slice(bytes).unwrap();
+        assert_eq!(value, json!({"name": "café", "id": "x"}));
+        assert!(matches!(&value["id"], JsonValue::String(Cow::Borrowed(_))));
+        assert_eq!(from_slice(b"\xef\xbb\xbf[1]").unwrap(), json!([1]));
+        assert_eq!(from_slice(b"\0[\0\"\0\xe9\0\"\0]").unwrap(), json!(["é"]));
+
+        let error = from_slice(b"[\n\"a\xff\"]").unwrap_err();
+        match error {
+            JsonError::Lex(ref e) => assert_eq!((e.kind, e.offset, e.line, e.column), (LexErrorKind::InvalidUtf8, 4, 2, 3)),
+            other => panic!("expected a lexing error, got {:?}", other),
+        }
+        assert_eq!(error.to_string(), "Invalid UTF-8 at line 2 column 3");
+        let lossy = Parser::new().lossy_decoding(true);
+        assert_eq!(lossy.parse_slice(b"[\n\"a\xff\"]").unwrap(), json!(["a\u{fffd}"]));
+
+        let utf16 = b"[\0\0\xd8]\0";
+        let error = from_slice(utf16).unwrap_err();
+        assert!(matches!(error, JsonError::Lex(LexError { kind: LexErrorKind::InvalidUtf16, offset: 2, .. })));
+        assert!(lossy.parse_slice(utf16).is_err(), "U+FFFD is not a value");
+        assert_eq!(lossy.parse_slice(b"\"\0\0\xd8\"\0").unwrap(), json!("\u{fffd}"));
+        assert_eq!(decode(b"1\0\0", Encoding::Utf16Le, true).unwrap(), "1\u{fffd}");
+        assert_eq!(decode(b"1\0\0\0\0\0\x11\0", Encoding::Utf32Le, false), Err(4));
+    }
+}
@@ -0,0 +1,156 @@
+use alloc::borrow::Cow;
+use core::fmt::{self, Write};
+
+use crate::prelude::*;
+
+/// Writes `s` as a quoted JSON string, escaping quotes, backslashes and
+/// control characters.
+pub(crate) fn write_escaped<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    let mut start = 0;
+    for (idx, chr) in s.char_indices() {
+        let escaped = match chr {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            '\u{0}'..='\u{1f}' => "",
+            _ => continue,
+        };
+        out.write_str(&s[start..idx])?;
+        if escaped.is_empty() {
+            write!(out, "\\u{:04x}", chr as u32)?;
+        } else {
+            out.write_str(escaped)?;
+        }
+        start = idx + chr.len_utf8();
+    }
+    out.write_str(&s[start..])?;
+    out.write_char('"')
+}
+
+fn hex_value(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Resolves the escape sequences of the content of a JSON string (without
+/// the surrounding quotes). Only allocates when `raw` contains a backslash.
+///
+/// Invalid `\uXXXX` sequences and unpaired surrogates are replaced with
+/// U+FFFD. The JSON5 escapes (`\'`, `\v`, `\0`, `\xXX` and escaped line
+/// terminators, which are removed) are resolved too.
+pub fn unescape(raw: &str) -> Cow<'_, str> {
+    let first = match raw.find('\\') {
+        Some(idx) => idx,
+        None => return Cow::Borrowed(raw),
+    };
+    let mut out = String::with_capacity(raw.len());
+    out.push_str(&raw[..first]);
+
+    let mut rest = &raw[first..];
+    while let Some(idx) = rest.find('\\') {
+        out.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        let mut chars = rest.chars();
+        let escaped = match chars.next() {
+            Some(chr) => chr,
+            None => break,
+        };
+        rest = chars.as_str();
+        match escaped {
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\u{b}'),
+            '0' => out.push('\0'),
+            'x' => match rest.get(..2).and_then(hex_value) {
+                Some(code) => {
+                    rest = &rest[2..];
+                    out.push(core::char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                None => out.push(char::REPLACEMENT_CHARACTER),
+            },
+            // Line continuation
+            '\r' => rest = rest.strip_prefix('\n').unwrap_or(rest),
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            'u' => {
+                let high = match rest.get(..4).and_then(hex_value) {
+                    Some(code) => code,
+                    None => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        continue;
+                    }
+                };
+                rest = &rest[4..];
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    // A high surrogate must be followed by an escaped low surrogate
+                    match rest
+                        .strip_prefix("\\u")
+                        .and_then(|r| r.get(..4))
+                        .and_then(hex_value)
+                    {
+                        Some(low) if (0xDC00..0xE000).contains(&low) => {
+                            rest = &rest[6..];
+                            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                        }
+                        _ => high,
+                    }
+                } else {
+                    high
+                };
+                out.push(core::char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            // '"', '\\' and '/'
+            other => out.push(other),
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_without_escapes_borrows() {
+        assert!(matches!(unescape("Hello"), Cow::Borrowed("Hello")));
+    }
+
+    #[test]
+    fn unescape_simple_escapes() {
+        assert_eq!(unescape(r#"a\nb\t\"c\"\\\/"#), "a\nb\t\"c\"\\/");
+        assert_eq!(unescape(r"\u00e9t\u00E9"), "été");
+    }
+
+    #[test]
+    fn unescape_json5_escapes() {
+        assert_eq!(unescape(r"it\'s\v\0\x41\x4"), "it's\u{b}\0A\u{fffd}4");
+        assert_eq!(unescape("a\\\nb\\\r\nc"), "abc");
+    }
+
+    #[test]
+    fn escape_round_trips() {
+        let original = "a\"b\\c\nd\u{1}é/";
+        let mut out = String::new();
+        write_escaped(&mut out, original).unwrap();
+        assert_eq!(out, r#""a\"b\\c\nd\u0001é/""#);
+        assert_eq!(unescape(&out[1..out.len() - 1]), original);
+    }
+
+    #[test]
+    fn unescape_surrogates() {
+        assert_eq!(unescape(r"\ud83d\ude00!"), "😀!");
+        assert_eq!(unescape(r"\ud83d!"), "\u{fffd}!");
+        assert_eq!(unescape(r"\ude00"), "\u{fffd}");
+        assert_eq!(unescape(r"\u12"), "\u{fffd}12");
+    }
+}
@@ -0,0 +1,265 @@
+//! MongoDB Extended JSON (v2), where the types that JSON lacks are wrapped
+//! in objects such as `{"$numberLong": "123"}` or `{"$date": ...}`, e.g. in
+//! the output of `mongoexport`.
+//!
+//! ```
+//! use yaj::{extjson, json};
+//! let source = r#"{"_id": {"$oid": "507f1f77bcf86cd799439011"}, "n": {"$numberLong": "123"}}"#;
+//! let value = extjson::parse_extended(source).unwrap();
+//! assert_eq!(value, json!({"_id": "507f1f77bcf86cd799439011", "n": 123}));
+//! assert_eq!(
+//!     extjson::to_extended_string(&value["n"], extjson::ExtendedMode::Canonical),
+//!     r#"{"$numberInt":"123"}"#
+//! );
+//! ```
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+
+use crate::prelude::*;
+use crate::{to_string, try_parse, JsonError, JsonNumber, JsonValue, Map};
+
+/// The flavors of Extended JSON written by [`to_extended`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExtendedMode {
+    /// Numbers are wrapped only when JSON cannot represent them, such as
+    /// infinite floats.
+    Relaxed,
+    /// Every number is wrapped with its BSON type, `$numberInt`,
+    /// `$numberLong` or `$numberDouble`.
+    Canonical,
+}
+
+/// Parses `source` like [`try_parse`], then replaces the
+/// Extended JSON wrappers with [`to_native`].
+pub fn parse_extended(source: &str) -> Result<JsonValue<'_>, JsonError> {
+    let mut value = try_parse(source)?;
+    to_native(&mut value);
+    Ok(value)
+}
+
+/// Replaces the Extended JSON wrappers in `value`, in either mode, with
+/// plain values:
+///
+/// - `$numberInt`, `$numberLong` and `$numberDouble` become numbers,
+///   `$timestamp` the integer `t << 32 | i` as in the `bson` module,
+/// - `$date` becomes an RFC 3339 date and time, such as
+///   `1970-01-01T00:00:00.000Z` for `{"$date": {"$numberLong": "0"}}`,
+/// - `$oid`, `$numberDecimal`, `$symbol` and `$code` become their string,
+///   `$binary` its base64 data and `$regularExpression` `/pattern/options`,
+/// - `$undefined` becomes `null`.
+///
+/// Objects that are not well-formed wrappers, and `$minKey` and `$maxKey`,
+/// are left as they are.
+pub fn to_native(value: &mut JsonValue<'_>) {
+    match value {
+        JsonValue::Array(array) => array.iter_mut().for_each(to_native),
+        JsonValue::Object(obj) => {
+            if let Some(native) = unwrap(obj) {
+                *value = native;
+            } else {
+                obj.values_mut().for_each(to_native);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The plain value of the wrapper `obj`.
+fn unwrap<'a>(obj: &Map<'a>) -> Option<JsonValue<'a>> {
+    let mut members = obj.iter();
+    let (key, wrapped) = members.next()?;
+    let (second_key, second) = match members.next() {
+        Some((key, value)) if members.next().is_none() => (Some(&**key), Some(value)),
+        Some(_) => return None,
+        None => (None, None),
+    };
+    let string = |value: &JsonValue<'a>| match value {
+        JsonValue::String(s) => Some(s.clone()),
+        _ => None,
+    };
+    let number = |n| Some(JsonValue::Number(n));
+    match (&**key, second_key) {
+        ("$numberInt", None) => number(JsonNumber::Integer(i64::from(string(wrapped)?.parse::<i32>().ok()?))),
+        ("$numberLong", None) => number(JsonNumber::Integer(string(wrapped)?.parse().ok()?)),
+        ("$numberDouble", None) => number(JsonNumber::Float(match &*string(wrapped)? {
+            "Infinity" => f64::INFINITY,
+            "-Infinity" => f64::NEG_INFINITY,
+            "NaN" => f64::NAN,
+            s if s.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) => s.parse().ok()?,
+            _ => return None,
+        })),
+        ("$numberDecimal", None) | ("$oid", None) | ("$symbol", None) | ("$code", None) => {
+            Some(JsonValue::String(string(wrapped)?))
+        }
+        ("$date", None) => match wrapped {
+            JsonValue::String(s) => Some(JsonValue::String(s.clone())),
+            JsonValue::Object(obj) => match unwrap(obj)? {
+                JsonValue::Number(JsonNumber::Integer(millis)) => {
+                    Some(JsonValue::String(Cow::Owned(date_time(millis))))
+                }
+                _ => None,
+            },
+            JsonValue::Number(JsonNumber::Integer(millis)) => Some(JsonValue::String(Cow::Owned(date_time(*millis)))),
+            _ => None,
+        },
+        ("$binary", None) => Some(JsonValue::String(string(wrapped.get("base64")?)?)),
+        ("$regularExpression", None) => {
+            let pattern = string(wrapped.get("pattern")?)?;
+            let options = string(wrapped.get("options")?)?;
+            Some(JsonValue::String(Cow::Owned(format!("/{}/{}", pattern, options))))
+        }
+        ("$timestamp", None) => {
+            let part = |key| u32::try_from(wrapped.get(key)?.as_u64()?).ok();
+            let n = u64::from(part("t")?) << 32 | u64::from(part("i")?);
+            number(match i64::try_from(n) {
+                Ok(n) => JsonNumber::Integer(n),
+                Err(_) => JsonNumber::Unsigned(n),
+            })
+        }
+        ("$undefined", None) if *wrapped == JsonValue::Boolean(true) => Some(JsonValue::Null),
+        // Code with a scope, whose scope is dropped.
+        ("$code", Some("$scope")) | ("$scope", Some("$code")) => {
+            let code = if &**key == "$code" { wrapped } else { second? };
+            Some(JsonValue::String(string(code)?))
+        }
+        _ => None,
+    }
+}
+
+/// Wraps the numbers of `value` that `mode` requires, the strings being
+/// left as they are.
+///
+/// Integers become `$numberInt` when they fit in 32 bits and `$numberLong`
+/// otherwise, and integers above `i64::MAX`, which BSON cannot hold,
+/// `$numberDecimal`.
+pub fn to_extended(value: &mut JsonValue<'_>, mode: ExtendedMode) {
+    match value {
+        JsonValue::Number(n) => {
+            let (key, text) = match (*n, mode) {
+                (JsonNumber::Float(f), _) if !f.is_finite() => {
+                    let text = if f.is_nan() {
+                        "NaN"
+                    } else if f > 0.0 {
+                        "Infinity"
+                    } else {
+                        "-Infinity"
+                    };
+                    ("$numberDouble", String::from(text))
+                }
+                (JsonNumber::Unsigned(n), _) if i64::try_from(n).is_err() => ("$numberDecimal", n.to_string()),
+                (_, ExtendedMode::Relaxed) => return,
+                (JsonNumber::Integer(n), _) if i32::try_from(n).is_ok() => ("$numberInt", n.to_string()),
+                (JsonNumber::Unsigned(n), _) if n <= i32::MAX as u64 => ("$numberInt", n.to_string()),
+                (JsonNumber::Integer(n), _) => ("$numberLong", n.to_string()),
+                (JsonNumber::Unsigned(n), _) => ("$numberLong", n.to_string()),
+                (JsonNumber::Float(f), _) => ("$numberDouble", to_string(&JsonValue::from(f))),
+            };
+            let mut wrapper = Map::new();
+            wrapper.insert(Cow::Borrowed(key), JsonValue::String(Cow::Owned(text)));
+            *value = JsonValue::Object(wrapper);
+        }
+        JsonValue::Array(array) => array.iter_mut().for_each(|element| to_extended(element, mode)),
+        JsonValue::Object(obj) => obj.values_mut().for_each(|member| to_extended(member, mode)),
+        _ => {}
+    }
+}
+
+/// Writes `value` as compact Extended JSON text, see [`to_extended`].
+pub fn to_extended_string(value: &JsonValue, mode: ExtendedMode) -> String {
+    let mut value = value.clone();
+    to_extended(&mut value, mode);
+    to_string(&value)
+}
+
+/// Milliseconds since the Unix epoch as an RFC 3339 date and time in UTC.
+pub(crate) fn date_time(millis: i64) -> String {
+    let days = millis.div_euclid(86_400_000);
+    let millis = millis.rem_euclid(86_400_000);
+    // Days to a civil date, from Howard Hinnant's `civil_from_days`.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_values() {
+        let source = r#"[
+            {"$numberInt": "-5"}, {"$numberLong": "9007199254740993"}, {"$numberDouble": "1.5e3"},
+            {"$numberDouble": "-Infinity"}, {"$numberDecimal": "1.50"},
+            {"$date": "2024-05-27T07:32:00Z"}, {"$date": {"$numberLong": "-1"}}, {"$date": 0},
+            {"$binary": {"base64": "aGk=", "subType": "00"}},
+            {"$regularExpression": {"pattern": "^a", "options": "i"}},
+            {"$timestamp": {"t": 1, "i": 2}}, {"$undefined": true}, {"$code": "f()", "$scope": {}},
+            {"nested": [{"$oid": "507f1f77bcf86cd799439011"}]}
+        ]"#;
+        let value = parse_extended(source).unwrap();
+        assert_eq!(value[3], JsonValue::from(f64::NEG_INFINITY));
+        assert_eq!(
+            value,
+            json!([
+                -5, 9007199254740993i64, 1500.0, f64::NEG_INFINITY, "1.50",
+                "2024-05-27T07:32:00Z", "1969-12-31T23:59:59.999Z", "1970-01-01T00:00:00.000Z",
+                "aGk=", "/^a/i", 4294967298u64, null, "f()",
+                {"nested": ["507f1f77bcf86cd799439011"]}
+            ])
+        );
+        // Not well-formed wrappers.
+        for source in [
+            r#"{"$numberInt": "2147483648"}"#,
+            r#"{"$numberLong": 1}"#,
+            r#"{"$numberDouble": "inf"}"#,
+            r#"{"$oid": "a", "x": 1}"#,
+            r#"{"$undefined": false}"#,
+            r#"{"$minKey": 1}"#,
+            r#"{"$timestamp": {"t": -1, "i": 0}}"#,
+        ] {
+            assert_eq!(parse_extended(source).unwrap(), crate::parse(source));
+        }
+        assert!(parse_extended("{").is_err());
+    }
+
+    #[test]
+    fn extended_values() {
+        let value = json!([1, -3000000000i64, 18446744073709551615u64, 2.0, f64::NAN, {"b": "s"}]);
+        assert_eq!(
+            to_extended_string(&value, ExtendedMode::Relaxed),
+            r#"[1,-3000000000,{"$numberDecimal":"18446744073709551615"},2.0,{"$numberDouble":"NaN"},{"b":"s"}]"#
+        );
+        let canonical = to_extended_string(&value, ExtendedMode::Canonical);
+        assert_eq!(
+            canonical,
+            concat!(
+                r#"[{"$numberInt":"1"},{"$numberLong":"-3000000000"},{"$numberDecimal":"18446744073709551615"},"#,
+                r#"{"$numberDouble":"2.0"},{"$numberDouble":"NaN"},{"b":"s"}]"#
+            )
+        );
+        let mut value = parse_extended(&canonical).unwrap();
+        assert!(matches!(value[4], JsonValue::Number(JsonNumber::Float(f)) if f.is_nan()));
+        *value.pointer_mut("/4").unwrap() = JsonValue::Null;
+        assert_eq!(
+            value,
+            json!([1, -3000000000i64, "18446744073709551615", 2.0, null, {"b": "s"}])
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Lexer fed with chunks of bytes, e.g. as they arrive from a socket.
+
+use crate::prelude::*;
+use crate::{line_column, JsonToken, JsonTokenType, LexError, LexErrorKind, Lexer};
+
+/// A token that owns its text, as produced by [`FeedLexer`] once the chunk
+/// it was read from is gone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonTokenOwned {
+    pub text: String,
+    pub token_type: JsonTokenType,
+    /// Byte offset of the token in the whole stream.
+    pub offset: usize,
+}
+
+/// Whether `error` only means the input stops in the middle of a token
+/// that the next chunk may complete.
+fn is_truncated(text: &str, error: &LexError) -> bool {
+    match error.kind {
+        LexErrorKind::UnterminatedString | LexErrorKind::UnexpectedEof => true,
+        LexErrorKind::InvalidLiteral => {
+            let rest = &text[error.offset..];
+            ["true", "false", "null"]
+                .iter()
+                .any(|literal| literal.len() > rest.len() && literal.starts_with(rest))
+        }
+        _ => false,
+    }
+}
+
+/// Length of the start of `bytes`, read inside a string, that the lexer
+/// accepts without the string ending, if the rest may only be the start of
+/// an escape or a character. `None` when the lexer must look at `bytes`:
+/// they may end the string or hold an error, or escape a surrogate.
+fn string_content(bytes: &[u8]) -> Option<usize> {
+    let mut len = 0;
+    while len < bytes.len() {
+        let rest = &bytes[len..];
+        len += match rest[0] {
+            b'"' | 0..=0x1F => return None,
+            b'\\' => match rest.get(1) {
+                None => break,
+                Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => 2,
+                Some(b'u') => {
+                    let digits = &rest[2..rest.len().min(6)];
+                    if !digits.iter().all(u8::is_ascii_hexdigit) || matches!(digits.first(), Some(b'd' | b'D')) {
+                        return None;
+                    }
+                    if digits.len() < 4 {
+                        break;
+                    }
+                    6
+                }
+                Some(_) => return None,
+            },
+            _ => 1,
+        };
+    }
+    match core::str::from_utf8(&bytes[..len]) {
+        Ok(_) => Some(len),
+        Err(e) if e.error_len().is_none() => Some(e.valid_up_to()),
+        Err(_) => None,
+    }
+}
+
+/// Resumable lexer: tokens split across chunks, including multi-byte
+/// characters, are returned once complete.
+///
+/// ```
+/// # use yaj::{FeedLexer, JsonTokenType};
+/// let mut lexer = FeedLexer::new();
+/// assert!(lexer.feed(b"[\"ab").unwrap().len() == 1);
+/// let tokens = lexer.feed(b"c\", 12").unwrap();
+/// assert_eq!(tokens[0].text, "\"abc\"");
+/// assert_eq!(tokens[1].token_type, JsonTokenType::Comma);
+/// // `12` may go on in the next chunk.
+/// let tokens = lexer.finish().unwrap();
+/// assert_eq!(tokens[0].text, "12");
+/// ```
+pub struct FeedLexer {
+    /// Input following the last token returned.
+    buffer: Vec<u8>,
+    /// Position of the start of `buffer` in the stream.
+    offset: usize,
+    /// Position in `buffer` up to which the string it ends with has been
+    /// read, so that a long string is not lexed again with every chunk.
+    string_end: Option<usize>,
+    line: usize,
+    column: usize,
+    failed: bool,
+}
+
+impl Default for FeedLexer {
+    fn default() -> Self {
+        Self {
+            buffer: Vec::new(),
+            offset: 0,
+            string_end: None,
+            line: 1,
+            column: 1,
+            failed: false,
+        }
+    }
+}
+
+impl FeedLexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `chunk` to the input and returns the tokens it completes.
+    /// Once an error has been returned, no more tokens are produced.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<JsonTokenOwned>, LexError> {
+        if self.failed {
+            return Ok(Vec::new());
+        }
+        self.buffer.extend_from_slice(chunk);
+        if let Some(string_end) = self.string_end.take() {
+            if let Some(len) = string_content(&self.buffer[string_end..]) {
+                self.string_end = Some(string_end + len);
+                return Ok(Vec::new());
+            }
+        }
+        self.lex(false)
+    }
+
+    /// Signals the end of the input and returns the remaining tokens. A
+    /// token still incomplete at this point is an error.
+    pub fn finish(&mut self) -> Result<Vec<JsonTokenOwned>, LexError> {
+        if self.failed {
+            return Ok(Vec::new());
+        }
+        self.string_end = None;
+        self.lex(true)
+    }
+
+    /// Translates a position in `buffer` into a position in the stream.
+    fn absolute(&self, mut error: LexError) -> LexError {
+        error.offset += self.offset;
+        if error.line == 1 {
+            error.column += self.column - 1;
+        }
+        error.line += self.line - 1;
+        error
+    }
+
+    fn own(&self, token: &JsonToken) -> JsonTokenOwned {
+        JsonTokenOwned {
+            text: token.slice.to_string(),
+            token_type: token.token_type.clone(),
+            offset: self.offset + token.offset,
+        }
+    }
+
+    fn lex(&mut self, last: bool) -> Result<Vec<JsonTokenOwned>, LexError> {
+        let text = match core::str::from_utf8(&self.buffer) {
+            Ok(text) => text,
+            // The last character may be completed by the next chunk.
+            Err(e) if e.error_len().is_none() && !last => {
+                core::str::from_utf8(&self.buffer[..e.valid_up_to()]).expect("checked prefix")
+            }
+            Err(e) => {
+                let valid = core::str::from_utf8(&self.buffer[..e.valid_up_to()]).expect("checked prefix");
+                let error = LexError {
+                    kind: LexErrorKind::InvalidUtf8,
+                    offset: e.valid_up_to(),
+                    line: 0,
+                    column: 0,
+                    chr: None,
+                };
+                self.failed = true;
+                return Err(self.absolute(error.locate(valid)));
+            }
+        };
+
+        let mut tokens = Vec::new();
+        // End of the input that does not need to be lexed again.
+        let mut end = text.len();
+        let mut string_end = None;
+        // Only the start of the stream may have a byte order mark.
+        let lexer = if self.offset == 0 {
+            Lexer::new(text)
+        } else {
+            Lexer::continuing(text)
+        };
+        for result in lexer {
+            match result {
+                Ok(token) => {
+                    let token_end = token.offset + token.slice.len();
+                    // A number is only terminated by the char following it.
+                    if !last && token.token_type == JsonTokenType::Number && token_end == text.len() {
+                        end = token.offset;
+                        break;
+                    }
+                    tokens.push(self.own(&token));
+                }
+                Err(e) if !last && is_truncated(text, &e) => {
+                    end = tokens.last().map_or(0, |t| t.offset - self.offset + t.text.len());
+                    if e.kind == LexErrorKind::UnterminatedString {
+                        // Only whitespace comes before the opening quote.
+                        string_end = text[end..].find('"').and_then(|quote| {
+                            let content = end + quote + 1;
+                            string_content(&self.buffer[content..]).map(|len| content + len - end)
+                        });
+                    }
+                    break;
+                }
+                Err(e) => {
+                    self.failed = true;
+                    return Err(self.absolute(e));
+                }
+            }
+        }
+
+        let (line, column) = line_column(text, end);
+        if line == 1 {
+            self.column += column - 1;
+        } else {
+            self.line += line - 1;
+            self.column = column;
+        }
+        self.offset += end;
+        self.buffer.drain(..end);
+        self.string_end = string_end;
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(chunks: &[&[u8]]) -> Result<Vec<JsonTokenOwned>, LexError> {
+        let mut lexer = FeedLexer::new();
+        let mut tokens = Vec::new();
+        for chunk in chunks {
+            tokens.extend(lexer.feed(chunk)?);
+        }
+        tokens.extend(lexer.finish()?);
+        Ok(tokens)
+    }
+
+    #[test]
+    fn any_split_gives_the_same_tokens() {
+        let source = "\u{feff}{\"caf\u{e9}\\u00e9\\ud83d\\ude00\\/\": [-1.5e3, true, null, \"\\\"\u{1f600}\"],\n \"b\": false, \"c\": 0}";
+        let expected: Vec<_> = Lexer::new(source)
+            .map(|token| {
+                let token = token.unwrap();
+                JsonTokenOwned {
+                    text: token.slice.to_string(),
+                    token_type: token.token_type,
+                    offset: token.offset,
+                }
+            })
+            .collect();
+        let bytes = source.as_bytes();
+        for split in 0..=bytes.len() {
+            assert_eq!(feed_all(&[&bytes[..split], &bytes[split..]]).unwrap(), expected);
+        }
+        let single_bytes: Vec<&[u8]> = bytes.chunks(1).collect();
+        assert_eq!(feed_all(&single_bytes).unwrap(), expected);
+    }
+
+    #[test]
+    fn errors_are_located_in_the_stream() {
+        let error = feed_all(&[b"[1,\n  tr", b"ue, x]"]).unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::InvalidChar);
+        assert_eq!((error.offset, error.line, error.column), (12, 2, 9));
+
+        let error = feed_all(&[b"[\"abc"]).unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::UnterminatedString);
+        let error = feed_all(&[b"[tr"]).unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::InvalidLiteral);
+        let error = feed_all(&[b"[\"\xc3"]).unwrap_err();
+        assert_eq!(error.kind, LexErrorKind::InvalidUtf8);
+        let error = feed_all(&[b"[1,", b" \"\xff"]).unwrap_err();
+        assert_eq!((error.kind, error.offset), (LexErrorKind::InvalidUtf8, 5));
+
+        for source in [
+            "null\u{feff}",
+            "[\"a\u{1}\"]",
+            "[\"ab\\x\"]",
+            "[\"\\u12g4\"]",
+            "[\"\\udc00\", 1]",
+        ]
+        .iter()
+        {
+            let expected = Lexer::new(source).find_map(Result::err);
+            let single_bytes: Vec<&[u8]> = source.as_bytes().chunks(1).collect();
+            assert_eq!(feed_all(&single_bytes).err(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn long_strings_are_read_once() {
+        let mut lexer = FeedLexer::new();
+        assert_eq!(lexer.feed(b"[\"").unwrap().len(), 1);
+        for _ in 0..100_000 {
+            assert!(lexer.feed("\\né".as_bytes()).unwrap().is_empty());
+        }
+        assert_eq!(lexer.string_end, Some(400_001));
+        let tokens = lexer.feed(b"\"]").unwrap();
+        assert_eq!(tokens[0].text.len(), 400_002);
+        assert_eq!(tokens[1].token_type, JsonTokenType::RightBracket);
+    }
+}
@@ -0,0 +1,459 @@
+//! Nested documents seen as flat maps from paths such as `age.value` to
+//! the values found there, for diff-friendly storage or for export as CSV
+//! or TSV, e.g. to open event streams in a spreadsheet.
+//!
+//! ```
+//! use yaj::{flatten, json};
+//! let events = [
+//!     json!({"age": {"value": 31}, "tags": ["a", "b"], "uid": 1}),
+//!     json!({"note": "x, y", "uid": 2}),
+//! ];
+//! assert_eq!(
+//!     flatten::to_csv(&events, &flatten::CsvOptions::default()),
+//!     "age.value,tags,uid,note\n31,a;b,1,\n,,2,\"x, y\"\n"
+//! );
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use crate::pointer::{array_index, push_token, reference_tokens};
+use crate::prelude::*;
+use crate::{stable_members, to_string, JsonValue, Map};
+
+/// How the paths of [`flatten`] and [`unflatten`] are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyStyle {
+    /// Keys joined with `.`, such as `faceRect.height` or `tags.0`, the
+    /// root being the empty path. Keys containing a `.` cannot be told
+    /// apart from nested ones.
+    Dotted,
+    /// JSON Pointers such as `/faceRect/height`, which allow any key.
+    Pointer,
+}
+
+/// Maps the path of each scalar, empty array and empty object in `value`
+/// to a copy of it. Array elements are found under their index.
+///
+/// ```
+/// use yaj::{flatten::{flatten, unflatten, KeyStyle}, json};
+/// let config = json!({"faceRect": {"height": 0.5}, "tags": ["a"], "extra": {}});
+/// let flat = flatten(&config, KeyStyle::Dotted);
+/// assert_eq!(flat.keys().collect::<Vec<_>>(), ["extra", "faceRect.height", "tags.0"]);
+/// assert_eq!(flat["tags.0"], json!("a"));
+/// assert_eq!(unflatten(flat, KeyStyle::Dotted).unwrap(), config);
+/// assert!(flatten(&config, KeyStyle::Pointer).contains_key("/faceRect/height"));
+/// ```
+pub fn flatten<'a>(value: &JsonValue<'a>, style: KeyStyle) -> BTreeMap<String, JsonValue<'a>> {
+    let mut flat = BTreeMap::new();
+    flatten_into(value, String::new(), style, &mut flat);
+    flat
+}
+
+fn flatten_into<'a>(value: &JsonValue<'a>, path: String, style: KeyStyle, flat: &mut BTreeMap<String, JsonValue<'a>>) {
+    let child = |key: &str| {
+        let mut child = path.clone();
+        match style {
+            KeyStyle::Dotted if path.is_empty() => child.push_str(key),
+            KeyStyle::Dotted => {
+                child.push('.');
+                child.push_str(key);
+            }
+            KeyStyle::Pointer => push_token(&mut child, key),
+        }
+        child
+    };
+    match value {
+        JsonValue::Object(obj) if !obj.is_empty() => {
+            for (key, member) in obj {
+                flatten_into(member, child(key), style, flat);
+            }
+        }
+        JsonValue::Array(array) if !array.is_empty() => {
+            for (idx, element) in array.iter().enumerate() {
+                flatten_into(element, child(&idx.to_string()), style, flat);
+            }
+        }
+        leaf => {
+            flat.insert(path, leaf.clone());
+        }
+    }
+}
+
+/// The entries given to [`unflatten`] do not describe a document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnflattenError {
+    pub msg: &'static str,
+    pub key: String,
+}
+impl fmt::Display for UnflattenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.msg, self.key)
+    }
+}
+impl core::error::Error for UnflattenError {}
+
+/// A value being rebuilt by [`unflatten`].
+enum Node<'a> {
+    Leaf(JsonValue<'a>),
+    Branch(BTreeMap<String, Node<'a>>),
+}
+
+impl<'a> Node<'a> {
+    /// An empty array or object, which nested paths can fill.
+    fn is_empty_container(&self) -> bool {
+        match self {
+            Node::Leaf(JsonValue::Array(array)) => array.is_empty(),
+            Node::Leaf(JsonValue::Object(obj)) => obj.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Objects whose keys are exactly the indices from 0 become arrays.
+    fn into_value(self) -> JsonValue<'a> {
+        match self {
+            Node::Leaf(value) => value,
+            Node::Branch(children) => {
+                let is_array = children
+                    .keys()
+                    .all(|key| array_index(key).is_some_and(|idx| idx < children.len()));
+                if is_array {
+                    let mut elements: Vec<_> = children
+                        .into_iter()
+                        .map(|(key, node)| (array_index(&key), node))
+                        .collect();
+                    elements.sort_unstable_by_key(|(idx, _)| *idx);
+                    JsonValue::Array(elements.into_iter().map(|(_, node)| node.into_value()).collect())
+                } else {
+                    JsonValue::Object(
+                        children
+                            .into_iter()
+                            .map(|(key, node)| (Cow::Owned(key), node.into_value()))
+                            .collect::<Map>(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Rebuilds the document whose [`flatten`]ed entries are `entries`:
+/// objects are created along the paths, and those whose keys are all the
+/// indices from 0 become arrays. A path that is an invalid pointer, or
+/// that is the same as or inside another one, is an error, except inside
+/// an empty array or object.
+pub fn unflatten<'a, I, K>(entries: I, style: KeyStyle) -> Result<JsonValue<'a>, UnflattenError>
+where
+    I: IntoIterator<Item = (K, JsonValue<'a>)>,
+    K: AsRef<str>,
+{
+    // Branches are only empty while the path leading to a new leaf is built.
+    let mut root = Node::Branch(BTreeMap::new());
+    let mut empty = true;
+    for (key, value) in entries {
+        let key = key.as_ref();
+        let error = |msg| UnflattenError {
+            msg,
+            key: String::from(key),
+        };
+        let tokens: Vec<Cow<str>> = match style {
+            KeyStyle::Dotted if key.is_empty() => Vec::new(),
+            KeyStyle::Dotted => key.split('.').map(Cow::Borrowed).collect(),
+            KeyStyle::Pointer => reference_tokens(key)
+                .ok_or_else(|| error("Invalid JSON Pointer"))?
+                .collect(),
+        };
+        let mut node = &mut root;
+        for token in tokens {
+            if node.is_empty_container() {
+                *node = Node::Branch(BTreeMap::new());
+            }
+            node = match node {
+                Node::Branch(children) => children
+                    .entry(token.into_owned())
+                    .or_insert_with(|| Node::Branch(BTreeMap::new())),
+                Node::Leaf(_) => return Err(error("Conflicting key")),
+            };
+        }
+        match node {
+            Node::Branch(children) if children.is_empty() => *node = Node::Leaf(value),
+            _ => return Err(error("Conflicting key")),
+        }
+        empty = false;
+    }
+    Ok(if empty { JsonValue::Null } else { root.into_value() })
+}
+
+/// How arrays are written, see [`CsvOptions::arrays`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArrayMode {
+    /// All the elements in one cell, separated by [`CsvOptions::join_separator`].
+    /// Arrays and objects among them are written as compact JSON text.
+    Join,
+    /// One row per element, the other cells being repeated. Several
+    /// arrays in a value give a row for each combination of elements, and
+    /// an empty array an empty cell.
+    Explode,
+}
+
+/// Settings of [`to_csv`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    /// Separator of the cells: `,` for CSV, `\t` for TSV.
+    pub delimiter: char,
+    /// Separator of the keys in the name of a column.
+    pub key_separator: String,
+    pub arrays: ArrayMode,
+    /// Separator of the elements joined with [`ArrayMode::Join`].
+    pub join_separator: String,
+    /// Start with a line of column names.
+    pub header: bool,
+}
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            key_separator: String::from("."),
+            arrays: ArrayMode::Join,
+            join_separator: String::from(";"),
+            header: true,
+        }
+    }
+}
+impl CsvOptions {
+    /// Tab-separated values, with the other settings by default.
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Self::default()
+        }
+    }
+}
+
+/// Cells of a row, by column name.
+type Row = Vec<(String, String)>;
+
+/// Writes `values` as CSV, each giving one row, or more when arrays are
+/// exploded. Columns are named after the keys leading to each scalar, and
+/// ordered as first seen, object members being visited in key order unless
+/// the `preserve_order` feature is enabled. Values that are not objects go
+/// in a column named `value`. Missing cells and `null` are left empty;
+/// cells holding the delimiter, quotes or line breaks are quoted. Each line
+/// ends with `\n`.
+pub fn to_csv(values: &[JsonValue], opts: &CsvOptions) -> String {
+    let mut columns: Vec<String> = Vec::new();
+    let mut rows = Vec::new();
+    for value in values {
+        for row in csv_rows(value, "", opts) {
+            for (column, _) in &row {
+                if !columns.contains(column) {
+                    columns.push(column.clone());
+                }
+            }
+            rows.push(row);
+        }
+    }
+
+    let mut out = String::new();
+    if opts.header && !columns.is_empty() {
+        write_line(&mut out, columns.iter().map(String::as_str), opts.delimiter);
+    }
+    for row in &rows {
+        let cells = columns.iter().map(|column| {
+            row.iter()
+                .find(|(name, _)| name == column)
+                .map_or("", |(_, cell)| cell.as_str())
+        });
+        write_line(&mut out, cells, opts.delimiter);
+    }
+    out
+}
+
+/// Rows standing for `value`, found under the column name `prefix`.
+fn csv_rows(value: &JsonValue, prefix: &str, opts: &CsvOptions) -> Vec<Row> {
+    let name = |key: &str| {
+        if prefix.is_empty() {
+            String::from(key)
+        } else {
+            format!("{}{}{}", prefix, opts.key_separator, key)
+        }
+    };
+    match value {
+        JsonValue::Object(obj) => {
+            let mut rows = vec![Row::new()];
+            for (key, member) in stable_members(obj) {
+                let member_rows = csv_rows(member, &name(key), opts);
+                rows = rows
+                    .iter()
+                    .flat_map(|row| {
+                        member_rows.iter().map(move |member_row| {
+                            let mut row = row.clone();
+                            row.extend(member_row.iter().cloned());
+                            row
+                        })
+                    })
+                    .collect();
+            }
+            rows
+        }
+        JsonValue::Array(array) if opts.arrays == ArrayMode::Explode && !array.is_empty() => array
+            .iter()
+            .flat_map(|element| csv_rows(element, prefix, opts))
+            .collect(),
+        JsonValue::Array(array) => {
+            let cells: Vec<_> = array
+                .iter()
+                .map(|element| match element {
+                    JsonValue::Array(_) | JsonValue::Object(_) => to_string(element),
+                    scalar => cell(scalar),
+                })
+                .collect();
+            vec![vec![(root_name(prefix), cells.join(&opts.join_separator))]]
+        }
+        scalar => vec![vec![(root_name(prefix), cell(scalar))]],
+    }
+}
+
+fn root_name(prefix: &str) -> String {
+    if prefix.is_empty() {
+        String::from("value")
+    } else {
+        String::from(prefix)
+    }
+}
+
+fn cell(scalar: &JsonValue) -> String {
+    match scalar {
+        JsonValue::String(s) => s.to_string(),
+        JsonValue::Null => String::new(),
+        other => to_string(other),
+    }
+}
+
+fn write_line<'c>(out: &mut String, cells: impl Iterator<Item = &'c str>, delimiter: char) {
+    for (idx, cell) in cells.enumerate() {
+        if idx > 0 {
+            out.push(delimiter);
+        }
+        if cell.contains([delimiter, '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&cell.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(cell);
+        }
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn joined_arrays() {
+        let values = [
+            parse(
+                r#"{"eventType": "faceCapture", "face": {"rect": {"x": 0.5}, "uid": 1}, "tags": [1, "a", null, [2], {"k": true}]}"#,
+            ),
+            parse(r#"{"eventType": "motion", "face": null, "note": "said \"hi\"\nthen left"}"#),
+            parse("\"bare\""),
+        ];
+        assert_eq!(
+            to_csv(&values, &CsvOptions::default()),
+            "eventType,face.rect.x,face.uid,tags,face,note,value\n\
+             faceCapture,0.5,1,\"1;a;;[2];{\"\"k\"\":true}\",,,\n\
+             motion,,,,,\"said \"\"hi\"\"\nthen left\",\n\
+             ,,,,,,bare\n"
+        );
+        let opts = CsvOptions {
+            key_separator: String::from("/"),
+            join_separator: String::from("|"),
+            ..CsvOptions::tsv()
+        };
+        assert_eq!(
+            to_csv(&values[..1], &opts),
+            "eventType\tface/rect/x\tface/uid\ttags\nfaceCapture\t0.5\t1\t\"1|a||[2]|{\"\"k\"\":true}\"\n"
+        );
+        let opts = CsvOptions {
+            header: false,
+            ..CsvOptions::default()
+        };
+        assert_eq!(to_csv(&values[2..], &opts), "bare\n");
+        assert_eq!(to_csv(&[], &CsvOptions::default()), "");
+    }
+
+    #[test]
+    fn exploded_arrays() {
+        let values = [
+            parse(r#"{"faces": [{"age": 31}, {"age": 25, "mask": true}], "tags": ["a", "b"], "uid": 1}"#),
+            parse(r#"{"faces": [], "tags": ["c"], "uid": 2}"#),
+        ];
+        let opts = CsvOptions {
+            arrays: ArrayMode::Explode,
+            ..CsvOptions::default()
+        };
+        assert_eq!(
+            to_csv(&values, &opts),
+            "faces.age,tags,uid,faces.mask,faces\n\
+             31,a,1,,\n\
+             31,b,1,,\n\
+             25,a,1,true,\n\
+             25,b,1,true,\n\
+             ,c,2,,\n"
+        );
+        assert_eq!(to_csv(&[parse("[1, [2, 3]]")], &opts), "value\n1\n2\n3\n");
+    }
+
+    #[test]
+    fn flat_round_trip() {
+        let value = parse(r#"{"a": {"b": [1, {"c": null}], "e": []}, "d/x.y": "s", "f": {}}"#);
+        let dotted = flatten(&value, KeyStyle::Dotted);
+        assert_eq!(
+            dotted.keys().collect::<Vec<_>>(),
+            ["a.b.0", "a.b.1.c", "a.e", "d/x.y", "f"]
+        );
+        assert_eq!(dotted["a.e"], json!([]));
+        let pointers = flatten(&value, KeyStyle::Pointer);
+        assert_eq!(
+            pointers.keys().collect::<Vec<_>>(),
+            ["/a/b/0", "/a/b/1/c", "/a/e", "/d~1x.y", "/f"]
+        );
+        assert_eq!(unflatten(pointers, KeyStyle::Pointer).unwrap(), value);
+        // The dot in "d/x.y" reads as nesting.
+        assert_eq!(
+            unflatten(dotted, KeyStyle::Dotted).unwrap().pointer("/d~1x/y"),
+            Some(&json!("s"))
+        );
+
+        let array = json!([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(
+            unflatten(flatten(&array, KeyStyle::Dotted), KeyStyle::Dotted).unwrap(),
+            array
+        );
+        assert_eq!(flatten(&json!(3), KeyStyle::Pointer)[""], json!(3));
+        assert_eq!(unflatten([("", json!(3))], KeyStyle::Dotted).unwrap(), json!(3));
+        assert_eq!(
+            unflatten(Vec::<(&str, JsonValue)>::new(), KeyStyle::Dotted).unwrap(),
+            JsonValue::Null
+        );
+        // Indices with a gap or a leading zero keep an object.
+        let sparse = unflatten([("0", json!(1)), ("2", json!(2)), ("01", json!(3))], KeyStyle::Dotted).unwrap();
+        assert_eq!(sparse, json!({"0": 1, "01": 3, "2": 2}));
+    }
+
+    #[test]
+    fn unflatten_errors() {
+        let error = unflatten([("a", json!(1)), ("a.b", json!(2))], KeyStyle::Dotted).unwrap_err();
+        assert_eq!(error.key, "a.b");
+        let error = unflatten([("/a/b", json!(1)), ("/a", json!(2))], KeyStyle::Pointer).unwrap_err();
+        assert_eq!(error.to_string(), "Conflicting key '/a'");
+        let error = unflatten([("a", json!(1))], KeyStyle::Pointer).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid JSON Pointer 'a'");
+        // An empty container is only a placeholder.
+        let value = unflatten([("/a", json!({})), ("/a/b", json!(1))], KeyStyle::Pointer).unwrap();
+        assert_eq!(value, json!({"a": {"b": 1}}));
+    }
+}
@@ -0,0 +1,334 @@
+//! Conversion between values and form data, as in query strings and
+//! `application/x-www-form-urlencoded` bodies, where `a[b][0]=1` is the
+//! first element of the array `b` of the object `a`.
+//!
+//! ```
+//! use yaj::{form, json};
+//! let value = form::from_urlencoded("user[name]=Jo+Doe&user[tags][]=a&user[tags][]=b&page=2").unwrap();
+//! assert_eq!(value, json!({"page": "2", "user": {"name": "Jo Doe", "tags": ["a", "b"]}}));
+//! assert_eq!(form::to_urlencoded(&json!({"n": [1], "q": "a&b"})).unwrap(), "n%5B0%5D=1&q=a%26b");
+//! ```
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::parser::ParseOptions;
+use crate::pointer::array_index;
+use crate::prelude::*;
+use crate::{stable_members, JsonValue, JsonValueOwned, Map};
+
+/// Settings of [`from_urlencoded_with`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormOptions {
+    /// Read the values `true`, `false`, `null` and numbers as such instead
+    /// of as strings.
+    pub infer_types: bool,
+}
+
+/// Form data that does not describe a value, or a value that cannot be
+/// written as form data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormError {
+    pub msg: &'static str,
+    /// The decoded key of the offending pair.
+    pub key: String,
+}
+impl fmt::Display for FormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.msg, self.key)
+    }
+}
+impl core::error::Error for FormError {}
+
+/// Reads form data as an object whose values are strings, arrays and
+/// objects, see [`from_urlencoded_with`].
+pub fn from_urlencoded(source: &str) -> Result<JsonValueOwned, FormError> {
+    from_urlencoded_with(source, &FormOptions::default())
+}
+
+/// Reads the `&`-separated `key=value` pairs of `source`, `+` and
+/// percent-encoded bytes being decoded first.
+///
+/// Keys such as `a[b]` set the member `b` of the object `a`, `a[0]` the
+/// first element of the array `a` and `a[]` a new element. An index past
+/// the end of an array adds an element, as arrays have no gaps. A key
+/// repeated, such as in `tag=a&tag=b`, gives an array. A key used both for
+/// a value and for members, or for members and elements, is an error, as
+/// is a key with more brackets than the default
+/// [`ParseOptions::max_depth`].
+pub fn from_urlencoded_with(source: &str, opts: &FormOptions) -> Result<JsonValueOwned, FormError> {
+    let mut root = Map::new();
+    for pair in source.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let key = decode(key).map_err(|msg| FormError {
+            msg,
+            key: String::from(key),
+        })?;
+        let error = |msg| FormError { msg, key: key.clone() };
+        let value = decode(value).map_err(error)?;
+        let value = if opts.infer_types {
+            scalar(value)
+        } else {
+            JsonValue::String(Cow::Owned(value))
+        };
+        let (name, path) = segments(&key).ok_or_else(|| error("Invalid brackets in key"))?;
+        if name.is_empty() {
+            return Err(error("Empty key"));
+        }
+        if path.len() > ParseOptions::default().max_depth {
+            return Err(error("Nesting depth exceeds the limit in key"));
+        }
+        let target = root.entry(Cow::Owned(String::from(name))).or_insert(JsonValue::Null);
+        insert(target, &path, value).map_err(error)?;
+    }
+    Ok(JsonValue::Object(root))
+}
+
+/// The name and the bracketed segments of `key`, such as `a` and `["b", ""]`
+/// for `a[b][]`.
+fn segments(key: &str) -> Option<(&str, Vec<&str>)> {
+    let (name, mut rest) = match key.find('[') {
+        Some(start) => (&key[..start], &key[start..]),
+        None => {
+            return if key.contains(']') {
+                None
+            } else {
+                Some((key, Vec::new()))
+            }
+        }
+    };
+    if name.contains(']') {
+        return None;
+    }
+    let mut path = Vec::new();
+    while !rest.is_empty() {
+        let end = rest.find(']')?;
+        let segment = rest.strip_prefix('[')?.get(..end - 1)?;
+        if segment.contains('[') {
+            return None;
+        }
+        path.push(segment);
+        rest = &rest[end + 1..];
+    }
+    Some((name, path))
+}
+
+/// Sets the value at `path` in `target`, `Null` meaning not set yet.
+fn insert(target: &mut JsonValueOwned, path: &[&str], value: JsonValueOwned) -> Result<(), &'static str> {
+    let (segment, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            match target {
+                JsonValue::Null => *target = value,
+                JsonValue::Array(array) => array.push(value),
+                JsonValue::Object(_) => return Err("Conflicting key"),
+                _ => *target = JsonValue::Array(vec![core::mem::take(target), value]),
+            }
+            return Ok(());
+        }
+    };
+    if target.is_null() {
+        *target = if segment.is_empty() || array_index(segment).is_some() {
+            JsonValue::Array(Vec::new())
+        } else {
+            JsonValue::Object(Map::new())
+        };
+    }
+    let child = match target {
+        JsonValue::Object(obj) => obj.entry(Cow::Owned(String::from(*segment))).or_insert(JsonValue::Null),
+        JsonValue::Array(array) => {
+            let index = match array_index(segment) {
+                Some(index) if index < array.len() => index,
+                _ if segment.is_empty() || array_index(segment).is_some() => {
+                    array.push(JsonValue::Null);
+                    array.len() - 1
+                }
+                _ => return Err("Conflicting key"),
+            };
+            &mut array[index]
+        }
+        _ => return Err("Conflicting key"),
+    };
+    insert(child, rest, value)
+}
+
+/// `value` as a boolean, `null` or number when it is one.
+fn scalar(value: String) -> JsonValueOwned {
+    match crate::try_parse(&value) {
+        Ok(JsonValue::Null) => JsonValue::Null,
+        Ok(JsonValue::Boolean(b)) => JsonValue::Boolean(b),
+        Ok(JsonValue::Number(n)) => JsonValue::Number(n),
+        _ => JsonValue::String(Cow::Owned(value)),
+    }
+}
+
+/// Decodes `+` and percent-encoded bytes.
+fn decode(text: &str) -> Result<String, &'static str> {
+    if !text.contains(['+', '%']) {
+        return Ok(String::from(text));
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = rest.get(..2).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+                match hex.and_then(|hex| u8::from_str_radix(core::str::from_utf8(hex).ok()?, 16).ok()) {
+                    Some(decoded) => bytes.push(decoded),
+                    None => return Err("Invalid percent-encoding in"),
+                }
+                rest = &rest[2..];
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| "Invalid UTF-8 in")
+}
+
+/// Writes the object `value` as form data, in the order of
+/// [`to_string`](crate::to_string).
+///
+/// Array elements get their index, as in `a[0]`, so that objects in
+/// arrays can be read back. `null` is written as an empty value, numbers
+/// and booleans as their text, and empty arrays and objects are left
+/// out.
+pub fn to_urlencoded(value: &JsonValue) -> Result<String, FormError> {
+    let obj = match value {
+        JsonValue::Object(obj) => obj,
+        _ => {
+            return Err(FormError {
+                msg: "Form data that is not an object",
+                key: String::new(),
+            })
+        }
+    };
+    let mut out = String::new();
+    for (key, member) in stable_members(obj) {
+        pairs(&mut out, &mut encode(key), member);
+    }
+    Ok(out)
+}
+
+/// Writes the pairs of `value`, whose encoded key is `key`.
+fn pairs(out: &mut String, key: &mut String, value: &JsonValue) {
+    match value {
+        JsonValue::Array(array) => {
+            for (index, element) in array.iter().enumerate() {
+                nested_pairs(out, key, &index.to_string(), element);
+            }
+        }
+        JsonValue::Object(obj) => {
+            for (member_key, member) in stable_members(obj) {
+                nested_pairs(out, key, member_key, member);
+            }
+        }
+        scalar => {
+            if !out.is_empty() {
+                out.push('&');
+            }
+            out.push_str(key);
+            out.push('=');
+            match scalar {
+                JsonValue::Null => {}
+                JsonValue::String(s) => out.push_str(&encode(s)),
+                other => out.push_str(&crate::to_string(other)),
+            }
+        }
+    }
+}
+
+/// Writes the pairs of the member or element `segment` of the value whose
+/// encoded key is `key`.
+fn nested_pairs(out: &mut String, key: &mut String, segment: &str, value: &JsonValue) {
+    let len = key.len();
+    key.push_str("%5B");
+    key.push_str(&encode(segment));
+    key.push_str("%5D");
+    pairs(out, key, value);
+    key.truncate(len);
+}
+
+/// Percent-encodes all but the alphanumerics and `*-._`, writing spaces
+/// as `+`.
+fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => out.push(char::from(byte)),
+            b' ' => out.push('+'),
+            _ => {
+                const HEX: &[u8; 16] = b"0123456789ABCDEF";
+                out.push('%');
+                out.push(char::from(HEX[usize::from(byte >> 4)]));
+                out.push(char::from(HEX[usize::from(byte & 0xf)]));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_form() {
+        let value = from_urlencoded("a[b][0]=1&a[b][1][c]=x%20y&&a[d]&tag=a&tag=b+c&e[]=&e[5]=z&%C3%A9=%3D").unwrap();
+        assert_eq!(
+            value,
+            json!({"a": {"b": ["1", {"c": "x y"}], "d": ""}, "e": ["", "z"], "tag": ["a", "b c"], "é": "="})
+        );
+        let opts = FormOptions { infer_types: true };
+        assert_eq!(
+            from_urlencoded_with("a=true&b=-1.5&c=null&d=1e&e=%221%22", &opts).unwrap(),
+            json!({"a": true, "b": -1.5, "c": null, "d": "1e", "e": "\"1\""})
+        );
+        assert_eq!(from_urlencoded("").unwrap(), json!({}));
+
+        let error = |source| from_urlencoded(source).unwrap_err().to_string();
+        assert_eq!(error("a=1&a[b]=2"), "Conflicting key 'a[b]'");
+        assert_eq!(error("a[b]=1&a=2"), "Conflicting key 'a'");
+        assert_eq!(error("a[0]=1&a[b]=2"), "Conflicting key 'a[b]'");
+        assert_eq!(error("a[b]=1&a[b][c]=2"), "Conflicting key 'a[b][c]'");
+        assert_eq!(
+            from_urlencoded("a[b]=1&a[b]=2").unwrap(),
+            json!({"a": {"b": ["1", "2"]}})
+        );
+        assert_eq!(error("a[b=1"), "Invalid brackets in key 'a[b'");
+        assert_eq!(error("a]=1"), "Invalid brackets in key 'a]'");
+        assert_eq!(error("a[b]c=1"), "Invalid brackets in key 'a[b]c'");
+        assert_eq!(error("[a]=1"), "Empty key '[a]'");
+        assert_eq!(error("a=%2"), "Invalid percent-encoding in 'a'");
+        assert_eq!(error("a=%+1"), "Invalid percent-encoding in 'a'");
+        assert_eq!(error("a%ff=1"), "Invalid UTF-8 in 'a%ff'");
+
+        let nested = |depth| format!("a{}=1", "[b]".repeat(depth));
+        assert!(from_urlencoded(&nested(128)).is_ok());
+        let deep = nested(200_000);
+        assert_eq!(
+            from_urlencoded(&deep).unwrap_err().msg,
+            "Nesting depth exceeds the limit in key"
+        );
+    }
+
+    #[test]
+    fn to_form() {
+        let value = json!({"a": {"b": [1, {"c": "x y"}], "d": null}, "e": [], "f & g": true});
+        let text = to_urlencoded(&value).unwrap();
+        assert_eq!(
+            text,
+            "a%5Bb%5D%5B0%5D=1&a%5Bb%5D%5B1%5D%5Bc%5D=x+y&a%5Bd%5D=&f+%26+g=true"
+        );
+        assert_eq!(
+            from_urlencoded(&text).unwrap(),
+            json!({"a": {"b": ["1", {"c": "x y"}], "d": ""}, "f & g": "true"})
+        );
+        assert_eq!(to_urlencoded(&json!({})).unwrap(), "");
+        assert_eq!(
+            to_urlencoded(&json!([1])).unwrap_err().msg,
+            "Form data that is not an object"
+        );
+    }
+}
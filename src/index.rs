@@ -0,0 +1,79 @@
+use core::ops::Index;
+
+use crate::JsonValue;
+
+static NULL: JsonValue<'static> = JsonValue::Null;
+
+impl<'a> JsonValue<'a> {
+    /// Value of the member `key` if `self` is an object containing it.
+    pub fn get(&self, key: &str) -> Option<&JsonValue<'a>> {
+        match self {
+            Self::Object(obj) => obj.get(key),
+            _ => None,
+        }
+    }
+
+    /// Element at `index` if `self` is an array long enough.
+    pub fn get_index(&self, index: usize) -> Option<&JsonValue<'a>> {
+        match self {
+            Self::Array(array) => array.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue<'a>> {
+        match self {
+            Self::Object(obj) => obj.get_mut(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut JsonValue<'a>> {
+        match self {
+            Self::Array(array) => array.get_mut(index),
+            _ => None,
+        }
+    }
+}
+
+/// `value["key"]` is `null` when `value` is not an object or has no such
+/// member, so lookups can be chained without checking each level.
+impl<'a> Index<&str> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, key: &str) -> &JsonValue<'a> {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// `value[i]` is `null` when `value` is not an array or is too short.
+impl<'a> Index<usize> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, index: usize) -> &JsonValue<'a> {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    #[test]
+    fn index_and_get() {
+        let value = parse(r#"{"faces": [{"uid": 1}, {"uid": 2}], "count": 2}"#);
+        assert_eq!(value["faces"][1]["uid"], json!(2));
+        assert_eq!(value.get("count"), Some(&json!(2)));
+        assert_eq!(value["faces"].get_index(0), Some(&json!({ "uid": 1 })));
+    }
+
+    #[test]
+    fn missing_entries_are_null() {
+        let value = parse(r#"{"faces": [{"uid": 1}]}"#);
+        assert_eq!(value["missing"]["deeper"], JsonValue::Null);
+        assert_eq!(value["faces"][5], JsonValue::Null);
+        assert_eq!(value[0], JsonValue::Null);
+        assert_eq!(value.get("faces").and_then(|f| f.get("uid")), None);
+        assert_eq!(value.get_index(0), None);
+    }
+}
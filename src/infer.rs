@@ -0,0 +1,261 @@
+//! Inference of a JSON Schema describing sample documents, e.g. to start
+//! documenting the events of a third-party API.
+
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use crate::prelude::*;
+use crate::{JsonNumber, JsonValue, JsonValueOwned, Map};
+
+/// Strings with at most this many distinct values, each seen twice on
+/// average, are described with an `enum`.
+const MAX_ENUM: usize = 8;
+
+/// What the values observed at one location have in common.
+#[derive(Default)]
+struct Shape<'v> {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    float: bool,
+    strings: usize,
+    /// Distinct strings, until there are too many for an `enum`.
+    string_values: Option<BTreeSet<&'v str>>,
+    /// Shape of the elements, once an array was seen.
+    items: Option<Box<Shape<'v>>>,
+    objects: usize,
+    /// Shape of each member and how many objects have it.
+    fields: BTreeMap<&'v str, (Shape<'v>, usize)>,
+}
+
+impl<'v> Shape<'v> {
+    fn add(&mut self, value: &'v JsonValue) {
+        match value {
+            JsonValue::Null => self.null = true,
+            JsonValue::Boolean(_) => self.boolean = true,
+            JsonValue::Number(JsonNumber::Float(_)) => self.float = true,
+            JsonValue::Number(_) => self.integer = true,
+            JsonValue::String(s) => {
+                if self.strings == 0 {
+                    self.string_values = Some(BTreeSet::new());
+                }
+                self.strings += 1;
+                if let Some(values) = &mut self.string_values {
+                    values.insert(s);
+                    if values.len() > MAX_ENUM {
+                        self.string_values = None;
+                    }
+                }
+            }
+            JsonValue::Array(array) => {
+                let items = self.items.get_or_insert_with(Box::default);
+                for element in array {
+                    items.add(element);
+                }
+            }
+            JsonValue::Object(obj) => {
+                self.objects += 1;
+                for (key, value) in obj {
+                    let (shape, count) = self.fields.entry(key).or_default();
+                    shape.add(value);
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    fn schema(&self) -> JsonValueOwned {
+        let mut schema = Map::new();
+        let mut types = Vec::new();
+        let mut add_type = |name: &'static str| types.push(JsonValue::from(name));
+        if self.null {
+            add_type("null");
+        }
+        if self.boolean {
+            add_type("boolean");
+        }
+        if self.float {
+            add_type("number");
+        } else if self.integer {
+            add_type("integer");
+        }
+        if self.strings > 0 {
+            add_type("string");
+        }
+        if self.items.is_some() {
+            add_type("array");
+        }
+        if self.objects > 0 {
+            add_type("object");
+        }
+
+        // `enum` applies to whole values, so only to strings and nulls.
+        let only_strings = types.len() == usize::from(self.null) + 1 && self.strings > 0;
+        match &self.string_values {
+            Some(values) if only_strings && self.strings >= 2 * values.len() => {
+                let mut allowed: Vec<_> = values
+                    .iter()
+                    .map(|s| JsonValue::from(s.to_string()))
+                    .collect();
+                if self.null {
+                    allowed.push(JsonValue::Null);
+                }
+                schema.insert(Cow::Borrowed("enum"), JsonValue::Array(allowed));
+            }
+            _ => {}
+        }
+        match &self.items {
+            Some(items) if items.is_observed() => {
+                schema.insert(Cow::Borrowed("items"), items.schema());
+            }
+            _ => {}
+        }
+        if self.objects > 0 {
+            let properties = self
+                .fields
+                .iter()
+                .map(|(key, (shape, _))| (key.to_string(), shape.schema()));
+            let required = self
+                .fields
+                .iter()
+                .filter(|(_, (_, count))| *count == self.objects)
+                .map(|(key, _)| key.to_string());
+            schema.insert(Cow::Borrowed("properties"), properties.collect());
+            schema.insert(Cow::Borrowed("required"), required.collect());
+        }
+        match types.len() {
+            0 => {}
+            1 => {
+                schema.insert(Cow::Borrowed("type"), types.remove(0));
+            }
+            _ => {
+                schema.insert(Cow::Borrowed("type"), JsonValue::Array(types));
+            }
+        }
+        JsonValue::Object(schema)
+    }
+
+    fn is_observed(&self) -> bool {
+        self.null
+            || self.boolean
+            || self.integer
+            || self.float
+            || self.strings > 0
+            || self.items.is_some()
+            || self.objects > 0
+    }
+}
+
+/// Builds a JSON Schema (draft 2020-12) that all of `values` satisfy,
+/// describing the types seen at each location, the object members present
+/// in every sample as `required`, and strings taking a few repeated values
+/// as an `enum`. Without samples the schema accepts anything.
+///
+/// ```
+/// use yaj::{infer::infer_schema, json};
+/// let events = [
+///     json!({"eventType": "faceCapture", "uid": 1, "age": 31.5}),
+///     json!({"eventType": "faceCapture", "uid": 2, "age": null, "tags": ["a"]}),
+/// ];
+/// let schema = infer_schema(&events);
+/// assert_eq!(schema["properties"]["eventType"], json!({"type": "string", "enum": ["faceCapture"]}));
+/// assert_eq!(schema["properties"]["age"]["type"], json!(["null", "number"]));
+/// assert_eq!(schema["properties"]["tags"], json!({"type": "array", "items": {"type": "string"}}));
+/// assert_eq!(schema["required"], json!(["age", "eventType", "uid"]));
+/// ```
+pub fn infer_schema(values: &[JsonValue]) -> JsonValueOwned {
+    let mut shape = Shape::default();
+    for value in values {
+        shape.add(value);
+    }
+    let mut schema = shape.schema();
+    schema.insert("$schema", "https://json-schema.org/draft/2020-12/schema");
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn shapes() {
+        let schema = |samples: &[&str]| {
+            let values: Vec<_> = samples.iter().map(|s| parse(s)).collect();
+            let mut schema = infer_schema(&values);
+            schema.remove("$schema");
+            schema
+        };
+        assert_eq!(schema(&[]), json!({}));
+        assert_eq!(schema(&["1", "2"]), json!({"type": "integer"}));
+        assert_eq!(schema(&["1", "2.5"]), json!({"type": "number"}));
+        assert_eq!(
+            schema(&["true", "null"]),
+            json!({"type": ["null", "boolean"]})
+        );
+        assert_eq!(schema(&["[]"]), json!({"type": "array"}));
+        assert_eq!(
+            schema(&["[[1], []]"]),
+            json!({"type": "array", "items": {"type": "array", "items": {"type": "integer"}}})
+        );
+        assert_eq!(
+            schema(&["\"x\"", "1"]),
+            json!({"type": ["integer", "string"]})
+        );
+
+        // Enums need repeated values
+        assert_eq!(schema(&["\"a\"", "\"b\""]), json!({"type": "string"}));
+        assert_eq!(
+            schema(&["\"on\"", "\"off\"", "\"on\"", "null", "\"off\""]),
+            json!({"type": ["null", "string"], "enum": ["off", "on", null]})
+        );
+        let many: Vec<_> = (0..2 * (MAX_ENUM + 1))
+            .map(|n| format!("\"{}\"", n % (MAX_ENUM + 1)))
+            .collect();
+        let many: Vec<_> = many.iter().map(String::as_str).collect();
+        assert_eq!(schema(&many), json!({"type": "string"}));
+
+        assert_eq!(
+            schema(&[
+                r#"{"a": 1, "b": {"c": true}}"#,
+                r#"{"a": 2}"#,
+                r#"[{"a": 3}]"#
+            ]),
+            json!({
+                "type": ["array", "object"],
+                "items": {"type": "object", "properties": {"a": {"type": "integer"}}, "required": ["a"]},
+                "properties": {
+                    "a": {"type": "integer"},
+                    "b": {"type": "object", "properties": {"c": {"type": "boolean"}}, "required": ["c"]},
+                },
+                "required": ["a"],
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "schema")]
+    fn samples_are_valid() {
+        let samples = [
+            parse(
+                r#"{"faceCapture": [{"faces": [{"uid": 1, "age": {"value": 31}, "tags": ["a"]}]}], "mode": "day"}"#,
+            ),
+            parse(
+                r#"{"faceCapture": [{"faces": []}, {"faces": [{"uid": 2, "age": null}]}], "mode": "day"}"#,
+            ),
+            parse(r#"{"faceCapture": [], "mode": null, "extra": 0.5}"#),
+        ];
+        let schema = crate::schema::Schema::compile(&infer_schema(&samples)).unwrap();
+        for sample in &samples {
+            assert!(schema.validate(sample).is_ok());
+        }
+        assert!(schema.validate(&json!({"faceCapture": [{"faces": [{"uid": 3, "age": {"value": 40}}]}], "mode": "day"})).is_ok());
+        assert!(schema
+            .validate(&json!({"faceCapture": [], "mode": "night"}))
+            .is_err());
+        assert!(schema
+            .validate(&json!({"faceCapture": [{"faces": [{"uid": "1"}]}], "mode": "day"}))
+            .is_err());
+        assert!(schema.validate(&json!({"mode": "day"})).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Sharing of the object keys repeated across a document.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+
+use crate::prelude::*;
+use crate::JsonValue;
+
+/// One copy of each distinct object key, which values then borrow instead
+/// of owning their own. In an owned document made of many objects of the
+/// same shape, each key is otherwise allocated once per object.
+///
+/// Keys are added first, then values rebuilt to borrow them:
+///
+/// ```
+/// # use yaj::{json, KeyPool};
+/// let faces = json!([{"uid": 1, "age": 31}, {"uid": 2, "age": 42}]);
+/// let mut pool = KeyPool::new();
+/// pool.add_keys(&faces);
+/// let faces = pool.intern(faces);
+/// assert_eq!(pool.len(), 2);
+/// assert_eq!(faces, json!([{"uid": 1, "age": 31}, {"uid": 2, "age": 42}]));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KeyPool {
+    keys: BTreeSet<Box<str>>,
+}
+
+impl KeyPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the keys of every object in `value`.
+    pub fn add_keys(&mut self, value: &JsonValue) {
+        for (_, node) in value.walk() {
+            for (key, _) in node.entries() {
+                if !self.keys.contains(key) {
+                    self.keys.insert(key.into());
+                }
+            }
+        }
+    }
+
+    /// Rebuilds `value` with the keys found in the pool borrowed from it,
+    /// dropping their own copies. Other keys are kept as they are.
+    pub fn intern<'p>(&'p self, value: JsonValue<'p>) -> JsonValue<'p> {
+        match value {
+            JsonValue::Array(array) => JsonValue::Array(array.into_iter().map(|e| self.intern(e)).collect()),
+            JsonValue::Object(obj) => JsonValue::Object(
+                obj.into_iter()
+                    .map(|(key, value)| {
+                        let key = match self.keys.get(&*key) {
+                            Some(shared) => Cow::Borrowed(&**shared),
+                            None => key,
+                        };
+                        (key, self.intern(value))
+                    })
+                    .collect(),
+            ),
+            scalar => scalar,
+        }
+    }
+
+    /// How many distinct keys the pool holds.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{try_parse, JsonValueOwned};
+
+    #[test]
+    fn keys_are_shared() {
+        let text = r#"[{"uid": 1, "rect": {"x": 0}}, {"uid": 2, "rect": {"x": 1}}, {"uid!": 3}]"#;
+        let faces: JsonValueOwned = try_parse(text).unwrap().into_owned();
+        let mut pool = KeyPool::new();
+        assert!(pool.is_empty());
+        pool.add_keys(&faces[0]);
+        assert_eq!(pool.len(), 3);
+
+        let interned = pool.intern(faces.clone());
+        assert_eq!(interned, faces);
+        let key = |value: &JsonValue<'_>, name: &str| match value.as_object().unwrap().get_key_value(name) {
+            Some((Cow::Borrowed(key), _)) => Some(key.as_ptr()),
+            _ => None,
+        };
+        assert_eq!(key(&interned[0], "uid"), key(&interned[1], "uid"));
+        assert_eq!(key(&interned[0]["rect"], "x"), key(&interned[1]["rect"], "x"));
+        assert!(key(&interned[1], "uid").is_some());
+        // Not added to the pool
+        assert_eq!(key(&interned[2], "uid!"), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Iteration over the members of containers and over whole documents.
+
+use crate::pointer::JsonPointer;
+use crate::prelude::*;
+use crate::{JsonValue, Map};
+
+impl<'a> JsonValue<'a> {
+    /// Key/value pairs of an object, empty for any other value.
+    pub fn entries(&self) -> Entries<'_, 'a> {
+        Entries {
+            iter: self.as_object().map(|obj| obj.iter()),
+        }
+    }
+
+    /// Elements of an array, empty for any other value.
+    pub fn members(&self) -> Members<'_, 'a> {
+        Members {
+            iter: self.as_array().map_or([].iter(), |array| array.iter()),
+        }
+    }
+
+    /// Every node of the document, `self` first, depth-first with the
+    /// pointer designating it.
+    ///
+    /// ```
+    /// use yaj::json;
+    /// let value = json!({"tags": ["a", "b"]});
+    /// let pointers: Vec<_> = value.walk().map(|(p, _)| p.into_string()).collect();
+    /// assert_eq!(pointers, ["", "/tags", "/tags/0", "/tags/1"]);
+    /// ```
+    pub fn walk(&self) -> Walk<'_, 'a> {
+        Walk {
+            stack: vec![(JsonPointer::root(), self)],
+        }
+    }
+}
+
+pub struct Entries<'v, 'a> {
+    iter: Option<<&'v Map<'a> as IntoIterator>::IntoIter>,
+}
+
+impl<'v, 'a> Iterator for Entries<'v, 'a> {
+    type Item = (&'v str, &'v JsonValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.iter.as_mut()?.next()?;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.as_ref().map_or((0, Some(0)), Iterator::size_hint)
+    }
+}
+
+impl ExactSizeIterator for Entries<'_, '_> {}
+
+pub struct Members<'v, 'a> {
+    iter: core::slice::Iter<'v, JsonValue<'a>>,
+}
+
+impl<'v, 'a> Iterator for Members<'v, 'a> {
+    type Item = &'v JsonValue<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Members<'_, '_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl ExactSizeIterator for Members<'_, '_> {}
+
+pub struct Walk<'v, 'a> {
+    // Nodes still to visit, the next one last.
+    stack: Vec<(JsonPointer, &'v JsonValue<'a>)>,
+}
+
+impl<'v, 'a> Iterator for Walk<'v, 'a> {
+    type Item = (JsonPointer, &'v JsonValue<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pointer, value) = self.stack.pop()?;
+        let start = self.stack.len();
+        match value {
+            JsonValue::Array(array) => {
+                for (index, element) in array.iter().enumerate() {
+                    let mut child = pointer.clone();
+                    child.push(&index.to_string());
+                    self.stack.push((child, element));
+                }
+            }
+            JsonValue::Object(obj) => {
+                for (key, member) in obj {
+                    let mut child = pointer.clone();
+                    child.push(key);
+                    self.stack.push((child, member));
+                }
+            }
+            _ => {}
+        }
+        self.stack[start..].reverse();
+        Some((pointer, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    #[test]
+    fn entries_and_members() {
+        let value = parse(r#"{"a": [1, 2], "b": null}"#);
+        let mut keys: Vec<_> = value.entries().map(|(k, _)| k).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["a", "b"]);
+        assert_eq!(value.entries().len(), 2);
+        assert_eq!(value.members().count(), 0);
+        assert_eq!(value["a"].members().rev().collect::<Vec<_>>(), [&json!(2), &json!(1)]);
+        assert_eq!(value["a"].entries().count(), 0);
+        assert_eq!(JsonValue::Null.members().len(), 0);
+    }
+
+    #[test]
+    fn walk_depth_first() {
+        let value = parse(r#"[{"a/b": [true]}, "x~"]"#);
+        let nodes: Vec<_> = value.walk().map(|(p, v)| (p.into_string(), v.clone())).collect();
+        assert_eq!(
+            nodes,
+            [
+                (String::from(""), value.clone()),
+                (String::from("/0"), json!({"a/b": [true]})),
+                (String::from("/0/a~1b"), json!([true])),
+                (String::from("/0/a~1b/0"), json!(true)),
+                (String::from("/1"), json!("x~")),
+            ]
+        );
+        for (pointer, node) in value.walk() {
+            assert_eq!(value.pointer(pointer.as_str()), Some(node));
+        }
+        let (pointer, _) = value.walk().nth(2).unwrap();
+        assert_eq!(pointer.tokens().collect::<Vec<_>>(), ["0", "a/b"]);
+    }
+}
@@ -0,0 +1,622 @@
+//! JSONPath queries (RFC 9535), selecting the nodes of a document that
+//! match an expression such as `$..faces[?@.age.value > 30].uid`.
+//!
+//! Supported are member names (`.name`, `['name']`), wildcards (`*`),
+//! recursive descent (`..`), indices and slices (`[0]`, `[-1]`,
+//! `[1:5:2]`), unions (`[0, 'a']`) and filters (`[?@.a]`,
+//! `[?@.a == 1 && !(@.b < $.max)]`). Function extensions are not.
+//!
+//! ```
+//! use yaj::{json, jsonpath};
+//! let doc = json!({"faceCapture": [{"faces": [{"age": {"value": 31}}, {"age": {"value": 25}}]}]});
+//! let ages = jsonpath::query(&doc, "$..faces[*].age.value").unwrap();
+//! assert_eq!(ages, vec![&json!(31), &json!(25)]);
+//! ```
+
+use core::cmp::Ordering;
+use core::str::FromStr;
+
+use crate::parser::ParseOptions;
+use crate::prelude::*;
+use crate::{try_parse, unescape, JsonValue, JsonValueOwned};
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    /// Selects among the children of each node.
+    Child(Vec<Selector>),
+    /// Selects among the descendants of each node, and the node itself.
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Filter),
+}
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    /// True when the query selects at least one node.
+    Exists(Query),
+    Compare(Operand, Comparison, Operand),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq)]
+enum Operand {
+    /// Compared when it selects exactly one node.
+    Query(Query),
+    Literal(JsonValueOwned),
+}
+
+#[derive(Debug, PartialEq)]
+struct Query {
+    /// Starts at the root `$` instead of the current node `@`.
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+/// A compiled JSONPath expression.
+#[derive(Debug, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+/// A JSONPath expression could not be compiled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathError {
+    pub msg: &'static str,
+    /// Byte offset in the expression where the error was found.
+    pub offset: usize,
+}
+impl core::fmt::Display for PathError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at offset {} of the JSONPath expression", self.msg, self.offset)
+    }
+}
+impl core::error::Error for PathError {}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, PathError> {
+        let mut parser = PathParser { path, offset: 0, depth: 0 };
+        if !parser.eat(b'$') {
+            return parser.error("Expected '$'");
+        }
+        let segments = parser.segments()?;
+        if parser.offset < path.len() {
+            return parser.error("Unexpected character");
+        }
+        Ok(Self { segments })
+    }
+
+    /// Nodes of `value` matched by the expression, in document order for
+    /// each selector.
+    pub fn query<'v, 'a>(&self, value: &'v JsonValue<'a>) -> Vec<&'v JsonValue<'a>> {
+        select(&self.segments, value, value)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, PathError> {
+        Self::parse(s)
+    }
+}
+
+/// Compiles `path` and runs it on `value`, see [`JsonPath`].
+pub fn query<'v, 'a>(value: &'v JsonValue<'a>, path: &str) -> Result<Vec<&'v JsonValue<'a>>, PathError> {
+    Ok(JsonPath::parse(path)?.query(value))
+}
+
+fn select<'v, 'a>(segments: &[Segment], root: &'v JsonValue<'a>, current: &'v JsonValue<'a>) -> Vec<&'v JsonValue<'a>> {
+    let mut nodes = vec![current];
+    for segment in segments {
+        let mut selected = Vec::new();
+        for node in nodes {
+            match segment {
+                Segment::Child(selectors) => {
+                    for selector in selectors {
+                        selector.select(root, node, &mut selected);
+                    }
+                }
+                Segment::Descendant(selectors) => descend(selectors, root, node, &mut selected),
+            }
+        }
+        nodes = selected;
+    }
+    nodes
+}
+
+/// Applies `selectors` to `node` and then to its descendants, depth first.
+fn descend<'v, 'a>(selectors: &[Selector], root: &'v JsonValue<'a>, node: &'v JsonValue<'a>, selected: &mut Vec<&'v JsonValue<'a>>) {
+    for selector in selectors {
+        selector.select(root, node, selected);
+    }
+    for child in children(node) {
+        descend(selectors, root, child, selected);
+    }
+}
+
+fn children<'v, 'a>(node: &'v JsonValue<'a>) -> Box<dyn Iterator<Item = &'v JsonValue<'a>> + 'v> {
+    match node {
+        JsonValue::Array(array) => Box::new(array.iter()),
+        JsonValue::Object(obj) => Box::new(obj.values()),
+        _ => Box::new(core::iter::empty()),
+    }
+}
+
+impl Selector {
+    fn select<'v, 'a>(&self, root: &'v JsonValue<'a>, node: &'v JsonValue<'a>, selected: &mut Vec<&'v JsonValue<'a>>) {
+        match (self, node) {
+            (Self::Name(name), JsonValue::Object(obj)) => selected.extend(obj.get(name.as_str())),
+            (Self::Wildcard, _) => selected.extend(children(node)),
+            (Self::Index(index), JsonValue::Array(array)) => {
+                let index = if *index < 0 { array.len() as i64 + index } else { *index };
+                if index >= 0 {
+                    selected.extend(array.get(index as usize));
+                }
+            }
+            (Self::Slice { start, end, step }, JsonValue::Array(array)) => {
+                let len = array.len() as i64;
+                let step = step.unwrap_or(1);
+                let bound = |n: i64, min, max| if n < 0 { len + n } else { n }.clamp(min, max);
+                if step > 0 {
+                    let mut i = bound(start.unwrap_or(0), 0, len);
+                    let upper = bound(end.unwrap_or(len), 0, len);
+                    while i < upper {
+                        selected.push(&array[i as usize]);
+                        i = match i.checked_add(step) {
+                            Some(next) => next,
+                            None => break,
+                        };
+                    }
+                } else if step < 0 {
+                    let mut i = bound(start.unwrap_or(len - 1), -1, len - 1);
+                    let lower = end.map_or(-1, |end| bound(end, -1, len - 1));
+                    while i > lower {
+                        selected.push(&array[i as usize]);
+                        i = match i.checked_add(step) {
+                            Some(next) => next,
+                            None => break,
+                        };
+                    }
+                }
+            }
+            (Self::Filter(filter), _) => {
+                selected.extend(children(node).filter(|child| filter.matches(root, child)));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, root: &JsonValue<'_>, current: &JsonValue<'_>) -> bool {
+        match self {
+            Self::Or(a, b) => a.matches(root, current) || b.matches(root, current),
+            Self::And(a, b) => a.matches(root, current) && b.matches(root, current),
+            Self::Not(filter) => !filter.matches(root, current),
+            Self::Exists(query) => !query.select(root, current).is_empty(),
+            Self::Compare(a, comparison, b) => {
+                let (a, b) = (a.value(root, current), b.value(root, current));
+                match comparison {
+                    Comparison::Eq => equal(a, b),
+                    Comparison::Ne => !equal(a, b),
+                    Comparison::Lt => less(a, b),
+                    Comparison::Le => less(a, b) || equal(a, b),
+                    Comparison::Gt => less(b, a),
+                    Comparison::Ge => less(b, a) || equal(a, b),
+                }
+            }
+        }
+    }
+}
+
+impl Query {
+    fn select<'v, 'a>(&self, root: &'v JsonValue<'a>, current: &'v JsonValue<'a>) -> Vec<&'v JsonValue<'a>> {
+        select(&self.segments, root, if self.absolute { root } else { current })
+    }
+}
+
+impl Operand {
+    /// The compared value, `None` when a query does not select a single
+    /// node.
+    fn value<'v>(&'v self, root: &'v JsonValue<'_>, current: &'v JsonValue<'_>) -> Option<&'v JsonValue<'v>> {
+        match self {
+            Self::Query(query) => match query.select(root, current).as_slice() {
+                [node] => Some(*node),
+                _ => None,
+            },
+            Self::Literal(value) => Some(value),
+        }
+    }
+}
+
+/// Two missing values are equal, as in RFC 9535.
+fn equal(a: Option<&JsonValue<'_>>, b: Option<&JsonValue<'_>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_by_value(b),
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+/// Only numbers and strings are ordered.
+fn less(a: Option<&JsonValue<'_>>, b: Option<&JsonValue<'_>>) -> bool {
+    match (a, b) {
+        (Some(JsonValue::Number(a)), Some(JsonValue::Number(b))) => a.cmp_value(b) == Ordering::Less,
+        (Some(JsonValue::String(a)), Some(JsonValue::String(b))) => a < b,
+        _ => false,
+    }
+}
+
+struct PathParser<'s> {
+    path: &'s str,
+    offset: usize,
+    /// Number of filters being parsed, each inside the previous one.
+    depth: usize,
+}
+
+impl<'s> PathParser<'s> {
+    fn error<T>(&self, msg: &'static str) -> Result<T, PathError> {
+        Err(PathError { msg, offset: self.offset })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.path.as_bytes().get(self.offset).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.offset += 1;
+        }
+        found
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let found = self.path[self.offset..].starts_with(s);
+        if found {
+            self.offset += s.len();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.offset += 1;
+        }
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, PathError> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat_str("..") {
+                let selectors = if self.eat(b'[') { self.bracket()? } else { vec![self.shorthand()?] };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat(b'.') {
+                segments.push(Segment::Child(vec![self.shorthand()?]));
+            } else if self.eat(b'[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    /// `*` or a member name after a dot.
+    fn shorthand(&mut self) -> Result<Selector, PathError> {
+        if self.eat(b'*') {
+            return Ok(Selector::Wildcard);
+        }
+        let rest = &self.path[self.offset..];
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || !c.is_ascii()))
+            .unwrap_or(rest.len());
+        if len == 0 || rest.as_bytes()[0].is_ascii_digit() {
+            return self.error("Expected a member name or '*'");
+        }
+        self.offset += len;
+        Ok(Selector::Name(rest[..len].to_string()))
+    }
+
+    /// Comma-separated selectors after `[`, up to the closing `]`.
+    fn bracket(&mut self) -> Result<Vec<Selector>, PathError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return Ok(selectors);
+            }
+            if !self.eat(b',') {
+                return self.error("Expected ',' or ']'");
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some(b'\'' | b'"') => Ok(Selector::Name(self.string()?)),
+            Some(b'*') => {
+                self.offset += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some(b'?') => {
+                self.offset += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.or()?))
+            }
+            Some(b'-' | b'0'..=b'9' | b':') => {
+                let start = self.integer()?;
+                self.skip_whitespace();
+                if !self.eat(b':') {
+                    return start.map(Selector::Index).map_or_else(|| self.error("Expected an index"), Ok);
+                }
+                self.skip_whitespace();
+                let end = self.integer()?;
+                self.skip_whitespace();
+                let step = if self.eat(b':') {
+                    self.skip_whitespace();
+                    self.integer()?
+                } else {
+                    None
+                };
+                Ok(Selector::Slice { start, end, step })
+            }
+            _ => self.error("Expected a selector"),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>, PathError> {
+        let rest = &self.path[self.offset..];
+        let len = rest
+            .char_indices()
+            .find(|&(idx, c)| !(c.is_ascii_digit() || (idx == 0 && c == '-')))
+            .map_or(rest.len(), |(idx, _)| idx);
+        if len == 0 {
+            return Ok(None);
+        }
+        let digits = rest[..len].trim_start_matches('-');
+        if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+            return self.error("Invalid integer");
+        }
+        match rest[..len].parse() {
+            Ok(n) => {
+                self.offset += len;
+                Ok(Some(n))
+            }
+            Err(_) => self.error("Invalid integer"),
+        }
+    }
+
+    /// A single- or double-quoted string with JSON escapes.
+    fn string(&mut self) -> Result<String, PathError> {
+        let quote = self.path.as_bytes()[self.offset] as char;
+        let rest = &self.path[self.offset + 1..];
+        let mut escaped = false;
+        for (idx, chr) in rest.char_indices() {
+            match chr {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                chr if chr == quote => {
+                    self.offset += idx + 2;
+                    return Ok(unescape(&rest[..idx]).into_owned());
+                }
+                _ => {}
+            }
+        }
+        self.error("Unterminated string")
+    }
+
+    fn or(&mut self) -> Result<Filter, PathError> {
+        let mut filter = self.and()?;
+        while self.eat_str("||") {
+            self.skip_whitespace();
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, PathError> {
+        let mut filter = self.unary()?;
+        while self.eat_str("&&") {
+            self.skip_whitespace();
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    /// A negation, a parenthesized filter, an existence test or a
+    /// comparison, and the whitespaces following it.
+    fn unary(&mut self) -> Result<Filter, PathError> {
+        if self.depth == ParseOptions::default().max_depth {
+            return self.error("Nesting depth exceeds the limit");
+        }
+        self.depth += 1;
+        let filter = if self.eat(b'!') {
+            self.skip_whitespace();
+            Filter::Not(Box::new(self.unary()?))
+        } else if self.eat(b'(') {
+            self.skip_whitespace();
+            let filter = self.or()?;
+            if !self.eat(b')') {
+                return self.error("Expected ')'");
+            }
+            filter
+        } else {
+            let left = self.operand()?;
+            self.skip_whitespace();
+            match (self.comparison(), left) {
+                (Some(comparison), left) => {
+                    self.skip_whitespace();
+                    let right = self.operand()?;
+                    Filter::Compare(left, comparison, right)
+                }
+                (None, Operand::Query(query)) => Filter::Exists(query),
+                (None, Operand::Literal(_)) => return self.error("Expected a comparison"),
+            }
+        };
+        self.depth -= 1;
+        self.skip_whitespace();
+        Ok(filter)
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        let operators = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+        operators
+            .iter()
+            .find(|(operator, _)| self.eat_str(operator))
+            .map(|&(_, comparison)| comparison)
+    }
+
+    fn operand(&mut self) -> Result<Operand, PathError> {
+        let absolute = match self.peek() {
+            Some(b'$') => true,
+            Some(b'@') => false,
+            Some(b'\'' | b'"') => return Ok(Operand::Literal(self.string()?.into())),
+            _ => return self.literal().map(Operand::Literal),
+        };
+        self.offset += 1;
+        let segments = self.segments()?;
+        Ok(Operand::Query(Query { absolute, segments }))
+    }
+
+    /// A number, `true`, `false` or `null`.
+    fn literal(&mut self) -> Result<JsonValueOwned, PathError> {
+        let rest = &self.path[self.offset..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.')))
+            .unwrap_or(rest.len());
+        match try_parse(&rest[..len]) {
+            Ok(value) if len > 0 && !value.is_string() => {
+                self.offset += len;
+                Ok(value.into_owned())
+            }
+            _ => self.error("Expected a literal or a query"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const STORE: &str = r#"{"store": {
+        "book": [
+            {"category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95},
+            {"category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99},
+            {"category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99},
+            {"category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99}
+        ],
+        "bicycle": {"color": "red", "price": 399}
+    }}"#;
+
+    fn titles(path: &str) -> Vec<String> {
+        let store = parse(STORE);
+        query(&store, path)
+            .unwrap()
+            .into_iter()
+            .map(|book| book["title"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn rfc_9535_examples() {
+        let store = parse(STORE);
+        let count = |path| query(&store, path).unwrap().len();
+        assert_eq!(query(&store, "$.store.book[*].author").unwrap()[3], &json!("J. R. R. Tolkien"));
+        assert_eq!(count("$..author"), 4);
+        assert_eq!(count("$.store.*"), 2);
+        assert_eq!(count("$.store..price"), 5);
+        assert_eq!(query(&store, "$..book[2].author").unwrap(), vec![&json!("Herman Melville")]);
+        assert_eq!(count("$..book[2].publisher"), 0);
+        assert_eq!(titles("$..book[-1]"), vec!["The Lord of the Rings"]);
+        assert_eq!(titles("$..book[0,1]"), titles("$..book[:2]"));
+        assert_eq!(titles("$..book[?@.isbn]"), vec!["Moby Dick", "The Lord of the Rings"]);
+        assert_eq!(titles("$..book[?@.price<10]"), vec!["Sayings of the Century", "Moby Dick"]);
+        assert_eq!(count("$..*"), 27);
+        assert_eq!(query(&store, "$").unwrap(), vec![&store]);
+        assert_eq!(query(&store, "$['store'][\"bicycle\"].color").unwrap(), vec![&json!("red")]);
+    }
+
+    #[test]
+    fn slices() {
+        let array = json!(["a", "b", "c", "d", "e", "f", "g"]);
+        let slice = |path| query(&array, path).unwrap().into_iter().map(|v| v.as_str().unwrap()).collect::<String>();
+        assert_eq!(slice("$[1:3]"), "bc");
+        assert_eq!(slice("$[5:]"), "fg");
+        assert_eq!(slice("$[1:5:2]"), "bd");
+        assert_eq!(slice("$[5:1:-2]"), "fd");
+        assert_eq!(slice("$[::-1]"), "gfedcba");
+        assert_eq!(slice("$[-2:]"), "fg");
+        assert_eq!(slice("$[::0]"), "");
+        assert_eq!(slice("$[-10:2]"), "ab");
+        assert_eq!(slice("$[1::9223372036854775807]"), "b");
+        assert_eq!(slice("$[::-9223372036854775808]"), "g");
+    }
+
+    #[test]
+    fn filters() {
+        let faces = parse(
+            r#"{"faces": [{"uid": 1, "age": {"value": 31}, "tag": "a"}, {"uid": 2, "age": {"value": 25.0}},
+                {"uid": 3, "age": {"value": 25}, "tag": "b"}], "min": 25}"#,
+        );
+        let uids = |path| query(&faces, path).unwrap();
+        assert_eq!(uids("$.faces[?@.age.value > 30].uid"), vec![&json!(1)]);
+        assert_eq!(uids("$.faces[?@.age.value == 25].uid"), vec![&json!(2), &json!(3)]);
+        assert_eq!(uids("$.faces[?@.age.value == $.min && @.tag].uid"), vec![&json!(3)]);
+        assert_eq!(uids("$.faces[? !@.tag || @.tag == 'a' ].uid"), vec![&json!(1), &json!(2)]);
+        assert_eq!(uids("$.faces[?(@.uid >= 2) && !(@.uid != 3)].uid"), vec![&json!(3)]);
+        assert_eq!(uids("$.faces[?@.missing == @.other].uid").len(), 3);
+        assert_eq!(uids("$.faces[?@.tag < 'b'].uid"), vec![&json!(1)]);
+        assert_eq!(uids("$.faces[?@.age < 'b'].uid").len(), 0);
+        assert_eq!(uids("$..[?@ == true]").len(), 0);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        let offset = |path: &str| JsonPath::parse(path).unwrap_err().offset;
+        assert_eq!(offset("store"), 0);
+        assert_eq!(offset("$.1a"), 2);
+        assert_eq!(offset("$[01]"), 2);
+        assert_eq!(offset("$['a'"), 5);
+        assert_eq!(offset("$[?@.a == ]"), 10);
+        assert_eq!(offset("$[?1]"), 4);
+        assert_eq!(offset("$.a b"), 3);
+        let deep = format!("$[?{}@]", "(".repeat(1000));
+        assert_eq!(JsonPath::parse(&deep).unwrap_err().msg, "Nesting depth exceeds the limit");
+        assert_eq!(
+            "$[".parse::<JsonPath>().unwrap_err().to_string(),
+            "Expected a selector at offset 2 of the JSONPath expression"
+        );
+    }
+}
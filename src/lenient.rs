@@ -0,0 +1,524 @@
+//! Best-effort parsing for editors: problems are collected instead of
+//! ending the parse, and the parser carries on from the next token that
+//! makes sense.
+
+use crate::prelude::*;
+use crate::parser::{expected, scalar, string_content, ParseOptions};
+use crate::{
+    line_column, unescape, JsonError, JsonToken, JsonTokenType, JsonValue, LexError, LexErrorKind, Lexer, Map,
+};
+
+fn starts_value(token_type: &JsonTokenType) -> bool {
+    matches!(
+        token_type,
+        JsonTokenType::LeftBrace
+            | JsonTokenType::LeftBracket
+            | JsonTokenType::String
+            | JsonTokenType::Number
+            | JsonTokenType::True
+            | JsonTokenType::False
+            | JsonTokenType::Null
+    )
+}
+
+struct Lenient<'a> {
+    source: &'a str,
+    /// Offset in `source` of the text read by `lexer`, which is restarted
+    /// after each lexing error.
+    base: usize,
+    lexer: Lexer<'a>,
+    /// Offset following the last token lexed.
+    lex_end: usize,
+    peeked: Option<Option<JsonToken<'a>>>,
+    /// Offset of the string token running to the end of the source, which
+    /// has no closing quote.
+    unterminated: Option<usize>,
+    errors: Vec<JsonError>,
+    /// Whether the end of the source was reported, once for all the
+    /// containers left open.
+    eof_reported: bool,
+    depth: usize,
+}
+
+impl<'a> Lenient<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            base: 0,
+            lexer: Lexer::new(source),
+            lex_end: 0,
+            peeked: None,
+            unterminated: None,
+            errors: Vec::new(),
+            eof_reported: false,
+            depth: 0,
+        }
+    }
+
+    fn error(&mut self, msg: &str, token: Option<&JsonToken<'a>>, expected: &'static [JsonTokenType]) {
+        self.errors.push(JsonError::at_token(self.source, msg, token, expected));
+    }
+
+    fn end_of_file(&mut self, expected: &'static [JsonTokenType]) {
+        if !self.eof_reported {
+            self.eof_reported = true;
+            self.error("Unexpected end of file", None, expected);
+        }
+    }
+
+    fn token(&self, start: usize, end: usize, token_type: JsonTokenType) -> JsonToken<'a> {
+        JsonToken {
+            slice: &self.source[start..end],
+            token_type,
+            offset: start,
+        }
+    }
+
+    fn restart(&mut self, offset: usize) {
+        self.base = offset;
+        self.lex_end = offset;
+        self.lexer = Lexer::new(&self.source[offset..]);
+    }
+
+    /// End of the word of letters, digits and number signs starting at
+    /// `start`, or of its first char when it is none of these.
+    fn word_end(&self, start: usize) -> usize {
+        let rest = &self.source[start..];
+        let len = rest
+            .find(|c: char| !c.is_alphanumeric() && !matches!(c, '+' | '-' | '.' | '_'))
+            .unwrap_or(rest.len());
+        let len = if len == 0 {
+            rest.chars().next().map_or(0, char::len_utf8)
+        } else {
+            len
+        };
+        start + len
+    }
+
+    /// Records `error` and returns what can be made of the token it
+    /// interrupted, the lexer being restarted after it.
+    fn recover(&mut self, error: LexError) -> Option<JsonToken<'a>> {
+        let offset = self.base + error.offset;
+        let (line, column) = line_column(self.source, offset);
+        let kind = error.kind;
+        self.errors.push(JsonError::Lex(LexError {
+            offset,
+            line,
+            column,
+            ..error
+        }));
+        let rest = &self.source[self.lex_end..];
+        let start = self.lex_end + (rest.len() - rest.trim_start().len());
+
+        match kind {
+            LexErrorKind::UnterminatedString
+            | LexErrorKind::InvalidEscape
+            | LexErrorKind::ControlCharacter
+            | LexErrorKind::LoneSurrogate
+                if self.source[start..].starts_with('"') =>
+            {
+                // Resume after the closing quote, if any.
+                let mut chars = self.source[offset..].char_indices();
+                let close = loop {
+                    match chars.next() {
+                        Some((_, '\\')) => {
+                            chars.next();
+                        }
+                        Some((idx, '"')) => break Some(offset + idx),
+                        Some(_) => {}
+                        None => break None,
+                    }
+                };
+                let end = match close {
+                    Some(close) => close + 1,
+                    None => {
+                        self.unterminated = Some(start);
+                        self.source.len()
+                    }
+                };
+                self.restart(end);
+                Some(self.token(start, end, JsonTokenType::String))
+            }
+            // Stands for the misspelt value.
+            LexErrorKind::InvalidLiteral | LexErrorKind::InvalidNumber | LexErrorKind::UnexpectedEof
+                if start <= offset =>
+            {
+                let end = self.word_end(start);
+                self.restart(end);
+                Some(self.token(start, end, JsonTokenType::Null))
+            }
+            _ => {
+                let end = self.word_end(offset.min(self.source.len()));
+                self.restart(end);
+                None
+            }
+        }
+    }
+
+    fn lex(&mut self) -> Option<JsonToken<'a>> {
+        loop {
+            match self.lexer.next()? {
+                Ok(mut token) => {
+                    token.offset += self.base;
+                    self.lex_end = token.offset + token.slice.len();
+                    return Some(token);
+                }
+                Err(error) => {
+                    if let Some(token) = self.recover(error) {
+                        return Some(token);
+                    }
+                }
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<JsonToken<'a>> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex());
+        }
+        self.peeked.clone().flatten()
+    }
+
+    fn next(&mut self) -> Option<JsonToken<'a>> {
+        match self.peeked.take() {
+            Some(token) => token,
+            None => self.lex(),
+        }
+    }
+
+    fn content(&self, token: &JsonToken<'a>) -> &'a str {
+        if self.unterminated == Some(token.offset) {
+            &token.slice[1..]
+        } else {
+            string_content(token)
+        }
+    }
+
+    /// Reads the value starting with `token`, which must be able to.
+    fn value(&mut self, token: JsonToken<'a>) -> JsonValue<'a> {
+        match token.token_type {
+            JsonTokenType::LeftBracket | JsonTokenType::LeftBrace => {
+                let limit = ParseOptions::default().max_depth;
+                if self.depth == limit {
+                    let (line, column) = line_column(self.source, token.offset);
+                    self.errors.push(JsonError::DepthLimitExceeded {
+                        limit,
+                        offset: token.offset,
+                        line,
+                        column,
+                    });
+                    self.skip_container();
+                    return JsonValue::Null;
+                }
+                self.depth += 1;
+                let value = if token.token_type == JsonTokenType::LeftBracket {
+                    JsonValue::Array(self.array())
+                } else {
+                    JsonValue::Object(self.object())
+                };
+                self.depth -= 1;
+                value
+            }
+            JsonTokenType::String => JsonValue::String(unescape(self.content(&token))),
+            _ => scalar(&token).unwrap_or(JsonValue::Null),
+        }
+    }
+
+    /// Skips the rest of the array or object whose opening token was read.
+    fn skip_container(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next().map(|token| token.token_type) {
+                Some(JsonTokenType::LeftBracket) | Some(JsonTokenType::LeftBrace) => depth += 1,
+                Some(JsonTokenType::RightBracket) | Some(JsonTokenType::RightBrace) => depth -= 1,
+                Some(_) => {}
+                None => return,
+            }
+        }
+    }
+
+    /// Reads the elements of an array whose `[` was read, up to its `]` or
+    /// to a `}` left to the enclosing object.
+    fn array(&mut self) -> Vec<JsonValue<'a>> {
+        let mut array = Vec::new();
+        // Whether a value may come next, and whether it follows a comma.
+        let mut expect_value = true;
+        let mut after_comma = false;
+        loop {
+            let (msg, expected) = if !expect_value {
+                ("Expected ',' or ']' after array element", expected::AFTER_ELEMENT)
+            } else if after_comma {
+                ("Unexpected token in place of a value", expected::VALUE)
+            } else {
+                ("Unexpected token in place of a value", expected::FIRST_ELEMENT)
+            };
+            let token = match self.peek() {
+                Some(token) => token,
+                None => {
+                    self.end_of_file(expected);
+                    return array;
+                }
+            };
+            match token.token_type {
+                JsonTokenType::RightBracket => {
+                    self.next();
+                    if after_comma {
+                        self.error(msg, Some(&token), expected);
+                    }
+                    return array;
+                }
+                JsonTokenType::RightBrace => {
+                    self.error(msg, Some(&token), expected);
+                    return array;
+                }
+                JsonTokenType::Comma if !expect_value => {
+                    self.next();
+                    expect_value = true;
+                    after_comma = true;
+                }
+                ref token_type if starts_value(token_type) => {
+                    // A missing comma
+                    if !expect_value {
+                        self.error(msg, Some(&token), expected);
+                    }
+                    self.next();
+                    let value = self.value(token);
+                    array.push(value);
+                    expect_value = false;
+                    after_comma = false;
+                }
+                _ => {
+                    self.error(msg, Some(&token), expected);
+                    self.next();
+                }
+            }
+        }
+    }
+
+    /// Reads the members of an object whose `{` was read, up to its `}` or
+    /// to a `]` left to the enclosing array.
+    fn object(&mut self) -> Map<'a> {
+        let mut obj = Map::new();
+        // Whether a key may come next, and whether it follows a comma.
+        let mut expect_key = true;
+        let mut after_comma = false;
+        loop {
+            let (msg, expected) = if !expect_key {
+                ("Expected ',' or '}' after object member", expected::AFTER_MEMBER)
+            } else if after_comma {
+                ("Unexpected token in place of string key in object", expected::KEY)
+            } else {
+                ("Unexpected token in place of string key in object", expected::FIRST_KEY)
+            };
+            let token = match self.peek() {
+                Some(token) => token,
+                None => {
+                    self.end_of_file(expected);
+                    return obj;
+                }
+            };
+            match token.token_type {
+                JsonTokenType::RightBrace => {
+                    self.next();
+                    if after_comma {
+                        self.error(msg, Some(&token), expected);
+                    }
+                    return obj;
+                }
+                JsonTokenType::RightBracket => {
+                    self.error(msg, Some(&token), expected);
+                    return obj;
+                }
+                JsonTokenType::Comma if !expect_key => {
+                    self.next();
+                    expect_key = true;
+                    after_comma = true;
+                }
+                JsonTokenType::String => {
+                    // A missing comma
+                    if !expect_key {
+                        self.error(msg, Some(&token), expected);
+                    }
+                    self.next();
+                    let key = unescape(self.content(&token));
+                    let value = self.member_value();
+                    obj.insert(key, value);
+                    expect_key = false;
+                    after_comma = false;
+                }
+                _ => {
+                    self.error(msg, Some(&token), expected);
+                    self.next();
+                }
+            }
+        }
+    }
+
+    /// Reads the `:` and the value following a key, `null` when missing.
+    fn member_value(&mut self) -> JsonValue<'a> {
+        let mut token = self.peek();
+        match &token {
+            Some(column) if column.token_type == JsonTokenType::Column => {
+                self.next();
+                token = self.peek();
+            }
+            // A missing colon
+            Some(value) if starts_value(&value.token_type) => {
+                self.error("Expected ':' after object key", Some(value), expected::COLUMN);
+            }
+            Some(other) => {
+                self.error("Expected ':' after object key", Some(other), expected::COLUMN);
+                return JsonValue::Null;
+            }
+            None => {
+                self.end_of_file(expected::COLUMN);
+                return JsonValue::Null;
+            }
+        }
+        match token {
+            Some(token) if starts_value(&token.token_type) => {
+                self.next();
+                self.value(token)
+            }
+            Some(token) => {
+                self.error("Unexpected token in place of a value", Some(&token), expected::VALUE);
+                JsonValue::Null
+            }
+            None => {
+                self.end_of_file(expected::VALUE);
+                JsonValue::Null
+            }
+        }
+    }
+}
+
+/// Parses as much of `source` as possible, for tools working on documents
+/// being edited. Instead of stopping at the first error, the parser skips
+/// what it cannot make sense of, closes the containers left open and
+/// returns what it read along with all the errors, empty for a valid
+/// document.
+///
+/// Missing values are `null`, and misspelt literals and numbers are read
+/// as `null` too.
+///
+/// ```
+/// # use yaj::{json, parse_lenient};
+/// let (value, errors) = parse_lenient(r#"{"a": [1 2,], "b": "unterminated"#);
+/// assert_eq!(value, json!({"a": [1, 2], "b": "unterminated"}));
+/// assert_eq!(errors.len(), 4);
+/// ```
+pub fn parse_lenient(source: &str) -> (JsonValue<'_>, Vec<JsonError>) {
+    let mut parser = Lenient::new(source);
+    let value = loop {
+        match parser.next() {
+            Some(token) if starts_value(&token.token_type) => break parser.value(token),
+            Some(token) => parser.error("Unexpected token in place of a value", Some(&token), expected::VALUE),
+            None => {
+                if parser.errors.is_empty() {
+                    parser.errors.push(JsonError::Empty);
+                }
+                break JsonValue::Null;
+            }
+        }
+    };
+    if let Some(token) = parser.next() {
+        parser.error("Invalid token at the end of document", Some(&token), &[]);
+    }
+    (value, parser.errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(errors: &[JsonError]) -> Vec<String> {
+        errors
+            .iter()
+            .map(|error| match error {
+                JsonError::Parse { msg, .. } => msg.clone(),
+                other => other.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_documents() {
+        let source = r#"{"a": [1, {"b": null}], "c": "d\te"}"#;
+        assert_eq!(parse_lenient(source), (crate::parse(source), vec![]));
+        assert_eq!(parse_lenient(" "), (JsonValue::Null, vec![JsonError::Empty]));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let (value, errors) = parse_lenient(r#"{"a": [1 2,, 3], "b" 4 "c": , "d": [}"#);
+        assert_eq!(value, json!({"a": [1, 2, 3], "b": 4, "c": null, "d": []}));
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "Expected ',' or ']' after array element",
+                "Unexpected token in place of a value",
+                "Expected ':' after object key",
+                "Expected ',' or '}' after object member",
+                "Unexpected token in place of a value",
+                "Unexpected token in place of a value",
+            ]
+        );
+
+        let (value, errors) = parse_lenient(r#"[{"a": 1], 2"#);
+        assert_eq!(value, json!([{"a": 1}]));
+        assert_eq!(
+            messages(&errors),
+            vec!["Expected ',' or '}' after object member", "Invalid token at the end of document"]
+        );
+        assert_eq!(errors[0].expected(), expected::AFTER_MEMBER);
+
+        let (value, errors) = parse_lenient(r#"[1, {"a": [2"#);
+        assert_eq!(value, json!([1, {"a": [2]}]));
+        assert_eq!(messages(&errors), vec!["Unexpected end of file"]);
+        assert_eq!(errors[0].expected(), expected::AFTER_ELEMENT);
+
+        let (value, errors) = parse_lenient("] [1] [2]");
+        assert_eq!(value, json!([1]));
+        assert_eq!(
+            messages(&errors),
+            vec!["Unexpected token in place of a value", "Invalid token at the end of document"]
+        );
+    }
+
+    #[test]
+    fn lexing_errors() {
+        let (value, errors) = parse_lenient("[tru, 1.e, \"a\\qb\", @, \"c\n\", 4]");
+        assert_eq!(value, json!([null, null, "aqb", "c\n", 4]));
+        let kinds: Vec<_> = errors
+            .iter()
+            .filter_map(|error| match error {
+                JsonError::Lex(e) => Some(e.kind),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LexErrorKind::InvalidLiteral,
+                LexErrorKind::InvalidNumber,
+                LexErrorKind::InvalidEscape,
+                LexErrorKind::InvalidChar,
+                LexErrorKind::ControlCharacter,
+            ]
+        );
+        assert_eq!(errors[3].offset(), Some(19));
+        assert_eq!(errors.len(), 6);
+
+        let (value, errors) = parse_lenient("{\"a\": \"b\\\"c");
+        assert_eq!(value, json!({"a": "b\"c"}));
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let source = format!("[{}{}, 1]", "[".repeat(200), "]".repeat(200));
+        let (value, errors) = parse_lenient(&source);
+        assert!(matches!(errors[..], [JsonError::DepthLimitExceeded { limit: 128, .. }]));
+        assert_eq!(value[1], json!(1));
+    }
+}
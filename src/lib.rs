@@ -1,5 +1,141 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+// No input may make the crate panic, except in the functions documented as
+// panicking.
+#![cfg_attr(not(test), deny(clippy::panic, clippy::unwrap_used))]
+
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::iter::FromIterator;
+use core::str::FromStr;
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
 use std::collections::HashMap;
-use std::str::{CharIndices, FromStr};
+
+use scan::Cursor;
+
+use crate::prelude::*;
+
+// Lets the derive macros name this crate `::yaj` in its own tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as yaj;
+
+#[macro_use]
+mod macros;
+mod prelude;
+
+#[cfg(feature = "async")]
+pub mod aio;
+#[cfg(feature = "arena")]
+pub mod arena;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "bson")]
+pub mod bson;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod conformance;
+pub mod cst;
+pub mod diff;
+mod convert;
+pub mod edit;
+mod encoding;
+mod escape;
+pub mod extjson;
+mod feed;
+pub mod flatten;
+pub mod form;
+mod index;
+pub mod infer;
+mod intern;
+mod iter;
+mod lenient;
+mod merge;
+mod minify;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod jsonpath;
+pub mod ndjson;
+mod number;
+pub mod ord;
+#[cfg(feature = "rayon")]
+pub mod par;
+mod parser;
+pub mod patch;
+mod pointer;
+mod project;
+#[cfg(feature = "prost")]
+mod prost;
+mod query;
+mod raw;
+#[cfg(feature = "std")]
+mod read;
+mod reader;
+mod scan;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod ser;
+#[cfg(feature = "serde_json")]
+mod serde_json;
+mod span;
+pub mod stream;
+pub mod tape;
+pub mod template;
+pub mod testing;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod transform;
+mod validate;
+mod visitor;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+pub use convert::{FromJson, FromJsonError, ToJson};
+#[cfg(feature = "derive")]
+pub use yaj_derive::{FromJson, ToJson};
+#[cfg(feature = "serde")]
+pub use de::from_str;
+pub use encoding::Encoding;
+pub use escape::unescape;
+pub use feed::{FeedLexer, JsonTokenOwned};
+pub use intern::KeyPool;
+pub use iter::{Entries, Members, Walk};
+pub use lenient::parse_lenient;
+pub use merge::{diff_to_merge_patch, merge_patch, ArrayMerge, MergeStrategy, NullMerge};
+pub use minify::{minify, minify_with_comments};
+pub use number::FromNumberExact;
+pub use parser::{DuplicateKeys, LimitKind, LoneSurrogates, ParseOptions, Parser};
+use parser::TokenParser;
+pub use pointer::JsonPointer;
+pub use project::parse_projection;
+pub use query::query_raw;
+pub use raw::{parse_shallow, RawValue};
+#[cfg(feature = "std")]
+pub use read::from_reader;
+pub use reader::{JsonEvent, JsonReader};
+pub use ser::{to_canonical_string, to_string, to_string_pretty, to_string_sorted, PrettyConfig};
+#[cfg(feature = "std")]
+pub use ser::{to_writer, to_writer_pretty, JsonWriter, WriterError};
+#[cfg(feature = "serde")]
+pub use ser::serde::{to_string as to_string_serde, to_writer as to_writer_serde};
+pub use span::{parse_with_spans, Spanned, SpannedMember, SpannedNode};
+pub use stream::StreamParser;
+pub use validate::validate;
+pub use visitor::{parse_with_visitor, JsonVisitor, Visit};
+
+/// Paths used by the exported macros, which must also expand in `no_std`
+/// crates.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::borrow::Cow;
+    pub use alloc::format;
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum JsonTokenType {
@@ -14,55 +150,812 @@ pub enum JsonTokenType {
     True,
     False,
     Null,
+    /// An unquoted object key, only produced in JSON5 mode.
+    Identifier,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct JsonToken<'a> {
     pub slice: &'a str,
     pub token_type: JsonTokenType,
+    /// Byte offset of the token in the source.
+    pub offset: usize,
 }
 
-#[derive(Debug, PartialEq)]
+impl JsonToken<'_> {
+    /// Byte range of the token in the source.
+    pub fn span(&self) -> core::ops::Range<usize> {
+        self.offset..self.offset + self.slice.len()
+    }
+}
+
+/// The token as found in the source.
+impl core::fmt::Display for JsonToken<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.slice)
+    }
+}
+
+/// Ordered and compared as described in the [`ord`] module.
+#[derive(Clone, Copy, Debug)]
 pub enum JsonNumber {
     Integer(i64),
+    /// An integer above `i64::MAX`; smaller ones are always `Integer`.
+    Unsigned(u64),
     Float(f64),
 }
 
 impl JsonNumber {
+    /// Reads the text of a number token. Text that is not a number, which
+    /// the lexer never produces, reads as NaN.
     pub fn parse(slice: &str) -> Self {
         if let Ok(n) = i64::from_str(slice) {
             Self::Integer(n)
+        } else if let Ok(n) = u64::from_str(slice) {
+            Self::Unsigned(n)
+        } else if let Some(n) = Self::parse_hex(slice) {
+            n
         } else {
-            Self::Float(f64::from_str(slice).unwrap())
+            Self::Float(f64::from_str(slice).unwrap_or(f64::NAN))
         }
     }
+
+    /// JSON5 hexadecimal integers, e.g. `-0xFF`.
+    fn parse_hex(slice: &str) -> Option<Self> {
+        let (negative, unsigned) = match slice.as_bytes().first()? {
+            b'-' => (true, &slice[1..]),
+            b'+' => (false, &slice[1..]),
+            _ => (false, slice),
+        };
+        let digits = unsigned.strip_prefix("0x").or_else(|| unsigned.strip_prefix("0X"))?;
+        let n = u64::from_str_radix(digits, 16).ok()?;
+        Some(if negative {
+            match 0i64.checked_sub_unsigned(n) {
+                Some(n) => Self::Integer(n),
+                None => Self::Float(-(n as f64)),
+            }
+        } else {
+            match i64::try_from(n) {
+                Ok(n) => Self::Integer(n),
+                Err(_) => Self::Unsigned(n),
+            }
+        })
+    }
 }
 
-#[derive(Debug, PartialEq)]
+/// Members of an object. With the `preserve_order` feature they are
+/// iterated in insertion order, without the `std` feature in key order,
+/// otherwise in an unspecified order.
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+pub type Map<'a> = HashMap<Cow<'a, str>, JsonValue<'a>>;
+#[cfg(not(feature = "std"))]
+pub type Map<'a> = alloc::collections::BTreeMap<Cow<'a, str>, JsonValue<'a>>;
+#[cfg(feature = "preserve_order")]
+pub type Map<'a> = indexmap::IndexMap<Cow<'a, str>, JsonValue<'a>>;
+
+/// Ordered and compared as described in the [`ord`] module. Cloning keeps
+/// borrowing the strings that borrow from the source.
+#[derive(Clone, Default, PartialEq, Eq)]
 pub enum JsonValue<'a> {
-    String(&'a str),
+    /// A string with its escape sequences resolved. Borrows from the source
+    /// unless it contained escapes.
+    String(Cow<'a, str>),
     Number(JsonNumber),
     Boolean(bool),
+    #[default]
     Null,
     Array(Vec<JsonValue<'a>>),
-    Object(HashMap<&'a str, JsonValue<'a>>),
+    Object(Map<'a>),
+}
+
+/// Formatted like a derived implementation, but with the members of
+/// objects in a stable order: sorted by key, or in insertion order with the
+/// `preserve_order` feature. Snapshots of debug output do not depend on the
+/// order a `HashMap` happens to iterate in.
+impl core::fmt::Debug for JsonValue<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::String(s) => f.debug_tuple("String").field(s).finish(),
+            Self::Number(n) => f.debug_tuple("Number").field(n).finish(),
+            Self::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            Self::Null => f.write_str("Null"),
+            Self::Array(array) => f.debug_tuple("Array").field(array).finish(),
+            Self::Object(obj) => f.debug_tuple("Object").field(&DebugMembers(obj)).finish(),
+        }
+    }
+}
+
+struct DebugMembers<'m, 'a>(&'m Map<'a>);
+
+impl core::fmt::Debug for DebugMembers<'_, '_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_map().entries(stable_members(self.0)).finish()
+    }
+}
+
+/// Members of `obj` sorted by key, or in insertion order with the
+/// `preserve_order` feature: unlike a `HashMap`, in the same order every
+/// time.
+pub(crate) fn stable_members<'m, 'a>(obj: &'m Map<'a>) -> Vec<(&'m Cow<'a, str>, &'m JsonValue<'a>)> {
+    #[cfg_attr(any(not(feature = "std"), feature = "preserve_order"), allow(unused_mut))]
+    let mut members: Vec<_> = obj.iter().collect();
+    #[cfg(all(feature = "std", not(feature = "preserve_order")))]
+    members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    members
 }
 
+/// A [`JsonValue`] that does not borrow from the source it was parsed from
+/// and can therefore outlive it.
+pub type JsonValueOwned = JsonValue<'static>;
+
+impl<'a> JsonValue<'a> {
+    /// An empty object.
+    pub fn new_object() -> Self {
+        Self::Object(Map::new())
+    }
+
+    /// An empty object with room for `capacity` members, when the [`Map`]
+    /// in use can reserve it.
+    pub fn object_with_capacity(capacity: usize) -> Self {
+        #[cfg(feature = "std")]
+        return Self::Object(Map::with_capacity(capacity));
+        #[cfg(not(feature = "std"))]
+        return {
+            let _ = capacity;
+            Self::Object(Map::new())
+        };
+    }
+
+    /// An empty array.
+    pub fn new_array() -> Self {
+        Self::Array(Vec::new())
+    }
+
+    /// An empty array with room for `capacity` elements.
+    pub fn array_with_capacity(capacity: usize) -> Self {
+        Self::Array(Vec::with_capacity(capacity))
+    }
+
+    /// Copies every borrowed string so the result no longer depends on the
+    /// source.
+    pub fn to_owned(&self) -> JsonValueOwned {
+        match self {
+            Self::String(s) => JsonValue::String(Cow::Owned(s.to_string())),
+            Self::Number(n) => JsonValue::Number(*n),
+            Self::Boolean(b) => JsonValue::Boolean(*b),
+            Self::Null => JsonValue::Null,
+            Self::Array(array) => JsonValue::Array(array.iter().map(Self::to_owned).collect()),
+            Self::Object(obj) => JsonValue::Object(
+                obj.iter()
+                    .map(|(k, v)| (Cow::Owned(k.to_string()), v.to_owned()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Same as [`JsonValue::to_owned`] but reuses the strings that are
+    /// already owned.
+    pub fn into_owned(self) -> JsonValueOwned {
+        match self {
+            Self::String(s) => JsonValue::String(Cow::Owned(s.into_owned())),
+            Self::Number(n) => JsonValue::Number(n),
+            Self::Boolean(b) => JsonValue::Boolean(b),
+            Self::Null => JsonValue::Null,
+            Self::Array(array) => {
+                JsonValue::Array(array.into_iter().map(Self::into_owned).collect())
+            }
+            Self::Object(obj) => JsonValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (Cow::Owned(k.into_owned()), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Self::Boolean(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Self::Number(_))
+    }
+
+    pub fn is_i64(&self) -> bool {
+        matches!(self, Self::Number(JsonNumber::Integer(_)))
+    }
+
+    /// Whether the value is an integer that fits in a `u64`.
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self, Self::Number(JsonNumber::Float(_)))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, Self::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Self::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, Self::Object(_))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The number if it was written as an integer; floats are not truncated.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Number(JsonNumber::Integer(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number if it is a non-negative integer.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Number(JsonNumber::Integer(n)) => u64::try_from(*n).ok(),
+            Self::Number(JsonNumber::Unsigned(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Any number, integers being converted to the nearest float.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(JsonNumber::Integer(n)) => Some(*n as f64),
+            Self::Number(JsonNumber::Unsigned(n)) => Some(*n as f64),
+            Self::Number(JsonNumber::Float(f)) => Some(*f),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue<'a>>> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<'a>> {
+        match self {
+            Self::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue<'a>>> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<'a>> {
+        match self {
+            Self::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Sets the member `key` of an object and returns its previous value.
+    /// `null` is first replaced by an empty object.
+    ///
+    /// # Panics
+    ///
+    /// If `self` is neither an object nor `null`.
+    #[allow(clippy::panic)]
+    pub fn insert<K, V>(&mut self, key: K, value: V) -> Option<JsonValue<'a>>
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<JsonValue<'a>>,
+    {
+        if self.is_null() {
+            *self = Self::Object(Map::new());
+        }
+        match self {
+            Self::Object(obj) => obj.insert(key.into(), value.into()),
+            other => panic!("Cannot insert a member into a non-object value: {:?}", other),
+        }
+    }
+
+    /// Removes the member `key` of an object and returns it.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue<'a>> {
+        #[cfg(not(feature = "preserve_order"))]
+        return self.as_object_mut()?.remove(key);
+        #[cfg(feature = "preserve_order")]
+        return self.as_object_mut()?.shift_remove(key);
+    }
+
+    /// Appends an element to an array. `null` is first replaced by an empty
+    /// array.
+    ///
+    /// # Panics
+    ///
+    /// If `self` is neither an array nor `null`.
+    #[allow(clippy::panic)]
+    pub fn push<V: Into<JsonValue<'a>>>(&mut self, value: V) {
+        if self.is_null() {
+            *self = Self::Array(Vec::new());
+        }
+        match self {
+            Self::Array(array) => array.push(value.into()),
+            other => panic!("Cannot push an element into a non-array value: {:?}", other),
+        }
+    }
+
+    /// Removes the last element of an array and returns it.
+    pub fn pop(&mut self) -> Option<JsonValue<'a>> {
+        self.as_array_mut()?.pop()
+    }
+
+    /// Moves the value out, leaving `null` in its place.
+    pub fn take(&mut self) -> JsonValue<'a> {
+        core::mem::replace(self, Self::Null)
+    }
+
+    /// Sorts the members of every object by key, the order the maps of the
+    /// `preserve_order` feature then keep. The other maps have no order to
+    /// sort: see [`to_string_sorted`] and [`PrettyConfig::sort_keys`] to
+    /// write them sorted.
+    #[cfg(feature = "preserve_order")]
+    pub fn sort_keys_recursive(&mut self) {
+        match self {
+            Self::Array(array) => array.iter_mut().for_each(Self::sort_keys_recursive),
+            Self::Object(obj) => {
+                obj.sort_keys();
+                obj.values_mut().for_each(Self::sort_keys_recursive);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses an owned value, e.g. with `text.parse::<JsonValueOwned>()`.
+impl FromStr for JsonValueOwned {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, JsonError> {
+        try_parse(s).map(JsonValue::into_owned)
+    }
+}
+
+impl From<bool> for JsonValue<'_> {
+    fn from(b: bool) -> Self {
+        Self::Boolean(b)
+    }
+}
+macro_rules! from_signed {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for JsonValue<'_> {
+            fn from(n: $ty) -> Self {
+                Self::Number(JsonNumber::Integer(n as i64))
+            }
+        }
+    )*};
+}
+from_signed!(i8, i16, i32, i64, isize);
+
+/// Integers above `i64::MAX` become [`JsonNumber::Unsigned`].
+macro_rules! from_unsigned {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for JsonValue<'_> {
+            fn from(n: $ty) -> Self {
+                let n = n as u64;
+                Self::Number(match i64::try_from(n) {
+                    Ok(n) => JsonNumber::Integer(n),
+                    Err(_) => JsonNumber::Unsigned(n),
+                })
+            }
+        }
+    )*};
+}
+from_unsigned!(u8, u16, u32, u64, usize);
+
+impl From<f32> for JsonValue<'_> {
+    fn from(f: f32) -> Self {
+        Self::Number(JsonNumber::Float(f.into()))
+    }
+}
+impl From<f64> for JsonValue<'_> {
+    fn from(f: f64) -> Self {
+        Self::Number(JsonNumber::Float(f))
+    }
+}
+impl From<JsonNumber> for JsonValue<'_> {
+    fn from(n: JsonNumber) -> Self {
+        Self::Number(n)
+    }
+}
+impl From<char> for JsonValue<'_> {
+    fn from(c: char) -> Self {
+        Self::String(Cow::Owned(c.to_string()))
+    }
+}
+impl<'a> From<&'a str> for JsonValue<'a> {
+    fn from(s: &'a str) -> Self {
+        Self::String(Cow::Borrowed(s))
+    }
+}
+impl From<String> for JsonValue<'_> {
+    fn from(s: String) -> Self {
+        Self::String(Cow::Owned(s))
+    }
+}
+impl<'a> From<Cow<'a, str>> for JsonValue<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        Self::String(s)
+    }
+}
+impl<'a, T: Into<JsonValue<'a>>> From<Vec<T>> for JsonValue<'a> {
+    fn from(array: Vec<T>) -> Self {
+        Self::Array(array.into_iter().map(Into::into).collect())
+    }
+}
+impl<'a, K: Into<Cow<'a, str>>, V: Into<JsonValue<'a>>> From<alloc::collections::BTreeMap<K, V>> for JsonValue<'a> {
+    fn from(object: alloc::collections::BTreeMap<K, V>) -> Self {
+        object.into_iter().collect()
+    }
+}
+#[cfg(feature = "std")]
+impl<'a, K: Into<Cow<'a, str>>, V: Into<JsonValue<'a>>, S> From<std::collections::HashMap<K, V, S>> for JsonValue<'a> {
+    fn from(object: std::collections::HashMap<K, V, S>) -> Self {
+        object.into_iter().collect()
+    }
+}
+#[cfg(feature = "preserve_order")]
+impl<'a, K: Into<Cow<'a, str>>, V: Into<JsonValue<'a>>, S> From<indexmap::IndexMap<K, V, S>> for JsonValue<'a> {
+    fn from(object: indexmap::IndexMap<K, V, S>) -> Self {
+        object.into_iter().collect()
+    }
+}
+/// `None` becomes `null`.
+impl<'a, T: Into<JsonValue<'a>>> From<Option<T>> for JsonValue<'a> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+/// Collects an array, e.g. `(1..4).collect::<JsonValue>()`.
+impl<'a, T: Into<JsonValue<'a>>> FromIterator<T> for JsonValue<'a> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+/// Collects an object; the last of duplicate keys wins.
+impl<'a, K: Into<Cow<'a, str>>, V: Into<JsonValue<'a>>> FromIterator<(K, V)> for JsonValue<'a> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::Object(iter.into_iter().map(|(key, value)| (key.into(), value.into())).collect())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LexErrorKind {
+    /// A string is not closed before the end of the input.
+    UnterminatedString,
+    /// A backslash is followed by a character that is not a valid escape.
+    InvalidEscape,
+    /// A number does not follow the JSON number grammar.
+    InvalidNumber,
+    /// Something that looked like `true`, `false` or `null` but is not.
+    InvalidLiteral,
+    /// A character that cannot start any token.
+    InvalidChar,
+    /// The input ended in the middle of a token.
+    UnexpectedEof,
+    /// Bytes fed to a [`FeedLexer`] or [`from_slice`] are not valid UTF-8.
+    InvalidUtf8,
+    /// Bytes given to [`from_slice`] look like UTF-16 but are not valid.
+    InvalidUtf16,
+    /// Bytes given to [`from_slice`] look like UTF-32 but are not valid.
+    InvalidUtf32,
+    /// A `/* block */` comment is not closed before the end of the input.
+    UnterminatedComment,
+    /// A `\uXXXX` escape of a UTF-16 surrogate is not part of a pair.
+    LoneSurrogate,
+    /// A string contains a raw control character instead of an escape.
+    ControlCharacter,
+}
+
+/// Returns the line and column (both starting at 1, the column being counted
+/// in chars) of the byte `offset` in `source`.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut column = 1;
+    for (idx, chr) in source.char_indices() {
+        if idx >= offset {
+            break;
+        }
+        if chr == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    /// Byte offset of the offending character in the source.
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+    /// The offending character, `None` when the input ended prematurely.
+    pub chr: Option<char>,
+}
+impl LexError {
+    fn new<T>(kind: LexErrorKind, offset: usize, chr: Option<char>) -> Result<T, Self> {
+        Err(Self { kind, offset, line: 0, column: 0, chr })
+    }
+    fn locate(mut self, source: &str) -> Self {
+        let (line, column) = line_column(source, self.offset);
+        self.line = line;
+        self.column = column;
+        self
+    }
+}
+impl core::fmt::Display for LexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let what = match self.kind {
+            LexErrorKind::UnterminatedString => "Unterminated string",
+            LexErrorKind::InvalidEscape => "Invalid escape sequence",
+            LexErrorKind::InvalidNumber => "Invalid number",
+            LexErrorKind::InvalidLiteral => "Invalid literal",
+            LexErrorKind::InvalidChar => "Invalid char",
+            LexErrorKind::UnexpectedEof => "Unexpected end of file",
+            LexErrorKind::InvalidUtf8 => "Invalid UTF-8",
+            LexErrorKind::InvalidUtf16 => "Invalid UTF-16",
+            LexErrorKind::InvalidUtf32 => "Invalid UTF-32",
+            LexErrorKind::UnterminatedComment => "Unterminated comment",
+            LexErrorKind::LoneSurrogate => "Unpaired surrogate escape",
+            LexErrorKind::ControlCharacter => "Unescaped control character in string",
+        };
+        write!(f, "{} at line {} column {}", what, self.line, self.column)?;
+        match self.chr {
+            Some(chr) => write!(f, ": '{}'", chr.escape_debug()),
+            None => Ok(()),
+        }
+    }
+}
+impl core::error::Error for LexError {}
+
 fn forward(iter: &mut impl Iterator, skip: usize) {
     for _ in 0..skip {
         let _ = iter.next();
     }
 }
 
-pub fn lex(source: &str) -> Vec<JsonToken> {
-    let mut tokens = Vec::new();
-    let mut indices = source.char_indices();
+/// Lexes `source`, panicking on malformed input. See [`try_lex`].
+#[allow(clippy::panic)]
+pub fn lex(source: &str) -> Vec<JsonToken<'_>> {
+    match try_lex(source) {
+        Ok(tokens) => tokens,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+pub fn try_lex(source: &str) -> Result<Vec<JsonToken<'_>>, LexError> {
+    Lexer::new(source).collect()
+}
+
+/// Writes `tokens` back as source text without whitespace, except for a
+/// space where two tokens would otherwise be lexed as one or rejected, such
+/// as `true` followed by `1`. Lexing the result gives the same tokens at
+/// other offsets.
+///
+/// ```
+/// let tokens = yaj::lex(r#"{ "a" : [1 , true] }"#);
+/// assert_eq!(yaj::tokens_to_string(&tokens), r#"{"a":[1,true]}"#);
+/// ```
+pub fn tokens_to_string(tokens: &[JsonToken]) -> String {
+    use JsonTokenType::{Comma, False, Identifier, Null, Number, RightBrace, RightBracket, True};
+    let mut out = String::new();
+    for (idx, token) in tokens.iter().enumerate() {
+        let separate = match (idx.checked_sub(1).map(|prev| &tokens[prev].token_type), &token.token_type) {
+            (Some(Number), Comma | RightBrace | RightBracket) => false,
+            (Some(Number), _) => true,
+            (Some(True | False | Null | Identifier), Number | True | False | Null | Identifier) => true,
+            _ => false,
+        };
+        if separate {
+            out.push(' ');
+        }
+        out.push_str(token.slice);
+    }
+    out
+}
+
+/// Produces the tokens of a source lazily. Once an error has been returned
+/// the iterator is exhausted.
+pub struct Lexer<'a> {
+    source: &'a str,
+    indices: Cursor<'a>,
+    /// A number is only terminated by the char following it, which may be
+    /// a token on its own.
+    pending: Option<JsonToken<'a>>,
+    failed: bool,
+    allow_comments: bool,
+    json5: bool,
+    reject_lone_surrogates: bool,
+    allow_control_chars: bool,
+    allow_non_finite: bool,
+}
+
+impl<'a> Lexer<'a> {
+    /// A byte order mark at the start of `source` is skipped.
+    pub fn new(source: &'a str) -> Self {
+        let mut lexer = Self::continuing(source);
+        if source.starts_with('\u{feff}') {
+            forward(&mut lexer.indices, 1);
+        }
+        lexer
+    }
 
-    while let Some((idx, chr)) = indices.next() {
-        // Skip whitespaces
-        if chr.is_whitespace() {
-            continue;
+    /// Lexer of `source` taken after the start of the input, where a byte
+    /// order mark is not skipped.
+    pub(crate) fn continuing(source: &'a str) -> Self {
+        Self {
+            source,
+            indices: Cursor::new(source),
+            pending: None,
+            failed: false,
+            allow_comments: false,
+            json5: false,
+            reject_lone_surrogates: false,
+            allow_control_chars: false,
+            allow_non_finite: false,
         }
+    }
+
+    /// Accepts strings containing raw control characters (U+0000 to
+    /// U+001F), which RFC 8259 requires to be escaped.
+    pub fn allow_control_chars(mut self, allow: bool) -> Self {
+        self.allow_control_chars = allow;
+        self
+    }
+
+    /// Accepts the `NaN`, `Infinity` and `-Infinity` numbers outside of
+    /// JSON5, as written by JavaScript and many telemetry sources.
+    pub fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.allow_non_finite = allow;
+        self
+    }
+
+    /// Reports `\uXXXX` escapes of UTF-16 surrogates that are not part of a
+    /// high-low pair, instead of leaving them to be replaced with U+FFFD.
+    pub fn reject_lone_surrogates(mut self, reject: bool) -> Self {
+        self.reject_lone_surrogates = reject;
+        self
+    }
+
+    /// Reads the `count` hex digits of an escape sequence.
+    fn hex_digits(&mut self, count: usize) -> Result<u32, LexError> {
+        let mut code = 0;
+        for _ in 0..count {
+            match self.indices.next() {
+                Some((_, digit)) if digit.is_ascii_hexdigit() => {
+                    code = code * 16 + digit.to_digit(16).unwrap_or(0);
+                }
+                Some((idx, other)) => return LexError::new(LexErrorKind::InvalidEscape, idx, Some(other)),
+                None => return LexError::new(LexErrorKind::UnterminatedString, self.source.len(), None),
+            }
+        }
+        Ok(code)
+    }
+
+    /// Makes sure a high surrogate escaped at `escape_idx` is immediately
+    /// followed by an escaped low surrogate, which is consumed.
+    fn check_surrogate(&mut self, code: u32, escape_idx: usize) -> Result<(), LexError> {
+        let lone = || LexError::new(LexErrorKind::LoneSurrogate, escape_idx, None);
+        match code {
+            0xD800..=0xDBFF => {
+                let rest = self.indices.as_str();
+                let low = rest
+                    .strip_prefix("\\u")
+                    .and_then(|r| r.get(..4))
+                    .and_then(|digits| u32::from_str_radix(digits, 16).ok());
+                match low {
+                    Some(0xDC00..=0xDFFF) => {
+                        forward(&mut self.indices, 6);
+                        Ok(())
+                    }
+                    _ => lone(),
+                }
+            }
+            0xDC00..=0xDFFF => lone(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Accepts the JSON5 syntax: comments, single-quoted strings with
+    /// extra escapes and line continuations, unquoted keys, hexadecimal
+    /// numbers, leading `+`, leading or trailing `.`, `Infinity` and `NaN`.
+    pub fn json5(mut self, json5: bool) -> Self {
+        self.json5 = json5;
+        self.allow_comments |= json5;
+        self
+    }
+
+    /// Skips `// line` and `/* block */` comments like whitespaces.
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Skips a comment whose `/` at `slash_idx` was just read.
+    fn skip_comment(&mut self, slash_idx: usize) -> Result<(), LexError> {
+        use LexErrorKind::*;
+        match self.indices.next() {
+            Some((_, '/')) => {
+                for (_, chr) in &mut self.indices {
+                    if chr == '\n' {
+                        break;
+                    }
+                }
+                Ok(())
+            }
+            Some((_, '*')) => {
+                let mut star = false;
+                for (_, chr) in &mut self.indices {
+                    if star && chr == '/' {
+                        return Ok(());
+                    }
+                    star = chr == '*';
+                }
+                LexError::new(UnterminatedComment, self.source.len(), None)
+            }
+            _ => LexError::new(InvalidChar, slash_idx, Some('/')),
+        }
+    }
+
+    fn token(&self, start: usize, end: usize, token_type: JsonTokenType) -> JsonToken<'a> {
+        JsonToken {
+            slice: &self.source[start..end],
+            token_type,
+            offset: start,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<JsonToken<'a>>, LexError> {
+        use LexErrorKind::*;
+        if let Some(token) = self.pending.take() {
+            return Ok(Some(token));
+        }
+        let source = self.source;
+
+        let (idx, chr) = loop {
+            self.indices.skip_whitespace();
+            match self.indices.next() {
+                // Skip whitespaces
+                Some((_, chr)) if is_whitespace(chr, self.json5) => {}
+                Some((idx, '/')) if self.allow_comments => self.skip_comment(idx)?,
+                Some(tuple) => break tuple,
+                None => return Ok(None),
+            }
+        };
         let token_type_single_char = match chr {
             '{' => Some(JsonTokenType::LeftBrace),
             '}' => Some(JsonTokenType::RightBrace),
@@ -74,124 +967,140 @@ pub fn lex(source: &str) -> Vec<JsonToken> {
         };
 
         if let Some(token_type) = token_type_single_char {
-            let next_idx = idx + 1;
-            tokens.push(JsonToken {
-                slice: &source[idx..next_idx],
-                token_type,
-            });
-        } else {
-            match chr {
-                // Try to find a string
-                '"' => {
-                    let next_idx = loop {
-                        match indices.next() {
-                            // Some escaped char
-                            Some((_, '\\')) => {
-                                if let Some((_, escaped)) = indices.next() {
-                                    match escaped {
-                                        '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => {}
-                                        'u' => {
-                                            // 4 hex digits
-                                            forward(&mut indices, 4);
-                                        }
-                                        _ => panic!(
-                                            "Unexpected escaped char '{}' while lexing a string",
-                                            escaped
-                                        ),
+            return Ok(Some(self.token(idx, idx + 1, token_type)));
+        }
+        let token = match chr {
+            // Try to find a string
+            '"' | '\'' if chr == '"' || self.json5 => {
+                let quote = chr;
+                let next_idx = loop {
+                    self.indices.skip_string_chars(quote);
+                    match self.indices.next() {
+                        // Some escaped char
+                        Some((backslash_idx, '\\')) => {
+                            match self.indices.next() {
+                                Some((_, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't')) => {}
+                                Some((_, 'u')) => {
+                                    let code = self.hex_digits(4)?;
+                                    if self.reject_lone_surrogates {
+                                        self.check_surrogate(code, backslash_idx)?;
                                     }
-                                } else {
-                                    panic!("Unexpected end of file while lexing a string with escape chars")
                                 }
-                                continue;
+                                Some((_, 'x')) if self.json5 => {
+                                    self.hex_digits(2)?;
+                                }
+                                // Anything but a digit, including line terminators
+                                Some((_, escaped)) if self.json5 && !matches!(escaped, '1'..='9') => {}
+                                Some((esc_idx, escaped)) => {
+                                    return LexError::new(InvalidEscape, esc_idx, Some(escaped))
+                                }
+                                None => return LexError::new(UnterminatedString, source.len(), None),
                             }
-                            // End of string
-                            Some((idx, '"')) => break idx + 1,
-                            // End of file
-                            None => panic!("Unexpected end of file while lexing a string"),
-                            _ => {}
+                            continue;
                         }
-                    };
-                    tokens.push(JsonToken {
-                        slice: &source[idx..next_idx],
-                        token_type: JsonTokenType::String,
-                    });
-                }
-                // Try to find a number
-                '-' | '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                    let (next_idx, next_char) = lex_number(idx, chr, &mut indices);
-                    tokens.push(JsonToken {
-                        slice: &source[idx..next_idx],
-                        token_type: JsonTokenType::Number,
-                    });
-                    match next_char {
-                        Some(',') => tokens.push(JsonToken {
-                            slice: &source[next_idx..(next_idx + 1)],
-                            token_type: JsonTokenType::Comma,
-                        }),
-                        Some('}') => tokens.push(JsonToken {
-                            slice: &source[next_idx..(next_idx + 1)],
-                            token_type: JsonTokenType::RightBrace,
-                        }),
-                        Some(']') => tokens.push(JsonToken {
-                            slice: &source[next_idx..(next_idx + 1)],
-                            token_type: JsonTokenType::RightBracket,
-                        }),
-                        Some(other) => {
-                            if !other.is_whitespace() {
-                                panic!("Number followed by '{}'", other);
-                            }
-                        },
-                        None => {}
-                    }
-                }
-                // Try to find `true`
-                't' => {
-                    let next_idx = idx + 4;
-                    match source.get(idx..next_idx) {
-                        Some("true") => {
-                            tokens.push(JsonToken {
-                                slice: &source[idx..next_idx],
-                                token_type: JsonTokenType::True,
-                            });
-                            forward(&mut indices, 3);
+                        // End of string
+                        Some((idx, chr)) if chr == quote => break idx + 1,
+                        // JSON5 only forbids line breaks
+                        Some((idx, chr))
+                            if chr < ' '
+                                && !self.allow_control_chars
+                                && (!self.json5 || chr == '\n' || chr == '\r') =>
+                        {
+                            return LexError::new(ControlCharacter, idx, Some(chr))
                         }
-                        _ => panic!("Failed to lex boolean `true`..."),
+                        // End of file
+                        None => return LexError::new(UnterminatedString, source.len(), None),
+                        _ => {}
                     }
-                }
-                // Try to find `false`
-                'f' => {
-                    let next_idx = idx + 5;
-                    match source.get(idx..next_idx) {
-                        Some("false") => {
-                            tokens.push(JsonToken {
-                                slice: &source[idx..next_idx],
-                                token_type: JsonTokenType::False,
-                            });
-                            forward(&mut indices, 4);
-                        }
-                        _ => panic!("Failed to lex boolean `false`..."),
+                };
+                self.token(idx, next_idx, JsonTokenType::String)
+            }
+            // Unquoted keys and literals
+            chr if self.json5 && is_identifier_start(chr) => {
+                let rest = &source[idx..];
+                let len = rest
+                    .find(|c: char| !is_identifier_start(c) && !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let token_type = match &rest[..len] {
+                    "true" => JsonTokenType::True,
+                    "false" => JsonTokenType::False,
+                    "null" => JsonTokenType::Null,
+                    "Infinity" | "NaN" => JsonTokenType::Number,
+                    _ => JsonTokenType::Identifier,
+                };
+                forward(&mut self.indices, rest[..len].chars().count() - 1);
+                self.token(idx, idx + len, token_type)
+            }
+            // Try to find a number
+            '-' | '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '+' | '.'
+                if chr.is_ascii_digit() || chr == '-' || self.json5 =>
+            {
+                let named = self.json5 || self.allow_non_finite;
+                let (next_idx, next_char) = lex_number(idx, chr, &mut self.indices, self.json5, named)?;
+                let following = match next_char {
+                    Some(',') => Some(JsonTokenType::Comma),
+                    Some('}') => Some(JsonTokenType::RightBrace),
+                    Some(']') => Some(JsonTokenType::RightBracket),
+                    Some('/') if self.allow_comments => {
+                        self.skip_comment(next_idx)?;
+                        None
                     }
-                }
-                // Try to find `null`
-                'n' => {
-                    let next_idx = idx + 4;
-                    match source.get(idx..next_idx) {
-                        Some("null") => {
-                            tokens.push(JsonToken {
-                                slice: &source[idx..next_idx],
-                                token_type: JsonTokenType::Null,
-                            });
-                            forward(&mut indices, 3);
-                        }
-                        _ => panic!("Failed to lex `null`..."),
+                    Some(other) if !is_whitespace(other, self.json5) => {
+                        return LexError::new(InvalidNumber, next_idx, Some(other))
                     }
+                    _ => None,
+                };
+                self.pending = following.map(|t| self.token(next_idx, next_idx + 1, t));
+                self.token(idx, next_idx, JsonTokenType::Number)
+            }
+            // Try to find `true`, `false` or `null`, or `Infinity` and `NaN`
+            't' | 'f' | 'n' | 'I' | 'N' if chr.is_lowercase() || self.allow_non_finite => {
+                let (literal, token_type) = match chr {
+                    't' => ("true", JsonTokenType::True),
+                    'f' => ("false", JsonTokenType::False),
+                    'n' => ("null", JsonTokenType::Null),
+                    'I' => ("Infinity", JsonTokenType::Number),
+                    _ => ("NaN", JsonTokenType::Number),
+                };
+                let next_idx = idx + literal.len();
+                if source.get(idx..next_idx) != Some(literal) {
+                    return LexError::new(InvalidLiteral, idx, Some(chr));
                 }
-                invalid => panic!("Invalid char encountered: '{}'", invalid),
+                forward(&mut self.indices, literal.len() - 1);
+                self.token(idx, next_idx, token_type)
+            }
+            invalid => return LexError::new(InvalidChar, idx, Some(invalid)),
+        };
+        Ok(Some(token))
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<JsonToken<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_token() {
+            Ok(token) => token.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e.locate(self.source)))
             }
         }
     }
+}
+
+/// Whether `chr` may separate tokens: the four whitespaces of RFC 8259, or
+/// in JSON5 any Unicode whitespace.
+fn is_whitespace(chr: char, json5: bool) -> bool {
+    matches!(chr, ' ' | '\t' | '\n' | '\r') || json5 && chr.is_whitespace()
+}
 
-    tokens
+/// First char of a JSON5 unquoted key.
+fn is_identifier_start(chr: char) -> bool {
+    chr.is_alphabetic() || chr == '_' || chr == '$'
 }
 
 #[derive(Debug, PartialEq)]
@@ -199,24 +1108,42 @@ enum NumberLexerState {
     Sign,
     FirstDigits,
     FirstZero,
+    /// JSON5 `.5`
+    LeadingDot,
     FractionDot,
     FractionDigits,
     Exponent,
     ExponentSign,
     ExponentDigits,
+    /// JSON5 `0x`
+    HexPrefix,
+    HexDigits,
+    /// `Infinity` or `NaN` after a sign
+    Named,
 }
-fn lex_number(start: usize, chr: char, indices: &mut CharIndices) -> (usize, Option<char>) {
+fn lex_number(
+    start: usize,
+    chr: char,
+    indices: &mut Cursor,
+    json5: bool,
+    named: bool,
+) -> Result<(usize, Option<char>), LexError> {
+    use LexErrorKind::*;
     use NumberLexerState::*;
     let mut state = match chr {
-        '-' => Sign,
+        '-' | '+' => Sign,
         '0' => FirstZero,
+        '.' => LeadingDot,
         _ => FirstDigits,
     };
     let mut current = start;
-    loop {
+    Ok(loop {
         let (idx, chr) = match indices.next() {
             Some(tuple) => tuple,
-            None if state == Sign => panic!("Unexpected end of file while lexing a number"),
+            None if state == FractionDot && json5 => break (current + 1, None),
+            None if matches!(state, Sign | LeadingDot | FractionDot | Exponent | ExponentSign | HexPrefix) => {
+                return LexError::new(UnexpectedEof, current + 1, None)
+            }
             None => break (current + 1, None),
         };
         current = idx;
@@ -225,7 +1152,27 @@ fn lex_number(start: usize, chr: char, indices: &mut CharIndices) -> (usize, Opt
             Sign => match chr {
                 '0' => state = FirstZero,
                 '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => state = FirstDigits,
-                other => panic!("Unexpected char '{}' while lexing a number", other),
+                '.' if json5 => state = LeadingDot,
+                'I' | 'N' if named => {
+                    let name = if chr == 'I' { "Infinity" } else { "NaN" };
+                    if !indices.as_str().starts_with(&name[1..]) {
+                        return LexError::new(InvalidNumber, idx, Some(chr));
+                    }
+                    forward(indices, name.len() - 1);
+                    current += name.len() - 1;
+                    state = Named;
+                }
+                other => return LexError::new(InvalidNumber, idx, Some(other)),
+            },
+            Named => break (current, Some(chr)),
+            LeadingDot => match chr {
+                '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => state = FractionDigits,
+                other => return LexError::new(InvalidNumber, idx, Some(other)),
+            },
+            HexPrefix | HexDigits => match chr {
+                chr if chr.is_ascii_hexdigit() => state = HexDigits,
+                other if state == HexPrefix => return LexError::new(InvalidNumber, idx, Some(other)),
+                other => break (current, Some(other)),
             },
             FirstDigits => match chr {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {}
@@ -234,9 +1181,11 @@ fn lex_number(start: usize, chr: char, indices: &mut CharIndices) -> (usize, Opt
                 other => break (current, Some(other)),
             },
             FirstZero => match chr {
+                // Leading zeros are not allowed
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' | '-' => {
-                    panic!("Invalid start of number '0{}'", chr)
+                    return LexError::new(InvalidNumber, idx, Some(chr))
                 }
+                'x' | 'X' if json5 => state = HexPrefix,
                 '.' => state = FractionDot,
                 'e' | 'E' => state = Exponent,
                 other => break (current, Some(other)),
@@ -245,10 +1194,10 @@ fn lex_number(start: usize, chr: char, indices: &mut CharIndices) -> (usize, Opt
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
                     state = FractionDigits;
                 }
-                other => panic!(
-                    "Unexpected char '{}' after '.' while lexing a number",
-                    other
-                ),
+                // JSON5 `5.`
+                'e' | 'E' if json5 => state = Exponent,
+                other if json5 => break (current, Some(other)),
+                other => return LexError::new(InvalidNumber, idx, Some(other)),
             },
             FractionDigits => match chr {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {}
@@ -258,184 +1207,267 @@ fn lex_number(start: usize, chr: char, indices: &mut CharIndices) -> (usize, Opt
             Exponent => match chr {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => state = ExponentDigits,
                 '-' | '+' => state = ExponentSign,
-                other => panic!(
-                    "Unexpected char '{}' after '[eE]' while lexing a number",
-                    other
-                ),
+                other => return LexError::new(InvalidNumber, idx, Some(other)),
             },
             ExponentSign => match chr {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => state = ExponentDigits,
-                other => panic!(
-                    "Unexpected char '{}' after exponent sign while lexing a number",
-                    other
-                ),
+                other => return LexError::new(InvalidNumber, idx, Some(other)),
             },
             ExponentDigits => match chr {
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {}
                 other => break (current, Some(other)),
             },
         }
-    }
+    })
 }
 
-pub struct ParseError<'a, 'b> {
-    pub token: &'a JsonToken<'b>,
-    pub view: &'a [JsonToken<'b>],
-    pub msg: String
-}
-impl<'a, 'b> ParseError<'a, 'b> {
-    fn new<M: AsRef<str>, T>(msg: M, token: &'a JsonToken<'b>, view: &'a [JsonToken<'b>]) -> Result<T, Self> {
-        Err(Self { msg: msg.as_ref().to_string(), token, view })
-    }
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonError {
+    /// The source could not be split into tokens.
+    Lex(LexError),
+    /// The tokens do not form a valid JSON document.
+    Parse {
+        msg: String,
+        /// Text of the offending token, empty at the end of the source.
+        token: String,
+        /// Byte offset of `token` in the source.
+        offset: usize,
+        line: usize,
+        column: usize,
+        /// Token types that would have been accepted in place of `token`.
+        expected: &'static [JsonTokenType],
+    },
+    /// Arrays and objects are nested deeper than
+    /// [`ParseOptions::max_depth`].
+    DepthLimitExceeded {
+        limit: usize,
+        /// Byte offset of the opening bracket or brace over the limit.
+        offset: usize,
+        line: usize,
+        column: usize,
+    },
+    /// The source exceeds a limit of [`ParseOptions`] other than the
+    /// depth.
+    LimitExceeded {
+        kind: LimitKind,
+        limit: usize,
+        /// Byte offset of the string, or of the element or member, over the
+        /// limit. For the size, offset of the last char boundary within the
+        /// limit.
+        offset: usize,
+        line: usize,
+        column: usize,
+    },
+    /// The source does not contain any token.
+    Empty,
+    /// An error reported by user code, e.g. a serde implementation.
+    Custom(String),
+    /// Reading the source or writing the output failed.
+    #[cfg(feature = "std")]
+    Io {
+        kind: std::io::ErrorKind,
+        msg: String,
+    },
 }
-impl <'a,'b> std::fmt::Debug for ParseError<'a, 'b> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let end = 5.min(self.view.len());
-        f.debug_struct("ParseError")
-            .field("msg", &self.msg)
-            .field("token", &self.token)
-            .field("view", &&self.view[..end])
-            .finish()
+impl JsonError {
+    /// Builds a parse error located at `token`, or at the end of `source` when
+    /// there is no token left.
+    pub(crate) fn at_token(
+        source: &str,
+        msg: &str,
+        token: Option<&JsonToken<'_>>,
+        expected: &'static [JsonTokenType],
+    ) -> Self {
+        let (token, offset) = match token {
+            Some(token) => (token.slice.to_string(), token.offset),
+            None => (String::new(), source.len()),
+        };
+        let (line, column) = line_column(source, offset);
+        Self::Parse {
+            msg: msg.to_string(),
+            token,
+            offset,
+            line,
+            column,
+            expected,
+        }
     }
-}
 
-pub fn parse(json: &str) -> JsonValue {
-    let tokens = lex(json);
-    match parse_value(&tokens) {
-        Ok(v) => v,
-        Err(e) => panic!("{:#?}", e)
+    /// Byte offset in the source where the error was found, if it comes
+    /// from the source.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::Lex(e) => Some(e.offset),
+            Self::Parse { offset, .. }
+            | Self::DepthLimitExceeded { offset, .. }
+            | Self::LimitExceeded { offset, .. } => Some(*offset),
+            _ => None,
+        }
     }
-}
 
-fn parse_value<'a, 'b>(tokens: &'a [JsonToken<'b>]) -> Result<JsonValue<'b>, ParseError<'a, 'b>> {
-    Ok(match tokens.first() {
-        Some(tok) => match (&tok.token_type, tokens.len()) {
-            (JsonTokenType::LeftBracket, len) => {
-                if len < 2 {
-                    ParseError::new("Incomplete array", tok, tokens)?;
-                }
-                let last_idx = len - 1;
-                if tokens[last_idx].token_type != JsonTokenType::RightBracket {
-                    ParseError::new("Invalid token at the end of document", &tokens[last_idx], &tokens[(last_idx - 3)..])?;
-                }
-                parse_array(&tokens[1..last_idx])?
-            }
-            (JsonTokenType::LeftBrace, len) => {
-                if len < 2 {
-                    ParseError::new("Incomplete object", tok, tokens)?;
-                }
-                let last_idx = len - 1;
-                if tokens[last_idx].token_type != JsonTokenType::RightBrace {
-                    ParseError::new("Invalid token at the end of document", &tokens[last_idx], &tokens[(last_idx - 3)..])?;
-                }
-                parse_object(&tokens[1..last_idx])?
+    /// Line and column, starting at 1, where the error was found, if it
+    /// comes from the source.
+    pub fn line_column(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Lex(e) => Some((e.line, e.column)),
+            Self::Parse { line, column, .. }
+            | Self::DepthLimitExceeded { line, column, .. }
+            | Self::LimitExceeded { line, column, .. } => {
+                Some((*line, *column))
             }
-            (JsonTokenType::String, 1) => JsonValue::String(&tok.slice[1..(tok.slice.len() - 1)]),
-            (JsonTokenType::Number, 1) => JsonValue::Number(JsonNumber::parse(tok.slice)),
-            (JsonTokenType::True, 1) => JsonValue::Boolean(true),
-            (JsonTokenType::False, 1) => JsonValue::Boolean(false),
-            (JsonTokenType::Null, 1) => JsonValue::Null,
-            _ => ParseError::new("Invalid JSON token stream", tok, tokens)?,
-        },
-        None => panic!("Empty JSON is invalid JSON"),
-    })
+            _ => None,
+        }
+    }
+
+    /// Token types that would have been accepted where a parse error was
+    /// found. Empty when nothing may follow, e.g. after the document, or
+    /// for other errors.
+    pub fn expected(&self) -> &'static [JsonTokenType] {
+        match self {
+            Self::Parse { expected, .. } => expected,
+            _ => &[],
+        }
+    }
 }
+impl JsonError {
+    /// Moves the location of an error found in a single line of a larger
+    /// source, starting at `offset` and numbered `line`.
+    pub(crate) fn on_line(self, offset: usize, line: usize) -> Self {
+        self.relocate(offset, line, 1)
+    }
 
-fn parse_array<'a, 'b>(tokens: &'a [JsonToken<'b>]) -> Result<JsonValue<'b>, ParseError<'a, 'b>> {
-    let len = tokens.len();
-    let mut array = Vec::new();
-    let mut idx = 0;
-    let mut start = idx;
-    let mut n_bracket = 0;
-    let mut n_brace = 0;
-    loop {
-        if idx == len {
-            if idx > start {
-                array.push(parse_value(&tokens[start..idx])?);
+    /// Moves the location of an error found in a part of a larger source,
+    /// the part starting at byte `offset`, on line `line` and column
+    /// `column`.
+    pub(crate) fn relocate(mut self, offset: usize, line: usize, column: usize) -> Self {
+        let (o, l, c) = match &mut self {
+            Self::Lex(e) => (&mut e.offset, &mut e.line, &mut e.column),
+            Self::Parse {
+                offset: o,
+                line: l,
+                column: c,
+                ..
             }
-            break;
-        }
-        match &tokens[idx].token_type {
-            JsonTokenType::LeftBracket => n_bracket += 1,
-            JsonTokenType::LeftBrace => n_brace += 1,
-            JsonTokenType::RightBracket => n_bracket -= 1,
-            JsonTokenType::RightBrace => n_brace -= 1,
-            JsonTokenType::Comma if n_bracket == 0 && n_brace == 0 => {
-                array.push(parse_value(&tokens[start..idx])?);
-                start = idx + 1;
+            | Self::DepthLimitExceeded {
+                offset: o,
+                line: l,
+                column: c,
+                ..
             }
-            _ => {}
+            | Self::LimitExceeded {
+                offset: o,
+                line: l,
+                column: c,
+                ..
+            } => (o, l, c),
+            _ => return self,
+        };
+        if *l == 1 {
+            *c += column - 1;
         }
-        idx += 1;
-    }
-    Ok(JsonValue::Array(array))
-}
-
-#[derive(PartialEq)]
-enum ObjectParserState<'a> {
-    BeforeKey,
-    Key(JsonToken<'a>),
-    Column(JsonToken<'a>, usize),
-}
-fn parse_object<'a, 'b>(tokens: &'a [JsonToken<'b>]) -> Result<JsonValue<'b>, ParseError<'a, 'b>> {
-    use ObjectParserState::*;
-
-    let len = tokens.len();
-    let mut obj = HashMap::new();
-    let mut idx = 0;
-    let mut n_bracket = 0;
-    let mut n_brace = 0;
-    let mut state = BeforeKey;
-    loop {
-        if idx == len {
-            match state {
-                BeforeKey => {}
-                Column(ref key, start) if idx > start => {
-                    let k = &key.slice[1..(key.slice.len() - 1)];
-                    obj.insert(k, parse_value(&tokens[start..idx])?);
-                }
-                Column(_, start) => {
-                    ParseError::new(format!("start({}) >= idx({})", start, idx), &tokens[idx - 1], tokens)?
-                },
-                Key(_) => ParseError::new("Incomplete object", &tokens[idx - 1], tokens)?,
-            }
-            break;
+        *o += offset;
+        *l += line - 1;
+        self
+    }
+}
+#[cfg(feature = "std")]
+impl From<std::io::Error> for JsonError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io {
+            kind: e.kind(),
+            msg: e.to_string(),
         }
-        let tok = &tokens[idx];
-        match state {
-            BeforeKey => {
-                if tok.token_type != JsonTokenType::String {
-                    ParseError::new(
-                        "Unexpected token in place of string key in object",
-                        tok,
-                        &tokens[(idx - 1)..]
-                    )?;
-                }
-                state = Key(tok.clone());
-            }
-            Key(ref key) => {
-                if tok.token_type != JsonTokenType::Column {
-                    ParseError::new("Expected ':' token, found '{}'", tok, tokens)?;
-                }
-                state = Column(key.clone(), idx + 1);
+    }
+}
+impl From<LexError> for JsonError {
+    fn from(e: LexError) -> Self {
+        Self::Lex(e)
+    }
+}
+impl core::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Lex(e) => e.fmt(f),
+            Self::Parse {
+                msg,
+                token,
+                line,
+                column,
+                ..
+            } => write!(f, "{} near '{}' at line {} column {}", msg, token, line, column),
+            Self::DepthLimitExceeded {
+                limit,
+                line,
+                column,
+                ..
+            } => write!(
+                f,
+                "Nesting depth exceeds the limit of {} at line {} column {}",
+                limit, line, column
+            ),
+            Self::LimitExceeded {
+                kind,
+                limit,
+                line,
+                column,
+                ..
+            } => {
+                let what = match kind {
+                    LimitKind::Size => "Document size",
+                    LimitKind::Len => "Number of elements or members",
+                    LimitKind::StringLen => "String length",
+                };
+                write!(f, "{} exceeds the limit of {} at line {} column {}", what, limit, line, column)
             }
-            Column(ref key, start) => match &tok.token_type {
-                JsonTokenType::LeftBracket => n_bracket += 1,
-                JsonTokenType::LeftBrace => n_brace += 1,
-                JsonTokenType::RightBracket => n_bracket -= 1,
-                JsonTokenType::RightBrace => n_brace -= 1,
-                JsonTokenType::Comma if n_bracket == 0 && n_brace == 0 => {
-                    let k = &key.slice[1..(key.slice.len() - 1)];
-                    obj.insert(k, parse_value(&tokens[start..idx])?);
-                    state = BeforeKey;
-                }
-                _ => {}
-            },
+            Self::Empty => f.write_str("Empty JSON is invalid JSON"),
+            Self::Custom(msg) => f.write_str(msg),
+            #[cfg(feature = "std")]
+            Self::Io { msg, .. } => write!(f, "I/O error: {}", msg),
+        }
+    }
+}
+impl core::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Lex(e) => Some(e),
+            _ => None,
         }
+    }
+}
 
-        idx += 1;
+/// Parses `json`, panicking on malformed input. See [`try_parse`].
+#[allow(clippy::panic)]
+pub fn parse(json: &str) -> JsonValue<'_> {
+    match try_parse(json) {
+        Ok(v) => v,
+        Err(e) => panic!("{}", e),
     }
-    Ok(JsonValue::Object(obj))
+}
+
+pub fn try_parse(json: &str) -> Result<JsonValue<'_>, JsonError> {
+    Parser::new().parse(json)
+}
+
+/// Parses a document from bytes, which saves a separate UTF-8 validation
+/// pass. UTF-16 and UTF-32 are detected as [`Encoding::detect`] does and
+/// transcoded, see [`Parser::parse_slice`].
+pub fn from_slice(bytes: &[u8]) -> Result<JsonValue<'_>, JsonError> {
+    Parser::new().parse_slice(bytes)
+}
+
+/// Same as [`try_parse`] with non-default [`ParseOptions`], see also
+/// [`Parser`].
+pub fn try_parse_with(json: &str, options: ParseOptions) -> Result<JsonValue<'_>, JsonError> {
+    Parser::from(options).parse(json)
+}
+
+/// Parses the document made of `tokens`, which must have been produced from
+/// `source` (used to locate errors), e.g. by a [`Lexer`].
+pub fn parse_tokens<'a, I>(source: &'a str, tokens: I) -> Result<JsonValue<'a>, JsonError>
+where
+    I: IntoIterator<Item = Result<JsonToken<'a>, LexError>>,
+{
+    TokenParser::new(source, tokens.into_iter(), ParseOptions::default()).parse_document()
 }
 
 #[cfg(test)]
@@ -448,6 +1480,15 @@ mod tests {
         assert_eq!(tokens, vec![]);
     }
 
+    #[test]
+    fn byte_order_mark() {
+        let tokens = lex("\u{feff}[ ]");
+        assert_eq!(tokens.iter().map(|t| t.offset).collect::<Vec<_>>(), [3, 5]);
+        assert_eq!(parse("\u{feff}{\"a\": 1}"), json!({"a": 1}));
+        assert!(try_lex("[\u{feff}]").is_err());
+        assert!(try_lex("\u{feff}\u{feff}").is_err());
+    }
+
     #[test]
     fn lex_random_seq_of_single_char_tokens() {
         let tokens = lex("\n{   ]\t{, :\t\t ,\r, \n");
@@ -468,12 +1509,366 @@ mod tests {
         );
     }
 
+    #[test]
+    fn try_lex_reports_errors() {
+        let err = try_lex("[\"abc").unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::UnterminatedString);
+        assert_eq!(err.offset, 5);
+        assert_eq!(err.chr, None);
+
+        let err = try_lex("\"a\\qb\"").unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::InvalidEscape);
+        assert_eq!((err.offset, err.chr), (3, Some('q')));
+
+        let err = try_lex("\"\\u12g4\"").unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::InvalidEscape);
+        assert_eq!((err.offset, err.chr), (5, Some('g')));
+        assert_eq!(try_lex("\"\\u12").unwrap_err().kind, LexErrorKind::UnterminatedString);
+
+        let err = try_lex("[1, tru]").unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::InvalidLiteral);
+        assert_eq!((err.offset, err.chr), (4, Some('t')));
+
+        let err = try_lex("{ @ }").unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::InvalidChar);
+        assert_eq!((err.offset, err.chr), (2, Some('@')));
+    }
+
+    #[test]
+    fn control_characters() {
+        let err = try_lex("[\"a\tb\"]").unwrap_err();
+        assert_eq!((err.kind, err.offset, err.chr), (LexErrorKind::ControlCharacter, 3, Some('\t')));
+        assert_eq!(err.to_string(), "Unescaped control character in string at line 1 column 4: '\\t'");
+        assert!(try_lex("[\"a\\tb\u{7f}\"]").is_ok());
+        let lenient = Lexer::new("[\"a\u{0}\nb\"]").allow_control_chars(true);
+        assert!(lenient.collect::<Result<Vec<_>, _>>().is_ok());
+        let json5 = |source| Lexer::new(source).json5(true).collect::<Result<Vec<_>, _>>();
+        assert!(json5("['a\tb']").is_ok());
+        assert!(json5("['a\nb']").is_err());
+    }
+
+    #[test]
+    fn whitespaces() {
+        for source in ["[\u{c}1]", "[1\u{b}]", "[\u{a0}1]", "[1\u{2028}]"].iter() {
+            assert!(try_lex(source).is_err(), "{:?}", source);
+            let json5 = Lexer::new(source).json5(true).collect::<Result<Vec<_>, _>>();
+            assert_eq!(json5.map(|tokens| tokens.len()), Ok(3), "{:?}", source);
+        }
+        assert_eq!(try_lex(" \t\r\n[ 1\t]\n").map(|tokens| tokens.len()), Ok(3));
+    }
+
+    #[test]
+    fn lone_surrogates() {
+        let lex = |source| Lexer::new(source).reject_lone_surrogates(true).collect::<Result<Vec<_>, _>>();
+        assert!(lex(r#"["\ud83d\ude00", "\u00e9"]"#).is_ok());
+        for lone in [r#"["\ud83d"]"#, r#"["\ud83d\u00e9"]"#, r#"["a\ude00"]"#].iter() {
+            let err = lex(lone).unwrap_err();
+            assert_eq!(err.kind, LexErrorKind::LoneSurrogate);
+        }
+        assert_eq!(lex(r#"["a\ude00"]"#).unwrap_err().offset, 3);
+        assert!(try_lex(r#"["\ud83d"]"#).is_ok());
+    }
+
+    #[test]
+    fn try_lex_reports_number_errors() {
+        assert_eq!(try_lex("01").unwrap_err().kind, LexErrorKind::InvalidNumber);
+        assert_eq!(try_lex("1.x").unwrap_err().kind, LexErrorKind::InvalidNumber);
+        assert_eq!(try_lex("12a").unwrap_err().kind, LexErrorKind::InvalidNumber);
+        assert_eq!(try_lex("-").unwrap_err().kind, LexErrorKind::UnexpectedEof);
+        assert!(try_lex("-0.5e+10").is_ok());
+    }
+
+    #[test]
+    fn lexer_is_lazy_and_fused() {
+        let mut lexer = Lexer::new("[12,true] @ null");
+        let first = lexer.next().unwrap().unwrap();
+        assert_eq!((first.token_type, first.offset), (JsonTokenType::LeftBracket, 0));
+        let types = lexer
+            .by_ref()
+            .take(4)
+            .map(|t| t.unwrap().token_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                JsonTokenType::Number,
+                JsonTokenType::Comma,
+                JsonTokenType::True,
+                JsonTokenType::RightBracket
+            ]
+        );
+        assert_eq!(lexer.next().unwrap().unwrap_err().kind, LexErrorKind::InvalidChar);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn parse_filtered_tokens() {
+        let source = "[1, 2, 3]";
+        // Drop `2,`
+        let tokens = Lexer::new(source).filter(|t| !matches!(t, Ok(t) if t.offset == 4 || t.offset == 5));
+        let value = parse_tokens(source, tokens).unwrap();
+        assert_eq!(to_string(&value), "[1,3]");
+    }
+
+    #[test]
+    fn try_parse_reports_errors() {
+        assert_eq!(try_parse("  "), Err(JsonError::Empty));
+        assert!(matches!(try_parse("[\"abc"), Err(JsonError::Lex(_))));
+        assert!(matches!(try_parse("[1,,2]"), Err(JsonError::Parse { .. })));
+        assert!(matches!(try_parse("{\"a\":,\"b\":1}"), Err(JsonError::Parse { .. })));
+        assert!(matches!(try_parse("[1"), Err(JsonError::Parse { .. })));
+        assert!(matches!(try_parse("{true:2}"), Err(JsonError::Parse { .. })));
+        assert_eq!(try_parse("[]"), Ok(JsonValue::Array(vec![])));
+    }
+
+    #[test]
+    fn errors_carry_line_and_column() {
+        let tokens = lex("{\n  \"a\": 1\n}");
+        assert_eq!(tokens.iter().map(|t| t.offset).collect::<Vec<_>>(), vec![0, 4, 7, 9, 11]);
+
+        let err = try_lex("[\n  1,\n  @]").unwrap_err();
+        assert_eq!((err.offset, err.line, err.column), (9, 3, 3));
+
+        match try_parse("{\n  \"é\": true,\n  true: 1\n}") {
+            Err(JsonError::Parse { offset, line, column, .. }) => {
+                assert_eq!((offset, line, column), (18, 3, 3))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error_details() {
+        fn load() -> Result<JsonValueOwned, Box<dyn core::error::Error>> {
+            let source = String::from("{\"a\": [1 2]}");
+            Ok(try_parse(&source)?.into_owned())
+        }
+        let err = load().unwrap_err();
+        assert_eq!(err.to_string(), "Expected ',' or ']' after array element near '2' at line 1 column 10");
+        let err = err.downcast::<JsonError>().unwrap();
+        assert_eq!(err.offset(), Some(9));
+        assert_eq!(err.line_column(), Some((1, 10)));
+        assert_eq!(err.expected(), &[JsonTokenType::Comma, JsonTokenType::RightBracket]);
+
+        let expected = |source| try_parse(source).unwrap_err().expected();
+        assert_eq!(expected("{\"a\" 1}"), &[JsonTokenType::Column]);
+        assert_eq!(expected("{"), &[JsonTokenType::String, JsonTokenType::RightBrace]);
+        assert_eq!(expected("[1,"), parser::expected::VALUE);
+        assert!(expected("[] 1").is_empty());
+        assert!(expected("[\"a").is_empty());
+        assert_eq!(try_parse("").unwrap_err().offset(), None);
+        assert_eq!(try_parse("[\n@").unwrap_err().line_column(), Some((2, 1)));
+    }
+
+    #[test]
+    fn owned_values_outlive_source() {
+        fn load() -> JsonValueOwned {
+            let source = String::from("{\"a\": [1, \"b\\tc\", null]}");
+            parse(&source).to_owned()
+        }
+        let mut obj = Map::new();
+        obj.insert(
+            "a".into(),
+            JsonValue::Array(vec![
+                JsonValue::Number(JsonNumber::Integer(1)),
+                JsonValue::String("b\tc".into()),
+                JsonValue::Null,
+            ]),
+        );
+        assert_eq!(load(), JsonValue::Object(obj));
+        assert_eq!(parse("[true]").into_owned(), JsonValue::Array(vec![JsonValue::Boolean(true)]));
+    }
+
+    #[test]
+    fn from_str_parses_owned_values() {
+        fn load<T: FromStr>(text: String) -> Result<T, T::Err> {
+            text.parse()
+        }
+        let value: JsonValueOwned = load(String::from(r#"{"a": ["b\nc"]}"#)).unwrap();
+        assert_eq!(value["a"][0].as_str(), Some("b\nc"));
+        assert!(matches!("[1,".parse::<JsonValueOwned>(), Err(JsonError::Parse { .. })));
+    }
+
+    #[test]
+    fn lex_comments() {
+        let source = "// head\n[1/* a */, 2// b\n, /**/3 /* * / */]";
+        let types: Vec<_> = Lexer::new(source)
+            .allow_comments(true)
+            .map(|t| t.unwrap().token_type)
+            .collect();
+        use JsonTokenType::*;
+        assert_eq!(types, vec![LeftBracket, Number, Comma, Number, Comma, Number, RightBracket]);
+
+        let kind = |source| Lexer::new(source).allow_comments(true).find_map(Result::err).map(|e| e.kind);
+        assert_eq!(kind("[1 /* open"), Some(LexErrorKind::UnterminatedComment));
+        assert_eq!(kind("[1 / 2]"), Some(LexErrorKind::InvalidChar));
+        assert_eq!(kind("[1// end"), None);
+        assert!(try_lex("[1 // no]").is_err());
+    }
+
+    #[test]
+    fn tokens_back_to_source() {
+        let source = "[1 2, true null, \"a\"\"b\", {\"k\" : -1.5e3}, 3]";
+        let tokens = lex(source);
+        let text = tokens_to_string(&tokens);
+        assert_eq!(text, r#"[1 2,true null,"a""b",{"k":-1.5e3},3]"#);
+        let relexed = lex(&text);
+        assert!(relexed.iter().zip(&tokens).all(|(a, b)| a.slice == b.slice && a.token_type == b.token_type));
+        assert_eq!(relexed.len(), tokens.len());
+
+        let json5 = Lexer::new("{a: 1 /* c */ b: Infinity}").json5(true).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(tokens_to_string(&json5), "{a:1 b:Infinity}");
+
+        let token = &tokens[8];
+        assert_eq!(token.to_string(), r#""b""#);
+        assert_eq!(&source[token.span()], token.slice);
+        assert_eq!(token.span(), 20..23);
+    }
+
+    #[test]
+    fn lex_non_finite() {
+        let slices: Vec<_> = Lexer::new("[NaN,-Infinity, Infinity]")
+            .allow_non_finite(true)
+            .map(|t| t.unwrap())
+            .filter(|t| t.token_type == JsonTokenType::Number)
+            .map(|t| t.slice)
+            .collect();
+        assert_eq!(slices, ["NaN", "-Infinity", "Infinity"]);
+
+        let kind = |source| Lexer::new(source).allow_non_finite(true).find_map(Result::err).map(|e| e.kind);
+        assert_eq!(kind("[Inf]"), Some(LexErrorKind::InvalidLiteral));
+        assert_eq!(kind("-NaNa"), Some(LexErrorKind::InvalidNumber));
+        assert_eq!(kind("+Infinity"), Some(LexErrorKind::InvalidChar));
+        assert!(try_lex("NaN").is_err());
+        assert!(try_lex("-Infinity").is_err());
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let value = parse(r#"{"portNo": 8080, "ratio": 0.5, "name": "cam", "on": true, "tags": [], "off": null}"#);
+        assert_eq!(value["portNo"].as_i64(), Some(8080));
+        assert_eq!(value["portNo"].as_f64(), Some(8080.0));
+        assert_eq!(value["portNo"].as_u64(), Some(8080));
+        assert_eq!(parse("-1").as_u64(), None);
+        let id = parse("18446744073709551615");
+        assert_eq!((id.as_u64(), id.as_i64(), id.is_u64(), id.is_i64()), (Some(u64::MAX), None, true, false));
+        assert_eq!(value["ratio"].as_i64(), None);
+        assert_eq!(value["ratio"].as_f64(), Some(0.5));
+        assert_eq!(value["name"].as_str(), Some("cam"));
+        assert_eq!(value["on"].as_bool(), Some(true));
+        assert_eq!(value["tags"].as_array().map(Vec::len), Some(0));
+        assert_eq!(value.as_object().map(Map::len), Some(6));
+        assert_eq!(value["name"].as_bool(), None);
+        assert!(value["off"].is_null() && value["missing"].is_null());
+        assert!(value["portNo"].is_i64() && value["ratio"].is_f64() && value["ratio"].is_number());
+        assert!(value["name"].is_string() && value["tags"].is_array() && value.is_object());
+        assert!(!value["on"].is_object() && value["on"].is_bool());
+    }
+
+    #[test]
+    fn mutation() {
+        let mut config = parse(r#"{"camera": {"portNo": 8080, "tags": ["a"]}, "debug": true}"#).into_owned();
+        let camera = config.get_mut("camera").unwrap();
+        assert_eq!(camera.insert("portNo", 9090), Some(JsonValue::from(8080)));
+        camera.get_mut("tags").unwrap().push("b");
+        assert_eq!(config.remove("debug"), Some(JsonValue::Boolean(true)));
+        assert_eq!(config.remove("debug"), None);
+        let mut logs = JsonValue::Null;
+        logs.push(json!({ "level": 1 }));
+        logs.get_index_mut(0).unwrap().insert(String::from("level"), 2);
+        config.insert("logs", logs);
+        assert_eq!(
+            config,
+            parse(r#"{"camera": {"portNo": 9090, "tags": ["a", "b"]}, "logs": [{"level": 2}]}"#)
+        );
+        let tags = config["camera"]["tags"].as_array().unwrap().len();
+        assert_eq!(tags, 2);
+        let mut camera = config.get_mut("camera").unwrap().take();
+        assert_eq!(camera.get_mut("tags").unwrap().pop(), Some(json!("b")));
+        assert_eq!(config["camera"], JsonValue::Null);
+    }
+
+    #[test]
+    fn from_rust_values() {
+        assert_eq!(JsonValue::from(u64::MAX), JsonValue::Number(JsonNumber::Unsigned(u64::MAX)));
+        assert_eq!(JsonValue::from(7u8), json!(7));
+        assert_eq!(JsonValue::from(-7i16), json!(-7));
+        assert_eq!(JsonValue::from(0.5f32), json!(0.5));
+        assert_eq!(JsonValue::from('x'), json!("x"));
+        assert_eq!(JsonValue::from(Cow::Borrowed("y")), json!("y"));
+        assert_eq!(JsonValue::from(vec![json!(1), JsonValue::Null]), json!([1, null]));
+
+        let mut ports = std::collections::HashMap::new();
+        ports.insert("http", 80u16);
+        ports.insert("https", 443);
+        assert_eq!(JsonValue::from(ports), json!({"http": 80, "https": 443}));
+        let mut tags = alloc::collections::BTreeMap::new();
+        tags.insert(String::from("a"), vec!["x", "y"]);
+        assert_eq!(JsonValue::from(tags), json!({"a": ["x", "y"]}));
+
+        let squares: JsonValue = (1..4).map(|n| n * n).collect();
+        assert_eq!(squares, json!([1, 4, 9]));
+        let object: JsonValue = vec![("a", 1), ("b", 2), ("a", 3)].into_iter().collect();
+        assert_eq!(object, json!({"a": 3, "b": 2}));
+        let n = 5u32;
+        assert_eq!(json!({"n": n, "c": 'c', "none": (None::<u8>)}), json!({"n": 5, "c": "c", "none": null}));
+    }
+
+    #[test]
+    fn debug_members_in_stable_order() {
+        let value = parse(r#"{"uid": 7, "age": {"value": 31, "confidence": 0.5}, "tags": ["a", null]}"#);
+        #[cfg(not(feature = "preserve_order"))]
+        let expected = r#"Object({"age": Object({"confidence": Number(Float(0.5)), "value": Number(Integer(31))}), "tags": Array([String("a"), Null]), "uid": Number(Integer(7))})"#;
+        #[cfg(feature = "preserve_order")]
+        let expected = r#"Object({"uid": Number(Integer(7)), "age": Object({"value": Number(Integer(31)), "confidence": Number(Float(0.5))}), "tags": Array([String("a"), Null])})"#;
+        assert_eq!(format!("{:?}", value), expected);
+        assert!(format!("{:#?}", value).starts_with("Object(\n    {\n"));
+    }
+
+    #[test]
+    fn constructors() {
+        assert_eq!(JsonValue::default(), JsonValue::Null);
+        assert_eq!(JsonValue::new_object(), json!({}));
+        assert_eq!(JsonValue::new_array(), json!([]));
+        let mut faces = JsonValue::array_with_capacity(2);
+        assert!(faces.as_array().unwrap().capacity() >= 2);
+        let mut face = JsonValue::object_with_capacity(2);
+        face.insert("uid", 1);
+        face.insert("age", 31);
+        faces.push(face);
+        assert_eq!(faces, json!([{"uid": 1, "age": 31}]));
+    }
+
+    #[test]
+    fn clone_subtree() {
+        let mut doc = parse(r#"{"camera": {"name": "front", "tags": ["a"]}}"#);
+        let mut camera = doc["camera"].clone();
+        assert!(matches!(camera["name"], JsonValue::String(Cow::Borrowed("front"))));
+        camera.insert("name", "back");
+        doc.insert("copy", camera);
+        assert_eq!(doc["camera"]["name"], json!("front"));
+        assert_eq!(doc["copy"], json!({"name": "back", "tags": ["a"]}));
+    }
+
+    #[test]
+    #[should_panic]
+    fn push_into_object_panics() {
+        json!({}).push(1);
+    }
+
     #[test]
     fn simple_values()  {
         assert_eq!(JsonValue::Number(JsonNumber::Integer(5)), parse("5"));
         assert_eq!(JsonValue::Number(JsonNumber::Float(6.626E-34)), parse("6.626e-34"));
+        assert_eq!(JsonValue::Number(JsonNumber::Unsigned(u64::MAX)), parse("18446744073709551615"));
+        assert_eq!(JsonValue::Number(JsonNumber::Integer(i64::MIN)), parse("-9223372036854775808"));
+        assert_eq!(JsonValue::Number(JsonNumber::Float(18446744073709551616.0)), parse("18446744073709551616"));
+        assert_eq!(JsonNumber::parse("-0x8000000000000000"), JsonNumber::Integer(i64::MIN));
+        assert_eq!(JsonNumber::parse("-0x8000000000000001"), JsonNumber::Float(-9223372036854775809.0));
+        assert_eq!(JsonNumber::parse("0xFFFFFFFFFFFFFFFF"), JsonNumber::Unsigned(u64::MAX));
         assert_eq!(JsonValue::Boolean(true), parse("true"));
         assert_eq!(JsonValue::Null, parse("null"));
-        assert_eq!(JsonValue::String("Hello"), parse("\"Hello\""));
+        assert_eq!(JsonValue::String("Hello".into()), parse("\"Hello\""));
+        assert_eq!(JsonValue::String("a\nb".into()), parse(r#""a\nb""#));
     }
 }
@@ -0,0 +1,149 @@
+/// Builds a [`JsonValue`](crate::JsonValue) from JSON-like syntax.
+///
+/// Values that are not `null`, `true`, `false`, an array or an object are
+/// Rust expressions converted with `JsonValue::from`. Keys are string
+/// literals or any expression turning into a `Cow<str>`; wrap it in
+/// parentheses when it is more than a single token.
+///
+/// ```
+/// # use yaj::json;
+/// let name = "faces";
+/// let value = json!({
+///     (name): [1, 2.5, null, { "ok": true }],
+/// });
+/// assert_eq!(yaj::to_string(&value), r#"{"faces":[1,2.5,null,{"ok":true}]}"#);
+/// ```
+#[macro_export]
+macro_rules! json {
+    ($($json:tt)+) => {
+        $crate::json_internal!($($json)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // Array elements are accumulated into `[...]` until no token is left.
+    (@array [$($elems:expr,)*]) => {
+        $crate::__private::vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        $crate::__private::vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::JsonValue::Null] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!({$($object)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Object members: the key tokens are munched into `(...)` up to the
+    // colon, then the value is parsed and the member inserted.
+    (@object $object:ident () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $object.insert($crate::__private::Cow::from($($key)+), $value);
+        $crate::json_internal!(@object $object () ($($rest)*));
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $object.insert($crate::__private::Cow::from($($key)+), $value);
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::JsonValue::Null) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!({$($map)*})) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)) , $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr)) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)));
+    };
+    (@object $object:ident () (($key:expr) : $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object ($key) (: $($rest)*));
+    };
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::json_internal!(@object $object ($($key)* $tt) ($($rest)*));
+    };
+
+    (null) => {
+        $crate::JsonValue::Null
+    };
+    (true) => {
+        $crate::JsonValue::Boolean(true)
+    };
+    (false) => {
+        $crate::JsonValue::Boolean(false)
+    };
+    ([]) => {
+        $crate::JsonValue::Array($crate::__private::Vec::new())
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::JsonValue::Array($crate::json_internal!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::JsonValue::Object($crate::Map::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::JsonValue::Object({
+            let mut object = $crate::Map::new();
+            $crate::json_internal!(@object object () ($($tt)+));
+            object
+        })
+    };
+    ($other:expr) => {
+        $crate::JsonValue::from($other)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue, JsonValueOwned};
+
+    #[test]
+    fn literals() {
+        let value: JsonValueOwned = json!({
+            "a": [1, 2, null, -3.5],
+            "b": true,
+            "n": null,
+            "c": { "d": [], "e": {}, "f": [[false], { "g": "h" }] },
+        });
+        assert_eq!(
+            value,
+            parse(r#"{"a":[1,2,null,-3.5],"b":true,"n":null,"c":{"d":[],"e":{},"f":[[false],{"g":"h"}]}}"#)
+        );
+        assert_eq!(json!(null), JsonValue::Null);
+        assert_eq!(json!([]), parse("[]"));
+    }
+
+    #[test]
+    fn interpolation() {
+        let key = String::from("key");
+        let values = vec![1, 2];
+        let value = json!({
+            (key.clone() + "s"): values,
+            key: 1 + 2,
+            "name": format!("n{}", 1),
+            "missing": None::<bool>,
+        });
+        assert_eq!(
+            value,
+            parse(r#"{"keys": [1, 2], "key": 3, "name": "n1", "missing": null}"#)
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use crate::prelude::*;
+use crate::{JsonValue, JsonValueOwned, Map};
+
+/// How [`JsonValue::merge`] combines two arrays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArrayMerge {
+    /// Keep the array being merged in.
+    Replace,
+    /// Append the elements being merged in.
+    Concat,
+    /// Merge elements at the same index, appending the extra ones.
+    ByIndex,
+}
+
+/// What [`JsonValue::merge`] does with a `null` being merged in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NullMerge {
+    /// Set the target to `null` like any other value.
+    Replace,
+    /// Leave the target untouched.
+    Ignore,
+    /// Remove the member of the object it is merged into, and leave other
+    /// targets untouched.
+    Remove,
+}
+
+/// Settings of [`JsonValue::merge`]. By default arrays are replaced and
+/// `null` overwrites.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MergeStrategy {
+    pub arrays: ArrayMerge,
+    pub nulls: NullMerge,
+}
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        Self {
+            arrays: ArrayMerge::Replace,
+            nulls: NullMerge::Replace,
+        }
+    }
+}
+
+impl<'a> JsonValue<'a> {
+    /// Merges `other` into `self`: the members of two objects are merged
+    /// recursively, arrays and `null` according to `strategy`, and any other
+    /// value of `other` replaces `self`. Members only found in `other` are
+    /// inserted as they are.
+    ///
+    /// ```
+    /// # use yaj::{json, MergeStrategy, NullMerge};
+    /// let mut config = json!({"camera": {"portNo": 8080, "debug": false}, "tags": ["a"]});
+    /// let user = json!({"camera": {"debug": true, "portNo": null}, "tags": ["b"]});
+    /// let strategy = MergeStrategy { nulls: NullMerge::Ignore, ..Default::default() };
+    /// config.merge(user, strategy);
+    /// assert_eq!(config, json!({"camera": {"portNo": 8080, "debug": true}, "tags": ["b"]}));
+    /// ```
+    pub fn merge(&mut self, other: JsonValue<'a>, strategy: MergeStrategy) {
+        match (self, other) {
+            (Self::Object(target), Self::Object(members)) => {
+                for (key, value) in members {
+                    if value.is_null() && strategy.nulls == NullMerge::Remove {
+                        #[cfg(not(feature = "preserve_order"))]
+                        target.remove(&*key);
+                        #[cfg(feature = "preserve_order")]
+                        target.shift_remove(&*key);
+                        continue;
+                    }
+                    match target.get_mut(&*key) {
+                        Some(existing) => existing.merge(value, strategy),
+                        None if value.is_null() && strategy.nulls == NullMerge::Ignore => {}
+                        None => {
+                            target.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (Self::Array(target), Self::Array(elements)) => match strategy.arrays {
+                ArrayMerge::Replace => *target = elements,
+                ArrayMerge::Concat => target.extend(elements),
+                ArrayMerge::ByIndex => {
+                    let mut elements = elements.into_iter();
+                    for (existing, element) in target.iter_mut().zip(&mut elements) {
+                        existing.merge(element, strategy);
+                    }
+                    target.extend(elements);
+                }
+            },
+            (_, Self::Null) if strategy.nulls != NullMerge::Replace => {}
+            (target, other) => *target = other,
+        }
+    }
+}
+
+/// Applies an RFC 7386 JSON Merge Patch, as sent with
+/// `PATCH application/merge-patch+json`: the members of an object patch are
+/// applied recursively, `null` removing the member, and any other patch
+/// replaces `target`.
+pub fn merge_patch<'a>(target: &mut JsonValue<'a>, patch: &JsonValue<'_>) {
+    let members = match patch {
+        JsonValue::Object(members) => members,
+        other => {
+            *target = other.to_owned();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = JsonValue::Object(Map::new());
+    }
+    for (key, value) in members {
+        if value.is_null() {
+            target.remove(key);
+            continue;
+        }
+        if let JsonValue::Object(obj) = target {
+            let member = obj.entry(key.to_string().into()).or_insert(JsonValue::Null);
+            merge_patch(member, value);
+        }
+    }
+}
+
+/// Builds the merge patch turning `a` into `b`, see [`merge_patch`].
+///
+/// Merge patches cannot set a member to `null`: such members of `b` are
+/// removed when the patch is applied.
+pub fn diff_to_merge_patch(a: &JsonValue<'_>, b: &JsonValue<'_>) -> JsonValueOwned {
+    let (a, b) = match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => (a, b),
+        _ => return b.to_owned(),
+    };
+    let mut patch = Map::new();
+    for key in a.keys().filter(|key| !b.contains_key(*key)) {
+        patch.insert(key.to_string().into(), JsonValue::Null);
+    }
+    for (key, value) in b {
+        let change = match a.get(key) {
+            Some(old) if old == value => continue,
+            Some(old) => diff_to_merge_patch(old, value),
+            None => value.to_owned(),
+        };
+        patch.insert(key.to_string().into(), change);
+    }
+    JsonValue::Object(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(mut target: JsonValue<'static>, other: JsonValue<'static>, strategy: MergeStrategy) -> JsonValue<'static> {
+        target.merge(other, strategy);
+        target
+    }
+
+    #[test]
+    fn layered_objects() {
+        let defaults = json!({"camera": {"portNo": 8080, "name": "cam"}, "debug": false});
+        let env = json!({"camera": {"portNo": 9090}, "logs": {"level": 1}});
+        let user = json!({"camera": {"name": "front"}, "debug": true});
+        let mut config = defaults;
+        config.merge(env, MergeStrategy::default());
+        config.merge(user, MergeStrategy::default());
+        assert_eq!(
+            config,
+            json!({"camera": {"portNo": 9090, "name": "front"}, "debug": true, "logs": {"level": 1}})
+        );
+        assert_eq!(merged(json!({"a": 1}), json!([1]), MergeStrategy::default()), json!([1]));
+        assert_eq!(merged(json!(1), json!({"a": 1}), MergeStrategy::default()), json!({"a": 1}));
+    }
+
+    #[test]
+    fn arrays() {
+        let strategy = |arrays| MergeStrategy { arrays, ..Default::default() };
+        let target = || json!({"tags": [{"a": 1}, 2, 3]});
+        let other = || json!({"tags": [{"b": 1}, 4]});
+        assert_eq!(merged(target(), other(), strategy(ArrayMerge::Replace)), json!({"tags": [{"b": 1}, 4]}));
+        assert_eq!(
+            merged(target(), other(), strategy(ArrayMerge::Concat)),
+            json!({"tags": [{"a": 1}, 2, 3, {"b": 1}, 4]})
+        );
+        assert_eq!(
+            merged(target(), other(), strategy(ArrayMerge::ByIndex)),
+            json!({"tags": [{"a": 1, "b": 1}, 4, 3]})
+        );
+        assert_eq!(merged(json!([1]), json!([2, 3]), strategy(ArrayMerge::ByIndex)), json!([2, 3]));
+    }
+
+    #[test]
+    fn nulls() {
+        let strategy = |nulls| MergeStrategy {
+            nulls,
+            arrays: ArrayMerge::ByIndex,
+        };
+        let target = || json!({"a": 1, "b": 2, "list": [1, 2]});
+        let other = || json!({"a": null, "c": null, "list": [null, 3]});
+        assert_eq!(
+            merged(target(), other(), strategy(NullMerge::Replace)),
+            json!({"a": null, "b": 2, "c": null, "list": [null, 3]})
+        );
+        assert_eq!(
+            merged(target(), other(), strategy(NullMerge::Ignore)),
+            json!({"a": 1, "b": 2, "list": [1, 3]})
+        );
+        assert_eq!(
+            merged(target(), other(), strategy(NullMerge::Remove)),
+            json!({"b": 2, "list": [1, 3]})
+        );
+        assert_eq!(merged(json!(1), json!(null), strategy(NullMerge::Remove)), json!(1));
+    }
+
+    #[test]
+    fn rfc_7386_examples() {
+        let cases = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (r#"{"a":{"b":"c"}}"#, r#"{"a":{"b":"d","c":null}}"#, r#"{"a":{"b":"d"}}"#),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (r#"{}"#, r#"{"a":{"bb":{"ccc":null}}}"#, r#"{"a":{"bb":{}}}"#),
+        ];
+        for (target, patch, result) in cases.iter() {
+            let mut value = crate::parse(target);
+            merge_patch(&mut value, &crate::parse(patch));
+            assert_eq!(value, crate::parse(result), "{} {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn diff_round_trip() {
+        let a = json!({"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"], "content": "This will be unchanged"});
+        let b = json!({"title": "Hello!", "author": {"givenName": "John"}, "tags": ["example"],
+            "content": "This will be unchanged", "phoneNumber": "+01-123-456-7890"});
+        let patch = diff_to_merge_patch(&a, &b);
+        assert_eq!(
+            patch,
+            json!({"title": "Hello!", "author": {"familyName": null}, "tags": ["example"],
+                "phoneNumber": "+01-123-456-7890"})
+        );
+        let mut patched = a;
+        merge_patch(&mut patched, &patch);
+        assert_eq!(patched, b);
+        assert_eq!(diff_to_merge_patch(&b, &b), json!({}));
+        assert_eq!(diff_to_merge_patch(&json!([1]), &json!(2)), json!(2));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::parser::ParseOptions;
+use crate::prelude::*;
+use crate::reader::RawEvent;
+use crate::{JsonError, JsonReader};
+
+/// Removes the whitespaces between the tokens of `source`, checking it like
+/// [`validate`](crate::validate) without building any value. Strings and
+/// numbers are kept as written.
+pub fn minify(source: &str) -> Result<String, JsonError> {
+    minify_with(source, ParseOptions::default())
+}
+
+/// Same as [`minify`] but also removes `// line` and `/* block */` comments
+/// (JSONC).
+pub fn minify_with_comments(source: &str) -> Result<String, JsonError> {
+    let options = ParseOptions {
+        allow_comments: true,
+        ..ParseOptions::default()
+    };
+    minify_with(source, options)
+}
+
+/// Writes the tokens of the events read from `source`, with the separators
+/// between them.
+fn minify_with(source: &str, options: ParseOptions) -> Result<String, JsonError> {
+    let mut reader = JsonReader::with_options(source, options);
+    let mut output = String::with_capacity(source.len());
+    // Whether a `,` comes before the next element or member.
+    let mut separated = false;
+    while let Some(event) = reader.next_raw()? {
+        if separated && !matches!(event, RawEvent::End(_)) {
+            output.push(',');
+        }
+        separated = match event {
+            RawEvent::Start(token) => {
+                output.push_str(token.slice);
+                false
+            }
+            RawEvent::Key(token) => {
+                output.push_str(token.slice);
+                output.push(':');
+                false
+            }
+            RawEvent::Value(token) | RawEvent::End(token) => {
+                output.push_str(token.slice);
+                true
+            }
+        };
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn strips_whitespaces() {
+        let source = "{\n  \"a b\": [1, 2.50, -3e+2],\r\n\t\"c\" : { \"d\" : \"e \\n f\" }, \"g\": [ ]\n}\n";
+        assert_eq!(minify(source).unwrap(), r#"{"a b":[1,2.50,-3e+2],"c":{"d":"e \n f"},"g":[]}"#);
+        assert_eq!(minify(" null ").unwrap(), "null");
+    }
+
+    #[test]
+    fn strips_comments() {
+        let source = "// config\n{\"a\": 1 /* one */, \"b\"://\n [2]}";
+        assert_eq!(minify_with_comments(source).unwrap(), r#"{"a":1,"b":[2]}"#);
+        assert!(minify(source).is_err());
+    }
+
+    #[test]
+    fn reports_errors() {
+        for source in ["", "[1,]", "{\"a\" 1}", "[] []", "[\"a\\q\"]"].iter() {
+            assert_eq!(minify(source).map(|_| ()), try_parse(source).map(|_| ()), "{}", source);
+        }
+    }
+}
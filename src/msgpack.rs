@@ -0,0 +1,376 @@
+//! Conversion between values and MessagePack, a compact binary encoding
+//! e.g. for caches.
+//!
+//! ```
+//! use yaj::{json, msgpack};
+//! let value = json!({"uid": 12, "tags": ["a", null]});
+//! let bytes = msgpack::encode(&value);
+//! assert_eq!(msgpack::decode(&bytes).unwrap(), value);
+//! ```
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::parser::ParseOptions;
+use crate::{JsonNumber, JsonValue, Map};
+
+/// Encodes `value` with the shortest forms: integers and lengths use the
+/// fewest bytes, and floats are written in single precision when it is
+/// lossless. Object keys are strings.
+pub fn encode(value: &JsonValue) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_into(&mut out, value);
+    out
+}
+
+fn encode_into(out: &mut Vec<u8>, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push(0xc0),
+        JsonValue::Boolean(false) => out.push(0xc2),
+        JsonValue::Boolean(true) => out.push(0xc3),
+        JsonValue::Number(JsonNumber::Integer(n)) if *n < 0 => integer(out, *n),
+        JsonValue::Number(JsonNumber::Integer(n)) => unsigned(out, *n as u64),
+        JsonValue::Number(JsonNumber::Unsigned(n)) => unsigned(out, *n),
+        JsonValue::Number(JsonNumber::Float(f)) => {
+            let single = *f as f32;
+            if f64::from(single) == *f || f.is_nan() {
+                out.push(0xca);
+                out.extend_from_slice(&single.to_be_bytes());
+            } else {
+                out.push(0xcb);
+                out.extend_from_slice(&f.to_be_bytes());
+            }
+        }
+        JsonValue::String(s) => string(out, s),
+        JsonValue::Array(array) => {
+            length(out, array.len(), 0x90, 0xdc);
+            for item in array {
+                encode_into(out, item);
+            }
+        }
+        JsonValue::Object(obj) => {
+            length(out, obj.len(), 0x80, 0xde);
+            for (key, item) in obj {
+                string(out, key);
+                encode_into(out, item);
+            }
+        }
+    }
+}
+
+fn unsigned(out: &mut Vec<u8>, n: u64) {
+    if n < 0x80 {
+        out.push(n as u8);
+    } else if n <= u64::from(u8::MAX) {
+        out.extend_from_slice(&[0xcc, n as u8]);
+    } else if n <= u64::from(u16::MAX) {
+        out.push(0xcd);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u64::from(u32::MAX) {
+        out.push(0xce);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(0xcf);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+/// Writes a negative integer.
+fn integer(out: &mut Vec<u8>, n: i64) {
+    if n >= -32 {
+        out.push(n as u8);
+    } else if n >= i64::from(i8::MIN) {
+        out.extend_from_slice(&[0xd0, n as u8]);
+    } else if n >= i64::from(i16::MIN) {
+        out.push(0xd1);
+        out.extend_from_slice(&(n as i16).to_be_bytes());
+    } else if n >= i64::from(i32::MIN) {
+        out.push(0xd2);
+        out.extend_from_slice(&(n as i32).to_be_bytes());
+    } else {
+        out.push(0xd3);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+/// Writes the length of an array or a map: `fix` holds lengths up to 15,
+/// then `marker` is followed by 16 bits and `marker + 1` by 32 bits.
+fn length(out: &mut Vec<u8>, len: usize, fix: u8, marker: u8) {
+    if len < 16 {
+        out.push(fix | len as u8);
+    } else if len <= usize::from(u16::MAX) {
+        out.push(marker);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(marker + 1);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+    let len = s.len();
+    if len < 32 {
+        out.push(0xa0 | len as u8);
+    } else if len <= usize::from(u8::MAX) {
+        out.extend_from_slice(&[0xd9, len as u8]);
+    } else if len <= usize::from(u16::MAX) {
+        out.push(0xda);
+        out.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        out.push(0xdb);
+        out.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// The bytes are not a MessagePack object that can be represented as a
+/// value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgpackError {
+    pub msg: &'static str,
+    /// Offset of the offending byte.
+    pub offset: usize,
+}
+impl fmt::Display for MsgpackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.msg, self.offset)
+    }
+}
+impl core::error::Error for MsgpackError {}
+
+/// Decodes a single MessagePack object, borrowing strings from `bytes`.
+///
+/// Binary and extension objects, and maps with keys that are not strings
+/// are rejected.
+pub fn decode(bytes: &[u8]) -> Result<JsonValue<'_>, MsgpackError> {
+    let mut decoder = Decoder {
+        bytes,
+        offset: 0,
+        depth: 0,
+    };
+    let value = decoder.object()?;
+    if decoder.offset < bytes.len() {
+        return decoder.error("Trailing bytes after the object");
+    }
+    Ok(value)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error<T>(&self, msg: &'static str) -> Result<T, MsgpackError> {
+        Err(MsgpackError {
+            msg,
+            offset: self.offset,
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MsgpackError> {
+        match self.offset.checked_add(len).and_then(|end| self.bytes.get(self.offset..end)) {
+            Some(slice) => {
+                self.offset += len;
+                Ok(slice)
+            }
+            None => self.error("Unexpected end of input"),
+        }
+    }
+
+    /// Reads a big-endian unsigned integer of `len` bytes.
+    fn uint(&mut self, len: usize) -> Result<u64, MsgpackError> {
+        Ok(self.take(len)?.iter().fold(0, |n, b| n << 8 | u64::from(*b)))
+    }
+
+    /// Reads a big-endian two's complement integer of `len` bytes.
+    fn int(&mut self, len: usize) -> Result<i64, MsgpackError> {
+        let shift = 64 - 8 * len as u32;
+        Ok(((self.uint(len)? << shift) as i64) >> shift)
+    }
+
+    /// Length of a string, array or map, checked against the bytes left so
+    /// that it cannot trigger a huge allocation.
+    fn len(&mut self, size: usize) -> Result<usize, MsgpackError> {
+        let start = self.offset;
+        let len = self.uint(size)? as usize;
+        if len > self.bytes.len() - self.offset {
+            self.offset = start;
+            return self.error("Length exceeds the input");
+        }
+        Ok(len)
+    }
+
+    fn object(&mut self) -> Result<JsonValue<'a>, MsgpackError> {
+        let start = self.offset;
+        let marker = self.take(1)?[0];
+        let value = match marker {
+            0x00..=0x7f => JsonValue::Number(JsonNumber::Integer(i64::from(marker))),
+            0xe0..=0xff => JsonValue::Number(JsonNumber::Integer(i64::from(marker as i8))),
+            0xc0 => JsonValue::Null,
+            0xc2 => JsonValue::Boolean(false),
+            0xc3 => JsonValue::Boolean(true),
+            0xcc..=0xcf => {
+                let n = self.uint(1 << (marker - 0xcc))?;
+                JsonValue::Number(match i64::try_from(n) {
+                    Ok(n) => JsonNumber::Integer(n),
+                    Err(_) => JsonNumber::Unsigned(n),
+                })
+            }
+            0xd0..=0xd3 => JsonValue::Number(JsonNumber::Integer(self.int(1 << (marker - 0xd0))?)),
+            0xca => JsonValue::Number(JsonNumber::Float(f64::from(f32::from_bits(self.uint(4)? as u32)))),
+            0xcb => JsonValue::Number(JsonNumber::Float(f64::from_bits(self.uint(8)?))),
+            0xa0..=0xbf => JsonValue::String(self.string(usize::from(marker & 0x1f))?),
+            0xd9..=0xdb => {
+                let len = self.len(1 << (marker - 0xd9))?;
+                JsonValue::String(self.string(len)?)
+            }
+            0x90..=0x9f => self.nested(start, usize::from(marker & 0x0f), false)?,
+            0xdc | 0xdd => {
+                let len = self.len(2 << (marker - 0xdc))?;
+                self.nested(start, len, false)?
+            }
+            0x80..=0x8f => self.nested(start, usize::from(marker & 0x0f), true)?,
+            0xde | 0xdf => {
+                let len = self.len(2 << (marker - 0xde))?;
+                self.nested(start, len, true)?
+            }
+            0xc4..=0xc6 => {
+                self.offset = start;
+                return self.error("Binary objects are not supported");
+            }
+            0xc7..=0xc9 | 0xd4..=0xd8 => {
+                self.offset = start;
+                return self.error("Extension objects are not supported");
+            }
+            _ => {
+                self.offset = start;
+                return self.error("Invalid marker byte");
+            }
+        };
+        Ok(value)
+    }
+
+    fn string(&mut self, len: usize) -> Result<Cow<'a, str>, MsgpackError> {
+        let start = self.offset;
+        match core::str::from_utf8(self.take(len)?) {
+            Ok(s) => Ok(Cow::Borrowed(s)),
+            Err(e) => {
+                self.offset = start + e.valid_up_to();
+                self.error("Invalid UTF-8 in string")
+            }
+        }
+    }
+
+    /// Reads the `len` elements of an array, or members of a map, whose
+    /// marker is at `start`.
+    fn nested(&mut self, start: usize, len: usize, map: bool) -> Result<JsonValue<'a>, MsgpackError> {
+        if self.depth == ParseOptions::default().max_depth {
+            self.offset = start;
+            return self.error("Nesting depth exceeds the limit");
+        }
+        self.depth += 1;
+        let value = if map {
+            let mut obj = Map::new();
+            for _ in 0..len {
+                let key_start = self.offset;
+                let key = match self.object()? {
+                    JsonValue::String(key) => key,
+                    _ => {
+                        self.offset = key_start;
+                        return self.error("Map keys must be strings");
+                    }
+                };
+                let value = self.object()?;
+                obj.insert(key, value);
+            }
+            JsonValue::Object(obj)
+        } else {
+            let mut array = Vec::with_capacity(len);
+            for _ in 0..len {
+                array.push(self.object()?);
+            }
+            JsonValue::Array(array)
+        };
+        self.depth -= 1;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        let cases = [
+            ("0", "00"),
+            ("127", "7f"),
+            ("128", "cc80"),
+            ("65536", "ce00010000"),
+            ("18446744073709551615", "cfffffffffffffffff"),
+            ("-1", "ff"),
+            ("-32", "e0"),
+            ("-33", "d0df"),
+            ("-32769", "d2ffff7fff"),
+            ("-9223372036854775808", "d38000000000000000"),
+            ("1.5", "ca3fc00000"),
+            ("1.1", "cb3ff199999999999a"),
+            ("true", "c3"),
+            ("null", "c0"),
+            (r#""ü""#, "a2c3bc"),
+            ("[1, [2, 3]]", "9201920203"),
+            (r#"{"a": 1}"#, "81a16101"),
+        ];
+        for (json, msgpack) in cases.iter() {
+            let value = parse(json);
+            assert_eq!(hex(&encode(&value)), *msgpack, "{}", json);
+            assert_eq!(decode(&unhex(msgpack)), Ok(value), "{}", msgpack);
+        }
+        let long = "x".repeat(40);
+        assert_eq!(&encode(&json!(long.as_str()))[..2], &[0xd9, 40]);
+        let array = JsonValue::Array((0..20).map(|_| JsonValue::Null).collect());
+        assert_eq!(&encode(&array)[..3], &[0xdc, 0, 20]);
+        assert_eq!(decode(&encode(&array)), Ok(array));
+    }
+
+    #[test]
+    fn decodes_other_forms() {
+        let decode = |msgpack| super::decode(&unhex(msgpack)).map(|value| value.into_owned());
+        assert_eq!(decode("cd0001"), Ok(json!(1)));
+        assert_eq!(decode("d1fffe"), Ok(json!(-2)));
+        assert_eq!(decode("da000161"), Ok(json!("a")));
+        assert_eq!(decode("de0001a161c0"), Ok(json!({"a": null})));
+        assert!(matches!(super::decode(&unhex("a161")), Ok(JsonValue::String(Cow::Borrowed("a")))));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let error = |msgpack| decode(&unhex(msgpack)).unwrap_err();
+        assert_eq!(error(""), MsgpackError { msg: "Unexpected end of input", offset: 0 });
+        assert_eq!(error("c40161").msg, "Binary objects are not supported");
+        assert_eq!(error("d40000").msg, "Extension objects are not supported");
+        assert_eq!(error("c1").msg, "Invalid marker byte");
+        assert_eq!(error("810101"), MsgpackError { msg: "Map keys must be strings", offset: 1 });
+        assert_eq!(error("a2c328"), MsgpackError { msg: "Invalid UTF-8 in string", offset: 1 });
+        assert_eq!(error("ddffffffff"), MsgpackError { msg: "Length exceeds the input", offset: 1 });
+        assert_eq!(error("0000").msg, "Trailing bytes after the object");
+        assert_eq!(error("cd01"), MsgpackError { msg: "Unexpected end of input", offset: 1 });
+        assert_eq!(decode(&vec![0x91; 1000]).unwrap_err().msg, "Nesting depth exceeds the limit");
+    }
+}
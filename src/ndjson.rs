@@ -0,0 +1,82 @@
+//! Newline-delimited JSON (JSON Lines): one document per line.
+
+use crate::{try_parse, JsonError, JsonValue};
+
+/// Iterator over the records of an NDJSON source, see [`parse_lines`].
+pub struct Lines<'a> {
+    source: &'a str,
+    /// Byte offset of the next line.
+    offset: usize,
+    /// Number of the next line, starting at 1.
+    line: usize,
+}
+
+/// Parses each non-blank line of `source` as a document. Errors are located
+/// in the whole source and do not stop the iteration.
+pub fn parse_lines(source: &str) -> Lines<'_> {
+    Lines {
+        source,
+        offset: 0,
+        line: 1,
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Result<JsonValue<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.source.len() {
+            let rest = &self.source[self.offset..];
+            let len = rest.find('\n').map_or(rest.len(), |idx| idx + 1);
+            let (offset, line) = (self.offset, self.line);
+            self.offset += len;
+            self.line += 1;
+
+            if let Some(result) = parse_record(&rest[..len], offset, line) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+/// Parses `text`, the line numbered `line` found at byte `offset`, `None`
+/// if it is blank.
+pub(crate) fn parse_record(text: &str, offset: usize, line: usize) -> Option<Result<JsonValue<'_>, JsonError>> {
+    let record = text.trim_end_matches('\n');
+    if record.trim().is_empty() {
+        return None;
+    }
+    Some(try_parse(record).map_err(|e| e.on_line(offset, line)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records() {
+        let source = "{\"a\": 1}\r\n\n   \n[true]\n\"last\"";
+        let records: Vec<_> = parse_lines(source).map(Result::unwrap).collect();
+        assert_eq!(records, vec![json!({ "a": 1 }), json!([true]), json!("last")]);
+        assert_eq!(parse_lines("\n\n").count(), 0);
+    }
+
+    #[test]
+    fn errors_do_not_stop_the_iteration() {
+        let mut lines = parse_lines("1\n\n[2,\n{\"a\": x}\n3\n");
+        assert_eq!(lines.next().unwrap().unwrap(), json!(1));
+        match lines.next().unwrap() {
+            Err(JsonError::Parse { offset, line, column, .. }) => {
+                assert_eq!((offset, line, column), (6, 3, 4));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        match lines.next().unwrap() {
+            Err(JsonError::Lex(e)) => assert_eq!((e.offset, e.line, e.column), (13, 4, 7)),
+            other => panic!("expected a lex error, got {:?}", other),
+        }
+        assert_eq!(lines.next().unwrap().unwrap(), json!(3));
+        assert!(lines.next().is_none());
+    }
+}
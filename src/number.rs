@@ -0,0 +1,142 @@
+//! Conversions of numbers that tell lossless from lossy ones.
+
+use core::convert::TryFrom;
+
+use crate::JsonNumber;
+
+/// Types a [`JsonNumber`] converts to with [`JsonNumber::exact`] when no
+/// precision is lost.
+pub trait FromNumberExact: Sized {
+    fn from_number_exact(number: &JsonNumber) -> Option<Self>;
+}
+
+/// The integer a number is equal to, floats with a fraction or out of the
+/// range of `i128` having none.
+fn integer(number: &JsonNumber) -> Option<i128> {
+    match *number {
+        JsonNumber::Integer(n) => Some(n.into()),
+        JsonNumber::Unsigned(n) => Some(n.into()),
+        // 2^127, exact as a float. Casts saturate outside of the range.
+        JsonNumber::Float(f) if (-1.7014118346046923e38..1.7014118346046923e38).contains(&f) => {
+            let n = f as i128;
+            (n as f64 == f).then_some(n)
+        }
+        JsonNumber::Float(_) => None,
+    }
+}
+
+macro_rules! integers {
+    ($($ty:ty),*) => {$(
+        impl FromNumberExact for $ty {
+            fn from_number_exact(number: &JsonNumber) -> Option<Self> {
+                <$ty>::try_from(integer(number)?).ok()
+            }
+        }
+    )*};
+}
+integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Integers are exact when they convert back to themselves. NaNs and
+/// infinities are kept.
+macro_rules! floats {
+    ($($ty:ty),*) => {$(
+        impl FromNumberExact for $ty {
+            fn from_number_exact(number: &JsonNumber) -> Option<Self> {
+                match *number {
+                    JsonNumber::Float(f) => {
+                        let converted = f as $ty;
+                        (converted as f64 == f || f.is_nan()).then_some(converted)
+                    }
+                    JsonNumber::Integer(n) => {
+                        let converted = n as $ty;
+                        (converted as i128 == i128::from(n)).then_some(converted)
+                    }
+                    JsonNumber::Unsigned(n) => {
+                        let converted = n as $ty;
+                        (converted as i128 == i128::from(n)).then_some(converted)
+                    }
+                }
+            }
+        }
+    )*};
+}
+floats!(f32, f64);
+
+impl JsonNumber {
+    /// The number as a `T` if it converts without losing precision, e.g.
+    /// `2.0` as an `i64` but not `2.5`, or `300` as an `f32` but not a `u8`.
+    ///
+    /// ```
+    /// # use yaj::JsonNumber;
+    /// assert_eq!(JsonNumber::Float(2.0).exact::<u8>(), Some(2));
+    /// assert_eq!(JsonNumber::Integer(300).exact::<u8>(), None);
+    /// assert_eq!(JsonNumber::Integer((1 << 53) + 1).exact::<f64>(), None);
+    /// ```
+    pub fn exact<T: FromNumberExact>(&self) -> Option<T> {
+        T::from_number_exact(self)
+    }
+
+    /// Whether [`exact`](Self::exact) converts the number to a `T`.
+    pub fn fits_in<T: FromNumberExact>(&self) -> bool {
+        self.exact::<T>().is_some()
+    }
+
+    /// The number as an `i64` if it is an integer in its range, floats
+    /// included.
+    pub fn as_i64_exact(&self) -> Option<i64> {
+        self.exact()
+    }
+
+    /// The number as a `u64` if it is a non-negative integer in its range,
+    /// floats included.
+    pub fn as_u64_exact(&self) -> Option<u64> {
+        self.exact()
+    }
+
+    /// The nearest float, which may not be equal to an integer above 2^53.
+    pub fn as_f64_lossy(&self) -> f64 {
+        match *self {
+            Self::Integer(n) => n as f64,
+            Self::Unsigned(n) => n as f64,
+            Self::Float(f) => f,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_conversions() {
+        use JsonNumber::*;
+        assert_eq!(Integer(-5).as_i64_exact(), Some(-5));
+        assert_eq!(Integer(-5).as_u64_exact(), None);
+        assert_eq!(Unsigned(u64::MAX).as_i64_exact(), None);
+        assert_eq!(Unsigned(u64::MAX).as_u64_exact(), Some(u64::MAX));
+        assert_eq!(Float(3.0).as_i64_exact(), Some(3));
+        assert_eq!(Float(-0.0).as_u64_exact(), Some(0));
+        assert_eq!(Float(3.5).as_i64_exact(), None);
+        assert_eq!(Float(9.3e18).as_i64_exact(), None);
+        assert_eq!(Float(9.3e18).as_u64_exact(), Some(9_300_000_000_000_000_000));
+        assert_eq!(Float(f64::NAN).as_i64_exact(), None);
+        assert_eq!(Float(f64::INFINITY).exact::<u128>(), None);
+        assert_eq!(Float(1e30).exact::<i128>(), Some(1_000_000_000_000_000_019_884_624_838_656));
+
+        assert!(Integer(255).fits_in::<u8>());
+        assert!(!Integer(256).fits_in::<u8>());
+        assert!(Integer(i64::MIN).fits_in::<f64>());
+        assert!(!Integer(i64::MAX).fits_in::<f64>());
+        assert!(Integer(1 << 24).fits_in::<f32>());
+        assert!(!Integer((1 << 24) + 1).fits_in::<f32>());
+        assert!(!Unsigned(u64::MAX).fits_in::<f64>());
+        assert!(Float(0.5).fits_in::<f32>());
+        assert!(!Float(0.1).fits_in::<f32>());
+        assert!(Float(f64::NAN).exact::<f32>().unwrap().is_nan());
+        assert_eq!(Float(f64::NEG_INFINITY).exact::<f32>(), Some(f32::NEG_INFINITY));
+
+        assert_eq!(Integer(i64::MAX).as_f64_lossy(), 9.223372036854776e18);
+        assert_eq!(Unsigned(u64::MAX).as_f64_lossy(), 1.8446744073709552e19);
+        assert_eq!(Float(0.1).as_f64_lossy(), 0.1);
+    }
+}
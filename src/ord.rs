@@ -0,0 +1,266 @@
+//! Total ordering and hashing of values, so that they can be sorted,
+//! deduplicated and kept in a `BTreeSet`, a `HashSet` or as map keys.
+//!
+//! Values of different types are ordered by type:
+//! `null < booleans < numbers < strings < arrays < objects`. Then:
+//! - `false < true`;
+//! - numbers are compared by value whatever their representation. Equal
+//!   values are ordered `Integer < Unsigned < Float`, so that `1` and `1.0`
+//!   stay distinct as with `==`, then floats of equal value follow
+//!   [`f64::total_cmp`], so that `-0.0` comes before `0.0`. NaNs come after
+//!   all other numbers, or before them if their sign bit is set;
+//! - strings are compared by code points;
+//! - arrays are compared element by element, a prefix coming first;
+//! - objects are compared as the lists of their members sorted by key, keys
+//!   as found in the source.
+//!
+//! `==` and `Hash` agree with this ordering, so a NaN is equal to itself
+//! and objects hash the same whatever the order of their members.
+
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+use crate::prelude::*;
+use crate::{JsonNumber, JsonValue};
+
+/// Compares an integer with a float by value; a NaN is above all other
+/// numbers, or below them if its sign bit is set.
+fn cmp_integer_float(integer: i128, float: f64) -> Ordering {
+    if float.is_nan() {
+        return if float.is_sign_negative() {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        };
+    }
+    // The nearest float is enough unless it equals `float`, which is then
+    // an integer small enough for `i128`.
+    match (integer as f64).partial_cmp(&float) {
+        Some(Ordering::Equal) | None => integer.cmp(&(float as i128)),
+        Some(ordering) => ordering,
+    }
+}
+
+impl JsonNumber {
+    /// Rank of the representation, to order numbers of equal value.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Integer(_) => 0,
+            Self::Unsigned(_) => 1,
+            Self::Float(_) => 2,
+        }
+    }
+
+    /// Compares numbers by value only, so that `1` and `1.0` are equal, as
+    /// are `0`, `-0.0` and `0.0`.
+    pub(crate) fn cmp_value(&self, other: &Self) -> Ordering {
+        use JsonNumber::*;
+        match (*self, *other) {
+            (Float(a), Float(b)) => a.partial_cmp(&b).unwrap_or_else(|| a.total_cmp(&b)),
+            (Integer(a), Integer(b)) => a.cmp(&b),
+            (Unsigned(a), Unsigned(b)) => a.cmp(&b),
+            (Integer(a), Unsigned(b)) => i128::from(a).cmp(&i128::from(b)),
+            (Unsigned(a), Integer(b)) => i128::from(a).cmp(&i128::from(b)),
+            (Integer(a), Float(b)) => cmp_integer_float(i128::from(a), b),
+            (Unsigned(a), Float(b)) => cmp_integer_float(i128::from(a), b),
+            (Float(a), Integer(b)) => cmp_integer_float(i128::from(b), a).reverse(),
+            (Float(a), Unsigned(b)) => cmp_integer_float(i128::from(b), a).reverse(),
+        }
+    }
+}
+
+impl Ord for JsonNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_value(other)
+            .then_with(|| self.rank().cmp(&other.rank()))
+            .then_with(|| match (self, other) {
+                (Self::Float(a), Self::Float(b)) => a.total_cmp(b),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for JsonNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for JsonNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for JsonNumber {}
+
+impl Hash for JsonNumber {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match *self {
+            Self::Integer(n) => n.hash(state),
+            Self::Unsigned(n) => n.hash(state),
+            // `total_cmp` only finds equal floats with the same bits.
+            Self::Float(f) => f.to_bits().hash(state),
+        }
+    }
+}
+
+impl JsonValue<'_> {
+    /// Rank of the type, to order values of different types.
+    fn type_rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Boolean(_) => 1,
+            Self::Number(_) => 2,
+            Self::String(_) => 3,
+            Self::Array(_) => 4,
+            Self::Object(_) => 5,
+        }
+    }
+}
+
+impl JsonValue<'_> {
+    /// As `==` but numbers are compared by value only, so that `1` and
+    /// `1.0` are equal, as in RFC 6902 and RFC 9535.
+    pub(crate) fn eq_by_value(&self, other: &JsonValue<'_>) -> bool {
+        match (self, other) {
+            (Self::Number(a), JsonValue::Number(b)) => a.cmp_value(b) == Ordering::Equal,
+            (Self::Array(a), JsonValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_by_value(b))
+            }
+            (Self::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| a.eq_by_value(b)))
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl Ord for JsonValue<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Boolean(a), Self::Boolean(b)) => a.cmp(b),
+            (Self::Number(a), Self::Number(b)) => a.cmp(b),
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Array(a), Self::Array(b)) => a.cmp(b),
+            (Self::Object(a), Self::Object(b)) => {
+                let mut a: Vec<_> = a.iter().collect();
+                let mut b: Vec<_> = b.iter().collect();
+                a.sort_unstable_by(|x, y| x.0.cmp(y.0));
+                b.sort_unstable_by(|x, y| x.0.cmp(y.0));
+                a.cmp(&b)
+            }
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for JsonValue<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for JsonValue<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_rank().hash(state);
+        match self {
+            Self::Null => {}
+            Self::Boolean(b) => b.hash(state),
+            Self::Number(n) => n.hash(state),
+            Self::String(s) => s.hash(state),
+            Self::Array(array) => array.hash(state),
+            Self::Object(obj) => {
+                let mut members: Vec<_> = obj.iter().collect();
+                members.sort_unstable_by(|x, y| x.0.cmp(y.0));
+                members.hash(state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn numbers() {
+        use JsonNumber::*;
+        let sorted = [
+            Float(-f64::NAN),
+            Float(f64::NEG_INFINITY),
+            Integer(i64::MIN),
+            Integer(-1),
+            Float(-0.5),
+            Integer(0),
+            Float(-0.0),
+            Float(0.0),
+            Integer(9_007_199_254_740_992),
+            Float(9_007_199_254_740_992.0),
+            Integer(9_007_199_254_740_993),
+            Integer(i64::MAX),
+            Unsigned(u64::MAX),
+            Float(1e300),
+            Float(f64::INFINITY),
+            Float(f64::NAN),
+        ];
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{:?} {:?}", a, b);
+                assert_eq!(a == b, i == j);
+            }
+        }
+        assert_ne!(Integer(1), Float(1.0));
+        assert_eq!(Float(f64::NAN), Float(f64::NAN));
+        for (a, b) in [
+            (Integer(0), Float(-0.0)),
+            (Integer(0), Float(0.0)),
+            (Float(-0.0), Float(0.0)),
+        ] {
+            assert_eq!(a.cmp_value(&b), Ordering::Equal, "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn values() {
+        let sorted = parse(
+            r#"[null, false, true, -1, 2.5, 3, "", "a", "b", "é", [], [1], [1, 2], [2],
+                {}, {"a": 1, "b": 1}, {"b": 2, "a": 1}, {"a": 2}, {"b": 0}]"#,
+        );
+        let sorted = sorted.as_array().unwrap();
+        for (i, a) in sorted.iter().enumerate() {
+            for (j, b) in sorted.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{:?} {:?}", a, b);
+            }
+        }
+
+        let set: BTreeSet<_> = parse(r#"[3, "x", null, 3, {"b": 1, "a": 2}, {"a": 2, "b": 1}]"#)
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.to_owned())
+            .collect();
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            vec![json!(null), json!(3), json!("x"), json!({"a": 2, "b": 1})]
+        );
+    }
+
+    #[test]
+    fn hash_agrees_with_eq() {
+        let records = parse(
+            r#"[{"id": 1, "tags": ["a"]}, {"tags": ["a"], "id": 1}, {"id": 1.0, "tags": ["a"]},
+                {"id": 1, "tags": ["a", "b"]}, null, null, [1, 2], [2, 1]]"#,
+        );
+        let unique: HashSet<_> = records.as_array().unwrap().iter().collect();
+        assert_eq!(unique.len(), 6);
+        assert!(unique.contains(&json!({"tags": ["a"], "id": 1})));
+
+        let numbers: HashSet<_> = [f64::NAN, f64::NAN, 0.0, -0.0].iter().map(|&f| JsonNumber::Float(f)).collect();
+        assert_eq!(numbers.len(), 3);
+    }
+}
@@ -0,0 +1,125 @@
+//! Parsing on several threads with `rayon`, for bulk exports made of many
+//! independent records.
+
+use core::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::ndjson::parse_record;
+use crate::scan::{skip_whitespace, string_end};
+use crate::{try_parse, JsonError, JsonValue, ParseOptions, Parser};
+
+/// Same as [`parse_lines`](crate::ndjson::parse_lines), the lines being
+/// parsed in parallel. Results are in the order of the lines.
+pub fn par_parse_lines(source: &str) -> Vec<Result<JsonValue<'_>, JsonError>> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for (idx, text) in source.split_inclusive('\n').enumerate() {
+        lines.push((text, offset, idx + 1));
+        offset += text.len();
+    }
+    lines
+        .into_par_iter()
+        .filter_map(|(text, offset, line)| parse_record(text, offset, line))
+        .collect()
+}
+
+/// Same as [`try_parse`] for a document whose top level is an array: its
+/// elements are found by a quick scan of brackets, quotes and commas, then
+/// parsed in parallel. Other documents, and invalid ones so that the error
+/// is the same, are parsed on the current thread.
+pub fn par_parse_array(source: &str) -> Result<JsonValue<'_>, JsonError> {
+    let ranges = match element_ranges(source) {
+        Some(ranges) => ranges,
+        None => return try_parse(source),
+    };
+    // The elements are nested one level deeper than their own documents,
+    // and a byte order mark before one of them is an error.
+    let parser = Parser::new().max_depth(ParseOptions::default().max_depth.saturating_sub(1));
+    let elements: Result<Vec<_>, _> = ranges
+        .into_par_iter()
+        .map(|range| parser.parse_continuing(&source[range]))
+        .collect();
+    match elements {
+        Ok(elements) => Ok(JsonValue::Array(elements)),
+        Err(_) => try_parse(source),
+    }
+}
+
+/// Byte ranges of the elements of `source` if it looks like an array. The
+/// elements themselves are not checked.
+fn element_ranges(source: &str) -> Option<Vec<Range<usize>>> {
+    let bytes = source.as_bytes();
+    let bom = if source.starts_with('\u{feff}') { 3 } else { 0 };
+    let mut pos = skip_whitespace(bytes, bom);
+    if bytes.get(pos) != Some(&b'[') {
+        return None;
+    }
+    pos += 1;
+    let mut start = pos;
+    let mut depth = 0usize;
+    let mut ranges = Vec::new();
+    while let Some(&byte) = bytes.get(pos) {
+        match byte {
+            b'"' => loop {
+                pos = string_end(bytes, (pos + 1).min(bytes.len()), b'"');
+                match bytes.get(pos)? {
+                    b'"' => break,
+                    // Skips the escaped char, which may be a quote.
+                    b'\\' => pos += 1,
+                    _ => {}
+                }
+            },
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' if depth > 0 => depth -= 1,
+            b']' => {
+                if !(ranges.is_empty() && source[start..pos].trim().is_empty()) {
+                    ranges.push(start..pos);
+                }
+                return (skip_whitespace(bytes, pos + 1) == bytes.len()).then_some(ranges);
+            }
+            b',' if depth == 0 => {
+                ranges.push(start..pos);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ndjson::parse_lines;
+
+    #[test]
+    fn lines() {
+        let source = "{\"a\": 1}\n\n[2,\n\"x\"\r\n3";
+        let sequential: Vec<_> = parse_lines(source).collect();
+        assert_eq!(par_parse_lines(source), sequential);
+        assert_eq!(par_parse_lines(source).len(), 4);
+        assert!(par_parse_lines("").is_empty());
+    }
+
+    #[test]
+    fn arrays() {
+        let large = format!("[{}]", vec![r#"{"id": 1, "tags": ["a,b", "]\"["], "n": [[]]}"#; 1000].join(", "));
+        assert_eq!(par_parse_array(&large).unwrap(), try_parse(&large).unwrap());
+        for source in &["[]", " [ ] ", "\u{feff}[1]", "[\"\\\\\", 2]", "{\"a\": [1]}", "3"] {
+            assert_eq!(par_parse_array(source).unwrap(), try_parse(source).unwrap());
+        }
+        for source in &["[1,]", "[,1]", "[1 2]", "[1] 2", "[[1]", "[\"a]", "[{\"a\" 1}]", ""] {
+            assert_eq!(par_parse_array(source), try_parse(source), "{}", source);
+        }
+        // A byte order mark is only skipped at the start of the document.
+        for source in &["[1,\u{feff}2]", "[\u{feff}1]"] {
+            assert!(par_parse_array(source).is_err(), "{}", source);
+            assert_eq!(par_parse_array(source), try_parse(source), "{}", source);
+        }
+        let deep = format!("[{}{}]", "[".repeat(128), "]".repeat(128));
+        assert!(par_parse_array(&deep).is_err());
+        assert_eq!(par_parse_array(&deep), try_parse(&deep));
+    }
+}
@@ -0,0 +1,819 @@
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::collections::btree_map::Entry;
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+use std::collections::hash_map::Entry;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::map::Entry;
+
+use crate::cst::Cst;
+use crate::encoding::{decode, decode_error, Encoding};
+use crate::prelude::*;
+use crate::span::parse_spanned;
+use crate::{
+    line_column, unescape, JsonError, JsonNumber, JsonToken, JsonTokenType, JsonValue, LexError, Lexer, Map, Spanned,
+};
+
+/// Content of a string token, without the quotes.
+pub(crate) fn string_content<'a>(token: &JsonToken<'a>) -> &'a str {
+    &token.slice[1..(token.slice.len() - 1)]
+}
+
+/// Name of the member introduced by the key `token`, with its escapes left
+/// as in the source.
+pub(crate) fn raw_key<'a>(token: &JsonToken<'a>) -> &'a str {
+    match token.token_type {
+        JsonTokenType::String => string_content(token),
+        _ => token.slice,
+    }
+}
+
+/// Value of a string, number or literal token, `None` for punctuation.
+pub(crate) fn scalar<'a>(token: &JsonToken<'a>) -> Option<JsonValue<'a>> {
+    Some(match token.token_type {
+        JsonTokenType::String => JsonValue::String(unescape(string_content(token))),
+        JsonTokenType::Number => JsonValue::Number(JsonNumber::parse(token.slice)),
+        JsonTokenType::True => JsonValue::Boolean(true),
+        JsonTokenType::False => JsonValue::Boolean(false),
+        JsonTokenType::Null => JsonValue::Null,
+        _ => return None,
+    })
+}
+
+/// Token types accepted at each point of the grammar, as reported by
+/// [`JsonError::expected`].
+pub(crate) mod expected {
+    use crate::JsonTokenType::{self, *};
+
+    pub(crate) const VALUE: &[JsonTokenType] = &[LeftBrace, LeftBracket, String, Number, True, False, Null];
+    pub(crate) const FIRST_ELEMENT: &[JsonTokenType] =
+        &[LeftBrace, LeftBracket, String, Number, True, False, Null, RightBracket];
+    pub(crate) const AFTER_ELEMENT: &[JsonTokenType] = &[Comma, RightBracket];
+    pub(crate) const FIRST_KEY: &[JsonTokenType] = &[String, RightBrace];
+    pub(crate) const KEY: &[JsonTokenType] = &[String];
+    pub(crate) const COLUMN: &[JsonTokenType] = &[Column];
+    pub(crate) const AFTER_MEMBER: &[JsonTokenType] = &[Comma, RightBrace];
+}
+
+/// What to do when an object contains the same key more than once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DuplicateKeys {
+    FirstWins,
+    LastWins,
+    /// Reject the document, as RFC 8259 leaves the behavior unspecified.
+    Error,
+}
+
+/// What to do with a `\uXXXX` escape of a UTF-16 surrogate that is not
+/// part of a high-low pair.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoneSurrogates {
+    /// Decode it as U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    Error,
+}
+
+/// Resource limit of [`ParseOptions`] reported by
+/// [`JsonError::LimitExceeded`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LimitKind {
+    /// [`ParseOptions::max_size`]
+    Size,
+    /// [`ParseOptions::max_len`]
+    Len,
+    /// [`ParseOptions::max_string_len`]
+    StringLen,
+}
+
+/// Settings of a [`Parser`], also accepted by [`try_parse_with`](crate::try_parse_with).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    pub duplicate_keys: DuplicateKeys,
+    /// How many arrays and objects may be nested in one another. Deeper
+    /// documents are rejected instead of exhausting the stack.
+    pub max_depth: usize,
+    /// Accept `// line` and `/* block */` comments (JSONC).
+    pub allow_comments: bool,
+    /// Accept JSON5 documents, see [`Lexer::json5`]. Comments and trailing
+    /// commas are allowed as well.
+    pub json5: bool,
+    pub lone_surrogates: LoneSurrogates,
+    /// Accept raw control characters in strings, as found in some legacy
+    /// data. RFC 8259 requires them to be escaped.
+    pub allow_control_chars: bool,
+    /// Accept the `NaN`, `Infinity` and `-Infinity` numbers written with
+    /// [`NonFinite::Literal`](crate::ser::NonFinite::Literal), which JSON5
+    /// allows too.
+    pub allow_non_finite: bool,
+    /// How many bytes the source may contain.
+    pub max_size: Option<usize>,
+    /// How many elements an array, or members an object, may contain.
+    pub max_len: Option<usize>,
+    /// How many bytes a string or a key may contain, counted before
+    /// escape sequences are resolved.
+    pub max_string_len: Option<usize>,
+    /// Decode bytes that are not valid in the encoding of the document as
+    /// U+FFFD REPLACEMENT CHARACTER in [`Parser::parse_slice`], instead of
+    /// rejecting them.
+    pub lossy_decoding: bool,
+    /// Resolve the escape sequences of object keys, as done for string
+    /// values. Otherwise keys are kept as written in the source, which
+    /// never allocates. Keys without escapes are borrowed either way.
+    pub unescape_keys: bool,
+}
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_keys: DuplicateKeys::LastWins,
+            max_depth: 128,
+            allow_comments: false,
+            json5: false,
+            lone_surrogates: LoneSurrogates::Replace,
+            allow_control_chars: false,
+            allow_non_finite: false,
+            max_size: None,
+            max_len: None,
+            max_string_len: None,
+            lossy_decoding: false,
+            unescape_keys: true,
+        }
+    }
+}
+impl ParseOptions {
+    pub(crate) fn lexer<'a>(&self, source: &'a str) -> Lexer<'a> {
+        self.configure(Lexer::new(source))
+    }
+
+    /// Lexer of `source` taken after the start of a document, see
+    /// [`Lexer::continuing`].
+    #[cfg(feature = "std")]
+    pub(crate) fn continuing_lexer<'a>(&self, source: &'a str) -> Lexer<'a> {
+        self.configure(Lexer::continuing(source))
+    }
+
+    fn configure<'a>(&self, lexer: Lexer<'a>) -> Lexer<'a> {
+        lexer
+            .allow_comments(self.allow_comments)
+            .json5(self.json5)
+            .reject_lone_surrogates(self.lone_surrogates == LoneSurrogates::Error)
+            .allow_control_chars(self.allow_control_chars)
+            .allow_non_finite(self.allow_non_finite)
+    }
+}
+
+/// Configurable entry point of the parser, [`try_parse`](crate::try_parse)
+/// being the same with the default settings.
+///
+/// ```
+/// # use yaj::{json, DuplicateKeys, Parser};
+/// let parser = Parser::new()
+///     .max_depth(16)
+///     .allow_comments(true)
+///     .duplicate_keys(DuplicateKeys::Error);
+/// assert_eq!(parser.parse("[1, /* two */ 2]").unwrap(), json!([1, 2]));
+/// assert!(parser.parse(r#"{"a": 1, "a": 2}"#).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Parser {
+    options: ParseOptions,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.options.duplicate_keys = policy;
+        self
+    }
+
+    /// See [`ParseOptions::max_depth`].
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.options.max_depth = max_depth;
+        self
+    }
+
+    /// Accept `// line` and `/* block */` comments (JSONC).
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.options.allow_comments = allow;
+        self
+    }
+
+    /// See [`ParseOptions::json5`].
+    pub fn json5(mut self, json5: bool) -> Self {
+        self.options.json5 = json5;
+        self
+    }
+
+    pub fn lone_surrogates(mut self, policy: LoneSurrogates) -> Self {
+        self.options.lone_surrogates = policy;
+        self
+    }
+
+    /// See [`ParseOptions::allow_control_chars`].
+    pub fn allow_control_chars(mut self, allow: bool) -> Self {
+        self.options.allow_control_chars = allow;
+        self
+    }
+
+    /// See [`ParseOptions::allow_non_finite`].
+    pub fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.options.allow_non_finite = allow;
+        self
+    }
+
+    /// See [`ParseOptions::max_size`].
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.options.max_size = Some(max_size);
+        self
+    }
+
+    /// See [`ParseOptions::max_len`].
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.options.max_len = Some(max_len);
+        self
+    }
+
+    /// See [`ParseOptions::max_string_len`].
+    pub fn max_string_len(mut self, max_string_len: usize) -> Self {
+        self.options.max_string_len = Some(max_string_len);
+        self
+    }
+
+    /// See [`ParseOptions::lossy_decoding`].
+    pub fn lossy_decoding(mut self, lossy: bool) -> Self {
+        self.options.lossy_decoding = lossy;
+        self
+    }
+
+    /// See [`ParseOptions::unescape_keys`].
+    pub fn unescape_keys(mut self, unescape: bool) -> Self {
+        self.options.unescape_keys = unescape;
+        self
+    }
+
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Parses a whole document, borrowing from `source` where possible.
+    pub fn parse<'a>(&self, source: &'a str) -> Result<JsonValue<'a>, JsonError> {
+        TokenParser::new(source, self.options.lexer(source), self.options.clone()).parse_document()
+    }
+
+    /// Same as [`parse`](Self::parse) for a value taken from inside a
+    /// document, where a byte order mark is an error.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_continuing<'a>(&self, source: &'a str) -> Result<JsonValue<'a>, JsonError> {
+        TokenParser::new(source, self.options.continuing_lexer(source), self.options.clone()).parse_document()
+    }
+
+    /// Same as [`parse`](Self::parse) from bytes, borrowing from them when
+    /// they are UTF-8. Documents in UTF-16 or UTF-32 are transcoded first,
+    /// and the offsets of parse errors are then in the UTF-8 text.
+    pub fn parse_slice<'a>(&self, bytes: &'a [u8]) -> Result<JsonValue<'a>, JsonError> {
+        let encoding = Encoding::detect(bytes);
+        match decode(bytes, encoding, self.options.lossy_decoding) {
+            Ok(Cow::Borrowed(source)) => self.parse(source),
+            Ok(Cow::Owned(source)) => self.parse(&source).map(JsonValue::into_owned),
+            Err(offset) => Err(JsonError::Lex(decode_error(bytes, encoding, offset))),
+        }
+    }
+
+    /// Same as [`parse`](Self::parse), recording where each value and key
+    /// is in the source.
+    pub fn parse_with_spans<'a>(&self, source: &'a str) -> Result<Spanned<'a>, JsonError> {
+        parse_spanned(source, self.options.clone())
+    }
+
+    /// Same as [`parse`](Self::parse), keeping all of the text of the
+    /// document, see the [`cst`](crate::cst) module. Repeated keys are all
+    /// kept unless [`DuplicateKeys::Error`] rejects them.
+    pub fn parse_cst<'a>(&self, source: &'a str) -> Result<Cst<'a>, JsonError> {
+        TokenParser::new(source, self.options.lexer(source), self.options.clone()).parse_cst_document()
+    }
+}
+
+impl From<ParseOptions> for Parser {
+    fn from(options: ParseOptions) -> Self {
+        Self { options }
+    }
+}
+
+/// Single-pass recursive-descent parser pulling tokens one at a time.
+pub(crate) struct TokenParser<'a, I> {
+    source: &'a str,
+    tokens: I,
+    options: ParseOptions,
+    /// Number of arrays and objects currently open.
+    depth: usize,
+    /// Byte offset following the last token read.
+    end: usize,
+}
+
+impl<'a, I> TokenParser<'a, I>
+where
+    I: Iterator<Item = Result<JsonToken<'a>, LexError>>,
+{
+    pub(crate) fn new(source: &'a str, tokens: I, options: ParseOptions) -> Self {
+        Self {
+            source,
+            tokens,
+            options,
+            depth: 0,
+            end: 0,
+        }
+    }
+
+    pub(crate) fn error<T>(
+        &self,
+        msg: &str,
+        token: Option<&JsonToken<'a>>,
+        expected: &'static [JsonTokenType],
+    ) -> Result<T, JsonError> {
+        Err(JsonError::at_token(self.source, msg, token, expected))
+    }
+
+    pub(crate) fn next_token(&mut self) -> Result<Option<JsonToken<'a>>, JsonError> {
+        let token = self.tokens.next().transpose()?;
+        if let Some(token) = &token {
+            self.end = token.offset + token.slice.len();
+            if let (JsonTokenType::String, Some(limit)) = (&token.token_type, self.options.max_string_len) {
+                if string_content(token).len() > limit {
+                    return Err(self.limit_exceeded(LimitKind::StringLen, limit, token.offset));
+                }
+            }
+        }
+        Ok(token)
+    }
+
+    // Kept out of the recursive functions so their frames stay small.
+    #[cold]
+    #[inline(never)]
+    fn limit_exceeded(&self, kind: LimitKind, limit: usize, offset: usize) -> JsonError {
+        let (line, column) = line_column(self.source, offset);
+        JsonError::LimitExceeded {
+            kind,
+            limit,
+            offset,
+            line,
+            column,
+        }
+    }
+
+    /// Checks the source against [`ParseOptions::max_size`].
+    pub(crate) fn check_size(&self) -> Result<(), JsonError> {
+        match self.options.max_size {
+            Some(limit) if self.source.len() > limit => {
+                let offset = (0..=limit).rev().find(|&idx| self.source.is_char_boundary(idx)).unwrap_or(0);
+                Err(self.limit_exceeded(LimitKind::Size, limit, offset))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the token following `len` elements or members of an array or
+    /// object, checking that another one may start there, see
+    /// [`ParseOptions::max_len`].
+    pub(crate) fn expect_item(&mut self, len: usize, expected: &'static [JsonTokenType]) -> Result<JsonToken<'a>, JsonError> {
+        let token = self.expect_token(expected)?;
+        match (&token.token_type, self.options.max_len) {
+            (JsonTokenType::RightBracket | JsonTokenType::RightBrace, _) => Ok(token),
+            (_, Some(limit)) if len == limit => Err(self.limit_exceeded(LimitKind::Len, limit, token.offset)),
+            _ => Ok(token),
+        }
+    }
+
+    pub(crate) fn end(&self) -> usize {
+        self.end
+    }
+
+    pub(crate) fn source(&self) -> &'a str {
+        self.source
+    }
+
+    pub(crate) fn expect_token(&mut self, expected: &'static [JsonTokenType]) -> Result<JsonToken<'a>, JsonError> {
+        match self.next_token()? {
+            Some(token) => Ok(token),
+            None => self.error("Unexpected end of file", None, expected),
+        }
+    }
+
+    /// Parses exactly one value and makes sure nothing follows it.
+    pub(crate) fn parse_document(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        self.check_size()?;
+        let first = match self.next_token()? {
+            Some(token) => token,
+            None => return Err(JsonError::Empty),
+        };
+        let value = self.parse_value(first)?;
+        match self.next_token()? {
+            None => Ok(value),
+            Some(token) => self.error("Invalid token at the end of document", Some(&token), &[]),
+        }
+    }
+
+    /// Parses the next value if any token is left, leaving what follows it.
+    pub(crate) fn parse_next(&mut self) -> Result<Option<JsonValue<'a>>, JsonError> {
+        self.check_size()?;
+        match self.next_token()? {
+            Some(token) => self.parse_value(token).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Accounts for the array or object opened by `token`.
+    pub(crate) fn enter(&mut self, token: &JsonToken<'a>) -> Result<(), JsonError> {
+        if self.depth == self.options.max_depth {
+            let (line, column) = line_column(self.source, token.offset);
+            return Err(JsonError::DepthLimitExceeded {
+                limit: self.options.max_depth,
+                offset: token.offset,
+                line,
+                column,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Accounts for the end of the innermost array or object.
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(crate) fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    fn parse_value(&mut self, token: JsonToken<'a>) -> Result<JsonValue<'a>, JsonError> {
+        Ok(match token.token_type {
+            JsonTokenType::LeftBracket => {
+                self.enter(&token)?;
+                let array = self.parse_array()?;
+                self.leave();
+                array
+            }
+            JsonTokenType::LeftBrace => {
+                self.enter(&token)?;
+                let obj = self.parse_object()?;
+                self.leave();
+                obj
+            }
+            _ => match scalar(&token) {
+                Some(value) => value,
+                None => return self.error("Unexpected token in place of a value", Some(&token), expected::VALUE),
+            },
+        })
+    }
+
+    /// Parses the elements of an array whose `[` was just consumed.
+    fn parse_array(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        let mut array = Vec::new();
+        let mut token = self.expect_item(0, expected::FIRST_ELEMENT)?;
+        if token.token_type == JsonTokenType::RightBracket {
+            return Ok(JsonValue::Array(array));
+        }
+        loop {
+            array.push(self.parse_value(token)?);
+            let separator = self.expect_token(expected::AFTER_ELEMENT)?;
+            match separator.token_type {
+                JsonTokenType::Comma => {
+                    token = self.expect_item(array.len(), expected::VALUE)?;
+                    if self.options.json5 && token.token_type == JsonTokenType::RightBracket {
+                        break;
+                    }
+                }
+                JsonTokenType::RightBracket => break,
+                _ => return self.error("Expected ',' or ']' after array element", Some(&separator), expected::AFTER_ELEMENT),
+            }
+        }
+        Ok(JsonValue::Array(array))
+    }
+
+    /// Whether `token` may introduce a member: a string, or in JSON5 any
+    /// identifier, `Infinity` and `NaN` included.
+    pub(crate) fn is_key(&self, token: &JsonToken<'a>) -> bool {
+        match token.token_type {
+            JsonTokenType::String => true,
+            JsonTokenType::Identifier | JsonTokenType::True | JsonTokenType::False | JsonTokenType::Null => {
+                self.options.json5
+            }
+            JsonTokenType::Number => self.options.json5 && token.slice.starts_with(char::is_alphabetic),
+            _ => false,
+        }
+    }
+
+    /// Name of the member introduced by `token`, see [`is_key`](Self::is_key).
+    /// Escapes are resolved according to [`ParseOptions::unescape_keys`].
+    pub(crate) fn key(&self, token: &JsonToken<'a>) -> Option<Cow<'a, str>> {
+        if !self.is_key(token) {
+            return None;
+        }
+        Some(match token.token_type {
+            JsonTokenType::String if self.options.unescape_keys => unescape(raw_key(token)),
+            _ => Cow::Borrowed(raw_key(token)),
+        })
+    }
+
+    /// Parses the members of an object whose `{` was just consumed.
+    fn parse_object(&mut self) -> Result<JsonValue<'a>, JsonError> {
+        let mut obj = Map::new();
+        let mut token = self.expect_item(0, expected::FIRST_KEY)?;
+        if token.token_type == JsonTokenType::RightBrace {
+            return Ok(JsonValue::Object(obj));
+        }
+        // Members read, duplicates included.
+        let mut len = 1;
+        loop {
+            let key = match self.key(&token) {
+                Some(key) => key,
+                None => return self.error("Unexpected token in place of string key in object", Some(&token), expected::KEY),
+            };
+            let column = self.expect_token(expected::COLUMN)?;
+            if column.token_type != JsonTokenType::Column {
+                return self.error("Expected ':' after object key", Some(&column), expected::COLUMN);
+            }
+            let first = self.expect_token(expected::VALUE)?;
+            let value = self.parse_value(first)?;
+            match obj.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(value);
+                }
+                Entry::Occupied(mut entry) => match self.options.duplicate_keys {
+                    DuplicateKeys::FirstWins => {}
+                    DuplicateKeys::LastWins => {
+                        entry.insert(value);
+                    }
+                    DuplicateKeys::Error => {
+                        return self.error("Duplicate key in object", Some(&token), &[]);
+                    }
+                },
+            }
+
+            let separator = self.expect_token(expected::AFTER_MEMBER)?;
+            match separator.token_type {
+                JsonTokenType::Comma => {
+                    token = self.expect_item(len, expected::KEY)?;
+                    len += 1;
+                    if self.options.json5 && token.token_type == JsonTokenType::RightBrace {
+                        break;
+                    }
+                }
+                JsonTokenType::RightBrace => break,
+                _ => return self.error("Expected ',' or '}' after object member", Some(&separator), expected::AFTER_MEMBER),
+            }
+        }
+        Ok(JsonValue::Object(obj))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, to_string, try_parse, try_parse_with};
+
+    fn parse_error(source: &str) -> String {
+        match try_parse(source) {
+            Err(JsonError::Parse { msg, .. }) => msg,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nested_documents() {
+        let value = parse(r#"{"a": [1, [2, {"b": []}], {}], "c": {"d": "e"}}"#);
+        match value {
+            JsonValue::Object(obj) => {
+                assert_eq!(to_string(&obj["a"]), r#"[1,[2,{"b":[]}],{}]"#);
+                assert_eq!(to_string(&obj["c"]), r#"{"d":"e"}"#);
+            }
+            other => panic!("expected an object, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        assert_eq!(parse_error("[1,]"), "Unexpected token in place of a value");
+        assert_eq!(parse_error("[1 2]"), "Expected ',' or ']' after array element");
+        assert_eq!(parse_error(r#"{"a" 1}"#), "Expected ':' after object key");
+        assert_eq!(parse_error(r#"{"a": 1,}"#), "Unexpected token in place of string key in object");
+        assert_eq!(parse_error(r#"{"a": 1 "b": 2}"#), "Expected ',' or '}' after object member");
+        assert_eq!(parse_error("[[]"), "Unexpected end of file");
+        assert_eq!(parse_error("[] []"), "Invalid token at the end of document");
+        assert_eq!(parse_error("]"), "Unexpected token in place of a value");
+    }
+
+    #[test]
+    fn deeply_nested_arrays() {
+        let depth = 500;
+        let source = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let options = ParseOptions {
+            max_depth: depth,
+            ..ParseOptions::default()
+        };
+        assert!(try_parse_with(&source, options).is_ok());
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = |depth: usize| format!(r#"{}{{"a":1}}{}"#, "[".repeat(depth - 1), "]".repeat(depth - 1));
+        assert!(try_parse(&nested(128)).is_ok());
+        match try_parse(&nested(129)) {
+            Err(JsonError::DepthLimitExceeded { limit, offset, .. }) => {
+                assert_eq!(limit, 128);
+                assert_eq!(offset, 128);
+            }
+            other => panic!("expected a depth error, got {:?}", other),
+        }
+        let source = "[".repeat(1_000_000);
+        assert!(matches!(try_parse(&source), Err(JsonError::DepthLimitExceeded { .. })));
+    }
+
+    #[test]
+    fn resource_limits() {
+        let source = r#"{"faces": [1, 2, 3], "name": "camera"}"#;
+        let limit = |error: Option<JsonError>| match error {
+            Some(JsonError::LimitExceeded { kind, limit, offset, .. }) => (kind, limit, offset),
+            other => panic!("expected a limit error, got {:?}", other),
+        };
+        assert!(Parser::new().max_size(38).max_len(3).max_string_len(6).parse(source).is_ok());
+        assert_eq!(limit(Parser::new().max_size(37).parse(source).err()), (LimitKind::Size, 37, 37));
+        assert_eq!(limit(Parser::new().max_len(2).parse(source).err()), (LimitKind::Len, 2, 17));
+        assert_eq!(limit(Parser::new().max_len(1).parse_with_spans(source).err()), (LimitKind::Len, 1, 14));
+        assert_eq!(limit(Parser::new().max_string_len(5).parse(source).err()), (LimitKind::StringLen, 5, 29));
+        assert_eq!(limit(Parser::new().max_size(1).parse("\"é\"").err()), (LimitKind::Size, 1, 1));
+        assert_eq!(
+            Parser::new().max_string_len(4).parse("\n[\"faces\"]").unwrap_err().to_string(),
+            "String length exceeds the limit of 4 at line 2 column 2"
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let source = r#"{"a": 1, "b": {"a": 2}, "a": 3}"#;
+        let with = |duplicate_keys| {
+            let options = ParseOptions {
+                duplicate_keys,
+                ..ParseOptions::default()
+            };
+            try_parse_with(source, options)
+        };
+        assert_eq!(with(DuplicateKeys::LastWins).unwrap()["a"], json!(3));
+        assert_eq!(with(DuplicateKeys::FirstWins).unwrap()["a"], json!(1));
+        match with(DuplicateKeys::Error) {
+            Err(JsonError::Parse { msg, offset, .. }) => {
+                assert_eq!(msg, "Duplicate key in object");
+                assert_eq!(offset, 24);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert_eq!(try_parse(source).unwrap()["a"], json!(3));
+    }
+
+    #[test]
+    fn escaped_keys() {
+        let source = r#"{"a\u0041": 1, "\"q\"": 2, "plain": 3, "aA": 4}"#;
+        let value = try_parse(source).unwrap();
+        assert_eq!(value, json!({"aA": 4, "\"q\"": 2, "plain": 3}));
+        let obj = value.as_object().unwrap();
+        assert!(matches!(obj.get_key_value("plain"), Some((Cow::Borrowed(_), _))));
+        assert!(matches!(obj.get_key_value("\"q\""), Some((Cow::Owned(_), _))));
+        assert_eq!(to_string(&value["\"q\""]), "2");
+
+        let raw = Parser::new().unescape_keys(false).parse(source).unwrap();
+        assert_eq!(raw, json!({"a\\u0041": 1, "\\\"q\\\"": 2, "plain": 3, "aA": 4}));
+        let obj = raw.as_object().unwrap();
+        assert!(obj.keys().all(|key| matches!(key, Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn comments() {
+        let source = r#"{
+            // Compiler settings
+            "strict": true, /* for now */
+            "paths": [1, 2 /* , 3 */]
+        }"#;
+        assert!(try_parse(source).is_err());
+        let options = ParseOptions {
+            allow_comments: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            try_parse_with(source, options).unwrap(),
+            json!({ "strict": true, "paths": [1, 2] })
+        );
+    }
+
+    #[test]
+    fn json5() {
+        let source = r#"{
+  // comments
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+  null: [Infinity, -Infinity, NaN, -0x10, 1.e2, +.5],
+}"#;
+        assert!(try_parse(source).is_err());
+        let options = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let value = try_parse_with(source, options).unwrap();
+        assert_eq!(value["unquoted"], json!("and you can quote me on that"));
+        assert_eq!(value["singleQuotes"], json!("I can use \"double quotes\" here"));
+        assert_eq!(value["lineBreaks"], json!("Look, Mom! No \\n's!"));
+        assert_eq!(value["hexadecimal"], json!(0xdecaf));
+        assert_eq!(value["leadingDecimalPoint"], json!(0.8675309));
+        assert_eq!(value["andTrailing"], json!(8675309.0));
+        assert_eq!(value["positiveSign"], json!(1));
+        assert_eq!(value["andIn"], json!(["arrays"]));
+        assert_eq!(value["backwardsCompatible"], json!("with JSON"));
+        let numbers: Vec<f64> = value["null"].as_array().unwrap().iter().map(|n| n.as_f64().unwrap()).collect();
+        assert_eq!(numbers[..2], [f64::INFINITY, f64::NEG_INFINITY]);
+        assert!(numbers[2].is_nan());
+        assert_eq!(numbers[3..], [-16.0, 100.0, 0.5]);
+    }
+
+    #[test]
+    fn json5_errors() {
+        let options = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        assert!(try_parse_with("[1,,]", options.clone()).is_err());
+        assert!(try_parse_with("[0x]", options.clone()).is_err());
+        assert!(try_parse_with("[+Inf]", options.clone()).is_err());
+        assert!(try_parse_with("{a: b}", options.clone()).is_err());
+        assert!(try_parse_with(r"['\1']", options).is_err());
+    }
+
+    #[test]
+    fn lone_surrogate_policy() {
+        let source = r#"["\ud83d\ude00", "a\udc00b"]"#;
+        assert_eq!(try_parse(source).unwrap(), json!(["\u{1f600}", "a\u{fffd}b"]));
+        let options = ParseOptions {
+            lone_surrogates: LoneSurrogates::Error,
+            ..ParseOptions::default()
+        };
+        match try_parse_with(source, options) {
+            Err(JsonError::Lex(e)) => assert_eq!((e.kind, e.offset), (crate::LexErrorKind::LoneSurrogate, 19)),
+            other => panic!("expected a lex error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn control_characters_policy() {
+        let source = "{\"log\": \"line 1\nline 2\"}";
+        assert!(matches!(try_parse(source), Err(JsonError::Lex(_))));
+        let options = ParseOptions {
+            allow_control_chars: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(try_parse_with(source, options).unwrap()["log"], json!("line 1\nline 2"));
+    }
+
+    #[test]
+    fn builder() {
+        let parser = Parser::new()
+            .max_depth(2)
+            .json5(true)
+            .duplicate_keys(DuplicateKeys::FirstWins)
+            .lone_surrogates(LoneSurrogates::Error)
+            .allow_control_chars(true)
+            .allow_non_finite(true)
+            .max_len(10)
+            .lossy_decoding(true)
+            .unescape_keys(false);
+        assert_eq!(
+            parser.options(),
+            &ParseOptions {
+                duplicate_keys: DuplicateKeys::FirstWins,
+                max_depth: 2,
+                allow_comments: false,
+                json5: true,
+                lone_surrogates: LoneSurrogates::Error,
+                allow_control_chars: true,
+                allow_non_finite: true,
+                max_size: None,
+                max_len: Some(10),
+                max_string_len: None,
+                lossy_decoding: true,
+                unescape_keys: false,
+            }
+        );
+        assert_eq!(parser.parse("{a: [1,], a: 2}").unwrap(), json!({"a": [1]}));
+        assert!(matches!(parser.parse("[[[]]]"), Err(JsonError::DepthLimitExceeded { limit: 2, .. })));
+        assert!(parser.parse("\"\\ud800\"").is_err());
+        assert_eq!(Parser::new().parse("[1]"), try_parse("[1]"));
+        assert_eq!(Parser::from(ParseOptions::default()), Parser::new());
+    }
+}
@@ -0,0 +1,354 @@
+//! RFC 6902 JSON Patch: a list of operations, each designating locations
+//! of a document with JSON Pointers.
+//!
+//! ```
+//! use yaj::{json, parse, patch::{self, JsonPatch}};
+//! let mut doc = json!({"faces": [{"uid": 1}]});
+//! let ops = parse(r#"[
+//!     {"op": "add", "path": "/faces/-", "value": {"uid": 2}},
+//!     {"op": "test", "path": "/faces/0/uid", "value": 1},
+//!     {"op": "remove", "path": "/faces/0"}
+//! ]"#);
+//! patch::apply(&mut doc, &JsonPatch::from_value(&ops).unwrap()).unwrap();
+//! assert_eq!(doc, json!({"faces": [{"uid": 2}]}));
+//! ```
+
+use crate::prelude::*;
+use crate::pointer::{array_index, push_token, reference_tokens};
+use crate::{JsonValue, JsonValueOwned, Map};
+
+#[derive(Debug, PartialEq)]
+pub enum PatchOperation {
+    /// Inserts `value` at `path`, `-` designating the end of an array.
+    Add { path: String, value: JsonValueOwned },
+    Remove { path: String },
+    Replace { path: String, value: JsonValueOwned },
+    /// Removes the value at `from` and adds it at `path`.
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    /// Checks that the value at `path` is `value`, numbers being compared
+    /// by value only.
+    Test { path: String, value: JsonValueOwned },
+}
+
+/// A list of operations, applied in order by [`apply`].
+#[derive(Debug, Default, PartialEq)]
+pub struct JsonPatch(pub Vec<PatchOperation>);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatchErrorKind {
+    /// An operation is not an object with a known `op` and the members it
+    /// requires.
+    InvalidOperation,
+    /// A path is not a JSON Pointer or designates a missing location.
+    PathNotFound,
+    /// The value compared by a `test` operation is different.
+    TestFailed,
+    /// A `move` into a child of the moved value.
+    MoveIntoChild,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchError {
+    pub kind: PatchErrorKind,
+    /// Position of the failing operation in the patch.
+    pub index: usize,
+}
+impl core::fmt::Display for PatchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let what = match self.kind {
+            PatchErrorKind::InvalidOperation => "Invalid operation",
+            PatchErrorKind::PathNotFound => "Path not found",
+            PatchErrorKind::TestFailed => "Test failed",
+            PatchErrorKind::MoveIntoChild => "Cannot move a value into one of its children",
+        };
+        write!(f, "{} in patch operation {}", what, self.index)
+    }
+}
+impl core::error::Error for PatchError {}
+
+impl JsonPatch {
+    /// Reads a patch from its JSON representation, an array of operation
+    /// objects.
+    pub fn from_value(value: &JsonValue<'_>) -> Result<Self, PatchError> {
+        let operations = value.as_array().ok_or(PatchError {
+            kind: PatchErrorKind::InvalidOperation,
+            index: 0,
+        })?;
+        operations
+            .iter()
+            .enumerate()
+            .map(|(index, op)| {
+                PatchOperation::from_value(op).ok_or(PatchError {
+                    kind: PatchErrorKind::InvalidOperation,
+                    index,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// JSON representation of the patch, as sent with
+    /// `PATCH application/json-patch+json`.
+    pub fn to_value(&self) -> JsonValueOwned {
+        JsonValue::Array(self.0.iter().map(PatchOperation::to_value).collect())
+    }
+}
+
+impl PatchOperation {
+    fn from_value(value: &JsonValue<'_>) -> Option<Self> {
+        let string = |key| value.get(key)?.as_str().map(str::to_string);
+        let path = string("path")?;
+        let value = || value.get("value").map(JsonValue::to_owned);
+        Some(match string("op")?.as_str() {
+            "add" => Self::Add { path, value: value()? },
+            "remove" => Self::Remove { path },
+            "replace" => Self::Replace { path, value: value()? },
+            "move" => Self::Move { from: string("from")?, path },
+            "copy" => Self::Copy { from: string("from")?, path },
+            "test" => Self::Test { path, value: value()? },
+            _ => return None,
+        })
+    }
+
+    fn to_value(&self) -> JsonValueOwned {
+        let (op, path) = match self {
+            Self::Add { path, .. } => ("add", path),
+            Self::Remove { path } => ("remove", path),
+            Self::Replace { path, .. } => ("replace", path),
+            Self::Move { path, .. } => ("move", path),
+            Self::Copy { path, .. } => ("copy", path),
+            Self::Test { path, .. } => ("test", path),
+        };
+        let mut obj = Map::new();
+        obj.insert("op".into(), op.into());
+        obj.insert("path".into(), path.clone().into());
+        match self {
+            Self::Add { value, .. } | Self::Replace { value, .. } | Self::Test { value, .. } => {
+                obj.insert("value".into(), value.to_owned());
+            }
+            Self::Move { from, .. } | Self::Copy { from, .. } => {
+                obj.insert("from".into(), from.clone().into());
+            }
+            Self::Remove { .. } => {}
+        }
+        JsonValue::Object(obj)
+    }
+}
+
+/// Applies the operations of `patch` in order. Either all of them succeed
+/// or `target` is left untouched.
+pub fn apply(target: &mut JsonValue<'_>, patch: &JsonPatch) -> Result<(), PatchError> {
+    let mut doc = target.to_owned();
+    for (index, op) in patch.0.iter().enumerate() {
+        apply_operation(&mut doc, op).map_err(|kind| PatchError { kind, index })?;
+    }
+    *target = doc;
+    Ok(())
+}
+
+fn apply_operation(doc: &mut JsonValue<'_>, op: &PatchOperation) -> Result<(), PatchErrorKind> {
+    match op {
+        PatchOperation::Add { path, value } => add(doc, path, value.to_owned()),
+        PatchOperation::Remove { path } => remove(doc, path).map(drop),
+        PatchOperation::Replace { path, value } => {
+            *doc.pointer_mut(path).ok_or(PatchErrorKind::PathNotFound)? = value.to_owned();
+            Ok(())
+        }
+        PatchOperation::Move { from, path } => {
+            if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                return Err(PatchErrorKind::MoveIntoChild);
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        PatchOperation::Copy { from, path } => {
+            let value = doc.pointer(from).ok_or(PatchErrorKind::PathNotFound)?.to_owned();
+            add(doc, path, value)
+        }
+        PatchOperation::Test { path, value } => match doc.pointer(path) {
+            Some(current) if current.eq_by_value(value) => Ok(()),
+            Some(_) => Err(PatchErrorKind::TestFailed),
+            None => Err(PatchErrorKind::PathNotFound),
+        },
+    }
+}
+
+/// Splits a non-empty `path` into the pointer of its parent and its last
+/// reference token.
+fn split_last(path: &str) -> Result<(&str, String), PatchErrorKind> {
+    let slash = path.rfind('/').ok_or(PatchErrorKind::PathNotFound)?;
+    let last = reference_tokens(&path[slash..])
+        .and_then(|mut tokens| tokens.next())
+        .ok_or(PatchErrorKind::PathNotFound)?;
+    Ok((&path[..slash], last.into_owned()))
+}
+
+fn add<'a>(doc: &mut JsonValue<'a>, path: &str, value: JsonValue<'a>) -> Result<(), PatchErrorKind> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, last) = split_last(path)?;
+    match doc.pointer_mut(parent).ok_or(PatchErrorKind::PathNotFound)? {
+        JsonValue::Object(obj) => {
+            obj.insert(last.into(), value);
+        }
+        JsonValue::Array(array) if last == "-" => array.push(value),
+        JsonValue::Array(array) => match array_index(&last) {
+            Some(index) if index <= array.len() => array.insert(index, value),
+            _ => return Err(PatchErrorKind::PathNotFound),
+        },
+        _ => return Err(PatchErrorKind::PathNotFound),
+    }
+    Ok(())
+}
+
+fn remove<'a>(doc: &mut JsonValue<'a>, path: &str) -> Result<JsonValue<'a>, PatchErrorKind> {
+    let (parent, last) = split_last(path)?;
+    let parent = doc.pointer_mut(parent).ok_or(PatchErrorKind::PathNotFound)?;
+    match parent {
+        JsonValue::Object(_) => parent.remove(&last),
+        JsonValue::Array(array) => match array_index(&last) {
+            Some(index) if index < array.len() => Some(array.remove(index)),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or(PatchErrorKind::PathNotFound)
+}
+
+/// Builds a patch turning `a` into `b`: members and elements are compared
+/// recursively, and values of different types replaced.
+pub fn diff(a: &JsonValue<'_>, b: &JsonValue<'_>) -> JsonPatch {
+    let mut operations = Vec::new();
+    diff_into(a, b, &mut String::new(), &mut operations);
+    JsonPatch(operations)
+}
+
+fn diff_into(a: &JsonValue<'_>, b: &JsonValue<'_>, path: &mut String, operations: &mut Vec<PatchOperation>) {
+    if a == b {
+        return;
+    }
+    let len = path.len();
+    match (a, b) {
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            for key in a.keys().filter(|key| !b.contains_key(*key)) {
+                push_token(path, key);
+                operations.push(PatchOperation::Remove { path: path.clone() });
+                path.truncate(len);
+            }
+            for (key, value) in b {
+                push_token(path, key);
+                match a.get(key) {
+                    Some(old) => diff_into(old, value, path, operations),
+                    None => operations.push(PatchOperation::Add {
+                        path: path.clone(),
+                        value: value.to_owned(),
+                    }),
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(a), JsonValue::Array(b)) => {
+            for (index, (old, value)) in a.iter().zip(b).enumerate() {
+                push_token(path, &index.to_string());
+                diff_into(old, value, path, operations);
+                path.truncate(len);
+            }
+            // From the end, so that indices stay valid.
+            for index in (b.len()..a.len()).rev() {
+                push_token(path, &index.to_string());
+                operations.push(PatchOperation::Remove { path: path.clone() });
+                path.truncate(len);
+            }
+            for value in b.iter().skip(a.len()) {
+                operations.push(PatchOperation::Add {
+                    path: format!("{}/-", path),
+                    value: value.to_owned(),
+                });
+            }
+        }
+        _ => operations.push(PatchOperation::Replace {
+            path: path.clone(),
+            value: b.to_owned(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn patched(doc: &str, patch: &str) -> Result<JsonValueOwned, PatchError> {
+        let mut doc = parse(doc).into_owned();
+        apply(&mut doc, &JsonPatch::from_value(&parse(patch))?)?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn rfc_6902_examples() {
+        let cases = [
+            (r#"{"foo": "bar"}"#, r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#, r#"{"baz": "qux", "foo": "bar"}"#),
+            (r#"{"foo": ["bar", "baz"]}"#, r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#, r#"{"foo": ["bar", "qux", "baz"]}"#),
+            (r#"{"baz": "qux", "foo": "bar"}"#, r#"[{"op": "remove", "path": "/baz"}]"#, r#"{"foo": "bar"}"#),
+            (r#"{"foo": ["bar", "qux", "baz"]}"#, r#"[{"op": "remove", "path": "/foo/1"}]"#, r#"{"foo": ["bar", "baz"]}"#),
+            (r#"{"baz": "qux", "foo": "bar"}"#, r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#, r#"{"baz": "boo", "foo": "bar"}"#),
+            (
+                r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+                r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+                r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#,
+            ),
+            (r#"{"foo": ["all", "grass", "cows", "eat"]}"#, r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#, r#"{"foo": ["all", "cows", "eat", "grass"]}"#),
+            (r#"{"foo": "bar"}"#, r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#, r#"{"foo": "bar", "child": {"grandchild": {}}}"#),
+            (r#"{"foo": ["bar"]}"#, r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#, r#"{"foo": ["bar", ["abc", "def"]]}"#),
+            (r#"{"/": 9, "~1": 10}"#, r#"[{"op": "test", "path": "/~01", "value": 10.0}]"#, r#"{"/": 9, "~1": 10}"#),
+            (r#"{"foo": 1}"#, r#"[{"op": "copy", "from": "/foo", "path": "/bar"}, {"op": "replace", "path": "", "value": [1]}]"#, r#"[1]"#),
+        ];
+        for (doc, patch, result) in cases.iter() {
+            assert_eq!(patched(doc, patch), Ok(parse(result).into_owned()), "{}", patch);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let kind = |doc, patch| patched(doc, patch).unwrap_err().kind;
+        use PatchErrorKind::*;
+        assert_eq!(kind(r#"{"foo": "bar"}"#, r#"[{"op": "add", "path": "/baz/bat", "value": "qux"}]"#), PathNotFound);
+        assert_eq!(kind(r#"{"baz": "qux"}"#, r#"[{"op": "test", "path": "/baz", "value": "bar"}]"#), TestFailed);
+        assert_eq!(kind(r#"[1]"#, r#"[{"op": "add", "path": "/2", "value": 0}]"#), PathNotFound);
+        assert_eq!(kind(r#"[1]"#, r#"[{"op": "remove", "path": "/-"}]"#), PathNotFound);
+        assert_eq!(kind(r#"{"a": {}}"#, r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#), MoveIntoChild);
+        assert_eq!(kind(r#"{}"#, r#"[{"op": "jump", "path": "/a"}]"#), InvalidOperation);
+        assert_eq!(kind(r#"{}"#, r#"[{"op": "add", "path": "/a"}]"#), InvalidOperation);
+
+        let mut doc = json!({"a": 1});
+        let err = apply(
+            &mut doc,
+            &JsonPatch(vec![
+                PatchOperation::Remove { path: "/a".into() },
+                PatchOperation::Remove { path: "/a".into() },
+            ]),
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Path not found in patch operation 1");
+        assert_eq!(doc, json!({"a": 1}));
+    }
+
+    #[test]
+    fn diff_round_trip() {
+        let a = parse(r#"{"faces": [{"uid": 1, "age": 30}, {"uid": 2}, {"uid": 3}], "a/b": 1, "gone": true, "n": 1}"#);
+        let b = parse(r#"{"faces": [{"uid": 1, "age": 31}, {"uid": 4}], "a/b": [1], "new": null, "n": 1.0}"#);
+        let patch = diff(&a, &b);
+        let mut doc = a.to_owned();
+        apply(&mut doc, &patch).unwrap();
+        assert_eq!(doc, b);
+        assert_eq!(JsonPatch::from_value(&patch.to_value()), Ok(patch));
+        assert_eq!(diff(&a, &a), JsonPatch::default());
+        assert_eq!(
+            diff(&json!([1]), &json!([1, {"x": 2}])).to_value(),
+            json!([{"op": "add", "path": "/-", "value": {"x": 2}}])
+        );
+    }
+}
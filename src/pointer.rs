@@ -0,0 +1,155 @@
+//! RFC 6901 JSON Pointer lookups.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::JsonValue;
+
+/// An escaped JSON Pointer, such as the location [`JsonValue::walk`] gives
+/// for every node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JsonPointer(String);
+
+impl JsonPointer {
+    /// The empty pointer, designating the whole document.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Appends the reference token `token`, escaping `~` and `/`.
+    pub fn push(&mut self, token: &str) {
+        push_token(&mut self.0, token);
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Unescaped reference tokens, from the root down.
+    pub fn tokens(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        reference_tokens(&self.0).into_iter().flatten()
+    }
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for JsonPointer {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for JsonPointer {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Splits `pointer` into its reference tokens, resolving `~1` and `~0`.
+/// `None` if the pointer is neither empty nor starting with `/`.
+pub(crate) fn reference_tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return None;
+    }
+    Some(pointer.split('/').skip(1).map(|token| {
+        if token.contains('~') {
+            // `~1` first, so that `~01` gives `~1` and not `/`.
+            Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+        } else {
+            Cow::Borrowed(token)
+        }
+    }))
+}
+
+/// Appends `/token` to a pointer, escaping `~` and `/`.
+pub(crate) fn push_token(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+/// Array index designated by `token`: decimal digits without leading zeros.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl<'a> JsonValue<'a> {
+    /// Looks up the value designated by a JSON Pointer such as
+    /// `/faceCapture/0/faces/0/faceRect/height`. The empty pointer
+    /// designates `self`.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue<'a>> {
+        let mut target = self;
+        for token in reference_tokens(pointer)? {
+            target = match target {
+                Self::Object(obj) => obj.get(&*token)?,
+                Self::Array(array) => array.get(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// Mutable variant of [`JsonValue::pointer`].
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue<'a>> {
+        let mut target = self;
+        for token in reference_tokens(pointer)? {
+            target = match target {
+                Self::Object(obj) => obj.get_mut(&*token)?,
+                Self::Array(array) => array.get_mut(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, JsonValue};
+
+    #[test]
+    fn rfc_6901_examples() {
+        let value = parse(r#"{"foo": ["bar", "baz"], "": 0, "a/b": 1, "c%d": 2, " ": 7, "m~n": 8}"#);
+        assert_eq!(value.pointer(""), Some(&value));
+        assert_eq!(value.pointer("/foo"), Some(&json!(["bar", "baz"])));
+        assert_eq!(value.pointer("/foo/0"), Some(&json!("bar")));
+        assert_eq!(value.pointer("/"), Some(&json!(0)));
+        assert_eq!(value.pointer("/a~1b"), Some(&json!(1)));
+        assert_eq!(value.pointer("/c%d"), Some(&json!(2)));
+        assert_eq!(value.pointer("/ "), Some(&json!(7)));
+        assert_eq!(value.pointer("/m~0n"), Some(&json!(8)));
+    }
+
+    #[test]
+    fn invalid_pointers() {
+        let value = parse(r#"{"foo": ["bar"], "~1": true}"#);
+        assert_eq!(value.pointer("foo"), None);
+        assert_eq!(value.pointer("/foo/01"), None);
+        assert_eq!(value.pointer("/foo/-"), None);
+        assert_eq!(value.pointer("/foo/1"), None);
+        assert_eq!(value.pointer("/foo/0/x"), None);
+        assert_eq!(value.pointer("/~01"), Some(&JsonValue::Boolean(true)));
+    }
+
+    #[test]
+    fn pointer_mut() {
+        let mut value = parse(r#"{"faceCapture": [{"faces": [{"faceRect": {"height": 1}}]}]}"#);
+        *value.pointer_mut("/faceCapture/0/faces/0/faceRect/height").unwrap() = json!(2);
+        assert_eq!(value["faceCapture"][0]["faces"][0]["faceRect"]["height"], json!(2));
+        assert!(value.pointer_mut("/faceCapture/1").is_none());
+    }
+}
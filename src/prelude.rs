@@ -0,0 +1,7 @@
+//! Items of the standard prelude that are missing from the `core` prelude,
+//! so that modules compile the same with and without the `std` feature.
+
+pub(crate) use alloc::boxed::Box;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec::Vec;
+pub(crate) use alloc::{format, vec};
@@ -0,0 +1,180 @@
+//! Parsing of only the values designated by a few JSON Pointers.
+
+use alloc::borrow::Cow;
+
+use crate::pointer::{array_index, reference_tokens};
+use crate::prelude::*;
+use crate::{try_parse, JsonError, JsonReader, JsonToken, JsonTokenType, JsonValue};
+
+/// A requested pointer, split into its reference tokens.
+struct Request<'p> {
+    /// Position of the pointer in the request, that of its result.
+    idx: usize,
+    tokens: Vec<Cow<'p, str>>,
+}
+
+/// Parses the values of the document `source` designated by `pointers`,
+/// one result per pointer in the same order: `None` when the pointer is
+/// invalid or designates no value. Everything else is checked without
+/// being built, which makes extracting a few fields of a large document
+/// much cheaper than parsing it whole.
+///
+/// ```
+/// let event = r#"{"eventType": "faceCapture", "faceCapture": [{"uid": 12, "faces": [{"age": 31}]}]}"#;
+/// let fields = yaj::parse_projection(event, &["/eventType", "/faceCapture/0/uid", "/missing"]).unwrap();
+/// assert_eq!(fields, vec![Some(yaj::json!("faceCapture")), Some(yaj::json!(12)), None]);
+/// ```
+pub fn parse_projection<'a>(source: &'a str, pointers: &[&str]) -> Result<Vec<Option<JsonValue<'a>>>, JsonError> {
+    let requests: Vec<_> = pointers
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, pointer)| {
+            let tokens = reference_tokens(pointer)?.collect();
+            Some(Request { idx, tokens })
+        })
+        .collect();
+    let mut projection = Projection {
+        source,
+        reader: JsonReader::new(source),
+        values: vec![None; pointers.len()],
+    };
+    let requests: Vec<_> = requests.iter().collect();
+    if let Some(first) = projection.reader.next_value()? {
+        projection.value(first, &requests, 0)?;
+    }
+    projection.reader.end_of_document()?;
+    Ok(projection.values)
+}
+
+/// Value designated by the reference tokens of a pointer, as in
+/// [`JsonValue::pointer`].
+fn lookup<'v, 'a>(value: &'v JsonValue<'a>, tokens: &[Cow<str>]) -> Option<&'v JsonValue<'a>> {
+    let mut target = value;
+    for token in tokens {
+        target = match target {
+            JsonValue::Object(obj) => obj.get(&**token)?,
+            JsonValue::Array(array) => array.get(array_index(token)?)?,
+            _ => return None,
+        };
+    }
+    Some(target)
+}
+
+struct Projection<'a> {
+    source: &'a str,
+    reader: JsonReader<'a>,
+    values: Vec<Option<JsonValue<'a>>>,
+}
+
+impl<'a> Projection<'a> {
+    /// Goes through the value starting with `token`, at `depth` in the
+    /// pointers of `requests`, only looking into the containers they go
+    /// through.
+    fn value(&mut self, token: JsonToken<'a>, requests: &[&Request], depth: usize) -> Result<(), JsonError> {
+        if requests.iter().any(|request| request.tokens.len() == depth) {
+            let start = token.offset;
+            self.reader.skip_value(&token)?;
+            let value = try_parse(&self.source[start..self.reader.end()])?;
+            for request in requests.iter().filter(|request| request.tokens.len() == depth) {
+                self.values[request.idx] = Some(value.clone());
+            }
+            // Values inside a requested one are taken from it.
+            for request in requests.iter().filter(|request| request.tokens.len() > depth) {
+                self.values[request.idx] = lookup(&value, &request.tokens[depth..]).cloned();
+            }
+            return Ok(());
+        }
+        match token.token_type {
+            JsonTokenType::LeftBracket if !requests.is_empty() => self.array(requests, depth),
+            JsonTokenType::LeftBrace if !requests.is_empty() => self.object(requests, depth),
+            _ => self.reader.skip_value(&token),
+        }
+    }
+
+    fn array(&mut self, requests: &[&Request], depth: usize) -> Result<(), JsonError> {
+        let mut idx = 0;
+        while let Some(token) = self.reader.next_value()? {
+            let inner: Vec<_> = requests
+                .iter()
+                .copied()
+                .filter(|request| array_index(&request.tokens[depth]) == Some(idx))
+                .collect();
+            self.value(token, &inner, depth + 1)?;
+            idx += 1;
+        }
+        Ok(())
+    }
+
+    fn object(&mut self, requests: &[&Request], depth: usize) -> Result<(), JsonError> {
+        while let Some(key) = self.reader.next_key()? {
+            let inner: Vec<_> = requests
+                .iter()
+                .copied()
+                .filter(|request| request.tokens[depth] == key)
+                .collect();
+            if let Some(value) = self.reader.next_value()? {
+                self.value(value, &inner, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_values_as_pointers() {
+        let source =
+            r#"{"eventType": "faceCapture", "a~/b": 1, "list": [{"x": [true]}, 2], "dup": 1, "dup": 2, "": {"": 3}, "\u0067": 4}"#;
+        let doc = try_parse(source).unwrap();
+        let pointers = [
+            "/eventType",
+            "/a~0~1b",
+            "/list",
+            "/list/0/x/0",
+            "/list/1",
+            "/list/2",
+            "/list/01",
+            "/list/-",
+            "/list/0/y",
+            "/eventType/0",
+            "/dup",
+            "//",
+            "",
+            "no slash",
+            "/g",
+            "/list/0/x/0",
+        ];
+        let values = parse_projection(source, &pointers).unwrap();
+        assert_eq!(values.len(), pointers.len());
+        for (pointer, value) in pointers.iter().zip(&values) {
+            assert_eq!(value.as_ref(), doc.pointer(pointer), "{}", pointer);
+        }
+        assert_eq!(values[10], Some(json!(2)));
+        assert!(parse_projection(source, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn source_is_checked() {
+        assert!(matches!(parse_projection("", &["/a"]), Err(JsonError::Empty)));
+        for source in [
+            "{\"a\": 1,}",
+            "{\"b\": [1 2], \"a\": 1}",
+            "{\"a\": [}",
+            "{\"a\": 1} 2",
+            "[1, \"\\q\"]",
+            &"[".repeat(200),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                parse_projection(source, &["/a"]).map(|_| ()),
+                try_parse(source).map(|_| ()),
+                "{}",
+                source
+            );
+        }
+    }
+}
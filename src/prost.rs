@@ -0,0 +1,148 @@
+//! Conversions from and to the well-known protobuf types `Struct` and
+//! `Value` of `prost_types`, e.g. for the `google.protobuf.Struct` fields
+//! of gRPC messages.
+//!
+//! ```
+//! use std::convert::TryFrom;
+//! use prost_types::Struct;
+//! use yaj::{json, JsonValueOwned};
+//! let fields = Struct::try_from(json!({"uid": 12, "tags": ["a", null]})).unwrap();
+//! assert_eq!(fields.fields.len(), 2);
+//! assert_eq!(JsonValueOwned::from(fields), json!({"uid": 12, "tags": ["a", null]}));
+//! ```
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+
+use prost_types::value::Kind;
+use prost_types::{ListValue, NullValue, Struct, Value};
+
+use crate::{FromJsonError, JsonNumber, JsonValue, JsonValueOwned, Map};
+
+/// Integers are stored as doubles by protobuf: doubles with no fractional
+/// part and below 2^53 in magnitude, where they are exact, become integers.
+fn number(f: f64) -> JsonNumber {
+    const EXACT: f64 = 9_007_199_254_740_992.0;
+    if f > -EXACT && f < EXACT && f as i64 as f64 == f {
+        JsonNumber::Integer(f as i64)
+    } else {
+        JsonNumber::Float(f)
+    }
+}
+
+impl From<Value> for JsonValueOwned {
+    fn from(value: Value) -> Self {
+        match value.kind {
+            None | Some(Kind::NullValue(_)) => JsonValue::Null,
+            Some(Kind::NumberValue(f)) => JsonValue::Number(number(f)),
+            Some(Kind::StringValue(s)) => JsonValue::String(Cow::Owned(s)),
+            Some(Kind::BoolValue(b)) => JsonValue::Boolean(b),
+            Some(Kind::StructValue(fields)) => fields.into(),
+            Some(Kind::ListValue(list)) => JsonValue::Array(list.values.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl From<Struct> for JsonValueOwned {
+    fn from(fields: Struct) -> Self {
+        JsonValue::Object(
+            fields
+                .fields
+                .into_iter()
+                .map(|(k, v)| (Cow::Owned(k), v.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Borrows the strings of `value`.
+impl<'a> From<&'a Value> for JsonValue<'a> {
+    fn from(value: &'a Value) -> Self {
+        match &value.kind {
+            None | Some(Kind::NullValue(_)) => JsonValue::Null,
+            Some(Kind::NumberValue(f)) => JsonValue::Number(number(*f)),
+            Some(Kind::StringValue(s)) => JsonValue::String(Cow::Borrowed(s)),
+            Some(Kind::BoolValue(b)) => JsonValue::Boolean(*b),
+            Some(Kind::StructValue(fields)) => fields.into(),
+            Some(Kind::ListValue(list)) => JsonValue::Array(list.values.iter().map(Into::into).collect()),
+        }
+    }
+}
+
+/// Borrows the keys and strings of `fields`.
+impl<'a> From<&'a Struct> for JsonValue<'a> {
+    fn from(fields: &'a Struct) -> Self {
+        JsonValue::Object(
+            fields
+                .fields
+                .iter()
+                .map(|(k, v)| (Cow::Borrowed(k.as_str()), v.into()))
+                .collect(),
+        )
+    }
+}
+
+/// Numbers become doubles, losing the precision of integers beyond 2^53.
+impl From<JsonValue<'_>> for Value {
+    fn from(value: JsonValue<'_>) -> Self {
+        let kind = match value {
+            JsonValue::Null => Kind::NullValue(NullValue::NullValue as i32),
+            JsonValue::Boolean(b) => Kind::BoolValue(b),
+            JsonValue::Number(n) => Kind::NumberValue(n.as_f64_lossy()),
+            JsonValue::String(s) => Kind::StringValue(s.into_owned()),
+            JsonValue::Array(array) => Kind::ListValue(ListValue {
+                values: array.into_iter().map(Into::into).collect(),
+            }),
+            JsonValue::Object(obj) => Kind::StructValue(to_struct(obj)),
+        };
+        Value { kind: Some(kind) }
+    }
+}
+
+/// Only objects can become a `Struct`.
+impl TryFrom<JsonValue<'_>> for Struct {
+    type Error = FromJsonError;
+
+    fn try_from(value: JsonValue<'_>) -> Result<Self, FromJsonError> {
+        match value {
+            JsonValue::Object(obj) => Ok(to_struct(obj)),
+            other => Err(FromJsonError::unexpected("an object", &other)),
+        }
+    }
+}
+
+fn to_struct(obj: Map<'_>) -> Struct {
+    Struct {
+        fields: obj.into_iter().map(|(k, v)| (k.into_owned(), v.into())).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let source = r#"{"a": [1, -2.5, 9007199254740993, "é", null], "b": {"c": true, "d": {}}}"#;
+        let yaj = crate::parse(source);
+        let value = Value::from(yaj.clone());
+        let fields = match &value.kind {
+            Some(Kind::StructValue(fields)) => fields.clone(),
+            other => panic!("expected a struct, got {:?}", other),
+        };
+        assert_eq!(Struct::try_from(yaj).unwrap(), fields);
+        // The integer beyond 2^53 is rounded to a double.
+        let expected = json!({"a": [1, -2.5, 9007199254740992.0, "é", null], "b": {"c": true, "d": {}}});
+        assert_eq!(JsonValueOwned::from(value.clone()), expected);
+        assert_eq!(JsonValue::from(&value), expected);
+        assert_eq!(JsonValueOwned::from(fields.clone()), expected);
+        assert!(matches!(
+            JsonValue::from(&fields)["a"][3],
+            JsonValue::String(Cow::Borrowed(_))
+        ));
+        assert_eq!(JsonValueOwned::from(Value { kind: None }), JsonValue::Null);
+
+        let error = Struct::try_from(json!([1])).unwrap_err();
+        assert_eq!(error, FromJsonError::unexpected("an object", &json!([1])));
+    }
+}
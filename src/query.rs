@@ -0,0 +1,268 @@
+//! Dotted path queries such as `faceCapture.0.faces.#.age.value`, a
+//! lighter alternative to [JSONPath](crate::jsonpath) that can also run on
+//! source text without parsing it.
+
+use crate::pointer::array_index;
+use crate::prelude::*;
+use crate::{JsonError, JsonReader, JsonToken, JsonTokenType, JsonValue, RawValue};
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// A member of an object, or an element of an array when it is an index.
+    Name(String),
+    /// Every element of an array.
+    Each,
+}
+
+impl Step {
+    fn matches_element(&self, idx: usize) -> bool {
+        match self {
+            Step::Name(name) => array_index(name) == Some(idx),
+            Step::Each => true,
+        }
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        match self {
+            Step::Name(name) => name == key,
+            Step::Each => false,
+        }
+    }
+}
+
+fn steps(path: &str) -> Vec<Step> {
+    let mut steps = Vec::new();
+    if path.is_empty() {
+        return steps;
+    }
+    let mut name = String::new();
+    let mut escaped = false;
+    let mut chars = path.chars();
+    loop {
+        match chars.next() {
+            Some('\\') => {
+                escaped = true;
+                name.extend(chars.next());
+            }
+            Some('.') => steps.push(step(&mut name, &mut escaped)),
+            Some(chr) => name.push(chr),
+            None => {
+                steps.push(step(&mut name, &mut escaped));
+                return steps;
+            }
+        }
+    }
+}
+
+/// Takes the step ending at a `.` or at the end of the path.
+fn step(name: &mut String, escaped: &mut bool) -> Step {
+    let step = if name == "#" && !*escaped {
+        name.clear();
+        Step::Each
+    } else {
+        Step::Name(core::mem::take(name))
+    };
+    *escaped = false;
+    step
+}
+
+impl<'a> JsonValue<'a> {
+    /// Values designated by a dotted path such as
+    /// `faceCapture.0.faces.#.age.value`.
+    ///
+    /// A path is made of steps separated by `.`: a member name, an array
+    /// index, or `#` for every element of an array. A backslash makes the
+    /// next char part of the name, as in `a\.b` or `\#`. The empty path
+    /// selects the whole value.
+    ///
+    /// ```
+    /// # use yaj::json;
+    /// let doc = json!({"faceCapture": [{"faces": [{"age": {"value": 31}}, {"age": {"value": 25}}, {}]}]});
+    /// assert_eq!(doc.query("faceCapture.0.faces.#.age.value"), vec![&json!(31), &json!(25)]);
+    /// assert_eq!(doc.query("faceCapture.0.faces.1.age"), vec![&json!({"value": 25})]);
+    /// assert!(doc.query("faceCapture.1").is_empty());
+    /// ```
+    pub fn query(&self, path: &str) -> Vec<&JsonValue<'a>> {
+        let mut selected = Vec::new();
+        select(self, &steps(path), &mut selected);
+        selected
+    }
+}
+
+fn select<'v, 'a>(value: &'v JsonValue<'a>, steps: &[Step], selected: &mut Vec<&'v JsonValue<'a>>) {
+    let (step, rest) = match steps.split_first() {
+        Some(split) => split,
+        None => return selected.push(value),
+    };
+    match value {
+        JsonValue::Array(array) => {
+            for (idx, element) in array.iter().enumerate() {
+                if step.matches_element(idx) {
+                    select(element, rest, selected);
+                }
+            }
+        }
+        JsonValue::Object(obj) => {
+            if let Step::Name(name) = step {
+                if let Some(member) = obj.get(name.as_str()) {
+                    select(member, rest, selected);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Runs a [dotted path query](JsonValue::query) on the document `source`
+/// without building its values: the text of the selected ones is returned,
+/// in source order, and all the rest is only checked. Every member with a
+/// matching key is selected when a key is repeated.
+///
+/// ```
+/// let source = r#"{"faceCapture": [{"faces": [{"uid": 1, "age": {"value": 31}}, {"uid": 2}]}]}"#;
+/// let uids = yaj::query_raw(source, "faceCapture.0.faces.#.uid").unwrap();
+/// let uids: Vec<_> = uids.iter().map(|uid| uid.get()).collect();
+/// assert_eq!(uids, ["1", "2"]);
+/// assert_eq!(yaj::query_raw(source, "faceCapture.0.faces.0.age").unwrap()[0].get(), r#"{"value": 31}"#);
+/// assert!(yaj::query_raw("[1, 2", "0").is_err());
+/// ```
+pub fn query_raw<'a>(source: &'a str, path: &str) -> Result<Vec<RawValue<'a>>, JsonError> {
+    let mut walk = RawWalk {
+        source,
+        reader: JsonReader::new(source),
+        selected: Vec::new(),
+    };
+    if let Some(first) = walk.reader.next_value()? {
+        walk.select(first, &steps(path))?;
+    }
+    walk.reader.end_of_document()?;
+    Ok(walk.selected)
+}
+
+struct RawWalk<'a> {
+    source: &'a str,
+    reader: JsonReader<'a>,
+    selected: Vec<RawValue<'a>>,
+}
+
+impl<'a> RawWalk<'a> {
+    /// Goes through the value starting with `token`, only looking into the
+    /// containers on the path.
+    fn select(&mut self, token: JsonToken<'a>, steps: &[Step]) -> Result<(), JsonError> {
+        let (step, rest) = match steps.split_first() {
+            Some(split) => split,
+            None => {
+                let start = token.offset;
+                self.reader.skip_value(&token)?;
+                self.selected
+                    .push(RawValue::unchecked(&self.source[start..self.reader.end()]));
+                return Ok(());
+            }
+        };
+        match token.token_type {
+            JsonTokenType::LeftBracket => self.array(step, rest),
+            JsonTokenType::LeftBrace => self.object(step, rest),
+            _ => Ok(()),
+        }
+    }
+
+    fn array(&mut self, step: &Step, rest: &[Step]) -> Result<(), JsonError> {
+        let mut idx = 0;
+        while let Some(token) = self.reader.next_value()? {
+            if step.matches_element(idx) {
+                self.select(token, rest)?;
+            } else {
+                self.reader.skip_value(&token)?;
+            }
+            idx += 1;
+        }
+        Ok(())
+    }
+
+    fn object(&mut self, step: &Step, rest: &[Step]) -> Result<(), JsonError> {
+        while let Some(key) = self.reader.next_key()? {
+            let matches = step.matches_key(&key);
+            if let Some(value) = self.reader.next_value()? {
+                if matches {
+                    self.select(value, rest)?;
+                } else {
+                    self.reader.skip_value(&value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn path_steps() {
+        let name = |name: &str| Step::Name(name.into());
+        assert_eq!(steps(""), vec![]);
+        assert_eq!(steps("a.0.#"), vec![name("a"), name("0"), Step::Each]);
+        assert_eq!(
+            steps(r"a\.b.\#.\\"),
+            vec![name("a.b"), name("#"), name("\\")]
+        );
+        assert_eq!(steps(".a."), vec![name(""), name("a"), name("")]);
+    }
+
+    #[test]
+    fn same_selection_on_values_and_source() {
+        let source =
+            r##"{"a": [{"b": 1}, {"b": [2, 3]}, 4, {"c": 5}], "0": {"#": 6}, "d.e": null, "": 7, "\u0066": 8}"##;
+        let doc = try_parse(source).unwrap();
+        let cases: &[(&str, &[JsonValue])] = &[
+            ("", core::slice::from_ref(&doc)),
+            ("a.#.b", &[json!(1), json!([2, 3])]),
+            ("a.#.b.#", &[json!(2), json!(3)]),
+            ("a.1.b.1", &[json!(3)]),
+            ("a.3", &[json!({"c": 5})]),
+            ("a.4", &[]),
+            ("a.01", &[]),
+            ("a.b", &[]),
+            ("0.\\#", &[json!(6)]),
+            ("0.#", &[]),
+            ("d\\.e", &[JsonValue::Null]),
+            ("d.e", &[]),
+            (".", &[]),
+            ("a.#.c.x", &[]),
+            ("f", &[json!(8)]),
+        ];
+        for (path, expected) in cases {
+            let selected: Vec<_> = doc.query(path).into_iter().cloned().collect();
+            assert_eq!(&selected, expected, "{}", path);
+            let raw = query_raw(source, path).unwrap();
+            let parsed: Vec<_> = raw.iter().map(|raw| raw.parse().unwrap()).collect();
+            assert_eq!(&parsed, expected, "{}", path);
+        }
+        assert_eq!(doc.query("#"), Vec::<&JsonValue>::new());
+        assert_eq!(query_raw(" [1, [2]] ", "#").unwrap()[1].get(), "[2]");
+    }
+
+    #[test]
+    fn source_is_checked() {
+        assert!(matches!(query_raw("", "a"), Err(JsonError::Empty)));
+        for source in [
+            "{\"a\": 1,}",
+            "[1 2]",
+            "{\"a\": [}",
+            "{\"a\": 1} 2",
+            "{1: 2}",
+            &"[".repeat(200),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                query_raw(source, "a").map(|_| ()),
+                try_parse(source).map(|_| ()),
+                "{}",
+                source
+            );
+        }
+    }
+}
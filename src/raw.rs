@@ -0,0 +1,123 @@
+//! Values kept as source text, to be parsed later or forwarded untouched.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{try_parse, JsonError, JsonReader, JsonTokenType, JsonValue};
+
+/// The exact source text of a valid JSON value, without the surrounding
+/// whitespace. Its contents are checked but not parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawValue<'a> {
+    json: &'a str,
+}
+
+impl<'a> RawValue<'a> {
+    /// Checks that `source` is a single JSON document and keeps its text.
+    pub fn new(source: &'a str) -> Result<Self, JsonError> {
+        let mut reader = JsonReader::new(source);
+        let mut json = "";
+        if let Some(first) = reader.next_value()? {
+            reader.skip_value(&first)?;
+            json = &source[first.offset..reader.end()];
+        }
+        reader.end_of_document()?;
+        Ok(Self { json })
+    }
+
+    /// Keeps `json`, already known to be a single valid value.
+    pub(crate) fn unchecked(json: &'a str) -> Self {
+        Self { json }
+    }
+
+    /// The source text of the value.
+    pub fn get(&self) -> &'a str {
+        self.json
+    }
+
+    /// Parses the value, borrowing from the original source.
+    pub fn parse(&self) -> Result<JsonValue<'a>, JsonError> {
+        try_parse(self.json)
+    }
+}
+
+impl fmt::Display for RawValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.json)
+    }
+}
+
+/// Parses the members of the object `source` without parsing their values,
+/// which are checked and returned as [`RawValue`]s in source order. Keys
+/// have their escapes resolved; duplicate keys are all kept.
+///
+/// ```
+/// let source = r#"{"route": "billing", "body": {"items": [1, 2, 3]}}"#;
+/// let members = yaj::parse_shallow(source).unwrap();
+/// assert_eq!(members[0].0, "route");
+/// assert_eq!(members[0].1.parse().unwrap(), yaj::json!("billing"));
+/// assert_eq!(members[1].1.get(), r#"{"items": [1, 2, 3]}"#);
+/// ```
+pub fn parse_shallow(source: &str) -> Result<Vec<(Cow<'_, str>, RawValue<'_>)>, JsonError> {
+    let mut reader = JsonReader::new(source);
+    let mut members = Vec::new();
+    if let Some(first) = reader.next_value()? {
+        if first.token_type != JsonTokenType::LeftBrace {
+            return Err(JsonError::at_token(
+                source,
+                "Expected an object",
+                Some(&first),
+                &[JsonTokenType::LeftBrace],
+            ));
+        }
+        while let Some(key) = reader.next_key()? {
+            if let Some(value) = reader.next_value()? {
+                reader.skip_value(&value)?;
+                members.push((
+                    key,
+                    RawValue {
+                        json: &source[value.offset..reader.end()],
+                    },
+                ));
+            }
+        }
+    }
+    reader.end_of_document()?;
+    Ok(members)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_values() {
+        let raw = RawValue::new(" \n[1, {\"a\": \"\\u00e9\"}] ").unwrap();
+        assert_eq!(raw.get(), "[1, {\"a\": \"\\u00e9\"}]");
+        assert_eq!(raw.to_string(), raw.get());
+        assert_eq!(raw.parse().unwrap(), json!([1, {"a": "é"}]));
+        assert!(matches!(RawValue::new(" "), Err(JsonError::Empty)));
+        assert!(RawValue::new("[1,]").is_err());
+        assert!(RawValue::new("1 2").is_err());
+    }
+
+    #[test]
+    fn shallow_objects() {
+        let source = r#"{"type": "order", "a\tb": 1, "body": {"deep": [[[]]]}, "type": null}"#;
+        let members = parse_shallow(source).unwrap();
+        let keys: Vec<_> = members.iter().map(|(key, _)| key.as_ref()).collect();
+        assert_eq!(keys, vec!["type", "a\tb", "body", "type"]);
+        assert_eq!(members[2].1.get(), r#"{"deep": [[[]]]}"#);
+        assert_eq!(members[3].1.parse().unwrap(), JsonValue::Null);
+        assert!(parse_shallow("{}").unwrap().is_empty());
+
+        let msg = |source| match parse_shallow(source) {
+            Err(JsonError::Parse { msg, .. }) => msg,
+            other => panic!("expected a parse error, got {:?}", other),
+        };
+        assert_eq!(msg("[1]"), "Expected an object");
+        assert_eq!(msg(r#"{"a": [1,]}"#), "Unexpected token in place of a value");
+        assert_eq!(msg(r#"{"a": 1} 2"#), "Invalid token at the end of document");
+    }
+}
@@ -0,0 +1,432 @@
+use std::io::{self, Read};
+
+use crate::encoding::{decode, decode_error, Encoding};
+use crate::parser::expected;
+use crate::{try_parse, JsonError, JsonTokenType, JsonValue, JsonValueOwned, ParseOptions, Parser};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+fn invalid_data(encoding: Encoding, offset: usize) -> JsonError {
+    JsonError::Io {
+        kind: io::ErrorKind::InvalidData,
+        msg: format!("Invalid {} at byte {}", encoding.name(), offset),
+    }
+}
+
+/// Reads into `chunk`, retrying interrupted reads.
+fn read_chunk<R: Read>(reader: &mut R, chunk: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match reader.read(chunk) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+/// Reads `reader` to the end, decoding UTF-8 chunk by chunk so that invalid
+/// input is rejected as soon as it is read, then parses the document.
+/// Documents in UTF-16 or UTF-32, told apart as [`Encoding::detect`] does,
+/// are read whole and then transcoded.
+pub fn from_reader<R: Read>(mut reader: R) -> Result<JsonValueOwned, JsonError> {
+    let mut chunk = [0; CHUNK_SIZE];
+    // Bytes not decoded yet: the start of a character split across reads.
+    let mut pending = Vec::with_capacity(CHUNK_SIZE + 3);
+    // The first four bytes give the encoding away.
+    while pending.len() < 4 {
+        let len = read_chunk(&mut reader, &mut chunk)?;
+        if len == 0 {
+            break;
+        }
+        pending.extend_from_slice(&chunk[..len]);
+    }
+    let encoding = Encoding::detect(&pending);
+    if encoding != Encoding::Utf8 {
+        reader.read_to_end(&mut pending)?;
+        let text = decode(&pending, encoding, false).map_err(|offset| invalid_data(encoding, offset))?;
+        return try_parse(&text).map(JsonValue::into_owned);
+    }
+
+    let mut text = String::new();
+    loop {
+        let decoded = match core::str::from_utf8(&pending) {
+            Ok(s) => s,
+            // The last character is incomplete, it may end in the next read.
+            Err(e) if e.error_len().is_none() => {
+                core::str::from_utf8(&pending[..e.valid_up_to()]).expect("checked prefix")
+            }
+            Err(e) => return Err(invalid_data(encoding, text.len() + e.valid_up_to())),
+        };
+        text.push_str(decoded);
+        let decoded_len = decoded.len();
+        pending.drain(..decoded_len);
+        let len = read_chunk(&mut reader, &mut chunk)?;
+        if len == 0 {
+            break;
+        }
+        pending.extend_from_slice(&chunk[..len]);
+    }
+    if !pending.is_empty() {
+        return Err(invalid_data(encoding, text.len()));
+    }
+    try_parse(&text).map(JsonValue::into_owned)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Before the opening bracket.
+    Start,
+    Elements,
+    /// After the closing bracket.
+    End,
+}
+
+/// Iterator over the elements of an array read from a reader, see
+/// [`array_elements`].
+pub struct ArrayElements<R> {
+    reader: R,
+    /// Source not consumed yet: the current element once in the array.
+    buffer: Vec<u8>,
+    /// Offset in `buffer` of the next byte to scan.
+    pos: usize,
+    state: State,
+    /// Arrays and objects open in the current element.
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// No element was found yet.
+    first: bool,
+    done: bool,
+    /// Byte offset, line and column of the start of `buffer` in the source.
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+/// Parses the elements of the array that makes up the document read from
+/// `reader` one at a time, so that memory use is bounded by the largest
+/// element rather than the whole document. Elements are found by brackets,
+/// braces, quotes and commas, then parsed as [`try_parse`] does. The
+/// iteration ends after the first error.
+pub fn array_elements<R: Read>(reader: R) -> ArrayElements<R> {
+    ArrayElements {
+        reader,
+        buffer: Vec::new(),
+        pos: 0,
+        state: State::Start,
+        depth: 0,
+        in_string: false,
+        escaped: false,
+        first: true,
+        done: false,
+        offset: 0,
+        line: 1,
+        column: 1,
+    }
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+impl<R: Read> ArrayElements<R> {
+    /// Byte offset, line and column in the source of `buffer[pos]`.
+    fn location(&self, pos: usize) -> (usize, usize, usize) {
+        let (mut line, mut column) = (self.line, self.column);
+        for &byte in &self.buffer[..pos] {
+            if byte == b'\n' {
+                line += 1;
+                column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Not a continuation byte: a new char.
+                column += 1;
+            }
+        }
+        (self.offset + pos, line, column)
+    }
+
+    /// Forgets the first `len` bytes of `buffer`.
+    fn consume(&mut self, len: usize) {
+        let (offset, line, column) = self.location(len);
+        self.offset = offset;
+        self.line = line;
+        self.column = column;
+        self.buffer.drain(..len);
+        self.pos = self.pos.saturating_sub(len);
+    }
+
+    /// Reads more of the source, `false` at its end.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let len = read_chunk(&mut self.reader, &mut chunk)?;
+        self.buffer.extend_from_slice(&chunk[..len]);
+        Ok(len > 0)
+    }
+
+    fn error(&self, msg: &str, expected: &'static [JsonTokenType]) -> JsonError {
+        let (offset, line, column) = self.location(self.pos);
+        let token = String::from_utf8_lossy(&self.buffer[self.pos..])
+            .chars()
+            .next()
+            .map_or_else(String::new, String::from);
+        JsonError::Parse {
+            msg: msg.to_string(),
+            token,
+            offset,
+            line,
+            column,
+            expected,
+        }
+    }
+
+    /// Parses the element in `buffer` up to `pos`, `None` if there is none
+    /// because the array is empty.
+    fn element(&mut self) -> Option<Result<JsonValueOwned, JsonError>> {
+        let bytes = &self.buffer[..self.pos];
+        if bytes.iter().copied().all(is_whitespace) {
+            if self.first && self.buffer.get(self.pos) == Some(&b']') {
+                return None;
+            }
+            return Some(Err(self.error("Unexpected token in place of a value", self.value_expected())));
+        }
+        self.first = false;
+        let text = match core::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => {
+                let error = JsonError::Lex(decode_error(bytes, Encoding::Utf8, e.valid_up_to()));
+                return Some(Err(error.relocate(self.offset, self.line, self.column)));
+            }
+        };
+        // Elements are nested one level deeper than their own documents,
+        // and a byte order mark before one of them is an error.
+        let parser = Parser::new().max_depth(ParseOptions::default().max_depth.saturating_sub(1));
+        let result = parser
+            .parse_continuing(text)
+            .map(JsonValue::into_owned)
+            .map_err(|e| e.relocate(self.offset, self.line, self.column));
+        Some(result)
+    }
+
+    /// Token types accepted in place of a missing element.
+    fn value_expected(&self) -> &'static [JsonTokenType] {
+        if self.first {
+            expected::FIRST_ELEMENT
+        } else {
+            expected::VALUE
+        }
+    }
+
+    /// Whether `buffer[pos]` is part of a byte order mark at the start of
+    /// the source.
+    fn in_byte_order_mark(&self) -> bool {
+        let offset = self.offset + self.pos;
+        offset < 3 && self.buffer[self.pos] == [0xEF, 0xBB, 0xBF][offset]
+    }
+
+    fn end_of_file(&mut self) -> Option<Result<JsonValueOwned, JsonError>> {
+        match self.state {
+            State::Start => Some(Err(JsonError::Empty)),
+            State::Elements if self.buffer.iter().copied().all(is_whitespace) => {
+                Some(Err(self.error("Unexpected end of file", self.value_expected())))
+            }
+            State::Elements => match self.element() {
+                Some(Err(e)) => Some(Err(e)),
+                _ => Some(Err(self.error("Unexpected end of file", expected::AFTER_ELEMENT))),
+            },
+            State::End => None,
+        }
+    }
+
+    fn next_element(&mut self) -> Option<Result<JsonValueOwned, JsonError>> {
+        loop {
+            let byte = match self.buffer.get(self.pos) {
+                Some(&byte) => byte,
+                None => {
+                    if self.state != State::Elements {
+                        self.consume(self.pos);
+                    }
+                    match self.fill() {
+                        Ok(true) => continue,
+                        Ok(false) => return self.end_of_file(),
+                        Err(e) => return Some(Err(e.into())),
+                    }
+                }
+            };
+            match self.state {
+                State::Start if is_whitespace(byte) || self.in_byte_order_mark() => {}
+                State::Start if byte == b'[' => {
+                    self.state = State::Elements;
+                    self.consume(self.pos + 1);
+                    continue;
+                }
+                State::Start => return Some(Err(self.error("Expected an array", &[JsonTokenType::LeftBracket]))),
+                State::Elements if self.in_string => {
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if byte == b'\\' {
+                        self.escaped = true;
+                    } else if byte == b'"' {
+                        self.in_string = false;
+                    }
+                }
+                State::Elements => match byte {
+                    b'"' => self.in_string = true,
+                    b'[' | b'{' => self.depth += 1,
+                    b']' | b'}' if self.depth > 0 => self.depth -= 1,
+                    b',' | b']' if self.depth == 0 => {
+                        let result = self.element();
+                        if byte == b']' {
+                            self.state = State::End;
+                        }
+                        self.consume(self.pos + 1);
+                        match result {
+                            Some(result) => return Some(result),
+                            None => continue,
+                        }
+                    }
+                    _ => {}
+                },
+                State::End if is_whitespace(byte) => {}
+                State::End => return Some(Err(self.error("Invalid token at the end of document", &[]))),
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+impl<R: Read> Iterator for ArrayElements<R> {
+    type Item = Result<JsonValueOwned, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_element();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    /// Hands out the data `step` bytes at a time.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.step.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn characters_split_across_reads() {
+        let source = r#"{"name": "café 😀", "values": [1, 2.5]}"#;
+        for step in 1..5 {
+            let reader = Trickle {
+                data: source.as_bytes(),
+                step,
+            };
+            assert_eq!(from_reader(reader).unwrap(), parse(source));
+        }
+        let large = format!("[{}\"é\"]", "1,".repeat(CHUNK_SIZE));
+        assert_eq!(from_reader(large.as_bytes()).unwrap(), parse(&large));
+    }
+
+    #[test]
+    fn errors() {
+        match from_reader(&b"[\"a\xff\"]"[..]) {
+            Err(JsonError::Io { kind, msg }) => {
+                assert_eq!(kind, io::ErrorKind::InvalidData);
+                assert_eq!(msg, "Invalid UTF-8 at byte 3");
+            }
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+        assert!(matches!(from_reader(&b"\"\xc3"[..]), Err(JsonError::Io { .. })));
+        assert!(matches!(from_reader(&b"[1,"[..]), Err(JsonError::Parse { .. })));
+        match from_reader(&b"[\0\0\xd8]\0"[..]) {
+            Err(JsonError::Io { msg, .. }) => assert_eq!(msg, "Invalid UTF-16LE at byte 2"),
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn utf16_and_utf32() {
+        let source = "\u{feff}{\"name\": \"café 😀\"}";
+        let utf16 = |be: bool| -> Vec<u8> {
+            source
+                .encode_utf16()
+                .flat_map(|unit| if be { unit.to_be_bytes() } else { unit.to_le_bytes() })
+                .collect()
+        };
+        let utf32 = |be: bool| -> Vec<u8> {
+            source
+                .chars()
+                .flat_map(|c| if be { (c as u32).to_be_bytes() } else { (c as u32).to_le_bytes() })
+                .collect()
+        };
+        let expected = parse(source);
+        let encoded = [(utf16(true), 2), (utf16(false), 2), (utf32(true), 4), (utf32(false), 4)];
+        for (bytes, bom_len) in encoded.iter().chain(Some(&(source.as_bytes().to_vec(), 3))) {
+            // With and without the byte order mark.
+            for data in [&bytes[..], &bytes[*bom_len..]] {
+                assert_eq!(from_reader(Trickle { data, step: 3 }).unwrap(), expected);
+            }
+        }
+        assert_eq!(from_reader(&b"1\0"[..]).unwrap(), parse("1"));
+    }
+
+    #[test]
+    fn streamed_array_elements() {
+        let source = "\u{feff} [{\"a\": [1, \"],\\\"\"]},\n 2 , \"café\", [], {}] ";
+        let expected = parse(source);
+        for step in 1..4 {
+            let elements: Vec<_> = array_elements(Trickle {
+                data: source.as_bytes(),
+                step,
+            })
+            .map(Result::unwrap)
+            .collect();
+            assert_eq!(&JsonValue::Array(elements), &expected);
+        }
+        assert_eq!(array_elements(&b" [ ] "[..]).count(), 0);
+        assert!(matches!(array_elements(&b""[..]).next(), Some(Err(JsonError::Empty))));
+    }
+
+    #[test]
+    fn streamed_array_errors() {
+        let error = |source: &str| {
+            let mut elements = array_elements(source.as_bytes());
+            let error = elements.find_map(Result::err);
+            assert!(elements.next().is_none());
+            error.map(|e| (e.to_string(), e.offset()))
+        };
+        let same_as_parse = |source: &str| {
+            let expected = try_parse(source).unwrap_err();
+            assert_eq!(error(source), Some((expected.to_string(), expected.offset())), "{}", source);
+        };
+        for source in &["[1,]", "[,1]", "[", "[1,", "[1", "[\n {\"a\" 1}]", "[[1]", "[1] [2]", "[\"\n\"]"] {
+            same_as_parse(source);
+        }
+        // A byte order mark is only skipped at the start of the source.
+        same_as_parse("[1,\u{feff}2]");
+        same_as_parse("[1, 2,\n\u{feff}3]");
+        assert_eq!(error("{}"), Some(("Expected an array near '{' at line 1 column 1".to_string(), Some(0))));
+        assert_eq!(error("[]x"), Some(("Invalid token at the end of document near 'x' at line 1 column 3".to_string(), Some(2))));
+        let (msg, offset) = error("[1, 2 3]").unwrap();
+        assert_eq!(offset, Some(6));
+        assert!(msg.ends_with("at line 1 column 7"));
+        let mut elements = array_elements(&b"[1, \"\xff\"]"[..]);
+        assert_eq!(elements.next().unwrap().unwrap(), json!(1));
+        assert!(matches!(elements.next(), Some(Err(JsonError::Lex(e))) if e.offset == 5));
+    }
+}
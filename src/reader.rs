@@ -0,0 +1,458 @@
+//! Pull parser producing one event at a time instead of a [`JsonValue`] tree.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+
+use crate::prelude::*;
+use crate::parser::{expected, scalar, DuplicateKeys, ParseOptions, TokenParser};
+use crate::{JsonError, JsonToken, JsonTokenType, JsonValue, Lexer};
+
+#[derive(Debug, PartialEq)]
+pub enum JsonEvent<'a> {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// Name of the next object member, with its escapes resolved.
+    Key(Cow<'a, str>),
+    /// A string, number, boolean or `null`; never an array or an object.
+    Value(JsonValue<'a>),
+}
+
+/// A [`JsonEvent`] as the token it was read from, before any key or value
+/// is built from it.
+pub(crate) enum RawEvent<'a> {
+    /// The `[` or `{` starting an array or an object.
+    Start(JsonToken<'a>),
+    /// The `]` or `}` ending it.
+    End(JsonToken<'a>),
+    Key(JsonToken<'a>),
+    /// A string, number, boolean or `null`.
+    Value(JsonToken<'a>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    Array,
+    Object,
+}
+
+/// An array or object being read.
+struct Open<'a> {
+    container: Container,
+    /// Number of elements or members read so far.
+    len: usize,
+    /// Keys read so far, only kept to reject repeated ones with
+    /// [`DuplicateKeys::Error`].
+    keys: BTreeSet<Cow<'a, str>>,
+    /// Key of the member being read when it repeats an earlier one, reported
+    /// once its value is read, as [`try_parse`](crate::try_parse) does.
+    duplicate: Option<JsonToken<'a>>,
+}
+
+/// What the next token may be.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Value,
+    FirstElement,
+    ElementSeparator,
+    FirstKey,
+    MemberSeparator,
+    /// The document is complete, only the end of the source may follow.
+    End,
+    Failed,
+}
+
+/// Reads a document as a sequence of [`JsonEvent`]s, checking its syntax
+/// along the way. Only the stack of open containers is kept in memory.
+pub struct JsonReader<'a> {
+    parser: TokenParser<'a, Lexer<'a>>,
+    stack: Vec<Open<'a>>,
+    state: State,
+}
+
+impl<'a> JsonReader<'a> {
+    /// Reader with the default [`ParseOptions`], reporting the same errors
+    /// as [`try_parse`](crate::try_parse).
+    pub fn new(source: &'a str) -> Self {
+        Self::with_options(source, ParseOptions::default())
+    }
+
+    /// Reader accepting what [`try_parse_with`](crate::try_parse_with)
+    /// accepts with `options`. Repeated keys are all read, unless
+    /// [`DuplicateKeys::Error`] rejects them.
+    pub fn with_options(source: &'a str, options: ParseOptions) -> Self {
+        Self {
+            parser: TokenParser::new(source, options.lexer(source), options),
+            stack: Vec::new(),
+            state: State::Value,
+        }
+    }
+
+    /// State following a complete value.
+    fn after_value(&self) -> State {
+        match self.stack.last() {
+            None => State::End,
+            Some(open) if open.container == Container::Array => State::ElementSeparator,
+            Some(_) => State::MemberSeparator,
+        }
+    }
+
+    fn open(&mut self, container: Container, token: JsonToken<'a>) -> Result<RawEvent<'a>, JsonError> {
+        self.parser.enter(&token)?;
+        self.stack.push(Open {
+            container,
+            len: 0,
+            keys: BTreeSet::new(),
+            duplicate: None,
+        });
+        self.state = match container {
+            Container::Array => State::FirstElement,
+            Container::Object => State::FirstKey,
+        };
+        Ok(RawEvent::Start(token))
+    }
+
+    fn close(&mut self, token: JsonToken<'a>) -> Result<RawEvent<'a>, JsonError> {
+        self.parser.leave();
+        self.stack.pop();
+        self.value_read()?;
+        Ok(RawEvent::End(token))
+    }
+
+    /// Moves past a complete value.
+    fn value_read(&mut self) -> Result<(), JsonError> {
+        if let Some(token) = self.stack.last_mut().and_then(|open| open.duplicate.take()) {
+            return self.parser.error("Duplicate key in object", Some(&token), &[]);
+        }
+        self.state = self.after_value();
+        Ok(())
+    }
+
+    fn value(&mut self, token: JsonToken<'a>) -> Result<RawEvent<'a>, JsonError> {
+        match token.token_type {
+            JsonTokenType::LeftBracket => self.open(Container::Array, token),
+            JsonTokenType::LeftBrace => self.open(Container::Object, token),
+            JsonTokenType::String
+            | JsonTokenType::Number
+            | JsonTokenType::True
+            | JsonTokenType::False
+            | JsonTokenType::Null => {
+                self.value_read()?;
+                Ok(RawEvent::Value(token))
+            }
+            _ => self.parser.error("Unexpected token in place of a value", Some(&token), expected::VALUE),
+        }
+    }
+
+    /// Reads an element of the innermost array, starting with `token`.
+    fn element(&mut self, token: JsonToken<'a>) -> Result<RawEvent<'a>, JsonError> {
+        if let Some(open) = self.stack.last_mut() {
+            open.len += 1;
+        }
+        self.value(token)
+    }
+
+    /// Reads the key `token` of a member of the innermost object, and the
+    /// `:` following it.
+    fn member(&mut self, token: JsonToken<'a>) -> Result<RawEvent<'a>, JsonError> {
+        if !self.parser.is_key(&token) {
+            return self.parser.error(
+                "Unexpected token in place of string key in object",
+                Some(&token),
+                expected::KEY,
+            );
+        }
+        let reject_duplicates = self.parser.options().duplicate_keys == DuplicateKeys::Error;
+        let key = if reject_duplicates { self.parser.key(&token) } else { None };
+        if let Some(open) = self.stack.last_mut() {
+            open.len += 1;
+            if let Some(key) = key {
+                if !open.keys.insert(key) {
+                    open.duplicate = Some(token.clone());
+                }
+            }
+        }
+        let column = self.parser.expect_token(expected::COLUMN)?;
+        if column.token_type != JsonTokenType::Column {
+            return self.parser.error("Expected ':' after object key", Some(&column), expected::COLUMN);
+        }
+        self.state = State::Value;
+        Ok(RawEvent::Key(token))
+    }
+
+    fn read_event(&mut self) -> Result<Option<RawEvent<'a>>, JsonError> {
+        let len = self.stack.last().map_or(0, |open| open.len);
+        let json5 = self.parser.options().json5;
+        let event = match self.state {
+            State::Failed => return Ok(None),
+            State::End => match self.parser.next_token()? {
+                None => return Ok(None),
+                Some(token) => return self.parser.error("Invalid token at the end of document", Some(&token), &[]),
+            },
+            State::Value if self.stack.is_empty() => {
+                self.parser.check_size()?;
+                match self.parser.next_token()? {
+                    None => return Err(JsonError::Empty),
+                    Some(token) => self.value(token)?,
+                }
+            }
+            State::Value => {
+                let token = self.parser.expect_token(expected::VALUE)?;
+                self.value(token)?
+            }
+            State::FirstElement => {
+                let token = self.parser.expect_item(0, expected::FIRST_ELEMENT)?;
+                match token.token_type {
+                    JsonTokenType::RightBracket => self.close(token)?,
+                    _ => self.element(token)?,
+                }
+            }
+            State::ElementSeparator => {
+                let separator = self.parser.expect_token(expected::AFTER_ELEMENT)?;
+                match separator.token_type {
+                    JsonTokenType::Comma => {
+                        let token = self.parser.expect_item(len, expected::VALUE)?;
+                        match token.token_type {
+                            JsonTokenType::RightBracket if json5 => self.close(token)?,
+                            _ => self.element(token)?,
+                        }
+                    }
+                    JsonTokenType::RightBracket => self.close(separator)?,
+                    _ => {
+                        return self.parser.error(
+                            "Expected ',' or ']' after array element",
+                            Some(&separator),
+                            expected::AFTER_ELEMENT,
+                        )
+                    }
+                }
+            }
+            State::FirstKey => {
+                let token = self.parser.expect_item(0, expected::FIRST_KEY)?;
+                match token.token_type {
+                    JsonTokenType::RightBrace => self.close(token)?,
+                    _ => self.member(token)?,
+                }
+            }
+            State::MemberSeparator => {
+                let separator = self.parser.expect_token(expected::AFTER_MEMBER)?;
+                match separator.token_type {
+                    JsonTokenType::Comma => {
+                        let token = self.parser.expect_item(len, expected::KEY)?;
+                        match token.token_type {
+                            JsonTokenType::RightBrace if json5 => self.close(token)?,
+                            _ => self.member(token)?,
+                        }
+                    }
+                    JsonTokenType::RightBrace => self.close(separator)?,
+                    _ => {
+                        return self.parser.error(
+                            "Expected ',' or '}' after object member",
+                            Some(&separator),
+                            expected::AFTER_MEMBER,
+                        )
+                    }
+                }
+            }
+        };
+        Ok(Some(event))
+    }
+
+    /// Same as [`next_event`](Self::next_event), without building keys and
+    /// values.
+    pub(crate) fn next_raw(&mut self) -> Result<Option<RawEvent<'a>>, JsonError> {
+        let event = self.read_event();
+        if event.is_err() {
+            self.state = State::Failed;
+        }
+        event
+    }
+
+    /// Reads the next event, `None` once the document is complete. After an
+    /// error, no more events are produced.
+    pub fn next_event(&mut self) -> Result<Option<JsonEvent<'a>>, JsonError> {
+        Ok(self.next_raw()?.map(|event| match event {
+            RawEvent::Start(token) if token.token_type == JsonTokenType::LeftBracket => JsonEvent::StartArray,
+            RawEvent::Start(_) => JsonEvent::StartObject,
+            RawEvent::End(token) if token.token_type == JsonTokenType::RightBracket => JsonEvent::EndArray,
+            RawEvent::End(_) => JsonEvent::EndObject,
+            RawEvent::Key(token) => JsonEvent::Key(self.key(&token)),
+            RawEvent::Value(token) => JsonEvent::Value(scalar(&token).unwrap_or(JsonValue::Null)),
+        }))
+    }
+
+    /// Name of the member introduced by the token of a key event.
+    pub(crate) fn key(&self, token: &JsonToken<'a>) -> Cow<'a, str> {
+        self.parser.key(token).unwrap_or_default()
+    }
+
+    /// Reads the token starting the next value of the innermost array, or
+    /// that of the member whose key was just read. `None` at the end of the
+    /// array.
+    pub(crate) fn next_value(&mut self) -> Result<Option<JsonToken<'a>>, JsonError> {
+        Ok(match self.next_raw()? {
+            Some(RawEvent::Start(token)) | Some(RawEvent::Value(token)) => Some(token),
+            _ => None,
+        })
+    }
+
+    /// Reads the key of the next member of the innermost object, `None` at
+    /// its end.
+    pub(crate) fn next_key(&mut self) -> Result<Option<Cow<'a, str>>, JsonError> {
+        Ok(match self.next_raw()? {
+            Some(RawEvent::Key(token)) => Some(self.key(&token)),
+            _ => None,
+        })
+    }
+
+    /// Byte offset following the token of the last event read, or the `:`
+    /// following a key.
+    pub(crate) fn end(&self) -> usize {
+        self.parser.end()
+    }
+
+    /// Number of arrays and objects currently open.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Skips the rest of the array or object whose start event was just
+    /// read, up to and including its end event.
+    pub fn skip_container(&mut self) -> Result<(), JsonError> {
+        let depth = self.depth();
+        while self.depth() >= depth {
+            if self.next_raw()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips the value starting with `token`, as returned by
+    /// [`next_value`](Self::next_value).
+    pub(crate) fn skip_value(&mut self, token: &JsonToken<'a>) -> Result<(), JsonError> {
+        match token.token_type {
+            JsonTokenType::LeftBracket | JsonTokenType::LeftBrace => self.skip_container(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads and checks the rest of the document, down to its end.
+    pub(crate) fn end_of_document(&mut self) -> Result<(), JsonError> {
+        while self.next_raw()?.is_some() {}
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for JsonReader<'a> {
+    type Item = Result<JsonEvent<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use JsonEvent::*;
+
+    fn events(source: &str) -> Result<Vec<JsonEvent<'_>>, JsonError> {
+        JsonReader::new(source).collect()
+    }
+
+    #[test]
+    fn event_sequence() {
+        assert_eq!(
+            events(r#"{"a\tb": [1, {}, []], "c": null}"#).unwrap(),
+            vec![
+                StartObject,
+                Key("a\tb".into()),
+                StartArray,
+                Value(json!(1)),
+                StartObject,
+                EndObject,
+                StartArray,
+                EndArray,
+                EndArray,
+                Key("c".into()),
+                Value(JsonValue::Null),
+                EndObject,
+            ]
+        );
+        assert_eq!(events(" true ").unwrap(), vec![Value(json!(true))]);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let msg = |source| match events(source) {
+            Err(JsonError::Parse { msg, .. }) => msg,
+            other => panic!("expected a parse error, got {:?}", other),
+        };
+        assert_eq!(msg("[1,]"), "Unexpected token in place of a value");
+        assert_eq!(msg("[1 2]"), "Expected ',' or ']' after array element");
+        assert_eq!(msg(r#"{"a" 1}"#), "Expected ':' after object key");
+        assert_eq!(msg(r#"{"a": 1,}"#), "Unexpected token in place of string key in object");
+        assert_eq!(msg(r#"{"a": 1 "b": 2}"#), "Expected ',' or '}' after object member");
+        assert_eq!(msg("[[]"), "Unexpected end of file");
+        assert_eq!(msg("[] []"), "Invalid token at the end of document");
+        assert!(matches!(events(""), Err(JsonError::Empty)));
+
+        let mut reader = JsonReader::new("[1,]");
+        assert!(reader.by_ref().any(|event| event.is_err()));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn pick_fields() {
+        let source = r#"{"faces": [{"rect": [1, 2]}, {"rect": [3]}], "uid": 7}"#;
+        let mut reader = JsonReader::new(source);
+        let mut uid = None;
+        while let Some(event) = reader.next_event().unwrap() {
+            if let Key(key) = event {
+                match reader.next_event().unwrap() {
+                    Some(Value(value)) if key == "uid" => uid = Some(value),
+                    Some(StartArray) | Some(StartObject) => reader.skip_container().unwrap(),
+                    _ => {}
+                }
+            }
+        }
+        assert_eq!(uid, Some(json!(7)));
+    }
+
+    #[test]
+    fn same_rules_as_parsing() {
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let events: Result<Vec<_>, _> = JsonReader::with_options("{a: [1,], 'b': 2,}", json5).collect();
+        assert_eq!(
+            events.unwrap(),
+            vec![StartObject, Key("a".into()), StartArray, Value(json!(1)), EndArray, Key("b".into()), Value(json!(2)), EndObject]
+        );
+
+        let duplicate_error = ParseOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..ParseOptions::default()
+        };
+        let limits = ParseOptions {
+            max_len: Some(2),
+            max_string_len: Some(3),
+            ..ParseOptions::default()
+        };
+        let cases = [
+            (&"[".repeat(200), ParseOptions::default()),
+            (&r#"{"a": 1, "a": 2}"#.into(), duplicate_error.clone()),
+            (&r#"{"a": 1, "\u0061": [}"#.into(), duplicate_error),
+            (&"[1, 2, 3]".into(), limits.clone()),
+            (&r#"["abcd"]"#.into(), limits),
+        ];
+        for (source, options) in cases.iter() {
+            let read: Result<Vec<_>, _> = JsonReader::with_options(source, options.clone()).collect();
+            let parsed = crate::try_parse_with(source, options.clone());
+            assert_eq!(read.map(|_| ()), parsed.map(|_| ()), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Byte-oriented fast paths of the lexer: runs of whitespaces and string
+//! contents are skipped several bytes at a time instead of char by char.
+//!
+//! Bytes are tested eight at a time in a `u64`, or sixteen at a time with
+//! SSE2 on x86_64 when the `simd` feature is enabled.
+
+use core::convert::TryInto;
+
+/// Iterates over the chars of a source with their byte offsets like
+/// [`CharIndices`](core::str::CharIndices), and can be moved forward to any
+/// char boundary.
+#[derive(Clone, Debug)]
+pub(crate) struct Cursor<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Self { source, pos: 0 }
+    }
+
+    /// The rest of the source.
+    pub(crate) fn as_str(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    /// Moves past the whitespaces of JSON, leaving other chars to `next`.
+    pub(crate) fn skip_whitespace(&mut self) {
+        self.pos = skip_whitespace(self.source.as_bytes(), self.pos);
+    }
+
+    /// Moves to the next `quote`, backslash or control character of a
+    /// string, or to the end of the source.
+    pub(crate) fn skip_string_chars(&mut self, quote: char) {
+        // Only ASCII bytes are looked for, so the cursor stays on a char boundary.
+        debug_assert!(quote.is_ascii());
+        self.pos = string_end(self.source.as_bytes(), self.pos, quote as u8);
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let idx = self.pos;
+        let chr = match *self.source.as_bytes().get(idx)? {
+            byte if byte.is_ascii() => byte as char,
+            _ => self.as_str().chars().next()?,
+        };
+        self.pos += chr.len_utf8();
+        Some((idx, chr))
+    }
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// `byte` in each of the eight lanes of a word.
+const fn splat(byte: u8) -> u64 {
+    u64::from_ne_bytes([byte; 8])
+}
+
+/// Sets the high bit of the lowest lane of `word` holding a byte below
+/// `n`, which must be at most 0x80. Higher lanes may be set wrongly.
+fn lanes_below(word: u64, n: u8) -> u64 {
+    word.wrapping_sub(splat(n)) & !word & splat(0x80)
+}
+
+/// Sets the high bit of the lowest lane of `word` equal to `byte`.
+fn lanes_equal(word: u64, byte: u8) -> u64 {
+    lanes_below(word ^ splat(byte), 1)
+}
+
+/// Offset of the first byte from `pos` that is not a JSON whitespace.
+pub(crate) fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        pos = sse2::skip_whitespace(bytes, pos);
+    }
+    // Indentation is mostly made of spaces.
+    while let Some(chunk) = bytes.get(pos..pos + 8) {
+        if u64::from_le_bytes(chunk.try_into().expect("8 bytes")) != splat(b' ') {
+            break;
+        }
+        pos += 8;
+    }
+    while pos < bytes.len() && is_whitespace(bytes[pos]) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Offset of the first `quote`, backslash or control character from `pos`,
+/// the length of `bytes` if there is none.
+pub(crate) fn string_end(bytes: &[u8], mut pos: usize, quote: u8) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        pos = sse2::string_end(bytes, pos, quote);
+    }
+    while let Some(chunk) = bytes.get(pos..pos + 8) {
+        let word = u64::from_le_bytes(chunk.try_into().expect("8 bytes"));
+        let found = lanes_below(word, 0x20) | lanes_equal(word, quote) | lanes_equal(word, b'\\');
+        if found != 0 {
+            return pos + found.trailing_zeros() as usize / 8;
+        }
+        pos += 8;
+    }
+    while pos < bytes.len() && bytes[pos] >= 0x20 && bytes[pos] != quote && bytes[pos] != b'\\' {
+        pos += 1;
+    }
+    pos
+}
+
+/// SSE2 is part of the x86_64 baseline, no runtime detection is needed.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use core::arch::x86_64::*;
+
+    /// Bit `i` of the result is set when byte `pos + i` matches.
+    fn mask(bytes: &[u8], pos: usize, matches: impl Fn(__m128i) -> __m128i) -> Option<u32> {
+        let chunk = bytes.get(pos..pos + 16)?;
+        // SAFETY: `chunk` is 16 bytes long and the load is unaligned.
+        let vector = unsafe { _mm_loadu_si128(chunk.as_ptr() as *const __m128i) };
+        // SAFETY: SSE2 is always available on x86_64.
+        Some(unsafe { _mm_movemask_epi8(matches(vector)) } as u32)
+    }
+
+    pub(super) fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+        // SAFETY: SSE2 is always available on x86_64.
+        let whitespaces = |v| unsafe {
+            let space = _mm_cmpeq_epi8(v, _mm_set1_epi8(b' ' as i8));
+            let tab = _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\t' as i8));
+            let lf = _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\n' as i8));
+            let cr = _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\r' as i8));
+            _mm_or_si128(_mm_or_si128(space, tab), _mm_or_si128(lf, cr))
+        };
+        while let Some(mask) = mask(bytes, pos, whitespaces) {
+            if mask != 0xFFFF {
+                return pos + mask.trailing_ones() as usize;
+            }
+            pos += 16;
+        }
+        pos
+    }
+
+    pub(super) fn string_end(bytes: &[u8], mut pos: usize, quote: u8) -> usize {
+        // SAFETY: SSE2 is always available on x86_64.
+        let stops = |v| unsafe {
+            let quote = _mm_cmpeq_epi8(v, _mm_set1_epi8(quote as i8));
+            let backslash = _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\\' as i8));
+            // Unsigned `v <= 0x1F`.
+            let limit = _mm_set1_epi8(0x1F);
+            let control = _mm_cmpeq_epi8(_mm_max_epu8(v, limit), limit);
+            _mm_or_si128(_mm_or_si128(quote, backslash), control)
+        };
+        while let Some(mask) = mask(bytes, pos, stops) {
+            if mask != 0 {
+                return pos + mask.trailing_zeros() as usize;
+            }
+            pos += 16;
+        }
+        pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_runs() {
+        for len in 0..40 {
+            let source = format!("{}x", " \t\r\n".repeat(len / 4) + &" ".repeat(len % 4));
+            assert_eq!(skip_whitespace(source.as_bytes(), 0), len, "{:?}", source);
+        }
+        assert_eq!(skip_whitespace(b"        ", 0), 8);
+        assert_eq!(skip_whitespace(b"[ 1]", 1), 2);
+    }
+
+    #[test]
+    fn string_stops() {
+        for len in 0..40 {
+            let text = "é".repeat(len / 2) + &"a".repeat(len % 2);
+            for stop in ["\"", "\\", "\n", "\u{0}", "\u{1f}"].iter() {
+                let source = format!("{}{}tail\"", text, stop);
+                assert_eq!(string_end(source.as_bytes(), 0, b'"'), text.len(), "{:?}", source);
+            }
+            assert_eq!(string_end(text.as_bytes(), 0, b'"'), text.len());
+        }
+        assert_eq!(string_end(b"a\"b'c", 0, b'\''), 3);
+        assert_eq!(string_end(b"\x7f\x80 \"", 0, b'"'), 3);
+    }
+
+    #[test]
+    fn cursor() {
+        let mut cursor = Cursor::new("  é\"x");
+        cursor.skip_whitespace();
+        assert_eq!(cursor.next(), Some((2, 'é')));
+        cursor.skip_string_chars('"');
+        assert_eq!(cursor.as_str(), "\"x");
+        assert_eq!(cursor.collect::<Vec<_>>(), vec![(4, '"'), (5, 'x')]);
+    }
+}
@@ -0,0 +1,725 @@
+//! Validation against a JSON Schema (draft 2020-12), without annotations
+//! and vocabularies beyond validation.
+//!
+//! Supported keywords are `type`, `enum`, `const`, `minimum`, `maximum`,
+//! `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`, `minLength`,
+//! `maxLength`, `pattern`, `items`, `prefixItems`, `minItems`, `maxItems`,
+//! `uniqueItems`, `contains`, `required`, `properties`,
+//! `patternProperties`, `additionalProperties`, `minProperties`,
+//! `maxProperties`, `allOf`, `anyOf`, `oneOf`, `not` and `$ref` to a
+//! location of the same document, e.g. `#/$defs/face`. Other keywords are
+//! ignored.
+//!
+//! ```
+//! use yaj::{json, schema::Schema};
+//! let schema = Schema::compile(&json!({
+//!     "type": "object",
+//!     "required": ["uid"],
+//!     "properties": {"uid": {"type": "integer", "minimum": 0}},
+//! }))
+//! .unwrap();
+//! assert!(schema.validate(&json!({"uid": 12})).is_ok());
+//! let errors = schema.validate(&json!({"uid": -1})).unwrap_err();
+//! assert_eq!(errors[0].to_string(), "/uid: -1 is less than the minimum of 0");
+//! ```
+
+use core::cmp::Ordering;
+use core::fmt;
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::pointer::push_token;
+use crate::{to_string, JsonNumber, JsonValue, JsonValueOwned};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Type {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    String,
+    Integer,
+}
+
+impl Type {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "null" => Self::Null,
+            "boolean" => Self::Boolean,
+            "object" => Self::Object,
+            "array" => Self::Array,
+            "number" => Self::Number,
+            "string" => Self::String,
+            "integer" => Self::Integer,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Integer => "integer",
+        }
+    }
+
+    /// Floats without a fraction are integers too.
+    fn matches(self, value: &JsonValue<'_>) -> bool {
+        match (self, value) {
+            (Self::Integer, JsonValue::Number(JsonNumber::Float(f))) => f.fract() == 0.0,
+            (Self::Integer, JsonValue::Number(_)) => true,
+            (Self::Null, JsonValue::Null)
+            | (Self::Boolean, JsonValue::Boolean(_))
+            | (Self::Object, JsonValue::Object(_))
+            | (Self::Array, JsonValue::Array(_))
+            | (Self::Number, JsonValue::Number(_))
+            | (Self::String, JsonValue::String(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    /// The `true` schema.
+    Always,
+    /// The `false` schema.
+    Never,
+    Keywords(Box<Keywords>),
+}
+
+#[derive(Debug, Default)]
+struct Keywords {
+    types: Option<Vec<Type>>,
+    enumeration: Option<Vec<JsonValueOwned>>,
+    constant: Option<JsonValueOwned>,
+    minimum: Option<JsonNumber>,
+    maximum: Option<JsonNumber>,
+    exclusive_minimum: Option<JsonNumber>,
+    exclusive_maximum: Option<JsonNumber>,
+    multiple_of: Option<JsonNumber>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    prefix_items: Vec<Node>,
+    items: Option<Node>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    unique_items: bool,
+    contains: Option<Node>,
+    required: Vec<String>,
+    properties: Vec<(String, Node)>,
+    pattern_properties: Vec<(Regex, Node)>,
+    additional_properties: Option<Node>,
+    min_properties: Option<usize>,
+    max_properties: Option<usize>,
+    all_of: Vec<Node>,
+    any_of: Vec<Node>,
+    one_of: Vec<Node>,
+    not: Option<Node>,
+    /// Key of the referenced schema in `Schema::references`.
+    reference: Option<String>,
+}
+
+/// A schema ready to validate documents.
+#[derive(Debug)]
+pub struct Schema {
+    root: Node,
+    /// Schemas designated by `$ref`, compiled once even when recursive.
+    references: HashMap<String, Node>,
+}
+
+/// A schema could not be compiled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    /// JSON Pointer of the offending keyword in the schema.
+    pub path: String,
+    pub msg: String,
+}
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid schema at '{}': {}", self.path, self.msg)
+    }
+}
+impl core::error::Error for SchemaError {}
+
+/// A document does not satisfy a keyword of the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// JSON Pointer of the offending value in the document.
+    pub path: String,
+    /// The keyword that is not satisfied, e.g. `required`.
+    pub keyword: &'static str,
+    pub msg: String,
+}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.msg)
+    }
+}
+impl core::error::Error for ValidationError {}
+
+impl Schema {
+    pub fn compile(schema: &JsonValue<'_>) -> Result<Self, SchemaError> {
+        let mut compiler = Compiler {
+            document: schema,
+            references: HashMap::new(),
+        };
+        let root = compiler.node(schema, &mut String::new())?;
+        let references = &compiler.references;
+        let mut checked = HashSet::new();
+        find_loop(references, &root, &mut String::new(), &mut Vec::new(), &mut checked)?;
+        let mut keys: Vec<&str> = references.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        for reference in keys {
+            if checked.insert(reference) {
+                let node = &references[reference];
+                find_loop(references, node, &mut reference[1..].to_string(), &mut vec![reference], &mut checked)?;
+            }
+        }
+        Ok(Self {
+            root,
+            references: compiler.references,
+        })
+    }
+
+    /// Checks `value` against the schema, reporting every keyword that is
+    /// not satisfied.
+    pub fn validate(&self, value: &JsonValue<'_>) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.check(&self.root, value, &mut String::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_valid(&self, value: &JsonValue<'_>) -> bool {
+        self.validate(value).is_ok()
+    }
+
+    fn matches(&self, node: &Node, value: &JsonValue<'_>) -> bool {
+        let mut errors = Vec::new();
+        self.check(node, value, &mut String::new(), &mut errors);
+        errors.is_empty()
+    }
+
+    fn check(&self, node: &Node, value: &JsonValue<'_>, path: &mut String, errors: &mut Vec<ValidationError>) {
+        let keywords = match node {
+            Node::Always => return,
+            Node::Never => return fail(errors, path, "false", String::from("No value is allowed")),
+            Node::Keywords(keywords) => keywords,
+        };
+        let shown = || to_string(value);
+
+        if let Some(types) = &keywords.types {
+            if !types.iter().any(|t| t.matches(value)) {
+                let names: Vec<_> = types.iter().map(|t| t.name()).collect();
+                fail(errors, path, "type", format!("{} is not of type {}", shown(), names.join(" or ")));
+            }
+        }
+        if let Some(values) = &keywords.enumeration {
+            if !values.iter().any(|v| v.eq_by_value(value)) {
+                fail(errors, path, "enum", format!("{} is not one of the allowed values", shown()));
+            }
+        }
+        if let Some(constant) = &keywords.constant {
+            if !constant.eq_by_value(value) {
+                fail(errors, path, "const", format!("{} is not {}", shown(), to_string(constant)));
+            }
+        }
+
+        if let JsonValue::Number(n) = value {
+            let bounds = [
+                ("minimum", keywords.minimum, "is less than the minimum of"),
+                ("maximum", keywords.maximum, "is greater than the maximum of"),
+                ("exclusiveMinimum", keywords.exclusive_minimum, "is not greater than"),
+                ("exclusiveMaximum", keywords.exclusive_maximum, "is not less than"),
+            ];
+            for (keyword, bound, what) in bounds.iter() {
+                let bound = match bound {
+                    Some(bound) => *bound,
+                    None => continue,
+                };
+                let ordering = n.cmp_value(&bound);
+                let failed = match *keyword {
+                    "minimum" => ordering == Ordering::Less,
+                    "maximum" => ordering == Ordering::Greater,
+                    "exclusiveMinimum" => ordering != Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                };
+                if failed {
+                    fail(errors, path, keyword, format!("{} {} {}", shown(), what, number_text(bound)));
+                }
+            }
+            if let Some(divisor) = keywords.multiple_of {
+                if !is_multiple(*n, divisor) {
+                    let msg = format!("{} is not a multiple of {}", shown(), number_text(divisor));
+                    fail(errors, path, "multipleOf", msg);
+                }
+            }
+        }
+
+        if let JsonValue::String(s) = value {
+            let len = s.chars().count();
+            if let Some(min) = keywords.min_length.filter(|min| len < *min) {
+                fail(errors, path, "minLength", format!("{} is shorter than {} characters", shown(), min));
+            }
+            if let Some(max) = keywords.max_length.filter(|max| len > *max) {
+                fail(errors, path, "maxLength", format!("{} is longer than {} characters", shown(), max));
+            }
+            if let Some(pattern) = &keywords.pattern {
+                if !pattern.is_match(s) {
+                    fail(errors, path, "pattern", format!("{} does not match '{}'", shown(), pattern.as_str()));
+                }
+            }
+        }
+
+        if let JsonValue::Array(array) = value {
+            if let Some(min) = keywords.min_items.filter(|min| array.len() < *min) {
+                fail(errors, path, "minItems", format!("Expected at least {} items", min));
+            }
+            if let Some(max) = keywords.max_items.filter(|max| array.len() > *max) {
+                fail(errors, path, "maxItems", format!("Expected at most {} items", max));
+            }
+            if keywords.unique_items {
+                let duplicate = (1..array.len()).any(|i| array[..i].iter().any(|other| other.eq_by_value(&array[i])));
+                if duplicate {
+                    fail(errors, path, "uniqueItems", String::from("Items are not unique"));
+                }
+            }
+            if let Some(contains) = &keywords.contains {
+                if !array.iter().any(|item| self.matches(contains, item)) {
+                    fail(errors, path, "contains", String::from("No item matches the `contains` schema"));
+                }
+            }
+            let len = path.len();
+            for (index, item) in array.iter().enumerate() {
+                let node = match keywords.prefix_items.get(index).or(keywords.items.as_ref()) {
+                    Some(node) => node,
+                    None => break,
+                };
+                push_token(path, &index.to_string());
+                self.check(node, item, path, errors);
+                path.truncate(len);
+            }
+        }
+
+        if let JsonValue::Object(obj) = value {
+            if let Some(min) = keywords.min_properties.filter(|min| obj.len() < *min) {
+                fail(errors, path, "minProperties", format!("Expected at least {} properties", min));
+            }
+            if let Some(max) = keywords.max_properties.filter(|max| obj.len() > *max) {
+                fail(errors, path, "maxProperties", format!("Expected at most {} properties", max));
+            }
+            for key in &keywords.required {
+                if !obj.contains_key(key.as_str()) {
+                    fail(errors, path, "required", format!("Missing required property '{}'", key));
+                }
+            }
+            let len = path.len();
+            for (key, member) in obj {
+                push_token(path, key);
+                let mut matched = false;
+                if let Some((_, node)) = keywords.properties.iter().find(|(name, _)| name == key) {
+                    matched = true;
+                    self.check(node, member, path, errors);
+                }
+                for (pattern, node) in &keywords.pattern_properties {
+                    if pattern.is_match(key) {
+                        matched = true;
+                        self.check(node, member, path, errors);
+                    }
+                }
+                if let (false, Some(node)) = (matched, &keywords.additional_properties) {
+                    self.check(node, member, path, errors);
+                }
+                path.truncate(len);
+            }
+        }
+
+        for node in &keywords.all_of {
+            self.check(node, value, path, errors);
+        }
+        if !keywords.any_of.is_empty() && !keywords.any_of.iter().any(|node| self.matches(node, value)) {
+            fail(errors, path, "anyOf", format!("{} does not match any of the schemas", shown()));
+        }
+        if !keywords.one_of.is_empty() {
+            let count = keywords.one_of.iter().filter(|node| self.matches(node, value)).count();
+            if count != 1 {
+                let msg = format!("{} matches {} of the schemas instead of one", shown(), count);
+                fail(errors, path, "oneOf", msg);
+            }
+        }
+        if let Some(not) = &keywords.not {
+            if self.matches(not, value) {
+                fail(errors, path, "not", format!("{} matches the `not` schema", shown()));
+            }
+        }
+        if let Some(reference) = &keywords.reference {
+            self.check(&self.references[reference], value, path, errors);
+        }
+    }
+}
+
+fn fail(errors: &mut Vec<ValidationError>, path: &str, keyword: &'static str, msg: String) {
+    errors.push(ValidationError {
+        path: path.to_string(),
+        keyword,
+        msg,
+    });
+}
+
+fn number_text(n: JsonNumber) -> String {
+    to_string(&JsonValue::Number(n))
+}
+
+fn is_multiple(n: JsonNumber, divisor: JsonNumber) -> bool {
+    match (n, divisor) {
+        (JsonNumber::Integer(n), JsonNumber::Integer(d)) => i128::from(n) % i128::from(d) == 0,
+        (JsonNumber::Unsigned(n), JsonNumber::Integer(d)) => i128::from(n) % i128::from(d) == 0,
+        _ => {
+            let quotient = as_f64(n) / as_f64(divisor);
+            quotient.is_finite() && quotient.fract() == 0.0
+        }
+    }
+}
+
+fn as_f64(n: JsonNumber) -> f64 {
+    match n {
+        JsonNumber::Integer(n) => n as f64,
+        JsonNumber::Unsigned(n) => n as f64,
+        JsonNumber::Float(f) => f,
+    }
+}
+
+struct Compiler<'s, 'a> {
+    document: &'s JsonValue<'a>,
+    references: HashMap<String, Node>,
+}
+
+impl Compiler<'_, '_> {
+    fn node(&mut self, schema: &JsonValue<'_>, path: &mut String) -> Result<Node, SchemaError> {
+        let obj = match schema {
+            JsonValue::Boolean(true) => return Ok(Node::Always),
+            JsonValue::Boolean(false) => return Ok(Node::Never),
+            JsonValue::Object(obj) => obj,
+            _ => return error(path, "Expected an object or a boolean"),
+        };
+        let mut keywords = Keywords::default();
+        let len = path.len();
+        for (key, value) in obj {
+            push_token(path, key);
+            match key.as_ref() {
+                "type" => {
+                    let names = match value {
+                        JsonValue::Array(names) => names.iter().collect(),
+                        name => vec![name],
+                    };
+                    let types = names.into_iter().map(|name| name.as_str().and_then(Type::parse));
+                    keywords.types = Some(match types.collect() {
+                        Some(types) => types,
+                        None => return error(path, "Unknown type"),
+                    });
+                }
+                "enum" => match value {
+                    JsonValue::Array(values) => keywords.enumeration = Some(values.iter().map(JsonValue::to_owned).collect()),
+                    _ => return error(path, "Expected an array"),
+                },
+                "const" => keywords.constant = Some(value.to_owned()),
+                "minimum" => keywords.minimum = Some(number(value, path)?),
+                "maximum" => keywords.maximum = Some(number(value, path)?),
+                "exclusiveMinimum" => keywords.exclusive_minimum = Some(number(value, path)?),
+                "exclusiveMaximum" => keywords.exclusive_maximum = Some(number(value, path)?),
+                "multipleOf" => {
+                    let divisor = number(value, path)?;
+                    if divisor.cmp_value(&JsonNumber::Integer(0)) != Ordering::Greater {
+                        return error(path, "Expected a number greater than 0");
+                    }
+                    keywords.multiple_of = Some(divisor);
+                }
+                "minLength" => keywords.min_length = Some(count(value, path)?),
+                "maxLength" => keywords.max_length = Some(count(value, path)?),
+                "pattern" => keywords.pattern = Some(pattern(value, path)?),
+                "prefixItems" => keywords.prefix_items = self.nodes(value, path)?,
+                "items" => keywords.items = Some(self.node(value, path)?),
+                "minItems" => keywords.min_items = Some(count(value, path)?),
+                "maxItems" => keywords.max_items = Some(count(value, path)?),
+                "uniqueItems" => match value {
+                    JsonValue::Boolean(unique) => keywords.unique_items = *unique,
+                    _ => return error(path, "Expected a boolean"),
+                },
+                "contains" => keywords.contains = Some(self.node(value, path)?),
+                "required" => {
+                    let names = value.as_array().and_then(|names| {
+                        names.iter().map(|name| name.as_str().map(str::to_string)).collect()
+                    });
+                    keywords.required = match names {
+                        Some(names) => names,
+                        None => return error(path, "Expected an array of strings"),
+                    };
+                }
+                "properties" => keywords.properties = self.members(value, path)?,
+                "patternProperties" => {
+                    for (pattern, node) in self.members(value, path)? {
+                        let regex = Regex::new(&pattern).or_else(|e| {
+                            push_token(path, &pattern);
+                            error(path, &e.to_string())
+                        })?;
+                        keywords.pattern_properties.push((regex, node));
+                    }
+                }
+                "additionalProperties" => keywords.additional_properties = Some(self.node(value, path)?),
+                "minProperties" => keywords.min_properties = Some(count(value, path)?),
+                "maxProperties" => keywords.max_properties = Some(count(value, path)?),
+                "allOf" => keywords.all_of = self.nodes(value, path)?,
+                "anyOf" => keywords.any_of = self.nodes(value, path)?,
+                "oneOf" => keywords.one_of = self.nodes(value, path)?,
+                "not" => keywords.not = Some(self.node(value, path)?),
+                "$ref" => keywords.reference = Some(self.reference(value, path)?),
+                _ => {}
+            }
+            path.truncate(len);
+        }
+        Ok(Node::Keywords(Box::new(keywords)))
+    }
+
+    /// Compiles a non-empty array of schemas.
+    fn nodes(&mut self, value: &JsonValue<'_>, path: &mut String) -> Result<Vec<Node>, SchemaError> {
+        let schemas = match value {
+            JsonValue::Array(schemas) if !schemas.is_empty() => schemas,
+            _ => return error(path, "Expected a non-empty array"),
+        };
+        let len = path.len();
+        let mut nodes = Vec::new();
+        for (index, schema) in schemas.iter().enumerate() {
+            push_token(path, &index.to_string());
+            nodes.push(self.node(schema, path)?);
+            path.truncate(len);
+        }
+        Ok(nodes)
+    }
+
+    /// Compiles the schemas of an object, returning them with their key.
+    fn members(&mut self, value: &JsonValue<'_>, path: &mut String) -> Result<Vec<(String, Node)>, SchemaError> {
+        let obj = match value {
+            JsonValue::Object(obj) => obj,
+            _ => return error(path, "Expected an object"),
+        };
+        let len = path.len();
+        let mut members = Vec::new();
+        for (key, schema) in obj {
+            push_token(path, key);
+            let node = self.node(schema, path)?;
+            members.push((key.to_string(), node));
+            path.truncate(len);
+        }
+        Ok(members)
+    }
+
+    /// Compiles the schema designated by a `$ref` unless it already was,
+    /// and returns its key.
+    fn reference(&mut self, value: &JsonValue<'_>, path: &str) -> Result<String, SchemaError> {
+        let reference = match value.as_str() {
+            Some(reference) if reference.starts_with('#') => reference,
+            Some(_) => return error(path, "Only references within the schema are supported"),
+            None => return error(path, "Expected a string"),
+        };
+        if !self.references.contains_key(reference) {
+            let pointer = &reference[1..];
+            let document = self.document;
+            let target = match document.pointer(pointer) {
+                Some(target) => target,
+                None => return error(path, "Unresolved reference"),
+            };
+            // Placeholder for recursive references.
+            self.references.insert(reference.to_string(), Node::Always);
+            let node = self.node(target, &mut pointer.to_string())?;
+            self.references.insert(reference.to_string(), node);
+        }
+        Ok(reference.to_string())
+    }
+}
+
+/// Fails when `$ref` and the keywords applying to the value itself lead
+/// from `node` back to a reference of `stack`, which validation would
+/// follow forever. References in `checked` are known not to loop.
+fn find_loop<'n>(
+    references: &'n HashMap<String, Node>,
+    node: &'n Node,
+    path: &mut String,
+    stack: &mut Vec<&'n str>,
+    checked: &mut HashSet<&'n str>,
+) -> Result<(), SchemaError> {
+    let keywords = match node {
+        Node::Keywords(keywords) => keywords,
+        Node::Always | Node::Never => return Ok(()),
+    };
+    let len = path.len();
+    let combinations = [("allOf", &keywords.all_of), ("anyOf", &keywords.any_of), ("oneOf", &keywords.one_of)];
+    for &(keyword, nodes) in combinations.iter() {
+        for (index, node) in nodes.iter().enumerate() {
+            push_token(path, keyword);
+            push_token(path, &index.to_string());
+            find_loop(references, node, path, stack, checked)?;
+            path.truncate(len);
+        }
+    }
+    if let Some(not) = &keywords.not {
+        push_token(path, "not");
+        find_loop(references, not, path, stack, checked)?;
+        path.truncate(len);
+    }
+    if let Some(reference) = &keywords.reference {
+        if stack.contains(&reference.as_str()) {
+            push_token(path, "$ref");
+            return error(path, "Reference loops back to itself");
+        }
+        if checked.insert(reference) {
+            stack.push(reference);
+            find_loop(references, &references[reference], &mut reference[1..].to_string(), stack, checked)?;
+            stack.pop();
+        }
+    }
+    Ok(())
+}
+
+fn error<T>(path: &str, msg: &str) -> Result<T, SchemaError> {
+    Err(SchemaError {
+        path: path.to_string(),
+        msg: msg.to_string(),
+    })
+}
+
+fn number(value: &JsonValue<'_>, path: &str) -> Result<JsonNumber, SchemaError> {
+    match value {
+        JsonValue::Number(n) => Ok(*n),
+        _ => error(path, "Expected a number"),
+    }
+}
+
+/// A non-negative integer.
+fn count(value: &JsonValue<'_>, path: &str) -> Result<usize, SchemaError> {
+    match value.as_u64() {
+        Some(n) => Ok(n as usize),
+        None => error(path, "Expected a non-negative integer"),
+    }
+}
+
+fn pattern(value: &JsonValue<'_>, path: &str) -> Result<Regex, SchemaError> {
+    match value.as_str() {
+        Some(pattern) => Regex::new(pattern).or_else(|e| error(path, &e.to_string())),
+        None => error(path, "Expected a string"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn errors(schema: &str, value: &str) -> Vec<(String, &'static str)> {
+        let schema = Schema::compile(&parse(schema)).unwrap();
+        match schema.validate(&parse(value)) {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|e| (e.path, e.keyword)).collect(),
+        }
+    }
+
+    fn valid(schema: &str, value: &str) -> bool {
+        errors(schema, value).is_empty()
+    }
+
+    #[test]
+    fn types_and_values() {
+        assert!(valid(r#"{"type": "integer"}"#, "1.0"));
+        assert!(!valid(r#"{"type": "integer"}"#, "1.5"));
+        assert!(valid(r#"{"type": ["string", "null"]}"#, "null"));
+        assert!(!valid(r#"{"type": "number"}"#, r#""1""#));
+        assert!(valid(r#"{"enum": [1, "a", [true]]}"#, "1.0"));
+        assert!(!valid(r#"{"enum": [1, "a", [true]]}"#, "[false]"));
+        assert!(valid(r#"{"const": {"a": [1]}}"#, r#"{"a": [1]}"#));
+        assert!(valid("true", "{}") && !valid("false", "{}"));
+    }
+
+    #[test]
+    fn numbers_and_strings() {
+        let schema = r#"{"minimum": 1, "exclusiveMaximum": 10, "multipleOf": 0.5}"#;
+        assert!(valid(schema, "1") && valid(schema, "9.5"));
+        assert_eq!(errors(schema, "0.25"), vec![(String::new(), "minimum"), (String::new(), "multipleOf")]);
+        assert_eq!(errors(schema, "10"), vec![(String::new(), "exclusiveMaximum")]);
+        assert!(valid(r#"{"multipleOf": 3}"#, "9223372036854775806"));
+        assert!(!valid(r#"{"maximum": 9223372036854775807}"#, "18446744073709551615"));
+
+        let schema = r#"{"minLength": 2, "maxLength": 3, "pattern": "^[a-zé]+$"}"#;
+        assert!(valid(schema, r#""éé""#));
+        assert_eq!(errors(schema, r#""a""#), vec![(String::new(), "minLength")]);
+        assert_eq!(errors(schema, r#""ABCD""#), vec![(String::new(), "maxLength"), (String::new(), "pattern")]);
+    }
+
+    #[test]
+    fn arrays_and_objects() {
+        let schema = r#"{"prefixItems": [{"type": "string"}], "items": {"type": "integer"},
+            "minItems": 1, "uniqueItems": true, "contains": {"const": 2}}"#;
+        assert!(valid(schema, r#"["a", 1, 2]"#));
+        assert_eq!(errors(schema, r#"["a", 1, 1.0, "b"]"#), vec![
+            (String::new(), "uniqueItems"),
+            (String::new(), "contains"),
+            ("/3".to_string(), "type"),
+        ]);
+
+        let schema = r#"{"required": ["uid"], "properties": {"uid": {"type": "integer"}},
+            "patternProperties": {"^x-": {"type": "string"}}, "additionalProperties": false, "maxProperties": 3}"#;
+        assert!(valid(schema, r#"{"uid": 1, "x-tag": "a"}"#));
+        assert_eq!(errors(schema, r#"{"x-tag": 1}"#), vec![
+            (String::new(), "required"),
+            ("/x-tag".to_string(), "type"),
+        ]);
+        assert_eq!(errors(schema, r#"{"uid": 1, "a/b": 0}"#), vec![("/a~1b".to_string(), "false")]);
+    }
+
+    #[test]
+    fn combinations_and_references() {
+        let schema = r#"{"anyOf": [{"type": "string"}, {"minimum": 0}], "not": {"const": "no"}}"#;
+        assert!(valid(schema, r#""yes""#) && valid(schema, "1"));
+        assert_eq!(errors(schema, "-1"), vec![(String::new(), "anyOf")]);
+        assert_eq!(errors(schema, r#""no""#), vec![(String::new(), "not")]);
+        assert_eq!(errors(r#"{"oneOf": [{"type": "integer"}, {"minimum": 0}]}"#, "1"), vec![(String::new(), "oneOf")]);
+        assert!(!valid(r#"{"allOf": [{"type": "integer"}, {"minimum": 0}]}"#, "-1"));
+
+        let tree = r##"{"$ref": "#/$defs/node", "$defs": {"node": {
+            "type": "object", "required": ["value"],
+            "properties": {"value": {"type": "integer"}, "children": {"items": {"$ref": "#/$defs/node"}}}}}}"##;
+        assert!(valid(tree, r#"{"value": 1, "children": [{"value": 2, "children": [{"value": 3}]}]}"#));
+        assert_eq!(errors(tree, r#"{"value": 1, "children": [{"children": []}]}"#), vec![(
+            "/children/0".to_string(),
+            "required"
+        )]);
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        let error = |schema| Schema::compile(&parse(schema)).unwrap_err();
+        assert_eq!(error(r#"{"type": "text"}"#).path, "/type");
+        assert_eq!(error(r#"{"properties": {"a": {"minimum": "1"}}}"#).path, "/properties/a/minimum");
+        assert_eq!(error(r#"{"pattern": "("}"#).path, "/pattern");
+        assert_eq!(error(r#"{"patternProperties": {"a/(": {}}}"#).path, "/patternProperties/a~1(");
+        assert_eq!(error(r#"{"$ref": "other.json"}"#).path, "/$ref");
+        assert_eq!(error(r#"{"allOf": []}"#).to_string(), "Invalid schema at '/allOf': Expected a non-empty array");
+        assert_eq!(error("1").path, "");
+        assert_eq!(error(r##"{"$ref": "#"}"##).to_string(), "Invalid schema at '/$ref': Reference loops back to itself");
+        let looping = r##"{"properties": {"a": {"$ref": "#/$defs/a"}}, "$defs": {"a": {"$ref": "#/$defs/a"}}}"##;
+        assert_eq!(error(looping).path, "/$defs/a/$ref");
+        let looping = r##"{"$defs": {"a": {"anyOf": [true, {"$ref": "#/$defs/b"}]}, "b": {"not": {"$ref": "#/$defs/a"}}},
+            "items": {"$ref": "#/$defs/b"}}"##;
+        assert_eq!(error(looping).path, "/$defs/b/not/$ref");
+        assert!(Schema::compile(&json!({"$defs": {"a": {"items": {"$ref": "#/$defs/a"}}}})).is_ok());
+    }
+}
@@ -0,0 +1,722 @@
+use alloc::borrow::Cow;
+use core::fmt::{self, Write};
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::prelude::*;
+
+use crate::escape::write_escaped;
+use crate::{JsonNumber, JsonPointer, JsonValue};
+
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "std")]
+mod writer;
+
+#[cfg(feature = "std")]
+pub use writer::{JsonWriter, WriterError};
+
+/// Adapts an [`io::Write`] to the [`fmt::Write`] the serializers write to,
+/// keeping the I/O error that `fmt::Error` cannot carry.
+#[cfg(feature = "std")]
+pub(crate) struct IoWriter<W> {
+    inner: W,
+    pub(crate) error: Option<io::Error>,
+}
+#[cfg(feature = "std")]
+impl<W: io::Write> IoWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Turns the outcome of formatting into the I/O error that caused it.
+    fn finish(self, result: fmt::Result) -> io::Result<()> {
+        match (result, self.error) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(e)) => Err(e),
+            (Err(fmt::Error), None) => Err(io::Error::other("formatter error")),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+pub(crate) fn write_number<W: Write>(out: &mut W, number: &JsonNumber) -> fmt::Result {
+    match number {
+        JsonNumber::Integer(n) => write!(out, "{}", n),
+        JsonNumber::Unsigned(n) => write!(out, "{}", n),
+        // `Debug` writes the shortest digits that read back as the same float
+        // and keeps a fraction or an exponent so the number is read back as
+        // a float, like the default `FloatFormat`. JSON has no
+        // representation for NaN and infinities.
+        JsonNumber::Float(f) if f.is_finite() => write!(out, "{:?}", f),
+        JsonNumber::Float(_) => out.write_str("null"),
+    }
+}
+
+/// How [`to_string_pretty`] writes floats. The default writes the shortest
+/// digits that read back as the same float, as [`to_string`] always does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FloatFormat {
+    /// Round to this many digits after the decimal point instead, which
+    /// loses precision. With `Some(0)` floats read back as integers.
+    pub decimal_places: Option<usize>,
+    /// Floats `d.ddd × 10^e` with an exponent `e` below this are written in
+    /// scientific notation, e.g. `1e-7`.
+    pub scientific_below: i32,
+    /// Floats with an exponent above this are written in scientific
+    /// notation, e.g. `1e16`.
+    pub scientific_above: i32,
+}
+impl Default for FloatFormat {
+    fn default() -> Self {
+        Self {
+            decimal_places: None,
+            scientific_below: -4,
+            scientific_above: 15,
+        }
+    }
+}
+
+/// What [`to_string_pretty`] writes for the NaN and infinite floats that
+/// JSON cannot represent. [`to_string`] always writes `null`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonFinite {
+    /// `null`, which reads back as a different value.
+    Null,
+    /// `NaN`, `Infinity` and `-Infinity` as in JSON5 and JavaScript, read
+    /// back with [`Parser::allow_non_finite`](crate::Parser::allow_non_finite).
+    Literal,
+    /// Fail with a [`NonFiniteError`], see [`try_to_string_pretty`].
+    Error,
+}
+
+/// A non-finite float met with [`NonFinite::Error`] or by
+/// [`try_to_canonical_string`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFiniteError {
+    /// Where the float is in the value.
+    pub pointer: JsonPointer,
+}
+impl fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Non-finite float at '{}'", self.pointer)
+    }
+}
+impl core::error::Error for NonFiniteError {}
+
+/// Fails on the first non-finite float of `value` with [`NonFinite::Error`].
+fn check_finite(value: &JsonValue, non_finite: NonFinite) -> Result<(), NonFiniteError> {
+    if non_finite != NonFinite::Error {
+        return Ok(());
+    }
+    match value
+        .walk()
+        .find(|(_, value)| matches!(value, JsonValue::Number(JsonNumber::Float(f)) if !f.is_finite()))
+    {
+        Some((pointer, _)) => Err(NonFiniteError { pointer }),
+        None => Ok(()),
+    }
+}
+
+fn write_float<W: Write>(out: &mut W, f: f64, config: &PrettyConfig) -> fmt::Result {
+    match config.non_finite {
+        _ if f.is_finite() => {}
+        NonFinite::Literal if f.is_nan() => return out.write_str("NaN"),
+        NonFinite::Literal if f > 0.0 => return out.write_str("Infinity"),
+        NonFinite::Literal => return out.write_str("-Infinity"),
+        // Rejected by `check_finite` before writing anything.
+        NonFinite::Null | NonFinite::Error => return out.write_str("null"),
+    }
+    let format = &config.float_format;
+    if let Some(places) = format.decimal_places {
+        return write!(out, "{:.*}", places, f);
+    }
+    // `{:e}` gives the shortest digits `d.ddd` and the exponent.
+    let scientific = format!("{:e}", f);
+    let (mantissa, exponent) = scientific.split_once('e').expect("`{:e}` has an exponent");
+    let exponent = exponent.parse::<i32>().expect("`{:e}` has an integer exponent");
+    if exponent < format.scientific_below || exponent > format.scientific_above {
+        return write!(out, "{}e{}", mantissa, exponent);
+    }
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+    // The decimal point goes after the `point`-th digit.
+    let point = exponent + 1;
+    out.write_str(sign)?;
+    if point <= 0 {
+        out.write_str("0.")?;
+        (point..0).try_for_each(|_| out.write_char('0'))?;
+        out.write_str(&digits)
+    } else if point as usize >= digits.len() {
+        out.write_str(&digits)?;
+        (digits.len()..point as usize).try_for_each(|_| out.write_char('0'))?;
+        out.write_str(".0")
+    } else {
+        let (int, fraction) = digits.split_at(point as usize);
+        write!(out, "{}.{}", int, fraction)
+    }
+}
+
+pub(crate) fn write_compact<W: Write>(out: &mut W, value: &JsonValue) -> fmt::Result {
+    write_minified(out, value, false)
+}
+
+fn write_minified<W: Write>(out: &mut W, value: &JsonValue, sort_keys: bool) -> fmt::Result {
+    match value {
+        JsonValue::String(s) => write_escaped(out, s),
+        JsonValue::Number(n) => write_number(out, n),
+        JsonValue::Boolean(true) => out.write_str("true"),
+        JsonValue::Boolean(false) => out.write_str("false"),
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Array(array) => {
+            out.write_char('[')?;
+            for (idx, item) in array.iter().enumerate() {
+                if idx > 0 {
+                    out.write_char(',')?;
+                }
+                write_minified(out, item, sort_keys)?;
+            }
+            out.write_char(']')
+        }
+        JsonValue::Object(obj) if sort_keys => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+            write_members(out, members, sort_keys)
+        }
+        JsonValue::Object(obj) => write_members(out, obj, sort_keys),
+    }
+}
+
+fn write_members<'m, 'a: 'm, W: Write>(
+    out: &mut W,
+    members: impl IntoIterator<Item = (&'m Cow<'a, str>, &'m JsonValue<'a>)>,
+    sort_keys: bool,
+) -> fmt::Result {
+    out.write_char('{')?;
+    for (idx, (key, item)) in members.into_iter().enumerate() {
+        if idx > 0 {
+            out.write_char(',')?;
+        }
+        write_escaped(out, key)?;
+        out.write_char(':')?;
+        write_minified(out, item, sort_keys)?;
+    }
+    out.write_char('}')
+}
+
+/// Serializes `value` as compact JSON text, without any whitespace.
+pub fn to_string(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_compact(&mut out, value).expect("writing to a String cannot fail");
+    out
+}
+
+/// Same as [`to_string`] with the members of objects sorted by key, so
+/// that the text does not depend on the order of the maps.
+///
+/// ```
+/// let value = yaj::json!({"b": [{"d": 1, "c": 2}], "a": null});
+/// assert_eq!(yaj::to_string_sorted(&value), r#"{"a":null,"b":[{"c":2,"d":1}]}"#);
+/// ```
+pub fn to_string_sorted(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_minified(&mut out, value, true).expect("writing to a String cannot fail");
+    out
+}
+
+/// Writes `value` as compact JSON text into `writer` as it is produced.
+/// Small writes are issued, so unbuffered writers are best wrapped in an
+/// [`io::BufWriter`].
+#[cfg(feature = "std")]
+pub fn to_writer<W: io::Write>(writer: W, value: &JsonValue) -> io::Result<()> {
+    let mut out = IoWriter::new(writer);
+    let result = write_compact(&mut out, value);
+    out.finish(result)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Newline {
+    Lf,
+    CrLf,
+    /// Keep the whole document on a single line.
+    None,
+}
+impl Newline {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::None => "",
+        }
+    }
+}
+
+/// Layout of the output of [`to_string_pretty`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrettyConfig {
+    /// Character repeated `indent_width` times per nesting level.
+    pub indent_char: char,
+    pub indent_width: usize,
+    pub newline: Newline,
+    pub space_after_colon: bool,
+    /// Only relevant with [`Newline::None`], otherwise commas are followed by
+    /// a line break.
+    pub space_after_comma: bool,
+    /// Write the members of objects sorted by key rather than in the order
+    /// of the map, for output that does not change from one run to the next.
+    pub sort_keys: bool,
+    pub float_format: FloatFormat,
+    pub non_finite: NonFinite,
+}
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        Self {
+            indent_char: ' ',
+            indent_width: 2,
+            newline: Newline::Lf,
+            space_after_colon: true,
+            space_after_comma: true,
+            sort_keys: false,
+            float_format: FloatFormat::default(),
+            non_finite: NonFinite::Null,
+        }
+    }
+}
+
+pub(crate) struct PrettyWriter<'c, W> {
+    out: W,
+    config: &'c PrettyConfig,
+    depth: usize,
+}
+impl<'c, W: Write> PrettyWriter<'c, W> {
+    pub(crate) fn new(out: W, config: &'c PrettyConfig) -> Self {
+        Self { out, config, depth: 0 }
+    }
+
+    fn line_break(&mut self) -> fmt::Result {
+        if self.config.newline == Newline::None {
+            return Ok(());
+        }
+        self.out.write_str(self.config.newline.as_str())?;
+        for _ in 0..(self.depth * self.config.indent_width) {
+            self.out.write_char(self.config.indent_char)?;
+        }
+        Ok(())
+    }
+
+    fn separator(&mut self) -> fmt::Result {
+        self.out.write_char(',')?;
+        if self.config.newline == Newline::None && self.config.space_after_comma {
+            self.out.write_char(' ')?;
+        }
+        self.line_break()
+    }
+
+    pub(crate) fn write_value(&mut self, value: &JsonValue) -> fmt::Result {
+        match value {
+            JsonValue::Array(array) if !array.is_empty() => {
+                self.out.write_char('[')?;
+                self.depth += 1;
+                for (idx, item) in array.iter().enumerate() {
+                    if idx > 0 {
+                        self.separator()?;
+                    } else {
+                        self.line_break()?;
+                    }
+                    self.write_value(item)?;
+                }
+                self.depth -= 1;
+                self.line_break()?;
+                self.out.write_char(']')
+            }
+            JsonValue::Object(obj) if !obj.is_empty() => {
+                self.out.write_char('{')?;
+                self.depth += 1;
+                let mut members: Vec<_> = obj.iter().collect();
+                if self.config.sort_keys {
+                    members.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                }
+                for (idx, (key, item)) in members.into_iter().enumerate() {
+                    if idx > 0 {
+                        self.separator()?;
+                    } else {
+                        self.line_break()?;
+                    }
+                    write_escaped(&mut self.out, key)?;
+                    self.out.write_char(':')?;
+                    if self.config.space_after_colon {
+                        self.out.write_char(' ')?;
+                    }
+                    self.write_value(item)?;
+                }
+                self.depth -= 1;
+                self.line_break()?;
+                self.out.write_char('}')
+            }
+            JsonValue::Number(JsonNumber::Float(f)) => write_float(&mut self.out, *f, self.config),
+            scalar_or_empty => write_compact(&mut self.out, scalar_or_empty),
+        }
+    }
+}
+
+/// Serializes `value` as indented JSON text laid out according to `config`.
+///
+/// # Panics
+///
+/// With [`NonFinite::Error`] when `value` contains a non-finite float, see
+/// [`try_to_string_pretty`] instead.
+pub fn to_string_pretty(value: &JsonValue, config: PrettyConfig) -> String {
+    try_to_string_pretty(value, config).expect("non-finite float with `NonFinite::Error`")
+}
+
+/// Same as [`to_string_pretty`], failing on non-finite floats with
+/// [`NonFinite::Error`].
+pub fn try_to_string_pretty(value: &JsonValue, config: PrettyConfig) -> Result<String, NonFiniteError> {
+    check_finite(value, config.non_finite)?;
+    let mut out = String::new();
+    PrettyWriter::new(&mut out, &config)
+        .write_value(value)
+        .expect("writing to a String cannot fail");
+    Ok(out)
+}
+
+/// Writes `value` as indented JSON text into `writer`, see [`to_writer`].
+/// A non-finite float with [`NonFinite::Error`] is an
+/// [`io::ErrorKind::InvalidData`] error, before anything is written.
+#[cfg(feature = "std")]
+pub fn to_writer_pretty<W: io::Write>(writer: W, value: &JsonValue, config: PrettyConfig) -> io::Result<()> {
+    check_finite(value, config.non_finite).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut out = IoWriter::new(writer);
+    let result = PrettyWriter::new(&mut out, &config).write_value(value);
+    out.finish(result)
+}
+
+/// Compact JSON text as with [`to_string`], or with `{:#}` indented JSON
+/// text as with the default [`PrettyConfig`].
+///
+/// ```
+/// let value = yaj::json!({"tags": ["a"]});
+/// assert_eq!(format!("{}", value), r#"{"tags":["a"]}"#);
+/// assert_eq!(format!("{:#}", value), "{\n  \"tags\": [\n    \"a\"\n  ]\n}");
+/// ```
+impl fmt::Display for JsonValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            PrettyWriter::new(f, &PrettyConfig::default()).write_value(self)
+        } else {
+            write_compact(f, self)
+        }
+    }
+}
+
+/// Writes a number as ECMAScript's `Number.prototype.toString` does, from
+/// the shortest digits that read back as the same float.
+fn write_canonical_number<W: Write>(out: &mut W, number: &JsonNumber) -> fmt::Result {
+    let f = match *number {
+        JsonNumber::Integer(n) => n as f64,
+        JsonNumber::Unsigned(n) => n as f64,
+        JsonNumber::Float(f) if f.is_finite() => f,
+        JsonNumber::Float(_) => return out.write_str("null"),
+    };
+    if f == 0.0 {
+        return out.write_char('0');
+    }
+    if f < 0.0 {
+        out.write_char('-')?;
+    }
+    // `d.ddde-x`, the digits being `s` and the decimal point after the
+    // `n`-th one.
+    let scientific = format!("{:e}", f.abs());
+    let (mantissa, exponent) = scientific.split_once('e').expect("`{:e}` has an exponent");
+    let s = mantissa.replace('.', "");
+    let k = s.len() as i32;
+    let n = exponent.parse::<i32>().expect("`{:e}` has an integer exponent") + 1;
+    if k <= n && n <= 21 {
+        out.write_str(&s)?;
+        (k..n).try_for_each(|_| out.write_char('0'))
+    } else if 0 < n && n <= 21 {
+        write!(out, "{}.{}", &s[..n as usize], &s[n as usize..])
+    } else if -6 < n && n <= 0 {
+        out.write_str("0.")?;
+        (n..0).try_for_each(|_| out.write_char('0'))?;
+        out.write_str(&s)
+    } else {
+        out.write_str(&s[..1])?;
+        if k > 1 {
+            write!(out, ".{}", &s[1..])?;
+        }
+        write!(out, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs())
+    }
+}
+
+fn write_canonical<W: Write>(out: &mut W, value: &JsonValue) -> fmt::Result {
+    match value {
+        JsonValue::Number(n) => write_canonical_number(out, n),
+        JsonValue::Array(array) => {
+            out.write_char('[')?;
+            for (idx, item) in array.iter().enumerate() {
+                if idx > 0 {
+                    out.write_char(',')?;
+                }
+                write_canonical(out, item)?;
+            }
+            out.write_char(']')
+        }
+        JsonValue::Object(obj) => {
+            let mut members: Vec<_> = obj.iter().collect();
+            members.sort_unstable_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.write_char('{')?;
+            for (idx, (key, item)) in members.into_iter().enumerate() {
+                if idx > 0 {
+                    out.write_char(',')?;
+                }
+                write_escaped(out, key)?;
+                out.write_char(':')?;
+                write_canonical(out, item)?;
+            }
+            out.write_char('}')
+        }
+        scalar => write_compact(out, scalar),
+    }
+}
+
+/// Serializes `value` following the RFC 8785 JSON Canonicalization Scheme,
+/// so that equal documents give the same bytes to hash or sign: no
+/// whitespace, members sorted by the UTF-16 code units of their keys, and
+/// numbers written as ECMAScript does.
+///
+/// Numbers are converted to floats first, so integers beyond 2^53 may lose
+/// precision. RFC 8785 has no representation for non-finite floats: they
+/// become `null` as with [`to_string`], see [`try_to_canonical_string`] to
+/// reject them as the RFC requires.
+pub fn to_canonical_string(value: &JsonValue) -> String {
+    let mut out = String::new();
+    write_canonical(&mut out, value).expect("writing to a String cannot fail");
+    out
+}
+
+/// Same as [`to_canonical_string`], failing on the first non-finite float
+/// instead of writing `null`.
+pub fn try_to_canonical_string(value: &JsonValue) -> Result<String, NonFiniteError> {
+    check_finite(value, NonFinite::Error)?;
+    Ok(to_canonical_string(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn compact_output() {
+        let value = parse(" [ 1 , -2.5 , 1e100, true, null, \"a\\u0000\\\"b\", [ ], { \"k\" : { } } ] ");
+        assert_eq!(
+            to_string(&value),
+            r#"[1,-2.5,1e100,true,null,"a\u0000\"b",[],{"k":{}}]"#
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = r#"{"name":"café 😀","values":[0.1,3.0,-7,{"x":null}],"ok":false}"#;
+        let value = parse(source);
+        assert_eq!(parse(&to_string(&value)), value);
+    }
+
+    #[test]
+    fn pretty_output() {
+        let value = parse(r#"{"a": [1, {"b": null}, []], "c": {}}"#);
+        let pretty = to_string_pretty(&value, PrettyConfig::default());
+        assert_eq!(parse(&pretty), value);
+        let value = parse(r#"[1, {"b": [true, "x"]}, []]"#);
+        assert_eq!(
+            to_string_pretty(&value, PrettyConfig::default()),
+            "[\n  1,\n  {\n    \"b\": [\n      true,\n      \"x\"\n    ]\n  },\n  []\n]"
+        );
+    }
+
+    #[test]
+    fn pretty_config() {
+        let value = parse(r#"[1, {"b": 2}]"#);
+        let tabs = PrettyConfig {
+            indent_char: '\t',
+            indent_width: 1,
+            newline: Newline::CrLf,
+            space_after_colon: false,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(to_string_pretty(&value, tabs), "[\r\n\t1,\r\n\t{\r\n\t\t\"b\":2\r\n\t}\r\n]");
+        let single_line = PrettyConfig {
+            newline: Newline::None,
+            ..PrettyConfig::default()
+        };
+        assert_eq!(to_string_pretty(&value, single_line), r#"[1, {"b": 2}]"#);
+        let sorted = PrettyConfig {
+            newline: Newline::None,
+            space_after_colon: false,
+            space_after_comma: false,
+            sort_keys: true,
+            ..PrettyConfig::default()
+        };
+        let value = parse(r#"{"z": 1, "b": {"y": [{"d": 0, "c": 0}], "x": 2}, "a": 3}"#);
+        assert_eq!(
+            to_string_pretty(&value, sorted),
+            r#"{"a":3,"b":{"x":2,"y":[{"c":0,"d":0}]},"z":1}"#
+        );
+    }
+
+    #[test]
+    fn display() {
+        let value = parse(r#"[1.5, {"b": [null, "\u00e9\n"]}, []]"#);
+        assert_eq!(value.to_string(), to_string(&value));
+        assert_eq!(format!("{:#}", value), to_string_pretty(&value, PrettyConfig::default()));
+        assert_eq!(format!("<{}>", JsonValue::from("x")), r#"<"x">"#);
+    }
+
+    #[test]
+    fn float_format() {
+        let floats = [0.1, -2.5, 3.0, -0.0, 1e-4, 1.5e-5, 123456789.125, 1e15, 1e16, 1e100, 5e-324, f64::MAX];
+        let value = JsonValue::Array(floats.iter().map(|&f| JsonValue::from(f)).collect());
+        let single_line = PrettyConfig {
+            newline: Newline::None,
+            space_after_comma: false,
+            ..PrettyConfig::default()
+        };
+        let shortest = to_string_pretty(&value, single_line.clone());
+        assert_eq!(shortest, to_string(&value));
+        assert_eq!(parse(&shortest), value);
+
+        let scientific = PrettyConfig {
+            float_format: FloatFormat {
+                scientific_below: -2,
+                scientific_above: 2,
+                ..FloatFormat::default()
+            },
+            ..single_line.clone()
+        };
+        assert_eq!(
+            to_string_pretty(&value, scientific),
+            "[0.1,-2.5,3.0,-0.0,1e-4,1.5e-5,1.23456789125e8,1e15,1e16,1e100,5e-324,1.7976931348623157e308]"
+        );
+        let fixed = PrettyConfig {
+            float_format: FloatFormat {
+                decimal_places: Some(2),
+                ..FloatFormat::default()
+            },
+            ..single_line
+        };
+        let value = json!([0.125, -1.0, 2.675, 7, {"nan": (f64::NAN)}]);
+        assert_eq!(to_string_pretty(&value, fixed), r#"[0.12,-1.00,2.67,7,{"nan": null}]"#);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn writers() {
+        let value = parse(r#"[1, {"b": "\u00e9"}]"#);
+        let mut out = Vec::new();
+        to_writer(&mut out, &value).unwrap();
+        assert_eq!(out, to_string(&value).as_bytes());
+        let mut out = Vec::new();
+        to_writer_pretty(&mut out, &value, PrettyConfig::default()).unwrap();
+        assert_eq!(out, to_string_pretty(&value, PrettyConfig::default()).as_bytes());
+
+        struct Full;
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::WriteZero, "disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let error = to_writer(Full, &value).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn canonical_output() {
+        let value = parse(
+            r#"{"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000001, 1e-7, -0.0, 100, 1e21, 123e20],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/", "literals": [null, true, false]}"#,
+        );
+        assert_eq!(
+            to_canonical_string(&value),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,0.000001,1e-7,0,100,1e+21,1.23e+22],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+        let keys = json!({"€": 1, "\r": 2, "😀": 3, "1": 4, "\u{80}": 5, "ö": 6, "\u{ff61}": 7});
+        assert_eq!(
+            to_canonical_string(&keys),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":5,\"ö\":6,\"€\":1,\"😀\":3,\"\u{ff61}\":7}"
+        );
+        assert_eq!(to_canonical_string(&json!(9007199254740993i64)), "9007199254740992");
+        assert_eq!(to_canonical_string(&json!(-1.5e-9)), "-1.5e-9");
+        assert_eq!(try_to_canonical_string(&keys).unwrap(), to_canonical_string(&keys));
+        let non_finite = json!({"a": [1, f64::NAN]});
+        assert_eq!(to_canonical_string(&non_finite), r#"{"a":[1,null]}"#);
+        let error = try_to_canonical_string(&non_finite).unwrap_err();
+        assert_eq!(error.to_string(), "Non-finite float at '/a/1'");
+    }
+
+    #[test]
+    fn non_finite_floats_become_null() {
+        assert_eq!(to_string(&JsonValue::Number(JsonNumber::Float(f64::NAN))), "null");
+    }
+
+    #[test]
+    fn non_finite_policy() {
+        let value = json!({"ok": 1.5, "readings": [(f64::NAN), (f64::INFINITY), (f64::NEG_INFINITY)]});
+        let config = |non_finite| PrettyConfig {
+            newline: Newline::None,
+            sort_keys: true,
+            non_finite,
+            ..PrettyConfig::default()
+        };
+        let value = JsonValue::Array(vec![value]);
+        assert_eq!(
+            to_string_pretty(&value, config(NonFinite::Null)),
+            r#"[{"ok": 1.5, "readings": [null, null, null]}]"#
+        );
+        let literals = to_string_pretty(&value, config(NonFinite::Literal));
+        assert_eq!(literals, r#"[{"ok": 1.5, "readings": [NaN, Infinity, -Infinity]}]"#);
+        let back = crate::Parser::new().allow_non_finite(true).parse(&literals).unwrap();
+        assert!(back[0]["readings"][0].as_f64().unwrap().is_nan());
+        assert_eq!(back[0]["readings"][1], JsonValue::from(f64::INFINITY));
+        assert_eq!(back[0]["readings"][2], JsonValue::from(f64::NEG_INFINITY));
+        assert!(crate::try_parse(&literals).is_err());
+
+        let error = try_to_string_pretty(&value, config(NonFinite::Error)).unwrap_err();
+        assert_eq!(error.to_string(), "Non-finite float at '/0/readings/0'");
+        assert!(try_to_string_pretty(&json!([1.5]), config(NonFinite::Error)).is_ok());
+        #[cfg(feature = "std")]
+        {
+            let mut out = Vec::new();
+            let error = to_writer_pretty(&mut out, &value, config(NonFinite::Error)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(out.is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "preserve_order")]
+    fn member_order_is_preserved() {
+        let source = r#"{"z":1,"a":{"y":2,"b":3},"m":[]}"#;
+        assert_eq!(to_string(&parse(source)), source);
+        let mut value = parse(source);
+        value.remove("z");
+        value.insert("c", 4);
+        assert_eq!(to_string(&value), r#"{"a":{"y":2,"b":3},"m":[],"c":4}"#);
+        value.sort_keys_recursive();
+        assert_eq!(to_string(&value), r#"{"a":{"b":3,"y":2},"c":4,"m":[]}"#);
+    }
+}
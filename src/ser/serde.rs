@@ -0,0 +1,570 @@
+//! serde support: serialize any `T: Serialize` as compact JSON text.
+//!
+//! The crate root `to_string` only accepts a [`JsonValue`](crate::JsonValue),
+//! these work for arbitrary types. They are re-exported at the crate root
+//! as [`to_string_serde`](crate::to_string_serde) and
+//! [`to_writer_serde`](crate::to_writer_serde).
+//!
+//! ```
+//! assert_eq!(yaj::to_string_serde(&(1, "a", [true])).unwrap(), r#"[1,"a",[true]]"#);
+//! ```
+
+use core::fmt::{self, Write};
+use std::io;
+
+use serde::ser::{self, Serialize};
+
+use super::{write_number, IoWriter};
+use crate::escape::write_escaped;
+use crate::{JsonError, JsonNumber};
+
+impl ser::Error for JsonError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        JsonError::Custom(msg.to_string())
+    }
+}
+
+impl From<fmt::Error> for JsonError {
+    fn from(_: fmt::Error) -> Self {
+        JsonError::Custom("Failed to write JSON output".to_string())
+    }
+}
+
+pub struct Serializer<W> {
+    out: W,
+}
+
+impl<W: Write> Serializer<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Serializes `value` as compact JSON text.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, JsonError> {
+    let mut serializer = Serializer::new(String::new());
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner())
+}
+
+/// Serializes `value` as compact JSON text into `writer`.
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<(), JsonError> {
+    let mut serializer = Serializer::new(IoWriter::new(writer));
+    let result = value.serialize(&mut serializer);
+    match serializer.out.error.take() {
+        Some(e) => Err(e.into()),
+        None => result,
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = JsonError;
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = Compound<'a, W>;
+    type SerializeTupleVariant = Compound<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), JsonError> {
+        Ok(self.out.write_str(if v { "true" } else { "false" })?)
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), JsonError> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), JsonError> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), JsonError> {
+        self.serialize_i64(v.into())
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+        Ok(write!(self.out, "{}", v)?)
+    }
+    fn serialize_i128(self, v: i128) -> Result<(), JsonError> {
+        Ok(write!(self.out, "{}", v)?)
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), JsonError> {
+        self.serialize_u64(v.into())
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), JsonError> {
+        self.serialize_u64(v.into())
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), JsonError> {
+        self.serialize_u64(v.into())
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+        Ok(write!(self.out, "{}", v)?)
+    }
+    fn serialize_u128(self, v: u128) -> Result<(), JsonError> {
+        Ok(write!(self.out, "{}", v)?)
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), JsonError> {
+        self.serialize_f64(v.into())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), JsonError> {
+        Ok(write_number(&mut self.out, &JsonNumber::Float(v))?)
+    }
+    fn serialize_char(self, v: char) -> Result<(), JsonError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+    fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+        Ok(write_escaped(&mut self.out, v)?)
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), JsonError> {
+        use ser::SerializeSeq;
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+    fn serialize_none(self) -> Result<(), JsonError> {
+        self.serialize_unit()
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), JsonError> {
+        Ok(self.out.write_str("null")?)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), JsonError> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.out.write_char('{')?;
+        write_escaped(&mut self.out, variant)?;
+        self.out.write_char(':')?;
+        value.serialize(&mut *self)?;
+        Ok(self.out.write_char('}')?)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a, W>, JsonError> {
+        self.out.write_char('[')?;
+        Ok(Compound::new(self, false))
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, W>, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, W>, JsonError> {
+        self.out.write_char('{')?;
+        write_escaped(&mut self.out, variant)?;
+        self.out.write_str(":[")?;
+        Ok(Compound::new(self, true))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a, W>, JsonError> {
+        self.out.write_char('{')?;
+        Ok(Compound::new(self, false))
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a, W>, JsonError> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, W>, JsonError> {
+        self.out.write_char('{')?;
+        write_escaped(&mut self.out, variant)?;
+        self.out.write_str(":{")?;
+        Ok(Compound::new(self, true))
+    }
+}
+
+/// State of an array or object being serialized.
+pub struct Compound<'a, W> {
+    ser: &'a mut Serializer<W>,
+    first: bool,
+    /// Whether the compound is wrapped in a `{"Variant": ...}` object.
+    variant: bool,
+}
+
+impl<'a, W: Write> Compound<'a, W> {
+    fn new(ser: &'a mut Serializer<W>, variant: bool) -> Self {
+        Self {
+            ser,
+            first: true,
+            variant,
+        }
+    }
+
+    fn separator(&mut self) -> Result<(), JsonError> {
+        if !self.first {
+            self.ser.out.write_char(',')?;
+        }
+        self.first = false;
+        Ok(())
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.separator()?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), JsonError> {
+        self.separator()?;
+        write_escaped(&mut self.ser.out, key)?;
+        self.ser.out.write_char(':')?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn close(self, end: char) -> Result<(), JsonError> {
+        self.ser.out.write_char(end)?;
+        if self.variant {
+            self.ser.out.write_char('}')?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close(']')
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTuple for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close(']')
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close(']')
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleVariant for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close(']')
+    }
+}
+
+impl<'a, W: Write> ser::SerializeMap for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JsonError> {
+        self.separator()?;
+        key.serialize(KeySerializer {
+            out: &mut self.ser.out,
+        })?;
+        Ok(self.ser.out.write_char(':')?)
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        value.serialize(&mut *self.ser)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close('}')
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close('}')
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStructVariant for Compound<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.close('}')
+    }
+}
+
+/// Object keys must be strings: strings, chars and integers are accepted,
+/// integers being quoted.
+struct KeySerializer<'a, W> {
+    out: &'a mut W,
+}
+
+impl<'a, W: Write> KeySerializer<'a, W> {
+    fn quoted<T: fmt::Display>(self, v: T) -> Result<(), JsonError> {
+        Ok(write!(self.out, "\"{}\"", v)?)
+    }
+}
+
+fn key_must_be_a_string() -> JsonError {
+    JsonError::Custom("Object key must be a string".to_string())
+}
+
+impl<'a, W: Write> ser::Serializer for KeySerializer<'a, W> {
+    type Ok = ();
+    type Error = JsonError;
+    type SerializeSeq = ser::Impossible<(), JsonError>;
+    type SerializeTuple = ser::Impossible<(), JsonError>;
+    type SerializeTupleStruct = ser::Impossible<(), JsonError>;
+    type SerializeTupleVariant = ser::Impossible<(), JsonError>;
+    type SerializeMap = ser::Impossible<(), JsonError>;
+    type SerializeStruct = ser::Impossible<(), JsonError>;
+    type SerializeStructVariant = ser::Impossible<(), JsonError>;
+
+    fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+        Ok(write_escaped(self.out, v)?)
+    }
+    fn serialize_char(self, v: char) -> Result<(), JsonError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_i128(self, v: i128) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_u128(self, v: u128) -> Result<(), JsonError> {
+        self.quoted(v)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), JsonError> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_f32(self, _v: f32) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_f64(self, _v: f64) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_none(self) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_unit(self) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, JsonError> {
+        Err(key_must_be_a_string())
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, JsonError> {
+        Err(key_must_be_a_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use alloc::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Face<'a> {
+        uid: u32,
+        face_rect: (f64, f64),
+        label: &'a str,
+        note: Option<String>,
+        kinds: Vec<Kind>,
+    }
+
+    #[derive(Serialize)]
+    enum Kind {
+        Face,
+        Body { tracked: bool },
+        Other(String),
+        Pair(u8, u8),
+    }
+
+    #[test]
+    fn serialize_struct() {
+        let face = Face {
+            uid: 7,
+            face_rect: (0.5, 1.0),
+            label: "a\"b",
+            note: None,
+            kinds: vec![
+                Kind::Face,
+                Kind::Body { tracked: true },
+                Kind::Other("x".to_string()),
+                Kind::Pair(1, 2),
+            ],
+        };
+        assert_eq!(
+            to_string(&face).unwrap(),
+            r#"{"uid":7,"faceRect":[0.5,1.0],"label":"a\"b","note":null,"kinds":["Face",{"Body":{"tracked":true}},{"Other":"x"},{"Pair":[1,2]}]}"#
+        );
+    }
+
+    #[test]
+    fn serialize_maps() {
+        let mut map = BTreeMap::new();
+        map.insert(1, vec![u64::MAX]);
+        map.insert(2, vec![]);
+        assert_eq!(to_string(&map).unwrap(), r#"{"1":[18446744073709551615],"2":[]}"#);
+
+        let mut bad = BTreeMap::new();
+        bad.insert(vec![1], 1);
+        assert!(matches!(to_string(&bad), Err(JsonError::Custom(_))));
+    }
+
+    #[test]
+    fn serialize_to_writer() {
+        let mut out = Vec::new();
+        to_writer(&mut out, &[Some(1.5), None]).unwrap();
+        assert_eq!(out, b"[1.5,null]");
+    }
+}
@@ -0,0 +1,241 @@
+//! Push-style output of a document, one token at a time, for documents too
+//! large to be built as a [`JsonValue`] first.
+
+use core::fmt;
+use std::io;
+
+use super::{write_compact, IoWriter};
+use crate::escape::write_escaped;
+use crate::JsonValue;
+
+/// A call to [`JsonWriter`] failed.
+#[derive(Debug)]
+pub enum WriterError {
+    /// Writing to the underlying writer failed.
+    Io(io::Error),
+    /// The call would make the output invalid JSON, e.g. a value where a
+    /// key is expected. Nothing was written.
+    Invalid(&'static str),
+}
+impl fmt::Display for WriterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+impl core::error::Error for WriterError {}
+impl From<io::Error> for WriterError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Container open in a [`JsonWriter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Frame {
+    Array { first: bool },
+    Object { first: bool, after_key: bool },
+}
+
+/// Writes compact JSON text into an [`io::Write`] as the document is
+/// described, checking that the calls make up a single valid document.
+///
+/// ```
+/// use yaj::JsonWriter;
+/// let mut writer = JsonWriter::new(Vec::new());
+/// writer.begin_object()?;
+/// writer.key("camera")?;
+/// writer.begin_array()?;
+/// writer.value(8080)?;
+/// writer.value("main")?;
+/// writer.end_array()?;
+/// writer.end_object()?;
+/// assert_eq!(writer.finish()?, br#"{"camera":[8080,"main"]}"#);
+/// # Ok::<(), yaj::WriterError>(())
+/// ```
+pub struct JsonWriter<W: io::Write> {
+    out: IoWriter<W>,
+    stack: Vec<Frame>,
+    /// The top-level value is complete.
+    done: bool,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    /// Small writes are issued, so unbuffered writers are best wrapped in
+    /// an [`io::BufWriter`].
+    pub fn new(writer: W) -> Self {
+        Self {
+            out: IoWriter::new(writer),
+            stack: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Runs `write`, turning a formatting error into the I/O error that
+    /// caused it.
+    fn write<F: FnOnce(&mut IoWriter<W>) -> fmt::Result>(&mut self, write: F) -> Result<(), WriterError> {
+        write(&mut self.out).map_err(|_| match self.out.error.take() {
+            Some(e) => WriterError::Io(e),
+            None => WriterError::Io(io::Error::other("formatter error")),
+        })
+    }
+
+    /// Checks that a value may come next and writes the comma before it.
+    fn before_value(&mut self) -> Result<(), WriterError> {
+        let separator = match self.stack.last_mut() {
+            None if self.done => return Err(WriterError::Invalid("The document is already complete")),
+            None => None,
+            Some(Frame::Array { first }) => Some(!core::mem::replace(first, false)),
+            Some(Frame::Object { after_key: false, .. }) => {
+                return Err(WriterError::Invalid("Expected a key in an object"))
+            }
+            Some(Frame::Object { after_key, .. }) => {
+                *after_key = false;
+                None
+            }
+        };
+        if separator == Some(true) {
+            self.write(|out| fmt::Write::write_char(out, ','))?;
+        }
+        Ok(())
+    }
+
+    /// Marks the top-level value as complete when no container is open.
+    fn after_value(&mut self) {
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.stack.push(Frame::Object {
+            first: true,
+            after_key: false,
+        });
+        self.write(|out| fmt::Write::write_char(out, '{'))
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(Frame::Object { after_key: false, .. }) => {}
+            Some(Frame::Object { .. }) => return Err(WriterError::Invalid("Expected a value after a key")),
+            _ => return Err(WriterError::Invalid("No object to end")),
+        }
+        self.stack.pop();
+        self.after_value();
+        self.write(|out| fmt::Write::write_char(out, '}'))
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.stack.push(Frame::Array { first: true });
+        self.write(|out| fmt::Write::write_char(out, '['))
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(Frame::Array { .. }) => {}
+            _ => return Err(WriterError::Invalid("No array to end")),
+        }
+        self.stack.pop();
+        self.after_value();
+        self.write(|out| fmt::Write::write_char(out, ']'))
+    }
+
+    /// Writes the key of the next member of the innermost object.
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        let comma = match self.stack.last_mut() {
+            Some(Frame::Object {
+                first,
+                after_key: after_key @ false,
+            }) => {
+                *after_key = true;
+                !core::mem::replace(first, false)
+            }
+            Some(Frame::Object { .. }) => return Err(WriterError::Invalid("Expected a value after a key")),
+            _ => return Err(WriterError::Invalid("A key can only be written in an object")),
+        };
+        self.write(|out| {
+            if comma {
+                fmt::Write::write_char(out, ',')?;
+            }
+            write_escaped(out, key)?;
+            fmt::Write::write_char(out, ':')
+        })
+    }
+
+    /// Writes a whole value, e.g. a number, a string or a [`JsonValue`]
+    /// built for a small part of the document.
+    pub fn value<'v, V: Into<JsonValue<'v>>>(&mut self, value: V) -> Result<(), WriterError> {
+        self.before_value()?;
+        let value = value.into();
+        self.after_value();
+        self.write(|out| write_compact(out, &value))
+    }
+
+    /// Checks that the document is complete, flushes and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        if !self.done {
+            return Err(WriterError::Invalid("The document is not complete"));
+        }
+        self.out.inner.flush()?;
+        Ok(self.out.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(write: impl FnOnce(&mut JsonWriter<Vec<u8>>) -> Result<(), WriterError>) -> String {
+        let mut writer = JsonWriter::new(Vec::new());
+        write(&mut writer).unwrap();
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn documents() {
+        let output = written(|w| {
+            w.begin_array()?;
+            w.begin_object()?;
+            w.key("a\"b")?;
+            w.value(json!({"x": [null]}))?;
+            w.key("c")?;
+            w.begin_array()?;
+            w.end_array()?;
+            w.end_object()?;
+            w.value(2.5)?;
+            w.begin_object()?;
+            w.end_object()?;
+            w.value(Some("é"))?;
+            w.end_array()
+        });
+        assert_eq!(output, r#"[{"a\"b":{"x":[null]},"c":[]},2.5,{},"é"]"#);
+        assert_eq!(written(|w| w.value(true)), "true");
+    }
+
+    #[test]
+    fn invalid_sequences() {
+        let mut writer = JsonWriter::new(Vec::new());
+        assert!(matches!(writer.key("a"), Err(WriterError::Invalid(_))));
+        assert!(matches!(writer.end_array(), Err(WriterError::Invalid(_))));
+        writer.begin_object().unwrap();
+        assert!(matches!(writer.value(1), Err(WriterError::Invalid("Expected a key in an object"))));
+        assert!(matches!(writer.end_array(), Err(WriterError::Invalid(_))));
+        writer.key("a").unwrap();
+        assert!(matches!(writer.key("b"), Err(WriterError::Invalid(_))));
+        assert!(matches!(writer.end_object(), Err(WriterError::Invalid(_))));
+        writer.value(1).unwrap();
+        writer.end_object().unwrap();
+        assert!(matches!(writer.value(2), Err(WriterError::Invalid(_))));
+        assert_eq!(writer.finish().unwrap(), br#"{"a":1}"#);
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        assert!(matches!(writer.finish(), Err(WriterError::Invalid("The document is not complete"))));
+    }
+}
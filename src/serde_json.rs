@@ -0,0 +1,85 @@
+//! Conversions from and to `serde_json::Value`, to mix both crates.
+//!
+//! ```
+//! use yaj::{json, JsonValueOwned};
+//! let value: serde_json::Value = json!({"uid": [1, 2.5]}).into();
+//! assert_eq!(value, serde_json::json!({"uid": [1, 2.5]}));
+//! assert_eq!(JsonValueOwned::from(value), json!({"uid": [1, 2.5]}));
+//! ```
+
+use alloc::borrow::Cow;
+
+use serde_json::{Number, Value};
+
+use crate::{JsonNumber, JsonValue, JsonValueOwned};
+
+fn number(n: &Number) -> JsonNumber {
+    if let Some(n) = n.as_i64() {
+        JsonNumber::Integer(n)
+    } else if let Some(n) = n.as_u64() {
+        JsonNumber::Unsigned(n)
+    } else {
+        JsonNumber::Float(n.as_f64().unwrap_or(f64::NAN))
+    }
+}
+
+impl From<Value> for JsonValueOwned {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => JsonValue::Null,
+            Value::Bool(b) => JsonValue::Boolean(b),
+            Value::Number(n) => JsonValue::Number(number(&n)),
+            Value::String(s) => JsonValue::String(Cow::Owned(s)),
+            Value::Array(array) => JsonValue::Array(array.into_iter().map(Into::into).collect()),
+            Value::Object(obj) => JsonValue::Object(obj.into_iter().map(|(k, v)| (Cow::Owned(k), v.into())).collect()),
+        }
+    }
+}
+
+/// Borrows the strings of `value`.
+impl<'a> From<&'a Value> for JsonValue<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Null => JsonValue::Null,
+            Value::Bool(b) => JsonValue::Boolean(*b),
+            Value::Number(n) => JsonValue::Number(number(n)),
+            Value::String(s) => JsonValue::String(Cow::Borrowed(s)),
+            Value::Array(array) => JsonValue::Array(array.iter().map(Into::into).collect()),
+            Value::Object(obj) => JsonValue::Object(obj.iter().map(|(k, v)| (Cow::Borrowed(k.as_str()), v.into())).collect()),
+        }
+    }
+}
+
+/// Infinite and NaN floats become `null`, as `serde_json` cannot represent
+/// them.
+impl From<JsonValue<'_>> for Value {
+    fn from(value: JsonValue<'_>) -> Self {
+        match value {
+            JsonValue::Null => Value::Null,
+            JsonValue::Boolean(b) => Value::Bool(b),
+            JsonValue::Number(JsonNumber::Integer(n)) => Value::Number(n.into()),
+            JsonValue::Number(JsonNumber::Unsigned(n)) => Value::Number(n.into()),
+            JsonValue::Number(JsonNumber::Float(f)) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+            JsonValue::String(s) => Value::String(s.into_owned()),
+            JsonValue::Array(array) => Value::Array(array.into_iter().map(Into::into).collect()),
+            JsonValue::Object(obj) => Value::Object(obj.into_iter().map(|(k, v)| (k.into_owned(), v.into())).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let source = r#"{"a": [1, -2, 18446744073709551615, 0.5, "é"], "b": {"c": null, "d": true}}"#;
+        let yaj = crate::parse(source);
+        let serde: Value = serde_json::from_str(source).unwrap();
+        assert_eq!(JsonValueOwned::from(serde.clone()), yaj);
+        assert_eq!(JsonValue::from(&serde), yaj);
+        assert!(matches!(JsonValue::from(&serde)["a"][4], JsonValue::String(Cow::Borrowed(_))));
+        assert_eq!(Value::from(yaj), serde);
+        assert_eq!(Value::from(json!([f64::NAN, 1.0])), serde_json::json!([null, 1.0]));
+    }
+}
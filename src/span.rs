@@ -0,0 +1,257 @@
+//! Values annotated with their location in the source, for tools reporting
+//! problems found after parsing, e.g. a field with an invalid value.
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+use crate::prelude::*;
+use crate::parser::{scalar, DuplicateKeys, ParseOptions, Parser};
+use crate::pointer::{array_index, reference_tokens};
+use crate::reader::RawEvent;
+use crate::{JsonError, JsonNumber, JsonReader, JsonTokenType, JsonValue, Map};
+
+/// A value with the byte range of its text in the source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<'a> {
+    pub span: Range<usize>,
+    pub node: SpannedNode<'a>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpannedNode<'a> {
+    Null,
+    Boolean(bool),
+    Number(JsonNumber),
+    String(Cow<'a, str>),
+    Array(Vec<Spanned<'a>>),
+    /// Members in source order, a repeated key being handled according to
+    /// [`ParseOptions::duplicate_keys`](crate::ParseOptions::duplicate_keys).
+    Object(Vec<SpannedMember<'a>>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpannedMember<'a> {
+    /// Escapes are resolved, as in [`JsonValue`] objects, borrowing from the
+    /// source only when the key has none.
+    pub key: Cow<'a, str>,
+    /// Byte range of the key, quotes included.
+    pub key_span: Range<usize>,
+    pub value: Spanned<'a>,
+}
+
+impl<'a> Spanned<'a> {
+    /// Member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&SpannedMember<'a>> {
+        match &self.node {
+            SpannedNode::Object(members) => members.iter().find(|member| member.key == key),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&Spanned<'a>> {
+        match &self.node {
+            SpannedNode::Array(array) => array.get(index),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value with a JSON Pointer, see [`JsonValue::pointer`].
+    pub fn pointer(&self, pointer: &str) -> Option<&Spanned<'a>> {
+        let mut target = self;
+        for token in reference_tokens(pointer)? {
+            target = match &target.node {
+                SpannedNode::Object(_) => &target.get(&token)?.value,
+                SpannedNode::Array(array) => array.get(array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(target)
+    }
+
+    /// The value without the spans.
+    pub fn to_value(&self) -> JsonValue<'a> {
+        match &self.node {
+            SpannedNode::Null => JsonValue::Null,
+            SpannedNode::Boolean(b) => JsonValue::Boolean(*b),
+            SpannedNode::Number(n) => JsonValue::Number(*n),
+            SpannedNode::String(s) => JsonValue::String(s.clone()),
+            SpannedNode::Array(array) => JsonValue::Array(array.iter().map(Spanned::to_value).collect()),
+            SpannedNode::Object(members) => {
+                let mut obj = Map::new();
+                for member in members {
+                    obj.insert(member.key.clone(), member.value.to_value());
+                }
+                JsonValue::Object(obj)
+            }
+        }
+    }
+}
+
+/// Parses `source` like [`try_parse`](crate::try_parse), recording where
+/// each value and key is.
+///
+/// ```
+/// let source = r#"{"name": "yaj", "tags": [1, true]}"#;
+/// let doc = yaj::parse_with_spans(source).unwrap();
+/// let tags = &doc.get("tags").unwrap().value;
+/// assert_eq!(&source[tags.span.clone()], "[1, true]");
+/// assert_eq!(&source[doc.pointer("/tags/1").unwrap().span.clone()], "true");
+/// assert_eq!(doc.get("name").unwrap().key_span, 1..7);
+/// ```
+pub fn parse_with_spans(source: &str) -> Result<Spanned<'_>, JsonError> {
+    Parser::new().parse_with_spans(source)
+}
+
+/// An array or object being built.
+enum Frame<'a> {
+    Array {
+        start: usize,
+        elements: Vec<Spanned<'a>>,
+    },
+    Object {
+        start: usize,
+        members: Vec<SpannedMember<'a>>,
+        /// Position of each key in `members`.
+        positions: BTreeMap<Cow<'a, str>, usize>,
+        /// Key of the member being read, with its span.
+        key: Option<(Cow<'a, str>, Range<usize>)>,
+    },
+}
+
+/// Builds the [`Spanned`] value of the events read from `source`.
+pub(crate) fn parse_spanned(source: &str, options: ParseOptions) -> Result<Spanned<'_>, JsonError> {
+    let duplicate_keys = options.duplicate_keys;
+    let mut reader = JsonReader::with_options(source, options);
+    let mut frames = Vec::new();
+    let mut root = None;
+    while let Some(event) = reader.next_raw()? {
+        let value = match event {
+            RawEvent::Start(token) => {
+                let start = token.offset;
+                frames.push(if token.token_type == JsonTokenType::LeftBracket {
+                    Frame::Array {
+                        start,
+                        elements: Vec::new(),
+                    }
+                } else {
+                    Frame::Object {
+                        start,
+                        members: Vec::new(),
+                        positions: BTreeMap::new(),
+                        key: None,
+                    }
+                });
+                continue;
+            }
+            RawEvent::Key(token) => {
+                if let Some(Frame::Object { key, .. }) = frames.last_mut() {
+                    *key = Some((reader.key(&token), token.offset..token.offset + token.slice.len()));
+                }
+                continue;
+            }
+            RawEvent::End(_) => match frames.pop() {
+                Some(Frame::Array { start, elements }) => Spanned {
+                    span: start..reader.end(),
+                    node: SpannedNode::Array(elements),
+                },
+                Some(Frame::Object { start, members, .. }) => Spanned {
+                    span: start..reader.end(),
+                    node: SpannedNode::Object(members),
+                },
+                None => continue,
+            },
+            RawEvent::Value(token) => Spanned {
+                span: token.offset..reader.end(),
+                node: match scalar(&token) {
+                    Some(JsonValue::String(s)) => SpannedNode::String(s),
+                    Some(JsonValue::Number(n)) => SpannedNode::Number(n),
+                    Some(JsonValue::Boolean(b)) => SpannedNode::Boolean(b),
+                    _ => SpannedNode::Null,
+                },
+            },
+        };
+        match frames.last_mut() {
+            None => root = Some(value),
+            Some(Frame::Array { elements, .. }) => elements.push(value),
+            Some(Frame::Object {
+                members,
+                positions,
+                key,
+                ..
+            }) => {
+                if let Some((key, key_span)) = key.take() {
+                    let member = SpannedMember {
+                        key: key.clone(),
+                        key_span,
+                        value,
+                    };
+                    match positions.get(&key) {
+                        None => {
+                            positions.insert(key, members.len());
+                            members.push(member);
+                        }
+                        // Repeated keys are rejected by the reader with
+                        // `DuplicateKeys::Error`.
+                        Some(&position) => {
+                            if duplicate_keys == DuplicateKeys::LastWins {
+                                members[position] = member;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    root.ok_or(JsonError::Empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn spans_of_values_and_keys() {
+        let source = "{\n  \"a\\tb\": [1, {\"c\": null}],\n  \"d\": -2.5e3\n}";
+        let doc = parse_with_spans(source).unwrap();
+        assert_eq!(doc.span, 0..source.len());
+        let text = |spanned: &Spanned| &source[spanned.span.clone()];
+        let a = doc.get("a\tb").unwrap();
+        assert_eq!(&source[a.key_span.clone()], "\"a\\tb\"");
+        assert_eq!(text(&a.value), "[1, {\"c\": null}]");
+        assert_eq!(text(doc.pointer("/a\tb/1/c").unwrap()), "null");
+        assert_eq!(text(doc.pointer("/d").unwrap()), "-2.5e3");
+        assert_eq!(doc.pointer("/a\tb/0").unwrap().node, SpannedNode::Number(JsonNumber::Integer(1)));
+        assert!(doc.pointer("/x").is_none());
+        assert_eq!(doc.to_value(), try_parse(source).unwrap());
+    }
+
+    #[test]
+    fn options_apply() {
+        let source = "{a: 1, // first\n a: [2,],}";
+        let parser = Parser::new().json5(true);
+        let doc = parser.parse_with_spans(source).unwrap();
+        match &doc.node {
+            SpannedNode::Object(members) => {
+                assert_eq!(members.len(), 1);
+                // The last member replaces the first one in place.
+                assert_eq!(members[0].key_span, 17..18);
+                assert_eq!(&source[members[0].value.span.clone()], "[2,]");
+            }
+            other => panic!("expected an object, got {:?}", other),
+        }
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeys::FirstWins,
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let doc = Parser::from(options).parse_with_spans(source).unwrap();
+        assert_eq!(doc.get("a").unwrap().value.span, 4..5);
+
+        assert_eq!(parse_with_spans("[1 2]").map(|_| ()), try_parse("[1 2]").map(|_| ()));
+        assert!(matches!(parse_with_spans(""), Err(JsonError::Empty)));
+        let deep = "[".repeat(200);
+        assert!(matches!(parse_with_spans(&deep), Err(JsonError::DepthLimitExceeded { .. })));
+    }
+}
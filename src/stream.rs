@@ -0,0 +1,79 @@
+//! Parsing sources that hold several documents, or a document too large to
+//! be kept in memory at once.
+
+use crate::parser::TokenParser;
+use crate::{JsonError, JsonValue, Lexer, ParseOptions};
+
+#[cfg(feature = "std")]
+pub use crate::read::{array_elements, ArrayElements};
+
+/// Parses the documents of a source where they follow one another, like
+/// `{"a":1}{"b":2}` or `1 2 3`, instead of rejecting trailing content.
+///
+/// Each item is a document with the byte offset following it, where the
+/// next one may start. The iteration ends after the first error.
+pub struct StreamParser<'a> {
+    parser: TokenParser<'a, Lexer<'a>>,
+    failed: bool,
+}
+
+impl<'a> StreamParser<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self::with_options(source, ParseOptions::default())
+    }
+
+    pub fn with_options(source: &'a str, options: ParseOptions) -> Self {
+        Self {
+            parser: TokenParser::new(source, options.lexer(source), options),
+            failed: false,
+        }
+    }
+}
+
+impl<'a> Iterator for StreamParser<'a> {
+    type Item = Result<(JsonValue<'a>, usize), JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.parser.parse_next() {
+            Ok(value) => value.map(|value| Ok((value, self.parser.end()))),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concatenated_documents() {
+        let source = "{\"a\":1}{\"b\":2}\n[3] 4\t\"five\"null ";
+        let documents: Vec<_> = StreamParser::new(source).map(Result::unwrap).collect();
+        assert_eq!(
+            documents,
+            vec![
+                (json!({ "a": 1 }), 7),
+                (json!({ "b": 2 }), 14),
+                (json!([3]), 18),
+                (json!(4), 20),
+                (json!("five"), 27),
+                (JsonValue::Null, 31),
+            ]
+        );
+        assert_eq!(StreamParser::new("  ").count(), 0);
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        let mut stream = StreamParser::new("[1] [2,] [3]");
+        assert_eq!(stream.next().unwrap().unwrap(), (json!([1]), 3));
+        assert!(matches!(stream.next(), Some(Err(JsonError::Parse { .. }))));
+        assert!(stream.next().is_none());
+    }
+}
@@ -0,0 +1,358 @@
+//! Flat representation of a document: a single vector of [`Node`]s in
+//! source order, where each array and object records where it ends.
+//!
+//! Parsing into a [`Tape`] grows a single vector instead of allocating for
+//! each array, object and escaped string, and walking it in order is a
+//! linear scan.
+//!
+//! ```
+//! let tape = yaj::tape::parse(r#"{"faces": [{"uid": 1}, {"uid": 2}]}"#).unwrap();
+//! let faces = tape.root().get("faces").unwrap();
+//! let uids: Vec<_> = faces
+//!     .elements()
+//!     .map(|face| face.get("uid").unwrap().to_value())
+//!     .collect();
+//! assert_eq!(uids, vec![yaj::json!(1), yaj::json!(2)]);
+//! ```
+
+use alloc::borrow::Cow;
+
+use crate::prelude::*;
+use crate::parser::{raw_key, string_content, ParseOptions};
+use crate::reader::RawEvent;
+use crate::{unescape, JsonError, JsonNumber, JsonReader, JsonTokenType, JsonValue, Map};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node<'a> {
+    /// `len` members follow, each as a [`Node::Key`] and its value. `end`
+    /// is the index of the node following the object.
+    Object { len: usize, end: usize },
+    /// `len` elements follow. `end` is the index of the node following the
+    /// array.
+    Array { len: usize, end: usize },
+    /// Member name, escapes left as in the source. Unquoted in JSON5 when
+    /// the source does not quote it.
+    Key(&'a str),
+    /// String contents, escapes left as in the source.
+    String(&'a str),
+    Number(JsonNumber),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tape<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> Tape<'a> {
+    /// All the nodes, the root value first.
+    pub fn nodes(&self) -> &[Node<'a>] {
+        &self.nodes
+    }
+
+    pub fn root(&self) -> Element<'_, 'a> {
+        Element { tape: self, index: 0 }
+    }
+}
+
+/// A value in a [`Tape`].
+#[derive(Clone, Copy, Debug)]
+pub struct Element<'t, 'a> {
+    tape: &'t Tape<'a>,
+    index: usize,
+}
+
+impl<'t, 'a> Element<'t, 'a> {
+    pub fn node(&self) -> &'t Node<'a> {
+        &self.tape.nodes[self.index]
+    }
+
+    /// Index of the node following this value.
+    fn end(&self) -> usize {
+        match self.node() {
+            Node::Object { end, .. } | Node::Array { end, .. } => *end,
+            _ => self.index + 1,
+        }
+    }
+
+    /// The string, with its escapes resolved.
+    pub fn as_str(&self) -> Option<Cow<'a, str>> {
+        match self.node() {
+            Node::String(s) => Some(unescape(s)),
+            _ => None,
+        }
+    }
+
+    /// Elements of an array, empty for any other value.
+    pub fn elements(&self) -> Elements<'t, 'a> {
+        let (index, len) = match self.node() {
+            Node::Array { len, .. } => (self.index + 1, *len),
+            _ => (self.index, 0),
+        };
+        Elements {
+            tape: self.tape,
+            index,
+            len,
+        }
+    }
+
+    /// Members of an object with their raw keys, empty for any other value.
+    pub fn members(&self) -> Members<'t, 'a> {
+        let (index, len) = match self.node() {
+            Node::Object { len, .. } => (self.index + 1, *len),
+            _ => (self.index, 0),
+        };
+        Members {
+            tape: self.tape,
+            index,
+            len,
+        }
+    }
+
+    /// Value of the member `key` of an object, compared with the escapes of
+    /// member names resolved, the last one if the key is repeated, like the
+    /// value [`try_parse`](crate::try_parse) keeps.
+    pub fn get(&self, key: &str) -> Option<Self> {
+        self.members()
+            .filter(|(k, _)| if k.contains('\\') { unescape(k) == key } else { *k == key })
+            .map(|(_, value)| value)
+            .last()
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<Self> {
+        self.elements().nth(index)
+    }
+
+    /// Builds the [`JsonValue`] this element stands for.
+    pub fn to_value(&self) -> JsonValue<'a> {
+        match self.node() {
+            Node::Object { .. } => {
+                let mut obj = Map::new();
+                for (key, value) in self.members() {
+                    obj.insert(unescape(key), value.to_value());
+                }
+                JsonValue::Object(obj)
+            }
+            Node::Array { .. } => JsonValue::Array(self.elements().map(|e| e.to_value()).collect()),
+            Node::String(s) => JsonValue::String(unescape(s)),
+            Node::Number(n) => JsonValue::Number(*n),
+            Node::Boolean(b) => JsonValue::Boolean(*b),
+            Node::Null | Node::Key(_) => JsonValue::Null,
+        }
+    }
+}
+
+pub struct Elements<'t, 'a> {
+    tape: &'t Tape<'a>,
+    index: usize,
+    len: usize,
+}
+
+impl<'t, 'a> Iterator for Elements<'t, 'a> {
+    type Item = Element<'t, 'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let element = Element {
+            tape: self.tape,
+            index: self.index,
+        };
+        self.index = element.end();
+        self.len -= 1;
+        Some(element)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for Elements<'_, '_> {}
+
+pub struct Members<'t, 'a> {
+    tape: &'t Tape<'a>,
+    index: usize,
+    len: usize,
+}
+
+impl<'t, 'a> Iterator for Members<'t, 'a> {
+    type Item = (&'a str, Element<'t, 'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        let key = match self.tape.nodes[self.index] {
+            Node::Key(key) => key,
+            _ => return None,
+        };
+        let value = Element {
+            tape: self.tape,
+            index: self.index + 1,
+        };
+        self.index = value.end();
+        self.len -= 1;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for Members<'_, '_> {}
+
+/// Counts a member of the innermost object when `key`, or an element of
+/// the innermost array otherwise.
+fn count(nodes: &mut [Node<'_>], open: &[usize], key: bool) {
+    if let Some(&start) = open.last() {
+        match &mut nodes[start] {
+            Node::Array { len, .. } if !key => *len += 1,
+            Node::Object { len, .. } if key => *len += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Parses `source` into a [`Tape`], with the same errors as
+/// [`try_parse`](crate::try_parse).
+pub fn parse(source: &str) -> Result<Tape<'_>, JsonError> {
+    parse_with(source, ParseOptions::default())
+}
+
+/// Same as [`parse`] with `options`, accepting what
+/// [`try_parse_with`](crate::try_parse_with) accepts. Repeated keys are all
+/// kept unless [`DuplicateKeys::Error`](crate::parser::DuplicateKeys::Error)
+/// rejects them.
+pub fn parse_with(source: &str, options: ParseOptions) -> Result<Tape<'_>, JsonError> {
+    let mut reader = JsonReader::with_options(source, options);
+    let mut nodes = Vec::new();
+    // Index of the node of each open array and object.
+    let mut open = Vec::new();
+    while let Some(event) = reader.next_raw()? {
+        let node = match event {
+            RawEvent::Start(token) => {
+                count(&mut nodes, &open, false);
+                open.push(nodes.len());
+                // Completed at the end of the contents.
+                nodes.push(if token.token_type == JsonTokenType::LeftBracket {
+                    Node::Array { len: 0, end: 0 }
+                } else {
+                    Node::Object { len: 0, end: 0 }
+                });
+                continue;
+            }
+            RawEvent::End(_) => {
+                let next = nodes.len();
+                if let Some(start) = open.pop() {
+                    if let Node::Array { end, .. } | Node::Object { end, .. } = &mut nodes[start] {
+                        *end = next;
+                    }
+                }
+                continue;
+            }
+            RawEvent::Key(token) => {
+                count(&mut nodes, &open, true);
+                Node::Key(raw_key(&token))
+            }
+            RawEvent::Value(token) => {
+                count(&mut nodes, &open, false);
+                match token.token_type {
+                    JsonTokenType::String => Node::String(string_content(&token)),
+                    JsonTokenType::Number => Node::Number(JsonNumber::parse(token.slice)),
+                    JsonTokenType::True => Node::Boolean(true),
+                    JsonTokenType::False => Node::Boolean(false),
+                    _ => Node::Null,
+                }
+            }
+        };
+        nodes.push(node);
+    }
+    Ok(Tape { nodes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{try_parse, try_parse_with};
+
+    #[test]
+    fn layout() {
+        let tape = parse(r#"{"a": [1, {}], "b": "x\ty"}"#).unwrap();
+        assert_eq!(
+            tape.nodes(),
+            &[
+                Node::Object { len: 2, end: 7 },
+                Node::Key("a"),
+                Node::Array { len: 2, end: 5 },
+                Node::Number(JsonNumber::Integer(1)),
+                Node::Object { len: 0, end: 5 },
+                Node::Key("b"),
+                Node::String("x\\ty"),
+            ]
+        );
+    }
+
+    #[test]
+    fn navigation() {
+        let source = r#"{"a": [1, [2, 3], {"b": null}], "c": "\u00e9", "c": true}"#;
+        let tape = parse(source).unwrap();
+        let root = tape.root();
+        assert_eq!(root.to_value(), try_parse(source).unwrap());
+        let a = root.get("a").unwrap();
+        assert_eq!(a.elements().len(), 3);
+        assert_eq!(a.get_index(1).unwrap().to_value(), json!([2, 3]));
+        assert_eq!(a.get_index(2).unwrap().get("b").unwrap().node(), &Node::Null);
+        assert!(a.get_index(3).is_none());
+        assert_eq!(root.get("c").unwrap().node(), &Node::Boolean(true));
+        let escaped = parse(r#"{"a\tb": 1, "\u0063": 2, "d\\n": 3}"#).unwrap();
+        assert_eq!(escaped.root().get("a\tb").unwrap().to_value(), json!(1));
+        assert!(escaped.root().get("a\\tb").is_none());
+        assert_eq!(escaped.root().get("c").unwrap().to_value(), json!(2));
+        assert_eq!(escaped.root().get("d\\n").unwrap().to_value(), json!(3));
+        assert_eq!(root.members().next().unwrap().0, "a");
+        assert_eq!(parse("\"\\u00e9\"").unwrap().root().as_str().unwrap(), "é");
+        assert!(root.get("missing").is_none());
+        assert_eq!(a.members().count(), 0);
+    }
+
+    #[test]
+    fn same_errors_as_parsing() {
+        for source in ["", "[1,]", r#"{"a" 1}"#, "[[]", "[] []", &"[".repeat(200)].iter() {
+            assert_eq!(parse(source).map(|_| ()), try_parse(source).map(|_| ()), "{}", source);
+        }
+    }
+
+    #[test]
+    fn options() {
+        let json5 = ParseOptions {
+            json5: true,
+            ..ParseOptions::default()
+        };
+        let tape = parse_with("{a: [1,], 'b': 2,}", json5).unwrap();
+        assert_eq!(
+            tape.nodes(),
+            &[
+                Node::Object { len: 2, end: 6 },
+                Node::Key("a"),
+                Node::Array { len: 1, end: 4 },
+                Node::Number(JsonNumber::Integer(1)),
+                Node::Key("b"),
+                Node::Number(JsonNumber::Integer(2)),
+            ]
+        );
+        let limits = ParseOptions {
+            max_depth: 1,
+            max_len: Some(2),
+            ..ParseOptions::default()
+        };
+        for source in ["[[]]", "[1, 2, 3]"].iter() {
+            let parsed = parse_with(source, limits.clone()).map(|_| ());
+            assert_eq!(parsed, try_parse_with(source, limits.clone()).map(|_| ()), "{}", source);
+            assert!(parsed.is_err(), "{}", source);
+        }
+    }
+}
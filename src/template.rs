@@ -0,0 +1,250 @@
+//! `${NAME}` placeholders in string values replaced with variables, e.g.
+//! to interpolate the environment in a configuration file.
+//!
+//! ```
+//! use yaj::{json, template};
+//! let mut config = yaj::parse(r#"{"url": "http://${HOST}:${PORT:-8080}/", "price": "$$5"}"#);
+//! template::substitute(&mut config, |name: &str| match name {
+//!     "HOST" => Some(String::from("localhost")),
+//!     _ => None,
+//! })
+//! .unwrap();
+//! assert_eq!(config, json!({"url": "http://localhost:8080/", "price": "$5"}));
+//! ```
+//!
+//! In a string, `${NAME}` is replaced with the value of the variable
+//! `NAME`, `${NAME:-default}` falls back on `default` when it is not
+//! set, and `$$` is a literal `$`. Any other `$` is left as is. Keys are
+//! left alone, and the replacements are not searched for placeholders.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use crate::pointer::JsonPointer;
+use crate::prelude::*;
+use crate::JsonValue;
+
+/// What [`substitute_with`] does with a variable that is not set and has
+/// no default.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Missing {
+    /// Fail with a [`TemplateError`].
+    Error,
+    /// Replace the placeholder with nothing.
+    Empty,
+    /// Leave the placeholder in the string.
+    Keep,
+}
+
+/// Settings of [`substitute_with`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateOptions {
+    pub missing: Missing,
+}
+impl Default for TemplateOptions {
+    fn default() -> Self {
+        Self {
+            missing: Missing::Error,
+        }
+    }
+}
+
+/// A placeholder that cannot be replaced.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateError {
+    pub msg: &'static str,
+    /// The name of the variable, or the rest of the string after an
+    /// unterminated `${`.
+    pub name: String,
+    /// Where the string is in the value.
+    pub pointer: JsonPointer,
+}
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}' at '{}'", self.msg, self.name, self.pointer)
+    }
+}
+impl core::error::Error for TemplateError {}
+
+/// Looks up the variables of the placeholders.
+pub trait Resolver {
+    /// The value of the variable `name`, `None` if it is not set.
+    fn resolve(&mut self, name: &str) -> Option<String>;
+}
+
+impl<F: FnMut(&str) -> Option<String>> Resolver for F {
+    fn resolve(&mut self, name: &str) -> Option<String> {
+        self(name)
+    }
+}
+
+/// Resolves the variables from the environment of the process.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Env;
+
+#[cfg(feature = "std")]
+impl Resolver for Env {
+    fn resolve(&mut self, name: &str) -> Option<String> {
+        std::env::var(name).ok()
+    }
+}
+
+/// Replaces the placeholders of every string in `value`, failing on the
+/// first variable that is not set. On error, the strings before it have
+/// already been replaced.
+pub fn substitute<R: Resolver>(value: &mut JsonValue<'_>, resolver: R) -> Result<(), TemplateError> {
+    substitute_with(value, resolver, &TemplateOptions::default())
+}
+
+/// Same as [`substitute`], handling the variables that are not set
+/// according to `opts`.
+pub fn substitute_with<R: Resolver>(
+    value: &mut JsonValue<'_>,
+    mut resolver: R,
+    opts: &TemplateOptions,
+) -> Result<(), TemplateError> {
+    substitute_into(value, &JsonPointer::root(), &mut resolver, opts)
+}
+
+fn substitute_into<R: Resolver>(
+    value: &mut JsonValue<'_>,
+    pointer: &JsonPointer,
+    resolver: &mut R,
+    opts: &TemplateOptions,
+) -> Result<(), TemplateError> {
+    match value {
+        JsonValue::String(s) if s.contains('$') => {
+            let new = expand(s, resolver, opts).map_err(|(msg, name)| TemplateError {
+                msg,
+                name,
+                pointer: pointer.clone(),
+            })?;
+            *s = Cow::Owned(new);
+        }
+        JsonValue::Array(array) => {
+            for (index, element) in array.iter_mut().enumerate() {
+                let mut child = pointer.clone();
+                child.push(&index.to_string());
+                substitute_into(element, &child, resolver, opts)?;
+            }
+        }
+        JsonValue::Object(obj) => {
+            for (key, member) in obj.iter_mut() {
+                let mut child = pointer.clone();
+                child.push(key);
+                substitute_into(member, &child, resolver, opts)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The string `s` with its placeholders replaced, or the message and name
+/// of the error.
+fn expand<R: Resolver>(s: &str, resolver: &mut R, opts: &TemplateOptions) -> Result<String, (&'static str, String)> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(dollar) = rest.find('$') {
+        out.push_str(&rest[..dollar]);
+        rest = &rest[dollar..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+            continue;
+        }
+        let inner = match rest.strip_prefix("${") {
+            Some(inner) => inner,
+            None => {
+                out.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let end = match inner.find('}') {
+            Some(end) => end,
+            None => return Err(("Unterminated placeholder", String::from(inner))),
+        };
+        let (name, default) = match inner[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&inner[..end], None),
+        };
+        if name.is_empty() {
+            return Err(("Empty variable name in placeholder", String::from(&inner[..end])));
+        }
+        match (resolver.resolve(name), default) {
+            (Some(resolved), _) => out.push_str(&resolved),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => match opts.missing {
+                Missing::Error => return Err(("Variable not set", String::from(name))),
+                Missing::Empty => {}
+                Missing::Keep => out.push_str(&rest[..end + 3]),
+            },
+        }
+        rest = &inner[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(name: &str) -> Option<String> {
+        match name {
+            "A" => Some(String::from("1")),
+            "B" => Some(String::from("${A}")),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn placeholders() {
+        let mut value = json!({
+            "list": ["${A}${B}", "x$A $ $$ $${A} ${EMPTY:-d}", {"k": "${C:-c:-}"}],
+            "${A}": 1,
+        });
+        substitute(&mut value, vars).unwrap();
+        assert_eq!(
+            value,
+            json!({"list": ["1${A}", "x$A $ $ ${A} ", {"k": "c:-"}], "${A}": 1})
+        );
+    }
+
+    #[test]
+    fn missing_variables() {
+        let template = json!({"a": ["ok", "<${A}${MISSING}>"]});
+        let mut value = template.clone();
+        let error = substitute(&mut value, vars).unwrap_err();
+        assert_eq!(error.to_string(), "Variable not set 'MISSING' at '/a/1'");
+        let mut value = template.clone();
+        let opts = TemplateOptions {
+            missing: Missing::Empty,
+        };
+        substitute_with(&mut value, vars, &opts).unwrap();
+        assert_eq!(value, json!({"a": ["ok", "<1>"]}));
+        let mut value = template.clone();
+        let opts = TemplateOptions { missing: Missing::Keep };
+        substitute_with(&mut value, vars, &opts).unwrap();
+        assert_eq!(value, json!({"a": ["ok", "<1${MISSING}>"]}));
+
+        let error = substitute(&mut json!("${A"), vars).unwrap_err();
+        assert_eq!((error.msg, error.name.as_str()), ("Unterminated placeholder", "A"));
+        let error = substitute(&mut json!(["${:-x}"]), vars).unwrap_err();
+        assert_eq!(error.to_string(), "Empty variable name in placeholder ':-x' at '/0'");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn environment() {
+        let mut value = json!("${YAJ_TEMPLATE_UNSET:-none} ${PATH}");
+        substitute(&mut value, Env).unwrap();
+        assert_eq!(
+            value,
+            JsonValue::String(format!("none {}", std::env::var("PATH").unwrap()).into())
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! Assertions on JSON values for tests, e.g. on the payloads an API
+//! returns: equality with a tolerance on floats, and patterns matching
+//! values partially.
+//!
+//! ```
+//! use yaj::json;
+//! use yaj::testing::{assert_json_eq, json_matches};
+//!
+//! let event = json!({"eventType": "faceCapture", "uid": 12, "age": {"value": 31.0000000001}});
+//! assert_json_eq!(event["age"], json!({"value": 31.0}));
+//! assert!(json_matches!(event, {"eventType": "faceCapture", "uid": _}));
+//! assert!(!json_matches!(event, {"age": {"value": 30}}));
+//! ```
+
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{to_string, JsonNumber, JsonPointer, JsonValue, JsonValueOwned};
+
+pub use crate::{assert_json_eq, json_matches, json_pattern};
+
+/// Tolerance on numbers used by the macros of this module.
+pub const DEFAULT_EPSILON: f64 = 1e-9;
+
+/// What a value must look like to match, built with [`json_pattern!`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// Any value, written `_`.
+    Any,
+    /// An equal number, string, boolean or `null`. Numbers are compared
+    /// with a tolerance.
+    Scalar(JsonValueOwned),
+    /// An array of the same length whose elements match.
+    Array(Vec<Pattern>),
+    /// An object with at least these members, whose values match. Other
+    /// members are ignored.
+    Object(Vec<(String, Pattern)>),
+}
+
+/// Objects and arrays become patterns matching their members and elements.
+impl From<JsonValue<'_>> for Pattern {
+    fn from(value: JsonValue<'_>) -> Self {
+        match value {
+            JsonValue::Array(array) => Self::Array(array.into_iter().map(Self::from).collect()),
+            JsonValue::Object(obj) => {
+                Self::Object(obj.into_iter().map(|(k, v)| (k.into_owned(), Self::from(v))).collect())
+            }
+            scalar => Self::Scalar(scalar.into_owned()),
+        }
+    }
+}
+
+impl Pattern {
+    /// Checks that `value` matches, numbers differing by at most `epsilon`.
+    pub fn matches(&self, value: &JsonValue<'_>, epsilon: f64) -> Result<(), Mismatch> {
+        self.check(value, epsilon, &JsonPointer::root())
+    }
+
+    fn check(&self, value: &JsonValue<'_>, epsilon: f64, pointer: &JsonPointer) -> Result<(), Mismatch> {
+        match (self, value) {
+            (Self::Any, _) => Ok(()),
+            (Self::Scalar(expected), _) if scalar_eq(expected, value, epsilon) => Ok(()),
+            (Self::Array(patterns), JsonValue::Array(array)) if patterns.len() == array.len() => {
+                for (idx, (pattern, element)) in patterns.iter().zip(array).enumerate() {
+                    let mut inner = pointer.clone();
+                    inner.push(&idx.to_string());
+                    pattern.check(element, epsilon, &inner)?;
+                }
+                Ok(())
+            }
+            (Self::Object(members), JsonValue::Object(obj)) => {
+                for (key, pattern) in members {
+                    let mut inner = pointer.clone();
+                    inner.push(key);
+                    match obj.get(key.as_str()) {
+                        Some(member) => pattern.check(member, epsilon, &inner)?,
+                        None => return Err(Mismatch::new(inner, pattern.describe(), None)),
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(Mismatch::new(pointer.clone(), self.describe(), Some(value))),
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn push_member(&mut self, key: String, pattern: Pattern) {
+        if let Self::Object(members) = self {
+            members.push((key, pattern));
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Any => String::from("_"),
+            Self::Scalar(value) => to_string(value),
+            Self::Array(patterns) => {
+                let elements: Vec<_> = patterns.iter().map(Self::describe).collect();
+                format!("[{}]", elements.join(","))
+            }
+            Self::Object(members) => {
+                let members: Vec<_> = members
+                    .iter()
+                    .map(|(key, pattern)| {
+                        format!("{}:{}", to_string(&JsonValue::from(key.as_str())), pattern.describe())
+                    })
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+}
+
+fn scalar_eq(expected: &JsonValue<'_>, value: &JsonValue<'_>, epsilon: f64) -> bool {
+    match (expected, value) {
+        (JsonValue::Number(a @ JsonNumber::Float(_)), JsonValue::Number(b))
+        | (JsonValue::Number(a), JsonValue::Number(b @ JsonNumber::Float(_))) => {
+            let diff = a.as_f64_lossy() - b.as_f64_lossy();
+            a == b || (diff <= epsilon && -diff <= epsilon)
+        }
+        // Integers are compared exactly, the tolerance being for floats.
+        (JsonValue::Number(a), JsonValue::Number(b)) => a == b,
+        (JsonValue::Array(_), _) | (JsonValue::Object(_), _) => false,
+        _ => expected == value,
+    }
+}
+
+/// Where a value differs from what was expected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub pointer: JsonPointer,
+    /// The expected value or pattern, as compact JSON text.
+    pub expected: String,
+    /// The value found as compact JSON text, `None` for a missing member.
+    pub actual: Option<String>,
+}
+
+impl Mismatch {
+    fn new(pointer: JsonPointer, expected: String, actual: Option<&JsonValue<'_>>) -> Self {
+        Self {
+            pointer,
+            expected,
+            actual: actual.map(to_string),
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(f, "at '{}': expected {}, found {}", self.pointer, self.expected, actual),
+            None => write!(f, "at '{}': expected {}, found nothing", self.pointer, self.expected),
+        }
+    }
+}
+
+impl core::error::Error for Mismatch {}
+
+/// Checks that `actual` and `expected` are equal, numbers differing by at
+/// most `epsilon`. Unlike a [`Pattern`], objects must have the same
+/// members.
+pub fn json_eq(actual: &JsonValue<'_>, expected: &JsonValue<'_>, epsilon: f64) -> Result<(), Mismatch> {
+    eq_at(actual, expected, epsilon, &JsonPointer::root())
+}
+
+fn eq_at(
+    actual: &JsonValue<'_>,
+    expected: &JsonValue<'_>,
+    epsilon: f64,
+    pointer: &JsonPointer,
+) -> Result<(), Mismatch> {
+    match (actual, expected) {
+        (JsonValue::Array(a), JsonValue::Array(b)) if a.len() == b.len() => {
+            for (idx, (a, b)) in a.iter().zip(b).enumerate() {
+                let mut inner = pointer.clone();
+                inner.push(&idx.to_string());
+                eq_at(a, b, epsilon, &inner)?;
+            }
+            Ok(())
+        }
+        (JsonValue::Object(a), JsonValue::Object(b)) => {
+            let mut keys: Vec<_> = a.keys().chain(b.keys().filter(|key| !a.contains_key(*key))).collect();
+            keys.sort_unstable();
+            for key in keys {
+                let mut inner = pointer.clone();
+                inner.push(key);
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => eq_at(a, b, epsilon, &inner)?,
+                    (None, Some(b)) => return Err(Mismatch::new(inner, to_string(b), None)),
+                    (Some(a), None) => return Err(Mismatch::new(inner, String::from("nothing"), Some(a))),
+                    (None, None) => {}
+                }
+            }
+            Ok(())
+        }
+        _ if scalar_eq(expected, actual, epsilon) => Ok(()),
+        _ => Err(Mismatch::new(pointer.clone(), to_string(expected), Some(actual))),
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+#[allow(clippy::panic)]
+pub fn assert_eq_failed(mismatch: Mismatch, actual: &JsonValue<'_>, expected: &JsonValue<'_>) -> ! {
+    panic!(
+        "JSON values differ {}\n  actual: {}\nexpected: {}",
+        mismatch,
+        to_string(actual),
+        to_string(expected)
+    )
+}
+
+/// Asserts that two [`JsonValue`]s are equal, numbers differing by at most
+/// [`DEFAULT_EPSILON`] or by the `epsilon` given, see [`json_eq`]. The
+/// panic message locates the first difference with a JSON Pointer.
+///
+/// ```should_panic
+/// # use yaj::{assert_json_eq, json};
+/// assert_json_eq!(json!([0.1 + 0.2]), json!([0.3]));
+/// assert_json_eq!(json!({"a": 1.04}), json!({"a": 1}), epsilon = 0.05);
+/// // JSON values differ at '/a': expected 2, found 1
+/// assert_json_eq!(json!({"a": 1}), json!({"a": 2}));
+/// ```
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_json_eq!($actual, $expected, epsilon = $crate::testing::DEFAULT_EPSILON)
+    };
+    ($actual:expr, $expected:expr, epsilon = $epsilon:expr $(,)?) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                if let Err(mismatch) = $crate::testing::json_eq(actual, expected, $epsilon) {
+                    $crate::testing::assert_eq_failed(mismatch, actual, expected);
+                }
+            }
+        }
+    };
+}
+
+/// Whether a [`JsonValue`] matches a pattern written like [`json!`]
+/// values, where `_` stands for any value and objects only need to have
+/// the members listed. Numbers differ by at most [`DEFAULT_EPSILON`].
+///
+/// ```
+/// # use yaj::{json, json_matches};
+/// let faces = json!([{"uid": 1, "age": 31}, {"uid": 2, "age": 25}]);
+/// assert!(json_matches!(faces, [{"uid": 1}, {"age": _}]));
+/// assert!(!json_matches!(faces, [{"uid": 1}]));
+/// ```
+#[macro_export]
+macro_rules! json_matches {
+    ($value:expr, $($pattern:tt)+) => {
+        $crate::json_pattern!($($pattern)+).matches(&$value, $crate::testing::DEFAULT_EPSILON).is_ok()
+    };
+}
+
+/// Builds a [`Pattern`] from the syntax of [`json_matches!`].
+///
+/// ```
+/// # use yaj::{json, json_pattern, testing::Pattern};
+/// let pattern = json_pattern!({"faces": [_, {"age": 31.5}]});
+/// let mismatch = pattern.matches(&json!({"faces": [null, {"age": 30}]}), 0.1).unwrap_err();
+/// assert_eq!(mismatch.to_string(), "at '/faces/1/age': expected 31.5, found 30");
+/// assert_eq!(json_pattern!([1, null]), Pattern::from(json!([1, null])));
+/// ```
+#[macro_export]
+macro_rules! json_pattern {
+    (@array [$($elems:expr,)*]) => {
+        $crate::__private::vec![$($elems,)*]
+    };
+    (@array [$($elems:expr,)*] _ $(, $($rest:tt)*)?) => {
+        $crate::json_pattern!(@array [$($elems,)* $crate::testing::Pattern::Any,] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::json_pattern!(@array [$($elems,)* $crate::json_pattern!(null),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::json_pattern!(@array [$($elems,)* $crate::json_pattern!([$($array)*]),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::json_pattern!(@array [$($elems,)* $crate::json_pattern!({$($object)*}),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] $next:expr $(, $($rest:tt)*)?) => {
+        $crate::json_pattern!(@array [$($elems,)* $crate::json_pattern!($next),] $($($rest)*)?)
+    };
+
+    // Members are pushed once their key, munched up to the colon, and
+    // their value are known.
+    (@object $object:ident () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) $(, $($rest:tt)*)?) => {
+        $object.push_member($crate::__private::Cow::from($($key)+).into_owned(), $value);
+        $crate::json_pattern!(@object $object () ($($($rest)*)?));
+    };
+    (@object $object:ident ($($key:tt)+) (: _ $($rest:tt)*)) => {
+        $crate::json_pattern!(@object $object [$($key)+] ($crate::testing::Pattern::Any) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*)) => {
+        $crate::json_pattern!(@object $object [$($key)+] ($crate::json_pattern!(null)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*)) => {
+        $crate::json_pattern!(@object $object [$($key)+] ($crate::json_pattern!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*)) => {
+        $crate::json_pattern!(@object $object [$($key)+] ($crate::json_pattern!({$($map)*})) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr $(, $($rest:tt)*)?)) => {
+        $crate::json_pattern!(@object $object [$($key)+] ($crate::json_pattern!($value)) $(, $($rest)*)?);
+    };
+    (@object $object:ident () (($key:expr) : $($rest:tt)*)) => {
+        $crate::json_pattern!(@object $object ($key) (: $($rest)*));
+    };
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::json_pattern!(@object $object ($($key)* $tt) ($($rest)*));
+    };
+
+    (_) => {
+        $crate::testing::Pattern::Any
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::testing::Pattern::Array($crate::json_pattern!(@array [] $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut object = $crate::testing::Pattern::Object($crate::__private::Vec::new());
+        $crate::json_pattern!(@object object () ($($tt)*));
+        object
+    }};
+    ($($other:tt)+) => {
+        $crate::testing::Pattern::from($crate::json!($($other)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn equality_with_tolerance() {
+        let a = parse(r#"{"x": [1, 2.0000000001, "s"], "y": null, "z": {"w": true}}"#);
+        assert_json_eq!(a, json!({"z": {"w": true}, "y": null, "x": [1.0, 2, "s"]}));
+        assert_json_eq!(json!(1.04), json!(1), epsilon = 0.05);
+
+        let mismatch = |b: JsonValue| json_eq(&a, &b, DEFAULT_EPSILON).unwrap_err().to_string();
+        assert_eq!(
+            mismatch(json!({"x": [1, 2, "t"], "y": null, "z": {"w": true}})),
+            r#"at '/x/2': expected "t", found "s""#
+        );
+        assert_eq!(
+            mismatch(json!({"x": [1, 2], "y": null, "z": {"w": true}})),
+            r#"at '/x': expected [1,2], found [1,2.0000000001,"s"]"#
+        );
+        assert_eq!(
+            mismatch(json!({"x": [1, 2, "s"], "y": null})),
+            "at '/z': expected nothing, found {\"w\":true}"
+        );
+        assert_eq!(
+            mismatch(json!({"x": [1, 2, "s"], "y": null, "z": {"w": true}, "~": 0})),
+            "at '/~0': expected 0, found nothing"
+        );
+        assert!(json_eq(&json!(0.5), &json!(0.6), 0.01).is_err());
+        assert!(json_eq(&json!(u64::MAX), &json!(u64::MAX), 0.0).is_ok());
+        assert!(json_eq(&json!(u64::MAX), &json!(u64::MAX - 1), 0.0).is_err());
+        assert!(json_eq(&json!(-1), &json!(u64::MAX), 1.0).is_err());
+        assert!(!json_matches!(json!(9007199254740993i64), 9007199254740992i64));
+        assert!(json_eq(&json!([]), &json!({}), 1.0).is_err());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "JSON values differ at '/a': expected 2, found 1\n  actual: {\"a\":1}\nexpected: {\"a\":2}"
+    )]
+    fn failed_assertion() {
+        assert_json_eq!(json!({"a": 1}), json!({"a": 2}));
+    }
+
+    #[test]
+    fn patterns() {
+        let event = parse(
+            r#"{"eventType": "faceCapture", "faces": [{"uid": 1, "age": 30.99999999999}, {"uid": 2}], "n": null}"#,
+        );
+        assert!(json_matches!(event, _));
+        assert!(json_matches!(event, {}));
+        assert!(json_matches!(event, {"faces": [_, _]}));
+        assert!(json_matches!(event, {"faces": [{"age": 31}, {"uid": 2,}], "n": null}));
+        let kind = "faceCapture";
+        assert!(json_matches!(event, {"eventType": kind, ("fa".to_string() + "ces"): [{"uid": 1}, _]}));
+        assert!(json_matches!(event["faces"][0]["uid"], 1));
+        assert!(!json_matches!(event, {"n": _, "missing": _}));
+        assert!(!json_matches!(event, {"faces": [_]}));
+        assert!(!json_matches!(event, {"faces": {}}));
+        assert!(!json_matches!(event, []));
+        assert!(!json_matches!(event["n"], false));
+
+        let pattern = json_pattern!({"faces": [{"uid": _}, {"uid": 3}]});
+        assert_eq!(
+            pattern.matches(&event, 0.0).unwrap_err().to_string(),
+            "at '/faces/1/uid': expected 3, found 2"
+        );
+        let pattern = json_pattern!([_, {"k": [1, _]}]);
+        assert_eq!(
+            pattern.matches(&json!([1]), 0.0).unwrap_err().to_string(),
+            r#"at '': expected [_,{"k":[1,_]}], found [1]"#
+        );
+        let mismatch = pattern.matches(&json!([1, {}]), 0.0).unwrap_err();
+        assert_eq!(mismatch.to_string(), "at '/1/k': expected [1,_], found nothing");
+        assert_eq!(mismatch.actual, None);
+    }
+}
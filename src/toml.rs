@@ -0,0 +1,199 @@
+//! Conversion between values and TOML documents, e.g. to serve a
+//! `Cargo.toml`-style configuration through a JSON API.
+//!
+//! ```
+//! use yaj::{json, toml};
+//! let value = toml::from_toml_str("name = \"yaj\"\n[deps]\nserde = { version = \"1\" }\n").unwrap();
+//! assert_eq!(value, json!({"name": "yaj", "deps": {"serde": {"version": "1"}}}));
+//! assert_eq!(toml::to_toml_string(&value).unwrap(), "name = \"yaj\"\n\n[deps.serde]\nversion = \"1\"\n");
+//! ```
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::fmt;
+
+use toml::{Table, Value};
+
+use crate::pointer::JsonPointer;
+use crate::prelude::*;
+use crate::{JsonNumber, JsonValue, JsonValueOwned, Map};
+
+/// A TOML document that cannot be read, or a value that cannot be
+/// written as one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TomlError {
+    /// The message of the `toml` crate, e.g. for a syntax error.
+    Toml(String),
+    /// A value with no TOML equivalent.
+    Unsupported { msg: &'static str, pointer: JsonPointer },
+}
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TomlError::Toml(msg) => f.write_str(msg.trim_end()),
+            TomlError::Unsupported { msg, pointer } => write!(f, "{} at '{}'", msg, pointer),
+        }
+    }
+}
+impl core::error::Error for TomlError {}
+
+/// Reads a TOML document as an object. Date and times become strings in
+/// RFC 3339 format, such as `1979-05-27T07:32:00Z` or `07:32:00`.
+pub fn from_toml_str(source: &str) -> Result<JsonValueOwned, TomlError> {
+    let table: Table = source
+        .parse()
+        .map_err(|e: toml::de::Error| TomlError::Toml(e.to_string()))?;
+    Ok(table_to_value(table))
+}
+
+fn table_to_value(table: Table) -> JsonValueOwned {
+    JsonValue::Object(
+        table
+            .into_iter()
+            .map(|(key, value)| (Cow::Owned(key), to_value(value)))
+            .collect(),
+    )
+}
+
+fn to_value(value: Value) -> JsonValueOwned {
+    match value {
+        Value::String(s) => JsonValue::String(Cow::Owned(s)),
+        Value::Integer(n) => JsonValue::Number(JsonNumber::Integer(n)),
+        Value::Float(f) => JsonValue::Number(JsonNumber::Float(f)),
+        Value::Boolean(b) => JsonValue::Boolean(b),
+        Value::Datetime(datetime) => JsonValue::String(Cow::Owned(datetime.to_string())),
+        Value::Array(array) => JsonValue::Array(array.into_iter().map(to_value).collect()),
+        Value::Table(table) => table_to_value(table),
+    }
+}
+
+/// Writes `value`, which must be an object, as a TOML document. Members
+/// set to `null` are left out, as TOML has no null; a `null` in an array
+/// and an integer above `i64::MAX` are errors. Tables are sorted by key.
+pub fn to_toml_string(value: &JsonValue) -> Result<String, TomlError> {
+    let root = JsonPointer::root();
+    match value {
+        JsonValue::Object(obj) => toml::to_string(&to_table(obj, &root)?).map_err(|e| TomlError::Toml(e.to_string())),
+        _ => Err(TomlError::Unsupported {
+            msg: "TOML document that is not an object",
+            pointer: root,
+        }),
+    }
+}
+
+/// The TOML value of `value`, `None` for `null`.
+fn to_toml(value: &JsonValue, pointer: &JsonPointer) -> Result<Option<Value>, TomlError> {
+    let unsupported = |msg| TomlError::Unsupported {
+        msg,
+        pointer: pointer.clone(),
+    };
+    Ok(Some(match value {
+        JsonValue::Null => return Ok(None),
+        JsonValue::Boolean(b) => Value::Boolean(*b),
+        JsonValue::Number(JsonNumber::Integer(n)) => Value::Integer(*n),
+        JsonValue::Number(JsonNumber::Unsigned(n)) => {
+            Value::Integer(i64::try_from(*n).map_err(|_| unsupported("Integer out of the TOML range"))?)
+        }
+        JsonValue::Number(JsonNumber::Float(f)) => Value::Float(*f),
+        JsonValue::String(s) => Value::String(String::from(&**s)),
+        JsonValue::Array(array) => {
+            let mut elements = Vec::with_capacity(array.len());
+            for (index, element) in array.iter().enumerate() {
+                let mut child = pointer.clone();
+                child.push(&index.to_string());
+                match to_toml(element, &child)? {
+                    Some(element) => elements.push(element),
+                    None => {
+                        return Err(TomlError::Unsupported {
+                            msg: "Null in a TOML array",
+                            pointer: child,
+                        })
+                    }
+                }
+            }
+            Value::Array(elements)
+        }
+        JsonValue::Object(obj) => Value::Table(to_table(obj, pointer)?),
+    }))
+}
+
+fn to_table(obj: &Map, pointer: &JsonPointer) -> Result<Table, TomlError> {
+    let mut table = Table::new();
+    for (key, member) in obj {
+        let mut child = pointer.clone();
+        child.push(key);
+        if let Some(member) = to_toml(member, &child)? {
+            table.insert(String::from(&**key), member);
+        }
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_toml_documents() {
+        let source = r#"
+            title = "example"
+            when = 1979-05-27T07:32:00Z
+            day = 1979-05-27
+            ratio = 0.5
+            ports = [8000, 8001]
+
+            [[servers]]
+            ip = "10.0.0.1"
+            enabled = true
+
+            [[servers]]
+            ip = "10.0.0.2"
+        "#;
+        assert_eq!(
+            from_toml_str(source).unwrap(),
+            json!({
+                "day": "1979-05-27",
+                "ports": [8000, 8001],
+                "ratio": 0.5,
+                "servers": [{"enabled": true, "ip": "10.0.0.1"}, {"ip": "10.0.0.2"}],
+                "title": "example",
+                "when": "1979-05-27T07:32:00Z",
+            })
+        );
+        let error = from_toml_str("a = ").unwrap_err();
+        assert!(matches!(error, TomlError::Toml(_)));
+        assert!(error.to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn to_toml_documents() {
+        let value = json!({
+            "a": [1, "b", {"c": 2.5}],
+            "d": null,
+            "e": {"f": [{"g": true}, {"g": false}]},
+            "h": 18446744073709551615u64,
+        });
+        let error = to_toml_string(&value).unwrap_err();
+        assert_eq!(error.to_string(), "Integer out of the TOML range at '/h'");
+
+        let value = json!({"a": [1, "b", {"c": 2.5}], "d": null, "e": {"f": [{"g": true}, {"g": false}]}});
+        let text = to_toml_string(&value).unwrap();
+        assert_eq!(
+            text,
+            "a = [1, \"b\", { c = 2.5 }]\n\n[[e.f]]\ng = true\n\n[[e.f]]\ng = false\n"
+        );
+        assert_eq!(
+            from_toml_str(&text).unwrap(),
+            json!({"a": [1, "b", {"c": 2.5}], "e": {"f": [{"g": true}, {"g": false}]}})
+        );
+
+        assert_eq!(
+            to_toml_string(&json!({"a": [{"b": [null]}]})).unwrap_err().to_string(),
+            "Null in a TOML array at '/a/0/b/0'"
+        );
+        assert_eq!(
+            to_toml_string(&json!([1])).unwrap_err().to_string(),
+            "TOML document that is not an object at ''"
+        );
+    }
+}
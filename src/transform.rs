@@ -0,0 +1,176 @@
+//! In-place rewriting of documents, e.g. to scrub personal data from a
+//! payload before logging it.
+//!
+//! ```
+//! use yaj::{json, transform};
+//! let mut value = json!({"camera": {"macAddress": "00:1B:44:11:3A:B7", "portNo": 8080}});
+//! transform::redact(&mut value, transform::Keys(&["macAddress", "ipAddress"]), json!("***"));
+//! assert_eq!(value, json!({"camera": {"macAddress": "***", "portNo": 8080}}));
+//! ```
+
+use alloc::borrow::Cow;
+use core::mem;
+
+use crate::pointer::JsonPointer;
+use crate::prelude::*;
+use crate::JsonValue;
+
+/// Chooses the nodes [`redact`] replaces.
+pub trait Selector {
+    /// Whether the node `value` at `pointer` is selected.
+    fn select(&mut self, pointer: &JsonPointer, value: &JsonValue<'_>) -> bool;
+
+    /// Same as [`Selector::select`] for the member `key` of an object.
+    fn select_member(&mut self, pointer: &JsonPointer, key: &str, value: &JsonValue<'_>) -> bool {
+        let _ = key;
+        self.select(pointer, value)
+    }
+}
+
+impl<F: FnMut(&JsonPointer, &JsonValue<'_>) -> bool> Selector for F {
+    fn select(&mut self, pointer: &JsonPointer, value: &JsonValue<'_>) -> bool {
+        self(pointer, value)
+    }
+}
+
+/// Selects the members with one of these keys, at any depth.
+#[derive(Clone, Copy, Debug)]
+pub struct Keys<'k>(pub &'k [&'k str]);
+
+impl Selector for Keys<'_> {
+    fn select(&mut self, _: &JsonPointer, _: &JsonValue<'_>) -> bool {
+        false
+    }
+
+    fn select_member(&mut self, _: &JsonPointer, key: &str, _: &JsonValue<'_>) -> bool {
+        self.0.contains(&key)
+    }
+}
+
+/// Selects the nodes designated by these escaped JSON Pointers.
+#[derive(Clone, Copy, Debug)]
+pub struct Pointers<'p>(pub &'p [&'p str]);
+
+impl Selector for Pointers<'_> {
+    fn select(&mut self, pointer: &JsonPointer, _: &JsonValue<'_>) -> bool {
+        self.0.iter().any(|p| pointer == p)
+    }
+}
+
+/// Replaces every string value for which `f` returns a new one. Keys are
+/// left alone, see [`map_keys`].
+pub fn map_strings<'a, F: FnMut(&str) -> Option<String>>(value: &mut JsonValue<'a>, mut f: F) {
+    map_strings_into(value, &mut f);
+}
+
+fn map_strings_into<'a, F: FnMut(&str) -> Option<String>>(value: &mut JsonValue<'a>, f: &mut F) {
+    match value {
+        JsonValue::String(s) => {
+            if let Some(new) = f(s) {
+                *s = Cow::Owned(new);
+            }
+        }
+        JsonValue::Array(array) => array.iter_mut().for_each(|element| map_strings_into(element, f)),
+        JsonValue::Object(obj) => obj.values_mut().for_each(|member| map_strings_into(member, f)),
+        _ => {}
+    }
+}
+
+/// Renames every object key for which `f` returns a new name, at any depth.
+/// When a new name collides with another key of the same object, the member
+/// that comes last in iteration order wins.
+pub fn map_keys<'a, F: FnMut(&str) -> Option<String>>(value: &mut JsonValue<'a>, mut f: F) {
+    map_keys_into(value, &mut f);
+}
+
+fn map_keys_into<'a, F: FnMut(&str) -> Option<String>>(value: &mut JsonValue<'a>, f: &mut F) {
+    match value {
+        JsonValue::Array(array) => array.iter_mut().for_each(|element| map_keys_into(element, f)),
+        JsonValue::Object(obj) => {
+            for (key, mut member) in mem::take(obj) {
+                map_keys_into(&mut member, f);
+                let key = f(&key).map_or(key, Cow::Owned);
+                obj.insert(key, member);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replaces every node `selector` picks with a copy of `replacement`,
+/// without looking into the nodes it replaces.
+pub fn redact<'a, S: Selector>(value: &mut JsonValue<'a>, mut selector: S, replacement: JsonValue<'a>) {
+    let root = JsonPointer::root();
+    if selector.select(&root, value) {
+        *value = replacement;
+    } else {
+        redact_into(value, &root, &mut selector, &replacement);
+    }
+}
+
+/// Redacts the members and elements of `value`.
+fn redact_into<'a, S: Selector>(
+    value: &mut JsonValue<'a>,
+    pointer: &JsonPointer,
+    selector: &mut S,
+    replacement: &JsonValue<'a>,
+) {
+    match value {
+        JsonValue::Array(array) => {
+            for (index, element) in array.iter_mut().enumerate() {
+                let mut child = pointer.clone();
+                child.push(&index.to_string());
+                if selector.select(&child, element) {
+                    *element = replacement.clone();
+                } else {
+                    redact_into(element, &child, selector, replacement);
+                }
+            }
+        }
+        JsonValue::Object(obj) => {
+            for (key, member) in obj.iter_mut() {
+                let mut child = pointer.clone();
+                child.push(key);
+                if selector.select_member(&child, key, member) {
+                    *member = replacement.clone();
+                } else {
+                    redact_into(member, &child, selector, replacement);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_keys_and_pointers() {
+        let mut value = json!({
+            "macAddress": "a",
+            "cameras": [{"ipAddress": "10.0.0.1", "ipAddress2": {"macAddress": "b"}}, ["macAddress"]]
+        });
+        redact(&mut value, Keys(&["macAddress", "ipAddress"]), JsonValue::Null);
+        assert_eq!(
+            value,
+            json!({"macAddress": null, "cameras": [{"ipAddress": null, "ipAddress2": {"macAddress": null}}, ["macAddress"]]})
+        );
+        redact(&mut value, Pointers(&["/cameras/1", "/missing"]), json!("***"));
+        assert_eq!(value["cameras"][1], json!("***"));
+        redact(&mut value, |_: &JsonPointer, v: &JsonValue<'_>| v.is_null(), json!(0));
+        assert_eq!(value["cameras"][0]["ipAddress2"], json!({"macAddress": 0}));
+        redact(&mut value, Pointers(&[""]), json!([]));
+        assert_eq!(value, json!([]));
+    }
+
+    #[test]
+    fn map_strings_and_keys() {
+        let mut value = json!({"name": "ab", "list": ["c", 1, {"name": "d"}]});
+        map_strings(&mut value, |s| Some(s.to_uppercase()));
+        assert_eq!(value, json!({"name": "AB", "list": ["C", 1, {"name": "D"}]}));
+        map_keys(&mut value, |k| if k == "name" { Some(String::from("id")) } else { None });
+        assert_eq!(value, json!({"id": "AB", "list": ["C", 1, {"id": "D"}]}));
+    }
+}
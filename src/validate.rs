@@ -0,0 +1,36 @@
+use crate::{JsonError, JsonReader};
+
+/// Checks that `source` is a valid JSON document, reporting the same errors
+/// as [`try_parse`](crate::try_parse), without building any value.
+///
+/// Strings are checked but not decoded and duplicate keys are not looked
+/// for, so only the stack of open arrays and objects is allocated.
+pub fn validate(source: &str) -> Result<(), JsonError> {
+    JsonReader::new(source).end_of_document()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn same_outcome_as_parsing() {
+        let sources = [
+            r#"{"a": [1, {"b": null}], "c": "é"}"#,
+            "",
+            "[1,]",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            r#"{"a": 1 "b": 2}"#,
+            "[[]",
+            "[] []",
+            "[\"a\\q\"]",
+            &"[".repeat(200),
+        ];
+        for source in sources.iter() {
+            assert_eq!(validate(source), try_parse(source).map(|_| ()), "{}", source);
+        }
+    }
+}
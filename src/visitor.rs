@@ -0,0 +1,158 @@
+//! Push-based traversal: the document is read with a [`JsonReader`] and each
+//! event is handed to a [`JsonVisitor`].
+
+use crate::{JsonError, JsonEvent, JsonNumber, JsonReader, JsonValue};
+
+/// Returned by [`JsonVisitor`] callbacks to steer the traversal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visit {
+    Continue,
+    /// From `begin_object`/`begin_array`: skip the contents, the matching
+    /// `end_*` callback is not called. From `on_key`: skip the member value.
+    /// Elsewhere, same as `Continue`.
+    SkipChildren,
+    /// End the traversal successfully without reading the rest of the source.
+    Stop,
+}
+
+/// Callbacks of [`parse_with_visitor`]. They all do nothing by default.
+#[allow(unused_variables)]
+pub trait JsonVisitor {
+    fn begin_object(&mut self) -> Visit {
+        Visit::Continue
+    }
+    fn end_object(&mut self) -> Visit {
+        Visit::Continue
+    }
+    fn begin_array(&mut self) -> Visit {
+        Visit::Continue
+    }
+    fn end_array(&mut self) -> Visit {
+        Visit::Continue
+    }
+    fn on_key(&mut self, key: &str) -> Visit {
+        Visit::Continue
+    }
+    fn on_string(&mut self, value: &str) -> Visit {
+        Visit::Continue
+    }
+    fn on_number(&mut self, value: JsonNumber) -> Visit {
+        Visit::Continue
+    }
+    fn on_bool(&mut self, value: bool) -> Visit {
+        Visit::Continue
+    }
+    fn on_null(&mut self) -> Visit {
+        Visit::Continue
+    }
+}
+
+fn visit_value<V: JsonVisitor + ?Sized>(visitor: &mut V, value: &JsonValue) -> Visit {
+    match value {
+        JsonValue::String(s) => visitor.on_string(s),
+        JsonValue::Number(n) => visitor.on_number(*n),
+        JsonValue::Boolean(b) => visitor.on_bool(*b),
+        _ => visitor.on_null(),
+    }
+}
+
+/// Reads `source` and calls `visitor` for each part of the document. Errors
+/// are reported for the part of the source read before a [`Visit::Stop`].
+pub fn parse_with_visitor<V: JsonVisitor + ?Sized>(
+    source: &str,
+    visitor: &mut V,
+) -> Result<(), JsonError> {
+    let mut reader = JsonReader::new(source);
+    while let Some(event) = reader.next_event()? {
+        let visit = match &event {
+            JsonEvent::StartObject => visitor.begin_object(),
+            JsonEvent::EndObject => visitor.end_object(),
+            JsonEvent::StartArray => visitor.begin_array(),
+            JsonEvent::EndArray => visitor.end_array(),
+            JsonEvent::Key(key) => visitor.on_key(key),
+            JsonEvent::Value(value) => visit_value(visitor, value),
+        };
+        match (visit, event) {
+            (Visit::Stop, _) => return Ok(()),
+            (Visit::SkipChildren, JsonEvent::StartObject)
+            | (Visit::SkipChildren, JsonEvent::StartArray) => reader.skip_container()?,
+            (Visit::SkipChildren, JsonEvent::Key(_)) => match reader.next_event()? {
+                Some(JsonEvent::StartObject) | Some(JsonEvent::StartArray) => reader.skip_container()?,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the callbacks as text, skipping members named `skip` and
+    /// stopping at a `stop` string.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl JsonVisitor for Recorder {
+        fn begin_object(&mut self) -> Visit {
+            self.0.push("{".into());
+            Visit::Continue
+        }
+        fn end_object(&mut self) -> Visit {
+            self.0.push("}".into());
+            Visit::Continue
+        }
+        fn begin_array(&mut self) -> Visit {
+            self.0.push("[".into());
+            if self.0.len() > 8 {
+                Visit::SkipChildren
+            } else {
+                Visit::Continue
+            }
+        }
+        fn end_array(&mut self) -> Visit {
+            self.0.push("]".into());
+            Visit::Continue
+        }
+        fn on_key(&mut self, key: &str) -> Visit {
+            self.0.push(format!("{}:", key));
+            if key == "skip" {
+                Visit::SkipChildren
+            } else {
+                Visit::Continue
+            }
+        }
+        fn on_string(&mut self, value: &str) -> Visit {
+            self.0.push(value.into());
+            if value == "stop" {
+                Visit::Stop
+            } else {
+                Visit::Continue
+            }
+        }
+        fn on_number(&mut self, value: JsonNumber) -> Visit {
+            self.0.push(format!("{:?}", value));
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn callbacks_and_control_flow() {
+        let mut recorder = Recorder::default();
+        let source = r#"{"skip": {"a": [1]}, "b": [2.5, true, null], "c": [[3]], "d": "stop", "e": 4} trailing"#;
+        parse_with_visitor(source, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            vec!["{", "skip:", "b:", "[", "Float(2.5)", "]", "c:", "[", "[", "]", "d:", "stop"]
+        );
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let mut recorder = Recorder::default();
+        assert!(parse_with_visitor(r#"{"skip": [1,], "a": 1}"#, &mut recorder).is_err());
+        assert!(parse_with_visitor("[1 2]", &mut recorder).is_err());
+    }
+}
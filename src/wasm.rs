@@ -0,0 +1,154 @@
+//! Bindings for JavaScript through `wasm-bindgen`, to run the same parsing
+//! in the browser, and conversions between values and `JsValue`.
+//!
+//! The exported functions are, in JavaScript:
+//!
+//! - `parse(source, options?)`, where `options` may set `json5`,
+//!   `allowComments`, `allowNonFinite` and `lenient` to `true`, throws on
+//!   invalid documents unless `lenient` is set,
+//! - `stringify(value, indent?)` writes compact text, or pretty text with
+//!   `indent` spaces,
+//! - `pointer(value, pointer)` returns the value a JSON Pointer designates
+//!   or `undefined`,
+//! - `validate(source)` returns the message of the syntax error of
+//!   `source`, or `null` for a valid document.
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::prelude::*;
+use crate::{JsonNumber, JsonValue, JsonValueOwned, Map, ParseOptions, PrettyConfig};
+
+/// Converts `value` to a JavaScript value. Numbers become doubles, losing
+/// the precision of integers beyond 2^53.
+pub fn to_js(value: &JsonValue) -> JsValue {
+    match value {
+        JsonValue::Null => JsValue::NULL,
+        JsonValue::Boolean(b) => JsValue::from_bool(*b),
+        JsonValue::Number(n) => JsValue::from_f64(n.as_f64_lossy()),
+        JsonValue::String(s) => JsValue::from_str(s),
+        JsonValue::Array(array) => array.iter().map(to_js).collect::<Array>().into(),
+        JsonValue::Object(obj) => {
+            let js = Object::new();
+            for (key, member) in obj {
+                // Cannot fail on a plain object.
+                let _ = Reflect::set(&js, &JsValue::from_str(key), &to_js(member));
+            }
+            js.into()
+        }
+    }
+}
+
+/// Converts a JavaScript value, as `JSON.stringify` would see it: `undefined`
+/// becomes `null`, objects are read through their own enumerable string
+/// keys, doubles without a fractional part become integers and `BigInt`s
+/// integers when they fit in 64 bits. Functions and symbols are errors,
+/// as are cycles, caught by the depth limit of [`ParseOptions`].
+pub fn from_js(value: &JsValue) -> Result<JsonValueOwned, JsError> {
+    from_js_at(value, 0)
+}
+
+fn from_js_at(value: &JsValue, depth: usize) -> Result<JsonValueOwned, JsError> {
+    if value.is_null_or_undefined() {
+        return Ok(JsonValue::Null);
+    }
+    if let Some(b) = value.as_bool() {
+        return Ok(JsonValue::Boolean(b));
+    }
+    if let Some(f) = value.as_f64() {
+        const EXACT: f64 = 9_007_199_254_740_992.0;
+        return Ok(JsonValue::Number(if f > -EXACT && f < EXACT && f as i64 as f64 == f {
+            JsonNumber::Integer(f as i64)
+        } else {
+            JsonNumber::Float(f)
+        }));
+    }
+    if let Some(s) = value.as_string() {
+        return Ok(JsonValue::String(Cow::Owned(s)));
+    }
+    if value.is_bigint() {
+        return match (i64::try_from(value.clone()), u64::try_from(value.clone())) {
+            (Ok(n), _) => Ok(JsonValue::Number(JsonNumber::Integer(n))),
+            (_, Ok(n)) => Ok(JsonValue::Number(JsonNumber::Unsigned(n))),
+            _ => Err(JsError::new("BigInt out of the 64-bit range")),
+        };
+    }
+    if value.is_function() || value.is_symbol() {
+        return Err(JsError::new("Functions and symbols have no JSON equivalent"));
+    }
+    if depth == ParseOptions::default().max_depth {
+        return Err(JsError::new("Nesting depth exceeds the limit"));
+    }
+    if Array::is_array(value) {
+        let array = Array::from(value);
+        return array
+            .iter()
+            .map(|element| from_js_at(&element, depth + 1))
+            .collect::<Result<_, _>>()
+            .map(JsonValue::Array);
+    }
+    let mut obj = Map::new();
+    for entry in Object::entries(&Object::from(value.clone())).iter() {
+        let entry = Array::from(&entry);
+        let key = entry.get(0).as_string().unwrap_or_default();
+        obj.insert(Cow::Owned(key), from_js_at(&entry.get(1), depth + 1)?);
+    }
+    Ok(JsonValue::Object(obj))
+}
+
+/// Whether the member `key` of the options object `options` is `true`.
+fn option(options: &JsValue, key: &str) -> bool {
+    !options.is_null_or_undefined()
+        && Reflect::get(options, &JsValue::from_str(key))
+            .ok()
+            .and_then(|v| v.as_bool())
+            == Some(true)
+}
+
+/// `parse(source, options?)` in JavaScript, see the module documentation.
+#[wasm_bindgen(js_name = parse)]
+pub fn parse_js(source: &str, options: JsValue) -> Result<JsValue, JsError> {
+    if option(&options, "lenient") {
+        return Ok(to_js(&crate::parse_lenient(source).0));
+    }
+    let options = ParseOptions {
+        json5: option(&options, "json5"),
+        allow_comments: option(&options, "allowComments"),
+        allow_non_finite: option(&options, "allowNonFinite"),
+        ..ParseOptions::default()
+    };
+    let value = crate::try_parse_with(source, options).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(to_js(&value))
+}
+
+/// `stringify(value, indent?)` in JavaScript.
+#[wasm_bindgen(js_name = stringify)]
+pub fn stringify_js(value: JsValue, indent: Option<u32>) -> Result<String, JsError> {
+    let value = from_js(&value)?;
+    Ok(match indent {
+        None => crate::to_string(&value),
+        Some(indent) => {
+            let config = PrettyConfig {
+                indent_width: indent as usize,
+                ..PrettyConfig::default()
+            };
+            crate::to_string_pretty(&value, config)
+        }
+    })
+}
+
+/// `pointer(value, pointer)` in JavaScript.
+#[wasm_bindgen(js_name = pointer)]
+pub fn pointer_js(value: JsValue, pointer: &str) -> Result<JsValue, JsError> {
+    let value = from_js(&value)?;
+    Ok(value.pointer(pointer).map_or(JsValue::UNDEFINED, to_js))
+}
+
+/// `validate(source)` in JavaScript.
+#[wasm_bindgen(js_name = validate)]
+pub fn validate_js(source: &str) -> Option<String> {
+    crate::validate(source).err().map(|e| e.to_string())
+}
@@ -0,0 +1,222 @@
+//! Conversion between values and YAML documents, e.g. to query Kubernetes
+//! manifests with [`JsonValue::pointer`] or [`jsonpath`](crate::jsonpath).
+//!
+//! ```
+//! use yaj::{json, yaml};
+//! let manifest = "kind: Service\nspec:\n  ports:\n  - port: 80\n";
+//! let value = yaml::from_yaml_str(manifest).unwrap();
+//! assert_eq!(value.pointer("/spec/ports/0/port"), Some(&json!(80)));
+//! assert_eq!(yaml::to_yaml_string(&value).unwrap(), manifest);
+//! ```
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use serde::Deserialize;
+use serde_yaml::{Mapping, Number, Value};
+
+use crate::pointer::JsonPointer;
+use crate::prelude::*;
+use crate::{stable_members, JsonNumber, JsonValue, JsonValueOwned, Map};
+
+/// A YAML document that cannot be read as a value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum YamlError {
+    /// The message of the `serde_yaml` crate, e.g. for a syntax error.
+    Yaml(String),
+    /// A YAML node with no JSON equivalent.
+    Unsupported { msg: &'static str, pointer: JsonPointer },
+}
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YamlError::Yaml(msg) => f.write_str(msg),
+            YamlError::Unsupported { msg, pointer } => write!(f, "{} at '{}'", msg, pointer),
+        }
+    }
+}
+impl core::error::Error for YamlError {}
+
+impl From<serde_yaml::Error> for YamlError {
+    fn from(error: serde_yaml::Error) -> Self {
+        YamlError::Yaml(error.to_string())
+    }
+}
+
+/// Reads a single YAML document, an empty one being `null`.
+///
+/// Tags are ignored. Keys that are numbers, booleans or `null` become
+/// their text, and keys that are sequences or mappings are errors.
+pub fn from_yaml_str(source: &str) -> Result<JsonValueOwned, YamlError> {
+    to_value(serde_yaml::from_str(source)?, &JsonPointer::root())
+}
+
+/// Reads every document of a stream separated by `---`, as in manifests
+/// bundling several Kubernetes resources.
+///
+/// ```
+/// let docs = yaj::yaml::from_yaml_documents("kind: Service\n---\nkind: Deployment\n").unwrap();
+/// assert_eq!(docs, [yaj::json!({"kind": "Service"}), yaj::json!({"kind": "Deployment"})]);
+/// ```
+pub fn from_yaml_documents(source: &str) -> Result<Vec<JsonValueOwned>, YamlError> {
+    serde_yaml::Deserializer::from_str(source)
+        .map(|document| to_value(Value::deserialize(document)?, &JsonPointer::root()))
+        .collect()
+}
+
+fn to_value(value: Value, pointer: &JsonPointer) -> Result<JsonValueOwned, YamlError> {
+    Ok(match value {
+        Value::Null => JsonValue::Null,
+        Value::Bool(b) => JsonValue::Boolean(b),
+        Value::Number(n) => JsonValue::Number(number(&n)),
+        Value::String(s) => JsonValue::String(Cow::Owned(s)),
+        Value::Sequence(sequence) => {
+            let mut array = Vec::with_capacity(sequence.len());
+            for (index, element) in sequence.into_iter().enumerate() {
+                let mut child = pointer.clone();
+                child.push(&index.to_string());
+                array.push(to_value(element, &child)?);
+            }
+            JsonValue::Array(array)
+        }
+        Value::Mapping(mapping) => {
+            let mut obj = Map::new();
+            for (key, member) in mapping {
+                let key = match key_text(key) {
+                    Some(key) => key,
+                    None => {
+                        return Err(YamlError::Unsupported {
+                            msg: "Mapping key that is not a scalar",
+                            pointer: pointer.clone(),
+                        })
+                    }
+                };
+                let mut child = pointer.clone();
+                child.push(&key);
+                let member = to_value(member, &child)?;
+                obj.insert(Cow::Owned(key), member);
+            }
+            JsonValue::Object(obj)
+        }
+        Value::Tagged(tagged) => to_value(tagged.value, pointer)?,
+    })
+}
+
+fn number(n: &Number) -> JsonNumber {
+    if let Some(n) = n.as_i64() {
+        JsonNumber::Integer(n)
+    } else if let Some(n) = n.as_u64() {
+        JsonNumber::Unsigned(n)
+    } else {
+        JsonNumber::Float(n.as_f64().unwrap_or(f64::NAN))
+    }
+}
+
+fn key_text(key: Value) -> Option<String> {
+    match key {
+        Value::Null => Some(String::from("null")),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s),
+        Value::Tagged(tagged) => key_text(tagged.value),
+        Value::Sequence(_) | Value::Mapping(_) => None,
+    }
+}
+
+/// Writes `value` as a YAML document, with the members of objects in the
+/// order of [`to_string`](crate::to_string).
+pub fn to_yaml_string(value: &JsonValue) -> Result<String, YamlError> {
+    Ok(serde_yaml::to_string(&to_yaml(value))?)
+}
+
+/// Writes `values` as a stream of documents separated by `---`.
+pub fn to_yaml_documents(values: &[JsonValue]) -> Result<String, YamlError> {
+    let mut out = String::new();
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            out.push_str("---\n");
+        }
+        out.push_str(&to_yaml_string(value)?);
+    }
+    Ok(out)
+}
+
+fn to_yaml(value: &JsonValue) -> Value {
+    match value {
+        JsonValue::Null => Value::Null,
+        JsonValue::Boolean(b) => Value::Bool(*b),
+        JsonValue::Number(JsonNumber::Integer(n)) => Value::Number((*n).into()),
+        JsonValue::Number(JsonNumber::Unsigned(n)) => Value::Number((*n).into()),
+        JsonValue::Number(JsonNumber::Float(f)) => Value::Number((*f).into()),
+        JsonValue::String(s) => Value::String(String::from(&**s)),
+        JsonValue::Array(array) => Value::Sequence(array.iter().map(to_yaml).collect()),
+        JsonValue::Object(obj) => {
+            let mut mapping = Mapping::with_capacity(obj.len());
+            for (key, member) in stable_members(obj) {
+                mapping.insert(Value::String(String::from(&**key)), to_yaml(member));
+            }
+            Value::Mapping(mapping)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_yaml() {
+        let source = "
+apiVersion: v1
+metadata:
+  labels: &labels {app: web}
+  copy: *labels
+data:
+  1: one
+  true: yes
+  ~: nothing
+  big: 18446744073709551615
+  ratio: .5
+  tagged: !custom [a, 'b']
+";
+        assert_eq!(
+            from_yaml_str(source).unwrap(),
+            json!({
+                "apiVersion": "v1",
+                "data": {
+                    "1": "one",
+                    "big": 18446744073709551615u64,
+                    "null": "nothing",
+                    "ratio": 0.5,
+                    "tagged": ["a", "b"],
+                    "true": "yes",
+                },
+                "metadata": {"copy": {"app": "web"}, "labels": {"app": "web"}},
+            })
+        );
+        assert_eq!(from_yaml_str("").unwrap(), JsonValue::Null);
+        let error = from_yaml_str("a:\n  ? [1]\n  : x\n").unwrap_err();
+        assert_eq!(error.to_string(), "Mapping key that is not a scalar at '/a'");
+        assert!(matches!(from_yaml_str("a: [1"), Err(YamlError::Yaml(_))));
+        assert!(matches!(from_yaml_str("a: 1\n---\nb: 2\n"), Err(YamlError::Yaml(_))));
+        assert_eq!(
+            from_yaml_documents("a: 1\n---\n- 2\n").unwrap(),
+            [json!({"a": 1}), json!([2])]
+        );
+    }
+
+    #[test]
+    fn to_yaml_documents_round_trip() {
+        let values = [
+            json!({"b": [1, -2, 0.5, null, "x: y"], "a": {"c": true, "d": {}}}),
+            json!("text"),
+        ];
+        let text = to_yaml_documents(&values).unwrap();
+        #[cfg(not(feature = "preserve_order"))]
+        assert_eq!(
+            text,
+            "a:\n  c: true\n  d: {}\nb:\n- 1\n- -2\n- 0.5\n- null\n- 'x: y'\n---\ntext\n"
+        );
+        assert_eq!(from_yaml_documents(&text).unwrap(), values);
+    }
+}
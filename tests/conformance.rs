@@ -0,0 +1,51 @@
+//! Runs `yaj::conformance::check` on the files of JSONTestSuite
+//! (https://github.com/nst/JSONTestSuite), whose names tell the expected
+//! verdict: `y_` accepted, `n_` rejected and `i_` implementation-defined.
+//!
+//! Run with `JSON_TEST_SUITE=path/to/JSONTestSuite/test_parsing cargo test
+//! --test conformance`. Without the variable there is nothing to check.
+
+use std::fs;
+
+use yaj::conformance::{check, Verdict};
+
+#[test]
+fn json_test_suite() {
+    let dir = match std::env::var_os("JSON_TEST_SUITE") {
+        Some(dir) => dir,
+        None => {
+            eprintln!("JSON_TEST_SUITE is not set, skipping the conformance suite");
+            return;
+        }
+    };
+    let mut entries: Vec<_> = fs::read_dir(&dir)
+        .expect("JSON_TEST_SUITE is not a directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    entries.sort();
+    assert!(!entries.is_empty(), "no test files in {:?}", dir);
+
+    let mut failures = Vec::new();
+    for path in &entries {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let source = fs::read(path).unwrap();
+        let verdict = check(&source);
+        let expected = match &name[..2] {
+            "y_" => verdict == Verdict::Accept,
+            "n_" => verdict == Verdict::Reject,
+            "i_" => matches!(verdict, Verdict::ImplementationDefined { .. }),
+            _ => continue,
+        };
+        if !expected {
+            failures.push(format!("{}: {:?}", name, verdict));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} files got an unexpected verdict:\n{}",
+        failures.len(),
+        entries.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,371 @@
+//! `#[derive(ToJson, FromJson)]` for the `yaj::ToJson` and `yaj::FromJson`
+//! traits, re-exported by `yaj` with its `derive` feature.
+//!
+//! Structs with named fields are objects, tuple structs arrays, newtypes
+//! their only field and unit structs `null`. Unit variants of enums are
+//! strings, other variants objects with the variant name as single key.
+//!
+//! Attributes:
+//! - `#[json(rename_all = "camelCase")]` on a struct or an enum renames its
+//!   fields or variants. `snake_case`, `kebab-case`, `PascalCase`,
+//!   `SCREAMING_SNAKE_CASE`, `lowercase` and `UPPERCASE` are supported too;
+//! - `#[json(rename = "name")]` on a field or a variant;
+//! - `#[json(default)]` on a field, which is then read as
+//!   `Default::default()` when missing, or `#[json(default = "path")]` to
+//!   call a function instead. Missing `Option` fields are always `None`.
+
+extern crate proc_macro;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, Ident, LitStr, Path};
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, Direction::To).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input, Direction::From).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    To,
+    From,
+}
+
+#[derive(Clone, Copy)]
+enum RenameAll {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+}
+
+impl RenameAll {
+    fn parse(lit: &LitStr) -> Result<Self, Error> {
+        Ok(match lit.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            _ => return Err(Error::new(lit.span(), "unknown case convention")),
+        })
+    }
+
+    /// Renames a field in snake case or a variant in Pascal case.
+    fn apply(self, name: &str) -> String {
+        let mut words = Vec::new();
+        let mut word = String::new();
+        for chr in name.chars() {
+            if (chr == '_' || chr.is_uppercase()) && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            if chr != '_' {
+                word.extend(chr.to_lowercase());
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+        };
+        match self {
+            Self::Lower => words.concat(),
+            Self::Upper => words.concat().to_uppercase(),
+            Self::Pascal => words.iter().map(capitalize).collect(),
+            Self::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(idx, word)| if idx == 0 { word.clone() } else { capitalize(word) })
+                .collect(),
+            Self::Snake => words.join("_"),
+            Self::ScreamingSnake => words.join("_").to_uppercase(),
+            Self::Kebab => words.join("-"),
+        }
+    }
+}
+
+enum FieldDefault {
+    Trait,
+    Function(Path),
+}
+
+/// The `#[json(...)]` attributes of an item.
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    rename_all: Option<RenameAll>,
+    default: Option<FieldDefault>,
+}
+
+fn parse_attrs(attrs: &[Attribute]) -> Result<Attrs, Error> {
+    let mut parsed = Attrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("json")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                parsed.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("rename_all") {
+                parsed.rename_all = Some(RenameAll::parse(&meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("default") {
+                parsed.default = Some(if meta.input.peek(syn::Token![=]) {
+                    FieldDefault::Function(meta.value()?.parse::<LitStr>()?.parse()?)
+                } else {
+                    FieldDefault::Trait
+                });
+            } else {
+                return Err(meta.error("unknown json attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(parsed)
+}
+
+/// A field with the key it is written under.
+struct Field {
+    /// Member of the struct, `0` for the first field of a tuple struct.
+    member: syn::Member,
+    /// Name bound to the field when matching a variant.
+    binding: Ident,
+    key: String,
+    default: Option<FieldDefault>,
+}
+
+fn fields(fields: &Fields, rename_all: Option<RenameAll>) -> Result<Vec<Field>, Error> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let attrs = parse_attrs(&field.attrs)?;
+            let (member, binding, name) = match &field.ident {
+                Some(ident) => {
+                    let name = ident.to_string();
+                    let name = name.strip_prefix("r#").unwrap_or(&name).to_string();
+                    (syn::Member::Named(ident.clone()), format_ident!("field_{}", name), name)
+                }
+                None => (syn::Member::Unnamed(idx.into()), format_ident!("field_{}", idx), idx.to_string()),
+            };
+            let key = match (attrs.rename, rename_all) {
+                (Some(rename), _) => rename,
+                (None, Some(rename_all)) => rename_all.apply(&name),
+                (None, None) => name,
+            };
+            Ok(Field {
+                member,
+                binding,
+                key,
+                default: attrs.default,
+            })
+        })
+        .collect()
+}
+
+/// Writes the fields bound to their `binding` names.
+fn to_json_fields(style: &Fields, fields: &[Field]) -> TokenStream {
+    let bindings = fields.iter().map(|field| &field.binding);
+    match style {
+        Fields::Named(_) => {
+            let keys = fields.iter().map(|field| &field.key);
+            quote! {{
+                let mut object = ::yaj::Map::new();
+                #(object.insert(::yaj::__private::Cow::Borrowed(#keys), ::yaj::ToJson::to_json(#bindings));)*
+                ::yaj::JsonValue::Object(object)
+            }}
+        }
+        Fields::Unnamed(_) if fields.len() == 1 => quote! { ::yaj::ToJson::to_json(#(#bindings)*) },
+        Fields::Unnamed(_) => quote! {
+            ::yaj::JsonValue::Array(::yaj::__private::vec![#(::yaj::ToJson::to_json(#bindings)),*])
+        },
+        Fields::Unit => quote! { ::yaj::JsonValue::Null },
+    }
+}
+
+/// Reads the fields from `value` and builds `constructor` with them.
+fn from_json_fields(style: &Fields, fields: &[Field], constructor: TokenStream, value: TokenStream) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    match style {
+        Fields::Named(_) => {
+            let reads = fields.iter().map(|field| {
+                let key = &field.key;
+                let missing = match &field.default {
+                    Some(FieldDefault::Trait) => quote! { ::core::default::Default::default() },
+                    Some(FieldDefault::Function(path)) => quote! { #path() },
+                    None => quote! { ::yaj::FromJson::from_missing().map_err(|e| e.in_member(#key))? },
+                };
+                quote! {
+                    match object.get(#key) {
+                        ::core::option::Option::Some(member) => {
+                            ::yaj::FromJson::from_json(member).map_err(|e| e.in_member(#key))?
+                        }
+                        ::core::option::Option::None => #missing,
+                    }
+                }
+            });
+            quote! {{
+                let object = #value
+                    .as_object()
+                    .ok_or_else(|| ::yaj::FromJsonError::unexpected("an object", #value))?;
+                #constructor { #(#members: #reads),* }
+            }}
+        }
+        Fields::Unnamed(_) if fields.len() == 1 => quote! {
+            #constructor(::yaj::FromJson::from_json(#value)?)
+        },
+        Fields::Unnamed(_) => {
+            let len = fields.len();
+            let expected = format!("an array of {} elements", len);
+            let indices = 0..len;
+            quote! {{
+                let array = match #value.as_array() {
+                    ::core::option::Option::Some(array) if array.len() == #len => array,
+                    _ => return ::core::result::Result::Err(::yaj::FromJsonError::unexpected(#expected, #value)),
+                };
+                #constructor(#(::yaj::FromJson::from_json(&array[#indices]).map_err(|e| e.at_index(#indices))?),*)
+            }}
+        }
+        Fields::Unit => quote! {{
+            if !#value.is_null() {
+                return ::core::result::Result::Err(::yaj::FromJsonError::unexpected("null", #value));
+            }
+            #constructor
+        }},
+    }
+}
+
+/// Pattern binding the fields of `path` to their `binding` names.
+fn pattern(path: TokenStream, style: &Fields, fields: &[Field]) -> TokenStream {
+    let members = fields.iter().map(|field| &field.member);
+    let bindings = fields.iter().map(|field| &field.binding);
+    match style {
+        Fields::Named(_) | Fields::Unnamed(_) => quote! { #path { #(#members: #bindings),* } },
+        Fields::Unit => path,
+    }
+}
+
+fn expand(mut input: DeriveInput, direction: Direction) -> Result<TokenStream, Error> {
+    let attrs = parse_attrs(&input.attrs)?;
+    let name = &input.ident;
+    let bound: Path = match direction {
+        Direction::To => parse_quote!(::yaj::ToJson),
+        Direction::From => parse_quote!(::yaj::FromJson),
+    };
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match (&input.data, direction) {
+        (Data::Struct(data), Direction::To) => {
+            let fields = fields(&data.fields, attrs.rename_all)?;
+            let pattern = pattern(quote!(Self), &data.fields, &fields);
+            let write = to_json_fields(&data.fields, &fields);
+            quote! {
+                let #pattern = self;
+                #write
+            }
+        }
+        (Data::Struct(data), Direction::From) => {
+            let fields = fields(&data.fields, attrs.rename_all)?;
+            let read = from_json_fields(&data.fields, &fields, quote!(Self), quote!(value));
+            quote! { ::core::result::Result::Ok(#read) }
+        }
+        (Data::Enum(data), _) => {
+            let mut arms = Vec::new();
+            let mut unit_arms = Vec::new();
+            for variant in &data.variants {
+                let variant_attrs = parse_attrs(&variant.attrs)?;
+                let ident = &variant.ident;
+                let key = match (variant_attrs.rename, attrs.rename_all) {
+                    (Some(rename), _) => rename,
+                    (None, Some(rename_all)) => rename_all.apply(&ident.to_string()),
+                    (None, None) => ident.to_string(),
+                };
+                let fields = fields(&variant.fields, variant_attrs.rename_all)?;
+                match (direction, &variant.fields) {
+                    (Direction::To, Fields::Unit) => arms.push(quote! {
+                        Self::#ident => ::yaj::JsonValue::String(::yaj::__private::Cow::Borrowed(#key)),
+                    }),
+                    (Direction::To, style) => {
+                        let pattern = pattern(quote!(Self::#ident), style, &fields);
+                        let write = to_json_fields(style, &fields);
+                        arms.push(quote! {
+                            #pattern => {
+                                let mut object = ::yaj::Map::new();
+                                object.insert(::yaj::__private::Cow::Borrowed(#key), #write);
+                                ::yaj::JsonValue::Object(object)
+                            }
+                        });
+                    }
+                    (Direction::From, Fields::Unit) => unit_arms.push(quote! {
+                        #key => ::core::result::Result::Ok(Self::#ident),
+                    }),
+                    (Direction::From, style) => {
+                        let read = from_json_fields(style, &fields, quote!(Self::#ident), quote!(inner));
+                        arms.push(quote! {
+                            #key => (|| ::core::result::Result::Ok(#read))().map_err(|e: ::yaj::FromJsonError| e.in_member(#key)),
+                        });
+                    }
+                }
+            }
+            let expected = "a string or an object with a single member";
+            match direction {
+                Direction::To => quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                },
+                Direction::From => quote! {
+                    match value {
+                        ::yaj::JsonValue::String(name) => match &**name {
+                            #(#unit_arms)*
+                            other => ::core::result::Result::Err(::yaj::FromJsonError::new(
+                                ::yaj::__private::format!("Unknown variant `{}`", other),
+                            )),
+                        },
+                        ::yaj::JsonValue::Object(object) => match (object.len(), object.iter().next()) {
+                            (1, ::core::option::Option::Some((name, inner))) => match &**name {
+                                #(#arms)*
+                                other => ::core::result::Result::Err(::yaj::FromJsonError::new(
+                                    ::yaj::__private::format!("Unknown variant `{}`", other),
+                                )),
+                            },
+                            _ => ::core::result::Result::Err(::yaj::FromJsonError::unexpected(#expected, value)),
+                        },
+                        _ => ::core::result::Result::Err(::yaj::FromJsonError::unexpected(#expected, value)),
+                    }
+                },
+            }
+        }
+        (Data::Union(_), _) => return Err(Error::new(Span::call_site(), "unions are not supported")),
+    };
+
+    Ok(match direction {
+        Direction::To => quote! {
+            impl #impl_generics ::yaj::ToJson for #name #ty_generics #where_clause {
+                fn to_json(&self) -> ::yaj::JsonValueOwned {
+                    #body
+                }
+            }
+        },
+        Direction::From => quote! {
+            impl #impl_generics ::yaj::FromJson for #name #ty_generics #where_clause {
+                fn from_json(value: &::yaj::JsonValue<'_>) -> ::core::result::Result<Self, ::yaj::FromJsonError> {
+                    #body
+                }
+            }
+        },
+    })
+}
@@ -0,0 +1,76 @@
+#ifndef YAJ_H
+#define YAJ_H
+
+/* Generated by cbindgen from yaj_ffi/src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct YajValue YajValue;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Parses the `len` bytes at `source`, which need not be NUL-terminated.
+//
+// Returns the document, or `NULL` when `source` is not a valid UTF-8 JSON
+// document; the message of the error is then stored in `*error` unless
+// `error` is `NULL`.
+//
+// # Safety
+//
+// `source` must point to `len` readable bytes, and `error` must be `NULL`
+// or valid for writes.
+YajValue *yaj_parse(const char *source, uintptr_t len, char **error);
+
+// Looks up the value designated by the JSON Pointer `pointer` in `value`,
+// e.g. `/users/0/name`.
+//
+// Returns `NULL` when there is no such value. The value returned belongs
+// to the document: it must not be freed, and is valid until the document
+// is freed.
+//
+// # Safety
+//
+// `value` must be `NULL` or a value returned by this library and not
+// freed yet, and `pointer` `NULL` or a NUL-terminated string.
+const YajValue *yaj_get_by_pointer(const YajValue *value, const char *pointer);
+
+// Serializes `value` as compact text, or indented with two spaces when
+// `pretty` is true.
+//
+// Returns a string to release with [`yaj_string_free`], or `NULL` when
+// `value` is `NULL`.
+//
+// # Safety
+//
+// `value` must be `NULL` or a value returned by this library and not
+// freed yet.
+char *yaj_to_string(const YajValue *value, bool pretty);
+
+// Frees a document returned by [`yaj_parse`], and with it the values
+// found in it. Does nothing when `value` is `NULL`.
+//
+// # Safety
+//
+// `value` must be `NULL` or a document returned by [`yaj_parse`] and not
+// freed yet; values returned by [`yaj_get_by_pointer`] must not be given.
+void yaj_free(YajValue *value);
+
+// Frees a string returned by this library. Does nothing when `s` is
+// `NULL`.
+//
+// # Safety
+//
+// `s` must be `NULL` or a string returned by this library and not freed
+// yet.
+void yaj_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* YAJ_H */
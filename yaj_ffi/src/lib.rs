@@ -0,0 +1,177 @@
+//! C API of yaj: documents are parsed into opaque handles, which are
+//! queried with JSON Pointers and serialized back to text.
+//!
+//! Strings are UTF-8 and NUL-terminated. Every string returned is owned by
+//! the caller and released with [`yaj_string_free`], every document with
+//! [`yaj_free`]. The header `include/yaj.h` is generated by cbindgen.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use yaj::{JsonValueOwned, PrettyConfig};
+
+/// A parsed document, or a value inside one.
+#[repr(transparent)]
+pub struct YajValue(JsonValueOwned);
+
+/// `s` as a C string, NUL bytes being cut off since C cannot see past them.
+fn c_string(mut s: String) -> *mut c_char {
+    if let Some(nul) = s.find('\0') {
+        s.truncate(nul);
+    }
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Parses the `len` bytes at `source`, which need not be NUL-terminated.
+///
+/// Returns the document, or `NULL` when `source` is not a valid UTF-8 JSON
+/// document; the message of the error is then stored in `*error` unless
+/// `error` is `NULL`.
+///
+/// # Safety
+///
+/// `source` must point to `len` readable bytes, and `error` must be `NULL`
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn yaj_parse(source: *const c_char, len: usize, error: *mut *mut c_char) -> *mut YajValue {
+    let fail = |msg: String| {
+        if !error.is_null() {
+            *error = c_string(msg);
+        }
+        ptr::null_mut()
+    };
+    if source.is_null() {
+        return fail(String::from("Null source"));
+    }
+    let bytes = std::slice::from_raw_parts(source as *const u8, len);
+    match yaj::from_slice(bytes) {
+        Ok(value) => Box::into_raw(Box::new(YajValue(value.into_owned()))),
+        Err(e) => fail(e.to_string()),
+    }
+}
+
+/// Looks up the value designated by the JSON Pointer `pointer` in `value`,
+/// e.g. `/users/0/name`.
+///
+/// Returns `NULL` when there is no such value. The value returned belongs
+/// to the document: it must not be freed, and is valid until the document
+/// is freed.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a value returned by this library and not
+/// freed yet, and `pointer` `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn yaj_get_by_pointer(value: *const YajValue, pointer: *const c_char) -> *const YajValue {
+    if value.is_null() || pointer.is_null() {
+        return ptr::null();
+    }
+    let pointer = match CStr::from_ptr(pointer).to_str() {
+        Ok(pointer) => pointer,
+        Err(_) => return ptr::null(),
+    };
+    match (*value).0.pointer(pointer) {
+        Some(found) => found as *const JsonValueOwned as *const YajValue,
+        None => ptr::null(),
+    }
+}
+
+/// Serializes `value` as compact text, or indented with two spaces when
+/// `pretty` is true.
+///
+/// Returns a string to release with [`yaj_string_free`], or `NULL` when
+/// `value` is `NULL`.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a value returned by this library and not
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn yaj_to_string(value: *const YajValue, pretty: bool) -> *mut c_char {
+    if value.is_null() {
+        return ptr::null_mut();
+    }
+    let value = &(*value).0;
+    c_string(if pretty {
+        yaj::to_string_pretty(value, PrettyConfig::default())
+    } else {
+        yaj::to_string(value)
+    })
+}
+
+/// Frees a document returned by [`yaj_parse`], and with it the values
+/// found in it. Does nothing when `value` is `NULL`.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a document returned by [`yaj_parse`] and not
+/// freed yet; values returned by [`yaj_get_by_pointer`] must not be given.
+#[no_mangle]
+pub unsafe extern "C" fn yaj_free(value: *mut YajValue) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+/// Frees a string returned by this library. Does nothing when `s` is
+/// `NULL`.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by this library and not freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn yaj_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null());
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_owned();
+        yaj_string_free(s);
+        owned
+    }
+
+    #[test]
+    fn parse_query_serialize() {
+        let source = r#"{"users": [{"tags": [1, null, "Zoë"]}]}"#;
+        unsafe {
+            let mut error = ptr::null_mut();
+            let doc = yaj_parse(source.as_ptr() as *const c_char, source.len(), &mut error);
+            assert!(!doc.is_null() && error.is_null());
+            let user = yaj_get_by_pointer(doc, b"/users/0\0".as_ptr() as *const c_char);
+            assert_eq!(take(yaj_to_string(user, false)), r#"{"tags":[1,null,"Zoë"]}"#);
+            let tags = yaj_get_by_pointer(user, b"/tags\0".as_ptr() as *const c_char);
+            assert_eq!(take(yaj_to_string(tags, true)), "[\n  1,\n  null,\n  \"Zoë\"\n]");
+            assert!(yaj_get_by_pointer(doc, b"/users/1\0".as_ptr() as *const c_char).is_null());
+            assert!(yaj_get_by_pointer(doc, ptr::null()).is_null());
+            assert!(yaj_to_string(ptr::null(), false).is_null());
+            yaj_free(doc);
+            yaj_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let source = "[1, 2";
+            assert!(yaj_parse(source.as_ptr() as *const c_char, source.len(), &mut error).is_null());
+            let expected = yaj::try_parse(source).unwrap_err().to_string();
+            assert_eq!(take(error), expected);
+
+            let mut error = ptr::null_mut();
+            let source = b"\"\xff\"";
+            assert!(yaj_parse(source.as_ptr() as *const c_char, source.len(), &mut error).is_null());
+            assert!(!take(error).is_empty());
+            assert!(yaj_parse(ptr::null(), 0, ptr::null_mut()).is_null());
+        }
+    }
+}